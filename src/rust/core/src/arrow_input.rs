@@ -0,0 +1,427 @@
+//! Arrow IPC and GeoParquet input
+//!
+//! Reads NVDB exports in the Arrow IPC file format (Feather v2, `.arrow`,
+//! `.feather`, uncompressed or with LZ4 or ZSTD bodies) and GeoParquet
+//! straight into segments, so attribute columns never pass through R. The
+//! files are decoded by the arrow-ipc and parquet crates one record batch at
+//! a time, reading only the columns the conversion uses; the values of each
+//! row are then converted to properties.
+//!
+//! The geometry column is the `primary_column` of the GeoParquet `geo`
+//! metadata, else the one with a GeoArrow extension type (`geoarrow.wkb`,
+//! `geoarrow.linestring`, `geoarrow.multilinestring`), else a binary column
+//! named `geometry`, `geom`, `wkb_geometry` or `SHAPE`. Its encoding follows
+//! from the column type: binary columns hold WKB, lists of coordinates
+//! (interleaved or separated, of which x and y are read) linestrings and
+//! lists of those multilinestrings.
+//!
+//! Integer, floating point, boolean, string, date and timestamp columns are
+//! read, including dictionary-encoded strings (R factors); other column
+//! types are skipped.
+
+use std::fs::File;
+use std::io::Read;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Date32Type, Date64Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_ipc::reader::FileReader;
+use arrow_schema::{ArrowError, DataType, Schema as ArrowSchema, TimeUnit};
+use geo_types::{Coord, LineString};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::models::{PropertyValue, Segment};
+use crate::schema::{is_esri_boolean, Schema};
+use crate::tag_mapper::dates::{date_from_days, datetime_from_seconds};
+use crate::wkb::{segment_from_line, segment_from_wkb_with, WkbError, WkbOptions};
+
+/// Names of binary columns read as WKB when no column has GeoArrow metadata
+const GEOMETRY_NAMES: [&str; 4] = ["geometry", "geom", "wkb_geometry", "shape"];
+
+/// Segments read from an Arrow IPC or GeoParquet file
+#[derive(Debug, Default)]
+pub struct ArrowInput {
+    pub segments: Vec<Segment>,
+    /// Rows whose geometry could not be read, with the reason
+    pub failures: Vec<(usize, String)>,
+    pub rows: usize,
+    /// Canonical names of schema fields no column resolved to (when read
+    /// with a schema)
+    pub unresolved_fields: Vec<&'static str>,
+}
+
+/// Read the record batches of an Arrow IPC or GeoParquet file as segments
+///
+/// The format is recognized by the magic bytes at the start of the file.
+/// Column names are resolved to the canonical NVDB names with `schema`
+/// (unless `None`, for other countries' profiles), and ESRI booleans (-1
+/// for true) are normalized like the R input. Rows are numbered across
+/// batches from 0 (`Segment::source_row`).
+pub fn read_arrow_segments(path: &str, schema: Option<&Schema>, options: &WkbOptions) -> Result<ArrowInput, String> {
+    let open = || File::open(path).map_err(|e| format!("Failed to read {}: {}", path, e));
+    let mut magic = [0u8; 6];
+    let read = open()?.read(&mut magic).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let result = match &magic[..read] {
+        [b'P', b'A', b'R', b'1', ..] => read_parquet(open()?, schema, options),
+        b"ARROW1" => read_ipc(open, schema, options),
+        _ => Err("neither an Arrow IPC file (Feather v2) nor GeoParquet; Feather v1 and Arrow streams are not supported".to_string()),
+    };
+    result.map_err(|e| format!("{}: {}", path, e))
+}
+
+fn read_ipc(open: impl Fn() -> Result<File, String>, schema: Option<&Schema>, options: &WkbOptions) -> Result<ArrowInput, String> {
+    // The footer is read twice, once to choose the columns to decode
+    let file_schema = FileReader::try_new_buffered(open()?, None).map_err(arrow_error)?.schema();
+    let columns = Columns::new(&file_schema, None, schema)?;
+    let reader = FileReader::try_new_buffered(open()?, Some(columns.projection.clone())).map_err(arrow_error)?;
+    columns.read(reader, options)
+}
+
+fn read_parquet(file: File, schema: Option<&Schema>, options: &WkbOptions) -> Result<ArrowInput, String> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| e.to_string())?;
+    let geo = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|pairs| pairs.iter().find(|pair| pair.key == "geo"))
+        .and_then(|pair| pair.value.clone());
+    let columns = Columns::new(builder.schema(), geo.as_deref(), schema)?;
+    let mask = ProjectionMask::roots(builder.parquet_schema(), columns.projection.clone());
+    let reader = builder.with_projection(mask).build().map_err(|e| e.to_string())?;
+    columns.read(reader, options)
+}
+
+fn arrow_error(error: ArrowError) -> String {
+    error.to_string()
+}
+
+/// Columns of a file that are read, in file order
+struct Columns {
+    /// Indices of the columns in the file
+    projection: Vec<usize>,
+    /// Canonical names of the projected columns
+    names: Vec<String>,
+    /// Position of the geometry column in `projection`
+    geometry: usize,
+    unresolved_fields: Vec<&'static str>,
+}
+
+impl Columns {
+    fn new(file_schema: &ArrowSchema, geo: Option<&str>, schema: Option<&Schema>) -> Result<Self, String> {
+        let geometry_index = geometry_column(file_schema, geo)?;
+        let mut names: Vec<String> = file_schema.fields().iter().map(|f| f.name().clone()).collect();
+        let unresolved_fields = match schema {
+            Some(schema) => schema.resolve_columns(&mut names).into_iter().map(|f| f.column()).collect(),
+            None => Vec::new(),
+        };
+        // Columns the conversion never reads are not decoded
+        let projection: Vec<usize> = (0..names.len())
+            .filter(|&i| i == geometry_index || schema.is_none_or(|schema| schema.uses_column(&names[i])))
+            .collect();
+        let geometry = projection.iter().position(|&i| i == geometry_index).expect("geometry column is projected");
+        let names = projection.iter().map(|&i| names[i].clone()).collect();
+        Ok(Self { projection, names, geometry, unresolved_fields })
+    }
+
+    fn read(self, batches: impl Iterator<Item = Result<RecordBatch, ArrowError>>, options: &WkbOptions) -> Result<ArrowInput, String> {
+        let mut input = ArrowInput { unresolved_fields: self.unresolved_fields.clone(), ..ArrowInput::default() };
+        for batch in batches {
+            let batch = batch.map_err(arrow_error)?;
+            let columns = batch.columns();
+            let first_row = input.rows;
+            let built: Vec<Result<Segment, String>> = (0..batch.num_rows())
+                .into_par_iter()
+                .map(|row| {
+                    let mut properties = FxHashMap::default();
+                    for (i, column) in columns.iter().enumerate() {
+                        if i == self.geometry {
+                            continue;
+                        }
+                        if let Some(value) = value(column.as_ref(), row) {
+                            properties.insert(self.names[i].clone(), normalize(&self.names[i], value));
+                        }
+                    }
+                    let mut segment = segment(columns[self.geometry].as_ref(), row, properties, options)?;
+                    segment.source_row = first_row + row;
+                    Ok(segment)
+                })
+                .collect();
+            for (row, result) in built.into_iter().enumerate() {
+                match result {
+                    Ok(segment) => input.segments.push(segment),
+                    Err(message) => input.failures.push((first_row + row, message)),
+                }
+            }
+            input.rows += batch.num_rows();
+        }
+        Ok(input)
+    }
+}
+
+/// Index of the geometry column
+fn geometry_column(schema: &ArrowSchema, geo: Option<&str>) -> Result<usize, String> {
+    let primary = geo
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+        .and_then(|geo| geo["primary_column"].as_str().map(str::to_string));
+    if let Some(name) = primary {
+        return schema.index_of(&name).map_err(|_| format!("GeoParquet primary column `{}` not found", name));
+    }
+    let fields = schema.fields();
+    fields
+        .iter()
+        .position(|f| f.extension_type_name().is_some_and(|e| e.starts_with("geoarrow.")))
+        .or_else(|| {
+            fields.iter().position(|f| {
+                matches!(f.data_type(), DataType::Binary | DataType::LargeBinary | DataType::BinaryView)
+                    && GEOMETRY_NAMES.contains(&f.name().to_lowercase().as_str())
+            })
+        })
+        .ok_or_else(|| "no geometry column (GeoParquet metadata, GeoArrow extension type or binary `geometry` column)".to_string())
+}
+
+/// Property as the R input would give it: whole doubles as integers, -1 as
+/// 1 in ESRI boolean columns
+fn normalize(name: &str, value: PropertyValue) -> PropertyValue {
+    match value {
+        PropertyValue::Float(f) if f.is_finite() && f == f.floor() => normalize(name, PropertyValue::Integer(f as i64)),
+        PropertyValue::Integer(-1) if is_esri_boolean(name) => PropertyValue::Integer(1),
+        value => value,
+    }
+}
+
+/// Attribute value of `row` (`None` for nulls and unsupported types)
+fn value(array: &dyn Array, row: usize) -> Option<PropertyValue> {
+    if array.is_null(row) {
+        return None;
+    }
+    if let Some(dictionary) = array.as_any_dictionary_opt() {
+        let PropertyValue::Integer(key) = value(dictionary.keys(), row)? else { return None };
+        let values = dictionary.values();
+        let key = usize::try_from(key).ok().filter(|&key| key < values.len())?;
+        return value(values.as_ref(), key);
+    }
+    let integer = |v: i64| Some(PropertyValue::Integer(v));
+    let float = |v: f64| (!v.is_nan()).then_some(PropertyValue::Float(v));
+    let string = |s: &str| (!s.is_empty()).then(|| PropertyValue::String(s.to_string()));
+    let timestamp = |v: i64, per_second: i64| Some(PropertyValue::String(datetime_from_seconds(v.div_euclid(per_second))));
+    match array.data_type() {
+        DataType::Int8 => integer(array.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => integer(array.as_primitive::<Int16Type>().value(row).into()),
+        DataType::Int32 => integer(array.as_primitive::<Int32Type>().value(row).into()),
+        DataType::Int64 => integer(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => integer(array.as_primitive::<UInt8Type>().value(row).into()),
+        DataType::UInt16 => integer(array.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => integer(array.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => integer(i64::try_from(array.as_primitive::<UInt64Type>().value(row)).ok()?),
+        DataType::Float32 => float(array.as_primitive::<Float32Type>().value(row).into()),
+        DataType::Float64 => float(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Boolean => Some(PropertyValue::Boolean(array.as_boolean().value(row))),
+        DataType::Utf8 => string(array.as_string::<i32>().value(row)),
+        DataType::LargeUtf8 => string(array.as_string::<i64>().value(row)),
+        DataType::Utf8View => string(array.as_string_view().value(row)),
+        DataType::Date32 => Some(PropertyValue::String(date_from_days(array.as_primitive::<Date32Type>().value(row).into()))),
+        DataType::Date64 => {
+            let millis = array.as_primitive::<Date64Type>().value(row);
+            Some(PropertyValue::String(date_from_days(millis.div_euclid(86_400_000))))
+        }
+        DataType::Timestamp(TimeUnit::Second, _) => timestamp(array.as_primitive::<TimestampSecondType>().value(row), 1),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => timestamp(array.as_primitive::<TimestampMillisecondType>().value(row), 1_000),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            timestamp(array.as_primitive::<TimestampMicrosecondType>().value(row), 1_000_000)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            timestamp(array.as_primitive::<TimestampNanosecondType>().value(row), 1_000_000_000)
+        }
+        _ => None,
+    }
+}
+
+/// Build the segment of `row` from the geometry column
+fn segment(array: &dyn Array, row: usize, properties: FxHashMap<String, PropertyValue>, options: &WkbOptions) -> Result<Segment, String> {
+    if array.is_null(row) {
+        return Err(WkbError::Empty.to_string());
+    }
+    let wkb = match array.data_type() {
+        DataType::Binary => Some(array.as_binary::<i32>().value(row)),
+        DataType::LargeBinary => Some(array.as_binary::<i64>().value(row)),
+        DataType::BinaryView => Some(array.as_binary_view().value(row)),
+        _ => None,
+    };
+    if let Some(wkb) = wkb {
+        return segment_from_wkb_with(wkb, properties, options).map_err(|e| e.to_string());
+    }
+    let item = list_item(array, row).ok_or("unsupported geometry column type")?;
+    let coords = if matches!(item.data_type(), DataType::List(_) | DataType::LargeList(_)) {
+        // Multilinestring: first non-empty part, as for WKB
+        let mut first = Vec::new();
+        for part in 0..item.len() {
+            let line = list_item(item.as_ref(), part).ok_or("multilinestring part out of range")?;
+            first = coords(line.as_ref())?;
+            if !first.is_empty() {
+                break;
+            }
+        }
+        first
+    } else {
+        coords(item.as_ref())?
+    };
+    if coords.is_empty() {
+        return Err(WkbError::Empty.to_string());
+    }
+    if coords.iter().any(|c| !c.x.is_finite() || !c.y.is_finite()) {
+        return Err(WkbError::NonFinite.to_string());
+    }
+    Ok(segment_from_line(LineString::from(coords), properties, options))
+}
+
+/// Item `row` of a list column
+fn list_item(array: &dyn Array, row: usize) -> Option<ArrayRef> {
+    if row >= array.len() {
+        return None;
+    }
+    match array.data_type() {
+        DataType::List(_) => Some(array.as_list::<i32>().value(row)),
+        DataType::LargeList(_) => Some(array.as_list::<i64>().value(row)),
+        _ => None,
+    }
+}
+
+/// Vertices of a linestring, given as its points
+fn coords(points: &dyn Array) -> Result<Vec<Coord>, String> {
+    let doubles = |array: &ArrayRef| {
+        array.as_primitive_opt::<Float64Type>().map(|a| a.values().to_vec()).ok_or_else(|| "coordinates are not doubles".to_string())
+    };
+    match points.data_type() {
+        // Interleaved: x, y[, z[, m]] per point
+        DataType::FixedSizeList(_, dims) if *dims >= 2 => {
+            let values = doubles(points.as_fixed_size_list().values())?;
+            Ok(values.chunks_exact(*dims as usize).map(|c| Coord { x: c[0], y: c[1] }).collect())
+        }
+        // Separated: struct of x, y[, z[, m]] arrays
+        DataType::Struct(fields) if fields.len() >= 2 => {
+            let points = points.as_struct();
+            let (xs, ys) = (doubles(points.column(0))?, doubles(points.column(1))?);
+            Ok(xs.into_iter().zip(ys).map(|(x, y)| Coord { x, y }).collect())
+        }
+        _ => Err("unsupported coordinate layout".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::builder::{FixedSizeListBuilder, Float64Builder, ListBuilder};
+    use arrow_array::types::Int32Type;
+    use arrow_array::{Array, ArrayRef, BinaryArray, DictionaryArray, Int32Array, RecordBatch};
+    use arrow_ipc::writer::{FileWriter, IpcWriteOptions};
+    use arrow_ipc::CompressionType;
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+    use geo_types::LineString;
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::metadata::KeyValue;
+
+    use super::read_arrow_segments;
+    use crate::geometry::linestring_to_wkb;
+    use crate::wkb::WkbOptions;
+
+    /// Two rows of road links: WKB geometry (the second empty), a factor of
+    /// street names and a speed limit
+    fn roads(geometry: &str) -> RecordBatch {
+        let wkb = linestring_to_wkb(&LineString::from(vec![(18.0, 59.0), (18.001, 59.0)]));
+        let extension = HashMap::from([("ARROW:extension:name".to_string(), "geoarrow.wkb".to_string())]);
+        let names: DictionaryArray<Int32Type> = vec!["Storgatan", "Kyrkogatan"].into_iter().collect();
+        let schema = ArrowSchema::new(vec![
+            Field::new(geometry, DataType::Binary, true).with_metadata(extension),
+            Field::new("Namn_130", names.data_type().clone(), true),
+            Field::new("Hogst_225", DataType::Int32, true),
+        ]);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(BinaryArray::from(vec![Some(wkb.as_slice()), None])),
+            Arc::new(names),
+            Arc::new(Int32Array::from(vec![Some(50), None])),
+        ];
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    fn write_ipc(name: &str, batch: &RecordBatch, compression: Option<CompressionType>) -> String {
+        let path = std::env::temp_dir().join(name).to_string_lossy().into_owned();
+        let options = IpcWriteOptions::default().try_with_compression(compression).unwrap();
+        let mut writer = FileWriter::try_new_with_options(std::fs::File::create(&path).unwrap(), &batch.schema(), options).unwrap();
+        // Two batches, numbered as one sequence of rows
+        writer.write(batch).unwrap();
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn compressed_arrow_files_are_read() {
+        for (name, compression) in [
+            ("nvdb2osm_uncompressed-test.arrow", None),
+            ("nvdb2osm_lz4-test.arrow", Some(CompressionType::LZ4_FRAME)),
+            ("nvdb2osm_zstd-test.arrow", Some(CompressionType::ZSTD)),
+        ] {
+            let path = write_ipc(name, &roads("geometry"), compression);
+            let input = read_arrow_segments(&path, None, &WkbOptions::default()).unwrap();
+            assert_eq!(input.rows, 4);
+            assert_eq!(input.failures.iter().map(|f| f.0).collect::<Vec<_>>(), vec![1, 3]);
+            assert_eq!(input.segments.iter().map(|s| s.source_row).collect::<Vec<_>>(), vec![0, 2]);
+            let segment = &input.segments[0];
+            assert_eq!(segment.geometry.0.len(), 2);
+            assert_eq!(segment.properties["Namn_130"].as_string(), "Storgatan");
+            assert_eq!(segment.properties["Hogst_225"].as_i64(), Some(50));
+        }
+    }
+
+    #[test]
+    fn geoparquet_primary_column_is_the_geometry() {
+        let batch = roads("the_geom");
+        let path = std::env::temp_dir().join("nvdb2osm_geoparquet-test.parquet").to_string_lossy().into_owned();
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(&path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        let geo = r#"{"version": "1.1.0", "primary_column": "the_geom", "columns": {"the_geom": {"encoding": "WKB"}}}"#;
+        writer.append_key_value_metadata(KeyValue::new("geo".to_string(), geo.to_string()));
+        writer.close().unwrap();
+
+        let input = read_arrow_segments(&path, None, &WkbOptions::default()).unwrap();
+        assert_eq!((input.rows, input.segments.len()), (2, 1));
+        assert_eq!(input.segments[0].properties["Namn_130"].as_string(), "Storgatan");
+        assert!(!input.segments[0].properties.contains_key("the_geom"));
+    }
+
+    #[test]
+    fn native_linestrings_are_read() {
+        // geoarrow.linestring with interleaved coordinates
+        let mut lines = ListBuilder::new(FixedSizeListBuilder::new(Float64Builder::new(), 2));
+        for (x, y) in [(18.0, 59.0), (18.001, 59.0), (18.002, 59.001)] {
+            lines.values().values().append_slice(&[x, y]);
+            lines.values().append(true);
+        }
+        lines.append(true);
+        let lines = lines.finish();
+        let extension = HashMap::from([("ARROW:extension:name".to_string(), "geoarrow.linestring".to_string())]);
+        let schema = ArrowSchema::new(vec![Field::new("geom", lines.data_type().clone(), true).with_metadata(extension)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(lines)]).unwrap();
+        let path = write_ipc("nvdb2osm_linestring-test.arrow", &batch, Some(CompressionType::LZ4_FRAME));
+
+        let input = read_arrow_segments(&path, None, &WkbOptions::default()).unwrap();
+        assert_eq!(input.segments.len(), 2);
+        assert_eq!(input.segments[0].geometry, LineString::from(vec![(18.0, 59.0), (18.001, 59.0), (18.002, 59.001)]));
+    }
+
+    #[test]
+    fn other_files_are_refused() {
+        let path = std::env::temp_dir().join("nvdb2osm_not_arrow-test.arrow");
+        std::fs::write(&path, b"FEA1").unwrap();
+        let error = read_arrow_segments(path.to_str().unwrap(), None, &WkbOptions::default()).unwrap_err();
+        assert!(error.contains("neither an Arrow IPC file"));
+    }
+}
@@ -0,0 +1,239 @@
+//! Checkpoints of long conversions
+//!
+//! A country-scale conversion parses for minutes and tags and simplifies for
+//! longer before anything is written, and a crash or interrupt loses all of
+//! it. With `Pipeline::checkpoint()` the state after each finished phase is
+//! saved to one file: the parsed segments, then the converted segments, ways,
+//! feature nodes and relations with their assigned IDs. A rerun resumes
+//! from the saved phase (`Pipeline::resume()`) if its first IDs and its
+//! `Pipeline::fingerprint()` of the options and input match the saved ones,
+//! and the file is removed once the output is written.
+//!
+//! The file is bincode: the format version, then the IDs, fingerprint,
+//! counts and annotations of the saved phases and the state. Floats are
+//! stored as their bits, so coordinates and non-finite property values read
+//! back unchanged.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{AnnotationKind, Segment};
+use crate::pipeline::Conversion;
+use crate::stats::{AnnotationRecord, ConversionStats};
+use crate::topology::duplicates::DUPLICATE_WAY;
+use crate::writer::{ElementIds, PendingFile};
+
+/// Format version, raised whenever the saved structures change
+const VERSION: u32 = 3;
+
+/// State of a conversion after its last finished phase
+#[derive(Serialize, Deserialize)]
+pub enum Checkpoint {
+    /// Segments parsed from the input, not yet tagged
+    Parsed(Vec<Segment>),
+    /// Tagged and simplified, not yet written
+    Converted(Conversion),
+}
+
+impl Checkpoint {
+    /// Name of the finished phase, "parsed" or "converted"
+    pub fn phase(&self) -> &'static str {
+        match self {
+            Checkpoint::Parsed(_) => "parsed",
+            Checkpoint::Converted(_) => "converted",
+        }
+    }
+}
+
+/// Counts and annotations of the saved phases, added to the stats of the
+/// resumed run
+#[derive(Serialize, Deserialize)]
+struct SavedStats {
+    segments_in: usize,
+    parse_failures: usize,
+    feature_nodes_out: usize,
+    ways_out: usize,
+    relations_out: usize,
+    /// Source row, kind and message of each `AnnotationRecord`
+    annotations: Vec<(usize, String, String)>,
+}
+
+/// Contents of a checkpoint file after the version; `S` is `&Checkpoint`
+/// when saving
+#[derive(Serialize, Deserialize)]
+struct CheckpointFile<S> {
+    ids: (i64, i64, i64),
+    fingerprint: u64,
+    stats: SavedStats,
+    state: S,
+}
+
+/// `AnnotationRecord::kind` of a saved annotation
+fn annotation_kind(name: &str) -> Option<&'static str> {
+    [AnnotationKind::AmbiguousClass, AnnotationKind::ConflictingAttributes, AnnotationKind::OutOfRange]
+        .iter()
+        .map(|kind| kind.as_str())
+        .chain([DUPLICATE_WAY])
+        .find(|kind| *kind == name)
+}
+
+/// Save `checkpoint` to `path`, replacing the previous one only once it is
+/// complete
+///
+/// `ids` are the first node, way and relation IDs of the conversion and
+/// `fingerprint` that of its options and input, which `load()` checks.
+pub fn save(path: &str, checkpoint: &Checkpoint, ids: ElementIds, fingerprint: u64, stats: &ConversionStats) -> Result<(), String> {
+    let file = PendingFile::new(path);
+    let temp = File::create(file.temp_path()).map_err(|e| format!("Failed to create checkpoint {}: {}", path, e))?;
+    let mut out = BufWriter::new(temp);
+    let contents = CheckpointFile {
+        ids: (ids.node, ids.way, ids.relation),
+        fingerprint,
+        stats: SavedStats {
+            segments_in: stats.segments_in,
+            parse_failures: stats.parse_failures,
+            feature_nodes_out: stats.feature_nodes_out,
+            ways_out: stats.ways_out,
+            relations_out: stats.relations_out,
+            annotations: stats
+                .annotations
+                .iter()
+                .map(|a| (a.source_row, a.kind.to_string(), a.message.clone()))
+                .collect(),
+        },
+        state: checkpoint,
+    };
+    bincode::serialize_into(&mut out, &VERSION)
+        .and_then(|_| bincode::serialize_into(&mut out, &contents))
+        .map_err(|e| format!("Failed to write checkpoint {}: {}", path, e))?;
+    out.flush().map_err(|e| format!("Failed to write checkpoint {}: {}", path, e))?;
+    drop(out);
+    file.commit()
+}
+
+/// Read the checkpoint at `path`, `None` if there is none
+///
+/// Fails for unreadable files and for checkpoints of conversions numbered
+/// from other first IDs than `ids` or with another `fingerprint` of the
+/// options and input (see `Pipeline::fingerprint()`); the counts and
+/// annotations of the saved phases are added to `stats`.
+pub fn load(path: &str, ids: ElementIds, fingerprint: u64, stats: &mut ConversionStats) -> Result<Option<Checkpoint>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to open checkpoint {}: {}", path, e)),
+    };
+    let mut reader = BufReader::new(file);
+    let unreadable = |e: bincode::Error| format!("Failed to read checkpoint {} (remove it to start over): {}", path, e);
+    let version: u32 = bincode::deserialize_from(&mut reader).map_err(unreadable)?;
+    if version != VERSION {
+        return Err(format!("Checkpoint {} is from another version of nvdb2osm; remove it to start over", path));
+    }
+    let contents: CheckpointFile<Checkpoint> = bincode::deserialize_from(&mut reader).map_err(unreadable)?;
+    if contents.ids != (ids.node, ids.way, ids.relation) {
+        return Err(format!("Checkpoint {} was numbered from other first IDs; remove it to start over", path));
+    }
+    if contents.fingerprint != fingerprint {
+        return Err(format!("Checkpoint {} is of another input or other options; remove it to start over", path));
+    }
+    let saved = contents.stats;
+    let mut annotations = Vec::with_capacity(saved.annotations.len());
+    for (source_row, kind, message) in saved.annotations {
+        let kind = annotation_kind(&kind)
+            .ok_or_else(|| format!("Checkpoint {} is from another version of nvdb2osm; remove it to start over", path))?;
+        annotations.push(AnnotationRecord { source_row, kind, message });
+    }
+    stats.segments_in += saved.segments_in;
+    stats.parse_failures += saved.parse_failures;
+    stats.feature_nodes_out += saved.feature_nodes_out;
+    stats.ways_out += saved.ways_out;
+    stats.relations_out += saved.relations_out;
+    stats.annotations.extend(annotations);
+    Ok(Some(contents.state))
+}
+
+/// Serde representation of geometries as `[x, y]` pairs, as geo-types is
+/// built without its serde feature
+pub(crate) mod coords {
+    use geo_types::{Coord, LineString};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(line: &LineString<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+        line.0.iter().map(|c| (c.x, c.y)).collect::<Vec<_>>().serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LineString<f64>, D::Error> {
+        let pairs = Vec::<(f64, f64)>::deserialize(deserializer)?;
+        Ok(LineString::new(pairs.into_iter().map(|(x, y)| Coord { x, y }).collect()))
+    }
+
+    /// `Segment::end_neighbours`
+    pub(crate) mod pair {
+        use super::*;
+
+        pub(crate) fn serialize<S: Serializer>(pair: &Option<(Coord, Coord)>, serializer: S) -> Result<S::Ok, S::Error> {
+            pair.map(|(a, b)| ((a.x, a.y), (b.x, b.y))).serialize(serializer)
+        }
+
+        #[allow(clippy::type_complexity)]
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<(Coord, Coord)>, D::Error> {
+            let pair = Option::<((f64, f64), (f64, f64))>::deserialize(deserializer)?;
+            Ok(pair.map(|((ax, ay), (bx, by))| (Coord { x: ax, y: ay }, Coord { x: bx, y: by })))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::LineString;
+
+    use crate::checkpoint::{self, Checkpoint};
+    use crate::{AnnotationRecord, ConversionStats, ElementIds, PropertyValue};
+    use crate::test_support::segment;
+
+    #[test]
+    fn save_keeps_float_bits_and_non_finite_values() {
+        let path = std::env::temp_dir().join("nvdb2osm-checkpoint-floats-test.bin");
+        let path = path.to_str().unwrap();
+        let (x, y) = (18.068581347465515, 0.1 + 0.2);
+        let mut segment = segment(vec![(x, y), (f64::MIN_POSITIVE, 59.0)]);
+        for (key, value) in [("nan", f64::NAN), ("inf", f64::INFINITY), ("-inf", f64::NEG_INFINITY)] {
+            segment.properties.insert(key.to_string(), PropertyValue::Float(value));
+        }
+        checkpoint::save(path, &Checkpoint::Parsed(vec![segment]), ElementIds::default(), 0, &ConversionStats::default()).unwrap();
+
+        let loaded = checkpoint::load(path, ElementIds::default(), 0, &mut ConversionStats::default()).unwrap();
+        let Some(Checkpoint::Parsed(segments)) = loaded else { panic!() };
+        let coords = &segments[0].geometry.0;
+        assert_eq!((coords[0].x.to_bits(), coords[0].y.to_bits()), (x.to_bits(), y.to_bits()));
+        assert_eq!(coords[1].x.to_bits(), f64::MIN_POSITIVE.to_bits());
+        let float = |key: &str| match segments[0].properties[key] { PropertyValue::Float(f) => f, _ => panic!() };
+        assert!(float("nan").is_nan());
+        assert_eq!((float("inf"), float("-inf")), (f64::INFINITY, f64::NEG_INFINITY));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_resumes_only_a_matching_checkpoint() {
+        let path = std::env::temp_dir().join("nvdb2osm-checkpoint-test.bin");
+        let path = path.to_str().unwrap();
+        let segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+        let annotation = AnnotationRecord { source_row: 3, kind: "duplicate_way", message: "same nodes".to_string() };
+        let stats = ConversionStats { segments_in: 1, annotations: vec![annotation], ..Default::default() };
+        checkpoint::save(path, &Checkpoint::Parsed(vec![segment]), ElementIds::default(), 7, &stats).unwrap();
+
+        let mut resumed = ConversionStats::default();
+        let Some(Checkpoint::Parsed(segments)) = checkpoint::load(path, ElementIds::default(), 7, &mut resumed).unwrap() else { panic!() };
+        assert_eq!(segments[0].geometry, LineString::from(vec![(18.0, 59.0), (18.001, 59.0)]));
+        assert_eq!(resumed.segments_in, 1);
+        assert_eq!((resumed.annotations[0].source_row, resumed.annotations[0].kind), (3, "duplicate_way"));
+
+        let other = ElementIds { node: 1000, ..Default::default() };
+        assert!(checkpoint::load(path, other, 7, &mut resumed).is_err());
+        assert!(checkpoint::load(path, ElementIds::default(), 8, &mut resumed).is_err());
+        std::fs::remove_file(path).unwrap();
+        assert!(checkpoint::load(path, ElementIds::default(), 7, &mut resumed).unwrap().is_none());
+    }
+}
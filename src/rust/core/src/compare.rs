@@ -0,0 +1,251 @@
+//! Comparison of two outputs
+//!
+//! Reviewing a change of the mapping rules means comparing the output of two
+//! versions. `compare_pbf()` reads two PBF (or other pbf-craft readable)
+//! files and matches their ways and tagged nodes either by ID, which is
+//! stable as long as the input and options are unchanged, or by geometry,
+//! for runs whose numbering differs. It reports added and removed elements,
+//! tag changes per key and, for elements matched by ID, geometry changes.
+
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+use geo::HaversineDistance;
+use geo_types::Point;
+use pbf_craft::models::Element;
+use pbf_craft::readers::IterableReader;
+use rustc_hash::{FxHashMap, FxHasher};
+
+/// How elements of the two files are paired
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchBy {
+    /// Same element ID (the default)
+    #[default]
+    Id,
+    /// Same coordinates, in either direction
+    Geometry,
+}
+
+impl From<&str> for MatchBy {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "geometry" => MatchBy::Geometry,
+            _ => MatchBy::Id, // Default
+        }
+    }
+}
+
+/// Counts of the tag changes of one key over all matched elements
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagChange {
+    pub key: String,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+/// Geometry change of an element matched by ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometryChange {
+    pub id: i64,
+    /// Largest distance in metres from a vertex of one version to the
+    /// nearest vertex of the other
+    pub max_offset_m: f64,
+    /// Length in the second file minus length in the first, in metres
+    pub length_delta_m: f64,
+}
+
+/// Differences of one element type (ways or tagged nodes)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElementDiff {
+    pub count_a: usize,
+    pub count_b: usize,
+    /// (ID in a, ID in b) of the matched elements
+    pub matched: Vec<(i64, i64)>,
+    /// IDs of elements only in the second file
+    pub added: Vec<i64>,
+    /// IDs of elements only in the first file
+    pub removed: Vec<i64>,
+    /// Matched elements whose tags differ, as (ID in a, ID in b)
+    pub retagged: Vec<(i64, i64)>,
+    pub geometry_changed: Vec<GeometryChange>,
+}
+
+/// Differences between two files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PbfDiff {
+    pub ways: ElementDiff,
+    pub nodes: ElementDiff,
+    /// Tag changes of ways and nodes together, sorted by key
+    pub tag_changes: Vec<TagChange>,
+}
+
+/// Way or tagged node as read for comparison
+pub(crate) struct Feature {
+    pub(crate) id: i64,
+    /// Coordinates in nanodegrees (lon, lat)
+    pub(crate) coords: Vec<(i64, i64)>,
+    pub(crate) tags: BTreeMap<String, String>,
+}
+
+/// Ways and tagged nodes of a file
+pub(crate) fn read_features(path: &str) -> Result<(Vec<Feature>, Vec<Feature>), String> {
+    let reader = IterableReader::from_path(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut coords: FxHashMap<i64, (i64, i64)> = FxHashMap::default();
+    let (mut ways, mut nodes) = (Vec::new(), Vec::new());
+    for element in reader {
+        match element {
+            Element::Node(node) => {
+                coords.insert(node.id, (node.longitude, node.latitude));
+                if !node.tags.is_empty() {
+                    nodes.push(Feature {
+                        id: node.id,
+                        coords: vec![(node.longitude, node.latitude)],
+                        tags: node.tags.into_iter().map(|t| (t.key, t.value)).collect(),
+                    });
+                }
+            }
+            Element::Way(way) => ways.push(Feature {
+                id: way.id,
+                coords: way.way_nodes.iter().filter_map(|wn| coords.get(&wn.id).copied()).collect(),
+                tags: way.tags.into_iter().map(|t| (t.key, t.value)).collect(),
+            }),
+            Element::Relation(_) => {}
+        }
+    }
+    Ok((ways, nodes))
+}
+
+/// Compare the ways and tagged nodes of two files
+pub fn compare_pbf(path_a: &str, path_b: &str, match_by: MatchBy) -> Result<PbfDiff, String> {
+    let (ways_a, nodes_a) = read_features(path_a)?;
+    let (ways_b, nodes_b) = read_features(path_b)?;
+    let mut tag_changes: BTreeMap<String, TagChange> = BTreeMap::new();
+    let ways = diff_features(&ways_a, &ways_b, match_by, &mut tag_changes);
+    let nodes = diff_features(&nodes_a, &nodes_b, match_by, &mut tag_changes);
+    Ok(PbfDiff { ways, nodes, tag_changes: tag_changes.into_values().collect() })
+}
+
+/// Hash of a geometry that does not depend on its direction
+fn geometry_hash(coords: &[(i64, i64)]) -> u64 {
+    let hash = |iter: &mut dyn Iterator<Item = &(i64, i64)>| {
+        let mut hasher = FxHasher::default();
+        iter.for_each(|c| c.hash(&mut hasher));
+        hasher.finish()
+    };
+    hash(&mut coords.iter()).min(hash(&mut coords.iter().rev()))
+}
+
+fn diff_features(
+    a: &[Feature],
+    b: &[Feature],
+    match_by: MatchBy,
+    tag_changes: &mut BTreeMap<String, TagChange>,
+) -> ElementDiff {
+    let key = |f: &Feature| match match_by {
+        MatchBy::Id => f.id as u64,
+        MatchBy::Geometry => geometry_hash(&f.coords),
+    };
+    // Unmatched elements of b by key; several can share a geometry
+    let mut unmatched: FxHashMap<u64, Vec<usize>> = FxHashMap::default();
+    for (i, f) in b.iter().enumerate().rev() {
+        unmatched.entry(key(f)).or_default().push(i);
+    }
+
+    let mut diff = ElementDiff { count_a: a.len(), count_b: b.len(), ..Default::default() };
+    let mut matched_b = vec![false; b.len()];
+    for fa in a {
+        let Some(j) = unmatched.get_mut(&key(fa)).and_then(Vec::pop) else {
+            diff.removed.push(fa.id);
+            continue;
+        };
+        matched_b[j] = true;
+        let fb = &b[j];
+        diff.matched.push((fa.id, fb.id));
+        if record_tag_changes(&fa.tags, &fb.tags, tag_changes) {
+            diff.retagged.push((fa.id, fb.id));
+        }
+        if match_by == MatchBy::Id && fa.coords != fb.coords {
+            diff.geometry_changed.push(GeometryChange {
+                id: fa.id,
+                max_offset_m: max_offset(&fa.coords, &fb.coords).max(max_offset(&fb.coords, &fa.coords)),
+                length_delta_m: length(&fb.coords) - length(&fa.coords),
+            });
+        }
+    }
+    diff.added = b.iter().zip(&matched_b).filter(|(_, &m)| !m).map(|(f, _)| f.id).collect();
+    diff
+}
+
+/// Count the tag differences of a matched pair; true if there are any
+fn record_tag_changes(
+    a: &BTreeMap<String, String>,
+    b: &BTreeMap<String, String>,
+    tag_changes: &mut BTreeMap<String, TagChange>,
+) -> bool {
+    let mut any = false;
+    let mut record = |key: &str, count: fn(&mut TagChange)| {
+        any = true;
+        count(tag_changes.entry(key.to_string()).or_insert_with(|| TagChange { key: key.to_string(), ..Default::default() }));
+    };
+    for (key, value) in a {
+        match b.get(key) {
+            None => record(key, |c| c.removed += 1),
+            Some(other) if other != value => record(key, |c| c.changed += 1),
+            _ => {}
+        }
+    }
+    for key in b.keys().filter(|k| !a.contains_key(*k)) {
+        record(key, |c| c.added += 1);
+    }
+    any
+}
+
+fn point((lon, lat): (i64, i64)) -> Point<f64> {
+    Point::new(lon as f64 / 1e9, lat as f64 / 1e9)
+}
+
+/// Largest distance from a vertex of `from` to the nearest vertex of `to`
+fn max_offset(from: &[(i64, i64)], to: &[(i64, i64)]) -> f64 {
+    from.iter()
+        .map(|&c| {
+            to.iter()
+                .map(|&d| point(c).haversine_distance(&point(d)))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .filter(|d| d.is_finite())
+        .fold(0.0, f64::max)
+}
+
+fn length(coords: &[(i64, i64)]) -> f64 {
+    coords.windows(2).map(|w| point(w[0]).haversine_distance(&point(w[1]))).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_pbf, MatchBy};
+    use crate::{PbfEmitter, Way};
+    use crate::test_support::segment;
+
+    #[test]
+    fn compare_pbf_matches_ways_and_counts_tag_changes() {
+        let dir = std::env::temp_dir();
+        let write = |name: &str, highway: &str| {
+            let mut segments = vec![segment(vec![(18.0, 59.0), (18.01, 59.0)])];
+            let way = Way::new(vec![0], [("highway".to_string(), highway.to_string())].into_iter().collect());
+            let path = dir.join(name).to_string_lossy().into_owned();
+            let mut emitter = PbfEmitter::create(&path, 1, 1).unwrap();
+            emitter.write_network_nodes(std::slice::from_ref(&way), &mut segments);
+            let pbf_way = emitter.build_way(&way, &segments);
+            emitter.write_way(pbf_way);
+            emitter.finish().unwrap();
+            path
+        };
+        let (a, b) = (write("compare_a.osm.pbf", "residential"), write("compare_b.osm.pbf", "service"));
+
+        let diff = compare_pbf(&a, &b, MatchBy::Geometry).unwrap();
+        assert_eq!(diff.ways.matched.len(), 1);
+        assert_eq!(diff.tag_changes[0].key, "highway");
+        assert_eq!(diff.tag_changes[0].changed, 1);
+    }
+}
@@ -0,0 +1,270 @@
+//! Coverage against an existing OSM extract
+//!
+//! Before an import, NVDB is compared with what OSM already has: which roads
+//! are missing from OSM, which OSM roads NVDB does not know, and how often the
+//! two disagree on tags like `maxspeed` and `surface`. `compare_coverage()`
+//! matches the highway ways of a conversion output and of an OSM extract by
+//! proximity: both are cut into short pieces, and a piece is matched when a
+//! way of the other file passes within the tolerance of its middle. The
+//! nearest such way is its counterpart for the tag comparison.
+//!
+//! OSM ways outside the bounding box of the conversion are left out, so an
+//! extract larger than the converted area does not count as unmatched.
+
+use geo::{Closest, ClosestPoint};
+use geo_types::{Coord, Line, Point};
+use rayon::prelude::*;
+use rstar::primitives::GeomWithData;
+use rstar::{Envelope, RTree, AABB};
+
+use crate::compare::{read_features, Feature};
+use crate::geometry::METRES_PER_DEGREE;
+
+/// Longest piece of a way tested for a counterpart, in metres
+const PIECE_M: f64 = 10.0;
+
+/// Way edge in degrees, with the index of its way
+type Edge = GeomWithData<rstar::primitives::Line<[f64; 2]>, usize>;
+
+/// Highway ways of one file and their matched length
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    pub ways: usize,
+    pub length_m: f64,
+    /// Length with no way of the other file within the tolerance
+    pub unmatched_m: f64,
+}
+
+/// Agreement on one key over the matched length of the conversion
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagAgreement {
+    pub key: String,
+    /// Tagged in both, with the same value
+    pub agree_m: f64,
+    /// Tagged in both, with different values
+    pub disagree_m: f64,
+    /// Tagged in the conversion only
+    pub nvdb_only_m: f64,
+    /// Tagged in OSM only
+    pub osm_only_m: f64,
+}
+
+/// A way with length that has no counterpart
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmatchedWay {
+    /// "nvdb" or "osm"
+    pub source: &'static str,
+    pub id: i64,
+    pub length_m: f64,
+    pub unmatched_m: f64,
+}
+
+/// Result of `compare_coverage()`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageReport {
+    pub nvdb: Coverage,
+    pub osm: Coverage,
+    /// One entry per compared key, in the order given
+    pub tags: Vec<TagAgreement>,
+    /// Ways of either file with unmatched length, longest unmatched first
+    pub unmatched: Vec<UnmatchedWay>,
+}
+
+/// Highway way in degrees
+struct Road {
+    coords: Vec<Coord>,
+    feature: Feature,
+}
+
+/// Result for one way: (length, unmatched length, per key (agree,
+/// disagree, nvdb only, osm only))
+type WayResult = (f64, f64, Vec<[f64; 4]>);
+
+/// Compare the highway ways of the conversion output at `nvdb_path` with
+/// those of the OSM extract at `osm_path`, matching them within
+/// `tolerance_m` metres and comparing the values of `keys`
+pub fn compare_coverage(nvdb_path: &str, osm_path: &str, tolerance_m: f64, keys: &[&str]) -> Result<CoverageReport, String> {
+    let nvdb = roads(read_features(nvdb_path)?.0);
+    let osm = roads(read_features(osm_path)?.0);
+
+    // Bounding box of the conversion, widened by the tolerance
+    let mut bbox: Option<AABB<[f64; 2]>> = None;
+    for c in nvdb.iter().flat_map(|r| &r.coords) {
+        let point = AABB::from_point([c.x, c.y]);
+        bbox = Some(bbox.map_or(point, |b| b.merged(&point)));
+    }
+    let Some(bbox) = bbox else {
+        return Err(format!("No highway ways in {}", nvdb_path));
+    };
+    let margin = tolerance_m / METRES_PER_DEGREE;
+    let (lower, upper) = (bbox.lower(), bbox.upper());
+    let lat = lower[1].abs().max(upper[1].abs()).min(89.0).to_radians();
+    let bbox = AABB::from_corners(
+        [lower[0] - margin / lat.cos(), lower[1] - margin],
+        [upper[0] + margin / lat.cos(), upper[1] + margin],
+    );
+
+    let nvdb_tree = edge_tree(&nvdb);
+    let osm_tree = edge_tree(&osm);
+    let nvdb_results: Vec<WayResult> = nvdb
+        .par_iter()
+        .map(|road| match_road(road, &osm_tree, &osm, tolerance_m, keys, None))
+        .collect();
+    let osm_results: Vec<WayResult> = osm
+        .par_iter()
+        .map(|road| match_road(road, &nvdb_tree, &nvdb, tolerance_m, &[], Some(&bbox)))
+        .collect();
+
+    let mut report = CoverageReport {
+        tags: keys.iter().map(|key| TagAgreement { key: key.to_string(), ..Default::default() }).collect(),
+        ..Default::default()
+    };
+    for (source, roads, results) in [("nvdb", &nvdb, nvdb_results), ("osm", &osm, osm_results)] {
+        for (road, (length_m, unmatched_m, tags)) in roads.iter().zip(results) {
+            if length_m == 0.0 {
+                continue;
+            }
+            let coverage = if source == "nvdb" { &mut report.nvdb } else { &mut report.osm };
+            coverage.ways += 1;
+            coverage.length_m += length_m;
+            coverage.unmatched_m += unmatched_m;
+            if unmatched_m > 0.0 {
+                report.unmatched.push(UnmatchedWay { source, id: road.feature.id, length_m, unmatched_m });
+            }
+            for (agreement, [agree, disagree, nvdb_only, osm_only]) in report.tags.iter_mut().zip(tags) {
+                agreement.agree_m += agree;
+                agreement.disagree_m += disagree;
+                agreement.nvdb_only_m += nvdb_only;
+                agreement.osm_only_m += osm_only;
+            }
+        }
+    }
+    report.unmatched.sort_by(|a, b| b.unmatched_m.total_cmp(&a.unmatched_m));
+    Ok(report)
+}
+
+/// Ways with a `highway` tag that are not areas, in degrees
+fn roads(ways: Vec<Feature>) -> Vec<Road> {
+    ways.into_iter()
+        .filter(|way| way.tags.contains_key("highway") && way.tags.get("area").map(String::as_str) != Some("yes"))
+        .map(|feature| Road {
+            coords: feature.coords.iter().map(|&(lon, lat)| Coord { x: lon as f64 / 1e9, y: lat as f64 / 1e9 }).collect(),
+            feature,
+        })
+        .collect()
+}
+
+fn edge_tree(roads: &[Road]) -> RTree<Edge> {
+    let edges = roads
+        .iter()
+        .enumerate()
+        .flat_map(|(i, road)| {
+            road.coords.windows(2).map(move |w| GeomWithData::new(rstar::primitives::Line::new([w[0].x, w[0].y], [w[1].x, w[1].y]), i))
+        })
+        .collect();
+    RTree::bulk_load(edges)
+}
+
+/// Length, unmatched length and tag agreement of the pieces of `road`,
+/// against the ways `others` indexed in `tree`; pieces outside `bbox` are
+/// left out
+fn match_road(road: &Road, tree: &RTree<Edge>, others: &[Road], tolerance_m: f64, keys: &[&str], bbox: Option<&AABB<[f64; 2]>>) -> WayResult {
+    let mut result: WayResult = (0.0, 0.0, vec![[0.0; 4]; keys.len()]);
+    for w in road.coords.windows(2) {
+        let edge_m = distance_m(w[0], w[1]);
+        let n = (edge_m / PIECE_M).ceil().max(1.0);
+        for i in 0..n as usize {
+            let t = (i as f64 + 0.5) / n;
+            let middle = Coord { x: w[0].x + (w[1].x - w[0].x) * t, y: w[0].y + (w[1].y - w[0].y) * t };
+            if bbox.is_some_and(|b| !b.contains_point(&[middle.x, middle.y])) {
+                continue;
+            }
+            let piece_m = edge_m / n;
+            result.0 += piece_m;
+            let Some(other) = nearest_way(tree, middle, tolerance_m) else {
+                result.1 += piece_m;
+                continue;
+            };
+            for (totals, key) in result.2.iter_mut().zip(keys) {
+                let column = match (road.feature.tags.get(*key), others[other].feature.tags.get(*key)) {
+                    (Some(a), Some(b)) if a == b => 0,
+                    (Some(_), Some(_)) => 1,
+                    (Some(_), None) => 2,
+                    (None, Some(_)) => 3,
+                    (None, None) => continue,
+                };
+                totals[column] += piece_m;
+            }
+        }
+    }
+    result
+}
+
+/// Index of the way nearest to `point` within `tolerance_m`
+fn nearest_way(tree: &RTree<Edge>, point: Coord, tolerance_m: f64) -> Option<usize> {
+    let dlat = tolerance_m / METRES_PER_DEGREE;
+    let dlon = dlat / point.y.to_radians().cos().max(0.01);
+    let envelope = AABB::from_corners([point.x - dlon, point.y - dlat], [point.x + dlon, point.y + dlat]);
+    // In metres around `point`
+    let x_scale = METRES_PER_DEGREE * point.y.to_radians().cos();
+    let local = |c: [f64; 2]| Coord { x: (c[0] - point.x) * x_scale, y: (c[1] - point.y) * METRES_PER_DEGREE };
+    let mut best: Option<(f64, usize)> = None;
+    for edge in tree.locate_in_envelope_intersecting(&envelope) {
+        let line = Line::new(local(edge.geom().from), local(edge.geom().to));
+        let closest = match line.closest_point(&Point::new(0.0, 0.0)) {
+            Closest::Intersection(p) | Closest::SinglePoint(p) => p.0,
+            Closest::Indeterminate => line.start,
+        };
+        let distance = closest.x.hypot(closest.y);
+        if distance <= tolerance_m && best.is_none_or(|(d, _)| distance < d) {
+            best = Some((distance, edge.data));
+        }
+    }
+    best.map(|(_, way)| way)
+}
+
+/// Distance in metres between two nearby coordinates
+fn distance_m(a: Coord, b: Coord) -> f64 {
+    let x_scale = METRES_PER_DEGREE * ((a.y + b.y) / 2.0).to_radians().cos();
+    ((b.x - a.x) * x_scale).hypot((b.y - a.y) * METRES_PER_DEGREE)
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::LineString;
+
+    use super::compare_coverage;
+    use crate::{PbfEmitter, Segment, Way};
+    use crate::test_support::tags;
+
+    #[test]
+    fn compare_coverage_measures_unmatched_length_and_tag_disagreement() {
+        let dir = std::env::temp_dir();
+        let write = |name: &str, lines: &[(LineString<f64>, &str)]| {
+            let mut segments: Vec<Segment> = lines.iter().map(|(l, _)| Segment::new(String::new(), l.clone())).collect();
+            let ways: Vec<Way> = lines.iter().enumerate().map(|(i, (_, maxspeed))| Way::new(vec![i], tags(&[("highway", "primary"), ("maxspeed", maxspeed)]))).collect();
+            let path = dir.join(name).to_string_lossy().into_owned();
+            let mut emitter = PbfEmitter::create(&path, 1, 1).unwrap();
+            emitter.write_network_nodes(&ways, &mut segments);
+            for way in &ways {
+                let pbf_way = emitter.build_way(way, &segments);
+                emitter.write_way(pbf_way);
+            }
+            emitter.finish().unwrap();
+            path
+        };
+        // OSM has the first road 5 m north with another speed limit, not the second
+        let nvdb = write("coverage_nvdb.osm.pbf", &[
+            (LineString::from(vec![(18.0, 59.0), (18.01, 59.0)]), "70"),
+            (LineString::from(vec![(18.01, 59.0), (18.01, 59.005)]), "50"),
+        ]);
+        let osm = write("coverage_osm.osm.pbf", &[(LineString::from(vec![(18.0, 59.000045), (18.01, 59.000045)]), "80")]);
+
+        let report = compare_coverage(&nvdb, &osm, 15.0, &["maxspeed"]).unwrap();
+        assert_eq!(report.nvdb.ways, 2);
+        assert!(report.nvdb.unmatched_m > 500.0 && report.nvdb.unmatched_m < 560.0);
+        assert!(report.osm.unmatched_m < 10.0);
+        assert!(report.tags[0].disagree_m > 500.0 && report.tags[0].agree_m == 0.0);
+        assert_eq!(report.unmatched[0].id, 2);
+    }
+}
@@ -0,0 +1,426 @@
+//! Digital elevation models in GeoTIFF
+//!
+//! A small reader for single-band elevation rasters as national mapping
+//! agencies and GDAL write them: classic (not Big) TIFF, little or big
+//! endian, in strips or tiles, uncompressed or Deflate compressed (with or
+//! without horizontal differencing), with 8 to 64 bit integer or floating
+//! point samples. The raster must be in geographic coordinates (WGS84
+//! longitude/latitude, like the input); reproject a DEM in SWEREF99 TM with
+//! `gdalwarp -t_srs EPSG:4326` first. The whole band is held in memory.
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use super::ElevationProvider;
+
+/// Elevation raster read from a GeoTIFF, sampled by bilinear interpolation
+#[derive(Debug, Clone)]
+pub struct GeoTiffDem {
+    width: usize,
+    height: usize,
+    /// Longitude and latitude of the outer corner of the top left pixel
+    origin: (f64, f64),
+    /// Pixel size in degrees (x, y)
+    scale: (f64, f64),
+    values: Vec<f32>,
+    nodata: Option<f32>,
+    /// See `ElevationProvider::identity()`; set by `open()`
+    identity: Option<String>,
+}
+
+impl GeoTiffDem {
+    /// Read a DEM from a GeoTIFF file
+    pub fn open(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Cannot read DEM {}: {}", path, e))?;
+        let mut dem = Self::from_bytes(&bytes).map_err(|e| format!("DEM {}: {}", path, e))?;
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        dem.identity = Some(format!("{} ({} bytes, modified {:?})", path, bytes.len(), modified));
+        Ok(dem)
+    }
+
+    /// Read a DEM from the bytes of a GeoTIFF
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let tiff = Tiff::new(bytes)?;
+        let ifd = tiff.first_ifd()?;
+
+        let width = ifd.usize(&tiff, TAG_IMAGE_WIDTH)?.ok_or("no image width")?;
+        let height = ifd.usize(&tiff, TAG_IMAGE_LENGTH)?.ok_or("no image length")?;
+        let bits = ifd.usize(&tiff, TAG_BITS_PER_SAMPLE)?.unwrap_or(1);
+        let format = ifd.usize(&tiff, TAG_SAMPLE_FORMAT)?.unwrap_or(1);
+        let samples = ifd.usize(&tiff, TAG_SAMPLES_PER_PIXEL)?.unwrap_or(1);
+        let compression = ifd.usize(&tiff, TAG_COMPRESSION)?.unwrap_or(1);
+        let predictor = ifd.usize(&tiff, TAG_PREDICTOR)?.unwrap_or(1);
+        if samples != 1 {
+            return Err(format!("{} bands, a DEM has one", samples));
+        }
+        let sample = SampleType::new(format, bits).ok_or_else(|| format!("unsupported {}-bit sample format {}", bits, format))?;
+        if !matches!(compression, 1 | 8 | 32946) {
+            return Err(format!("unsupported compression {} (only none and Deflate)", compression));
+        }
+        if !(predictor == 1 || predictor == 2 && !sample.is_float()) {
+            return Err(format!("unsupported predictor {}", predictor));
+        }
+
+        // Georeferencing: pixel scale and one tie point, in degrees
+        if tiff.geo_key(&ifd, GEO_KEY_MODEL_TYPE)? == Some(MODEL_TYPE_PROJECTED) {
+            return Err("projected raster, reproject it to WGS84 longitude/latitude (EPSG:4326)".to_string());
+        }
+        let scale = ifd.f64s(&tiff, TAG_MODEL_PIXEL_SCALE)?.ok_or("no ModelPixelScale, not a GeoTIFF")?;
+        let tiepoint = ifd.f64s(&tiff, TAG_MODEL_TIEPOINT)?.ok_or("no ModelTiepoint, not a GeoTIFF")?;
+        if scale.len() < 2 || tiepoint.len() < 6 || scale[0] <= 0.0 || scale[1] <= 0.0 {
+            return Err("unsupported georeferencing".to_string());
+        }
+        let origin = (tiepoint[3] - tiepoint[0] * scale[0], tiepoint[4] + tiepoint[1] * scale[1]);
+        let nodata = ifd.ascii(&tiff, TAG_GDAL_NODATA)?.and_then(|text| text.trim().parse::<f32>().ok());
+
+        // Blocks: tiles, or strips as tiles of the full width
+        let (block_width, block_height, offsets, counts) = match ifd.usize(&tiff, TAG_TILE_WIDTH)? {
+            Some(tile_width) => (
+                tile_width,
+                ifd.usize(&tiff, TAG_TILE_LENGTH)?.ok_or("no tile length")?,
+                ifd.u64s(&tiff, TAG_TILE_OFFSETS)?.ok_or("no tile offsets")?,
+                ifd.u64s(&tiff, TAG_TILE_BYTE_COUNTS)?.ok_or("no tile byte counts")?,
+            ),
+            None => (
+                width,
+                ifd.usize(&tiff, TAG_ROWS_PER_STRIP)?.unwrap_or(height).min(height),
+                ifd.u64s(&tiff, TAG_STRIP_OFFSETS)?.ok_or("no strip offsets")?,
+                ifd.u64s(&tiff, TAG_STRIP_BYTE_COUNTS)?.ok_or("no strip byte counts")?,
+            ),
+        };
+        if block_width == 0 || block_height == 0 || offsets.len() != counts.len() {
+            return Err("bad block layout".to_string());
+        }
+        let blocks_across = width.div_ceil(block_width);
+        let blocks_down = height.div_ceil(block_height);
+        if offsets.len() < blocks_across * blocks_down {
+            return Err("fewer blocks than the image needs".to_string());
+        }
+
+        let bytes_per_sample = bits / 8;
+        let block_bytes = block_width * block_height * bytes_per_sample;
+        let mut values = vec![f32::NAN; width * height];
+        for (block, (&offset, &count)) in offsets.iter().zip(&counts).enumerate().take(blocks_across * blocks_down) {
+            let raw = tiff.slice(offset as usize, count as usize)?;
+            let mut data = match compression {
+                1 => raw.to_vec(),
+                _ => {
+                    let mut data = Vec::with_capacity(block_bytes);
+                    ZlibDecoder::new(raw).read_to_end(&mut data).map_err(|e| format!("Deflate: {}", e))?;
+                    data
+                }
+            };
+            // The last strip may be short
+            let rows = (data.len() / (block_width * bytes_per_sample)).min(block_height);
+            data.truncate(rows * block_width * bytes_per_sample);
+            let mut samples: Vec<f64> = data
+                .chunks_exact(bytes_per_sample)
+                .map(|chunk| sample.read(chunk, tiff.little_endian))
+                .collect();
+            if predictor == 2 {
+                for row in samples.chunks_mut(block_width) {
+                    for i in 1..row.len() {
+                        row[i] = sample.wrap(row[i] + row[i - 1]);
+                    }
+                }
+            }
+            let (bx, by) = (block % blocks_across * block_width, block / blocks_across * block_height);
+            for (r, row) in samples.chunks(block_width).enumerate() {
+                let y = by + r;
+                if y >= height {
+                    break;
+                }
+                for (c, &v) in row.iter().enumerate().take(width.saturating_sub(bx)) {
+                    values[y * width + bx + c] = v as f32;
+                }
+            }
+        }
+
+        Ok(Self { width, height, origin, scale: (scale[0], scale[1]), values, nodata, identity: None })
+    }
+
+    /// Value of pixel (`col`, `row`), `None` for no data
+    fn pixel(&self, col: usize, row: usize) -> Option<f64> {
+        let v = self.values[row * self.width + col];
+        (v.is_finite() && Some(v) != self.nodata).then_some(f64::from(v))
+    }
+}
+
+impl ElevationProvider for GeoTiffDem {
+    fn elevation(&self, lon: f64, lat: f64) -> Option<f64> {
+        // Continuous pixel position, with pixel centres at whole numbers
+        let x = (lon - self.origin.0) / self.scale.0 - 0.5;
+        let y = (self.origin.1 - lat) / self.scale.1 - 0.5;
+        if !(-0.5..=self.width as f64 - 0.5).contains(&x) || !(-0.5..=self.height as f64 - 0.5).contains(&y) {
+            return None;
+        }
+        let clamp = |v: f64, n: usize| v.clamp(0.0, (n - 1) as f64);
+        let (x, y) = (clamp(x, self.width), clamp(y, self.height));
+        let (c0, r0) = (x.floor() as usize, y.floor() as usize);
+        let (c1, r1) = ((c0 + 1).min(self.width - 1), (r0 + 1).min(self.height - 1));
+        let (fx, fy) = (x - c0 as f64, y - r0 as f64);
+        let top = self.pixel(c0, r0)? * (1.0 - fx) + self.pixel(c1, r0)? * fx;
+        let bottom = self.pixel(c0, r1)? * (1.0 - fx) + self.pixel(c1, r1)? * fx;
+        Some(top * (1.0 - fy) + bottom * fy)
+    }
+
+    fn identity(&self) -> Option<String> {
+        self.identity.clone()
+    }
+}
+
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_PREDICTOR: u16 = 317;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_TILE_BYTE_COUNTS: u16 = 325;
+const TAG_SAMPLE_FORMAT: u16 = 339;
+const TAG_MODEL_PIXEL_SCALE: u16 = 33550;
+const TAG_MODEL_TIEPOINT: u16 = 33922;
+const TAG_GEO_KEY_DIRECTORY: u16 = 34735;
+const TAG_GDAL_NODATA: u16 = 42113;
+
+const GEO_KEY_MODEL_TYPE: u16 = 1024;
+const MODEL_TYPE_PROJECTED: u16 = 1;
+
+/// Sample type of the raster (TIFF SampleFormat and BitsPerSample)
+#[derive(Debug, Clone, Copy)]
+enum SampleType {
+    Unsigned(usize),
+    Signed(usize),
+    Float(usize),
+}
+
+impl SampleType {
+    fn new(format: usize, bits: usize) -> Option<Self> {
+        match (format, bits) {
+            (1, 8 | 16 | 32 | 64) => Some(SampleType::Unsigned(bits)),
+            (2, 8 | 16 | 32 | 64) => Some(SampleType::Signed(bits)),
+            (3, 32 | 64) => Some(SampleType::Float(bits)),
+            _ => None,
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, SampleType::Float(_))
+    }
+
+    fn read(&self, b: &[u8], le: bool) -> f64 {
+        let mut buf = [0u8; 8];
+        let n = b.len();
+        if le {
+            buf[..n].copy_from_slice(b);
+        } else {
+            for (i, &byte) in b.iter().rev().enumerate() {
+                buf[i] = byte;
+            }
+        }
+        let raw = u64::from_le_bytes(buf);
+        match *self {
+            SampleType::Unsigned(_) => raw as f64,
+            SampleType::Signed(bits) => ((raw << (64 - bits)) as i64 >> (64 - bits)) as f64,
+            SampleType::Float(32) => f64::from(f32::from_bits(raw as u32)),
+            SampleType::Float(_) => f64::from_bits(raw),
+        }
+    }
+
+    /// Integer overflow of horizontal differencing (predictor 2)
+    fn wrap(&self, v: f64) -> f64 {
+        match *self {
+            SampleType::Unsigned(bits) if bits < 64 => v.rem_euclid((1u64 << bits) as f64),
+            SampleType::Signed(bits) if bits < 64 => {
+                let range = (1u64 << bits) as f64;
+                (v + range / 2.0).rem_euclid(range) - range / 2.0
+            }
+            _ => v,
+        }
+    }
+}
+
+/// TIFF file in memory
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+/// Directory entry: tag, field type, value count and the value bytes
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    /// Offset of the values (inline in the entry if they fit in 4 bytes)
+    offset: usize,
+}
+
+/// Image file directory
+struct Ifd {
+    entries: Vec<Entry>,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        let little_endian = match bytes.get(..4) {
+            Some(b"II*\0") => true,
+            Some(b"MM\0*") => false,
+            Some(b"II+\0") | Some(b"MM\0+") => return Err("BigTIFF is not supported".to_string()),
+            _ => return Err("not a TIFF file".to_string()),
+        };
+        Ok(Self { bytes, little_endian })
+    }
+
+    fn slice(&self, offset: usize, len: usize) -> Result<&'a [u8], String> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| "truncated TIFF".to_string())
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, String> {
+        let b: [u8; 2] = self.slice(offset, 2)?.try_into().unwrap();
+        Ok(if self.little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, String> {
+        let b: [u8; 4] = self.slice(offset, 4)?.try_into().unwrap();
+        Ok(if self.little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn first_ifd(&self) -> Result<Ifd, String> {
+        let start = self.u32(4)? as usize;
+        let count = self.u16(start)? as usize;
+        let entries = (0..count)
+            .map(|i| {
+                let at = start + 2 + i * 12;
+                let (tag, kind, count) = (self.u16(at)?, self.u16(at + 2)?, self.u32(at + 4)? as usize);
+                let size = count.saturating_mul(type_size(kind));
+                let offset = if size <= 4 { at + 8 } else { self.u32(at + 8)? as usize };
+                Ok(Entry { tag, kind, count, offset })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Ifd { entries })
+    }
+
+    /// Numeric values of an entry
+    fn values(&self, entry: &Entry) -> Result<Vec<f64>, String> {
+        let size = type_size(entry.kind);
+        let data = self.slice(entry.offset, entry.count.saturating_mul(size))?;
+        let reader = match entry.kind {
+            1 => SampleType::Unsigned(8),
+            3 => SampleType::Unsigned(16),
+            4 => SampleType::Unsigned(32),
+            11 => SampleType::Float(32),
+            12 => SampleType::Float(64),
+            kind => return Err(format!("unsupported field type {} of tag {}", kind, entry.tag)),
+        };
+        Ok(data.chunks_exact(size).map(|chunk| reader.read(chunk, self.little_endian)).collect())
+    }
+
+    /// Value of a GeoTIFF key stored in the GeoKeyDirectory itself
+    fn geo_key(&self, ifd: &Ifd, key: u16) -> Result<Option<u16>, String> {
+        let Some(directory) = ifd.u64s(self, TAG_GEO_KEY_DIRECTORY)? else { return Ok(None) };
+        Ok(directory
+            .get(4..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .find(|k| k[0] == u64::from(key) && k[1] == 0)
+            .map(|k| k[3] as u16))
+    }
+}
+
+impl Ifd {
+    fn entry(&self, tag: u16) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.tag == tag)
+    }
+
+    fn f64s(&self, tiff: &Tiff, tag: u16) -> Result<Option<Vec<f64>>, String> {
+        self.entry(tag).map(|e| tiff.values(e)).transpose()
+    }
+
+    fn u64s(&self, tiff: &Tiff, tag: u16) -> Result<Option<Vec<u64>>, String> {
+        Ok(self.f64s(tiff, tag)?.map(|v| v.into_iter().map(|x| x as u64).collect()))
+    }
+
+    fn usize(&self, tiff: &Tiff, tag: u16) -> Result<Option<usize>, String> {
+        Ok(self.f64s(tiff, tag)?.and_then(|v| v.first().map(|&x| x as usize)))
+    }
+
+    fn ascii(&self, tiff: &Tiff, tag: u16) -> Result<Option<String>, String> {
+        let Some(entry) = self.entry(tag).filter(|e| e.kind == 2) else { return Ok(None) };
+        let data = tiff.slice(entry.offset, entry.count)?;
+        Ok(Some(String::from_utf8_lossy(data).trim_end_matches('\0').to_string()))
+    }
+}
+
+/// Bytes per value of a TIFF field type
+fn type_size(kind: u16) -> usize {
+    match kind {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 | 16 | 17 | 18 => 8,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeoTiffDem;
+    use crate::elevation::ElevationProvider;
+
+    #[test]
+    fn geotiff_elevations_are_interpolated() {
+        // 2 x 2 float raster of 0.01° pixels from 18°E 59.02°N
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend(8u32.to_le_bytes());
+        let entries: [(u16, u16, u32, u32); 10] = [
+            (256, 3, 1, 2),     // ImageWidth
+            (257, 3, 1, 2),     // ImageLength
+            (258, 3, 1, 32),    // BitsPerSample
+            (259, 3, 1, 1),     // Compression: none
+            (273, 4, 1, 200),   // StripOffsets
+            (278, 3, 1, 2),     // RowsPerStrip
+            (279, 4, 1, 16),    // StripByteCounts
+            (339, 3, 1, 3),     // SampleFormat: float
+            (33550, 12, 3, 216), // ModelPixelScale
+            (33922, 12, 6, 240), // ModelTiepoint
+        ];
+        tiff.extend((entries.len() as u16).to_le_bytes());
+        for (tag, kind, count, value) in entries {
+            tiff.extend(tag.to_le_bytes());
+            tiff.extend(kind.to_le_bytes());
+            tiff.extend(count.to_le_bytes());
+            tiff.extend(value.to_le_bytes());
+        }
+        tiff.extend(0u32.to_le_bytes());
+        tiff.resize(200, 0);
+        for v in [10.0f32, 20.0, 30.0, 40.0] {
+            tiff.extend(v.to_le_bytes());
+        }
+        for v in [0.01f64, 0.01, 0.0, 0.0, 0.0, 0.0, 18.0, 59.02, 0.0] {
+            tiff.extend(v.to_le_bytes());
+        }
+
+        let dem = GeoTiffDem::from_bytes(&tiff).unwrap();
+        let ele = |lon, lat| dem.elevation(lon, lat).map(|e| (e * 100.0).round() / 100.0);
+        assert_eq!(ele(18.005, 59.015), Some(10.0));
+        assert_eq!(ele(18.01, 59.01), Some(25.0));
+        assert_eq!(ele(17.0, 59.01), None);
+        assert_eq!(dem.identity(), None);
+
+        let path = std::env::temp_dir().join("nvdb2osm-geotiff-test.tif");
+        std::fs::write(&path, &tiff).unwrap();
+        let path = path.to_str().unwrap();
+        let identity = GeoTiffDem::open(path).unwrap().identity().unwrap();
+        assert!(identity.starts_with(&format!("{} ({} bytes, modified ", path, tiff.len())));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! Elevation providers
+//!
+//! NVDB geometries are mostly 2D. An `ElevationProvider` gives the terrain
+//! height at a coordinate, from which the pipeline fills in the elevation of
+//! segment ends without Z values (for `tag_mapper::incline`) and tags the
+//! feature nodes with `ele`. `GeoTiffDem` reads a digital elevation model
+//! from a GeoTIFF; other sources implement the trait.
+
+use rayon::prelude::*;
+
+use crate::models::{NodeFeature, Segment};
+
+pub mod geotiff;
+
+pub use geotiff::GeoTiffDem;
+
+/// Terrain height in metres at a WGS84 coordinate
+pub trait ElevationProvider: std::fmt::Debug + Send + Sync {
+    /// Height at `lon`/`lat`, `None` outside the covered area or where the
+    /// source has no data
+    fn elevation(&self, lon: f64, lat: f64) -> Option<f64>;
+
+    /// Path, size and modification time of the source file, which
+    /// `Pipeline::fingerprint()` hashes so that a checkpoint is not resumed
+    /// with another DEM; `None` for sources not read from a file
+    fn identity(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Give the segments without elevations (2D input) the elevation of their
+/// ends from `provider`
+pub fn sample_segment_ends(segments: &mut [Segment], provider: &dyn ElevationProvider) {
+    segments.par_iter_mut().filter(|segment| segment.elevations.is_empty()).for_each(|segment| {
+        let ends = [(segment.start_node, *segment.start_coord()), (segment.end_node, *segment.end_coord())];
+        segment.elevations = ends
+            .into_iter()
+            .filter_map(|(hash, c)| provider.elevation(c.x, c.y).map(|ele| (hash, ele)))
+            .collect();
+    });
+}
+
+/// Tag the feature nodes with their `ele`, in metres to one decimal; an
+/// `ele` already set is kept
+pub fn tag_node_elevations(nodes: &mut [NodeFeature], provider: &dyn ElevationProvider) {
+    nodes.par_iter_mut().for_each(|node| {
+        if node.tags.contains_key("ele") {
+            return;
+        }
+        if let Some(ele) = provider.elevation(node.lon, node.lat) {
+            node.tags.insert("ele".to_string(), format!("{:.1}", ele));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ElevationProvider, sample_segment_ends};
+    use crate::test_support::segment;
+
+    #[test]
+    fn sample_segment_ends_reads_terrain_height() {
+        /// Terrain rising 1 m per 0.001° northwards
+        #[derive(Debug)]
+        struct Slope;
+        impl ElevationProvider for Slope {
+            fn elevation(&self, _lon: f64, lat: f64) -> Option<f64> {
+                Some((lat - 59.0) * 1000.0)
+            }
+        }
+
+        let mut segments = vec![segment(vec![(18.0, 59.0), (18.0, 59.002)])];
+        sample_segment_ends(&mut segments, &Slope);
+        assert_eq!(segments[0].elevations[0], (segments[0].start_node, 0.0));
+        assert!((segments[0].elevations[1].1 - 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,610 @@
+use geo::line_intersection::{line_intersection, LineIntersection};
+use geo::{BoundingRect, Contains, Intersects};
+use geo_types::{Coord, Line, LineString, MultiPolygon, Polygon};
+use rayon::prelude::*;
+use rstar::{RTree, AABB};
+use crate::models::Segment;
+
+/// Compute bearing between two coordinates (0-360 degrees)
+/// 
+/// Bearing is the direction from `from` to `to` in degrees,
+/// where 0 = North, 90 = East, 180 = South, 270 = West
+pub fn compute_bearing(from: &Coord, to: &Coord) -> f64 {
+    let lat1 = from.y.to_radians();
+    let lat2 = to.y.to_radians();
+    let dlon = (to.x - from.x).to_radians();
+    
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    
+    let bearing = y.atan2(x).to_degrees();
+    (bearing + 360.0) % 360.0
+}
+
+/// Compute junction angle between two segments
+/// 
+/// Returns the angle difference in degrees (-180 to 180).
+/// Positive = left turn, Negative = right turn
+///
+/// The bearings are taken from the vertices next to the junction before
+/// Douglas-Peucker where recorded (`Segment::end_neighbours`), so that a
+/// short stub left by the simplification does not turn the angle.
+/// 
+/// # Arguments
+/// * `seg1` - First segment
+/// * `seg2` - Second segment
+/// * `connection_type` - How segments connect (start-to-end, etc.)
+pub fn compute_junction_angle(seg1: &Segment, seg2: &Segment) -> f64 {
+    // Determine how segments connect
+    let (bearing1, bearing2) = if seg1.end_node == seg2.start_node {
+        // seg1 -> seg2 (normal forward connection)
+        let b1 = compute_bearing(
+            seg1.penultimate_coord(),
+            seg1.end_coord()
+        );
+        let b2 = compute_bearing(
+            seg2.start_coord(),
+            seg2.second_coord()
+        );
+        (b1, b2)
+    } else if seg1.start_node == seg2.end_node {
+        // seg1 <- seg2 (reverse connection)
+        let b1 = compute_bearing(
+            seg1.second_coord(),
+            seg1.start_coord()
+        );
+        let b2 = compute_bearing(
+            seg2.end_coord(),
+            seg2.penultimate_coord()
+        );
+        (b1, b2)
+    } else if seg1.start_node == seg2.start_node {
+        // seg1 starts at same point as seg2
+        let b1 = compute_bearing(
+            seg1.second_coord(),
+            seg1.start_coord()
+        );
+        let b2 = compute_bearing(
+            seg2.start_coord(),
+            seg2.second_coord()
+        );
+        (b1, b2)
+    } else {
+        // seg1.end_node == seg2.end_node
+        let b1 = compute_bearing(
+            seg1.penultimate_coord(),
+            seg1.end_coord()
+        );
+        let b2 = compute_bearing(
+            seg2.end_coord(),
+            seg2.penultimate_coord()
+        );
+        (b1, b2)
+    };
+    
+    let mut delta = bearing2 - bearing1;
+    delta = (delta + 360.0) % 360.0;
+    if delta > 180.0 {
+        delta -= 360.0;
+    }
+    delta
+}
+
+/// Douglas-Peucker polygon simplification
+/// 
+/// Removes points that are within `epsilon` meters of the line
+/// connecting their neighbors.
+pub fn simplify_polygon(coords: &[Coord], epsilon: f64) -> Vec<Coord> {
+    if coords.len() <= 2 {
+        return coords.to_vec();
+    }
+    
+    // Find point with maximum distance from line between first and last
+    let first = &coords[0];
+    let last = &coords[coords.len() - 1];
+    
+    let mut max_dist = 0.0;
+    let mut max_idx = 0;
+    
+    for (i, point) in coords.iter().enumerate().skip(1).take(coords.len() - 2) {
+        let dist = point_to_line_distance(first, last, point);
+        if dist > max_dist {
+            max_dist = dist;
+            max_idx = i;
+        }
+    }
+    
+    // If max distance is >= epsilon, recursively simplify (matches Python)
+    if max_dist >= epsilon {
+        let left = simplify_polygon(&coords[..=max_idx], epsilon);
+        let right = simplify_polygon(&coords[max_idx..], epsilon);
+        
+        let mut result = left;
+        result.pop(); // Remove duplicate point
+        result.extend(right);
+        result
+    } else {
+        vec![*first, *last]
+    }
+}
+
+/// Compute distance from point p3 to line segment [s1, s2]
+/// 
+/// Uses simplified reprojection for short distances
+fn point_to_line_distance(s1: &Coord, s2: &Coord, p3: &Coord) -> f64 {
+    // Convert to radians
+    let x1 = s1.x.to_radians();
+    let y1 = s1.y.to_radians();
+    let x2 = s2.x.to_radians();
+    let y2 = s2.y.to_radians();
+    let x3 = p3.x.to_radians();
+    let y3 = p3.y.to_radians();
+    
+    // Simplified reprojection of latitude
+    let x1 = x1 * y1.cos();
+    let x2 = x2 * y2.cos();
+    let x3 = x3 * y3.cos();
+    
+    let a = x3 - x1;
+    let b = y3 - y1;
+    let c = x2 - x1;
+    let d = y2 - y1;
+    
+    let dot = a * c + b * d;
+    let len_sq = c * c + d * d;
+    
+    let param = if len_sq != 0.0 { dot / len_sq } else { -1.0 };
+    
+    let (xx, yy) = if param < 0.0 {
+        (x1, y1)
+    } else if param > 1.0 {
+        (x2, y2)
+    } else {
+        (x1 + param * c, y1 + param * d)
+    };
+    
+    let dx = x3 - xx;
+    let dy = y3 - yy;
+    
+    // Convert back to meters (approximate)
+    (dx * dx + dy * dy).sqrt() * 6_371_000.0 // Earth's radius in meters
+}
+
+
+
+/// Clip a line to a boundary polygon, splitting it where it crosses the
+/// boundary
+///
+/// Returns the parts inside the boundary (or on it), each starting and
+/// ending at an original vertex or at a new vertex at the crossing point.
+pub fn clip_line(line: &LineString<f64>, boundary: &MultiPolygon<f64>) -> Vec<LineString<f64>> {
+    let (Some(line_box), Some(boundary_box)) = (line.bounding_rect(), boundary.bounding_rect()) else {
+        return Vec::new();
+    };
+    if !line_box.intersects(&boundary_box) {
+        return Vec::new();
+    }
+    let edges: Vec<Line<f64>> = boundary
+        .iter()
+        .flat_map(|p| std::iter::once(p.exterior()).chain(p.interiors()))
+        .flat_map(|ring| ring.lines())
+        .filter(|edge| edge.bounding_rect().intersects(&line_box))
+        .collect();
+    if edges.is_empty() {
+        return if boundary.contains(&line.0[0]) { vec![line.clone()] } else { Vec::new() };
+    }
+
+    let mut parts = Vec::new();
+    let mut current: Vec<Coord> = Vec::new();
+    for seg in line.lines() {
+        // Positions along the line segment where it meets the boundary
+        let mut cuts = vec![0.0, 1.0];
+        for edge in &edges {
+            match line_intersection(seg, *edge) {
+                Some(LineIntersection::SinglePoint { intersection, .. }) => {
+                    cuts.push(position_on(&seg, intersection));
+                }
+                Some(LineIntersection::Collinear { intersection }) => {
+                    cuts.push(position_on(&seg, intersection.start));
+                    cuts.push(position_on(&seg, intersection.end));
+                }
+                None => {}
+            }
+        }
+        cuts.sort_by(|a, b| a.total_cmp(b));
+        cuts.dedup_by(|a, b| (*a - *b).abs() < 1e-12);
+
+        for pair in cuts.windows(2) {
+            let a = point_at(&seg, pair[0]);
+            let b = point_at(&seg, pair[1]);
+            let mid = Coord { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0 };
+            if boundary.intersects(&mid) {
+                if current.last() != Some(&a) {
+                    current.push(a);
+                }
+                current.push(b);
+            } else if current.len() >= 2 {
+                parts.push(LineString::new(std::mem::take(&mut current)));
+            } else {
+                current.clear();
+            }
+        }
+    }
+    if current.len() >= 2 {
+        parts.push(LineString::new(current));
+    }
+    parts
+}
+
+/// Clip segments to a boundary polygon (see `clip_line()`)
+///
+/// Segments inside are kept as they are and segments outside dropped; the
+/// parts of crossing segments become segments of their own with the
+/// attributes of the original, ending at new nodes on the boundary.
+pub fn clip_segments(segments: Vec<Segment>, boundary: &MultiPolygon<f64>) -> Vec<Segment> {
+    segments
+        .into_par_iter()
+        .flat_map_iter(|seg| {
+            let parts = clip_line(&seg.geometry, boundary);
+            if parts.len() == 1 && parts[0] == seg.geometry {
+                return vec![seg];
+            }
+            parts
+                .into_iter()
+                .map(|part| {
+                    let mut piece = Segment::new(String::new(), part);
+                    if piece.start_node == seg.start_node {
+                        piece.global_start_node_id = seg.global_start_node_id;
+                        piece.global_start_owned = seg.global_start_owned;
+                    }
+                    if piece.end_node == seg.end_node {
+                        piece.global_end_node_id = seg.global_end_node_id;
+                        piece.global_end_owned = seg.global_end_owned;
+                    }
+                    piece.tags = seg.tags.clone();
+                    piece.properties = seg.properties.clone();
+                    piece.oneway_direction = seg.oneway_direction;
+                    piece.source_row = seg.source_row;
+                    piece.annotations = seg.annotations.clone();
+                    piece.elevations = seg.elevations.clone();
+                    piece
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Read a clipping boundary from GeoJSON: a Polygon or MultiPolygon
+/// geometry, or a Feature or FeatureCollection of them (all polygons are
+/// combined)
+pub fn boundary_from_geojson(text: &str) -> Result<MultiPolygon<f64>, String> {
+    let value: serde_json::Value = serde_json::from_str(text)
+        .map_err(|e| format!("Invalid GeoJSON boundary: {}", e))?;
+    let mut polygons = Vec::new();
+    collect_geojson_polygons(&value, &mut polygons)?;
+    if polygons.is_empty() {
+        return Err("GeoJSON boundary contains no polygon".to_string());
+    }
+    Ok(MultiPolygon::new(polygons))
+}
+
+fn collect_geojson_polygons(value: &serde_json::Value, out: &mut Vec<Polygon<f64>>) -> Result<(), String> {
+    let ring = |value: &serde_json::Value| -> Result<LineString<f64>, String> {
+        let coords = value.as_array().ok_or("GeoJSON ring is not an array")?;
+        coords
+            .iter()
+            .map(|c| match (c.get(0).and_then(|v| v.as_f64()), c.get(1).and_then(|v| v.as_f64())) {
+                (Some(x), Some(y)) => Ok(Coord { x, y }),
+                _ => Err("GeoJSON position is not a coordinate pair".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(LineString::new)
+    };
+    let polygon = |value: &serde_json::Value| -> Result<Polygon<f64>, String> {
+        let rings = value.as_array().ok_or("GeoJSON polygon is not an array")?;
+        let mut rings = rings.iter().map(ring).collect::<Result<Vec<_>, _>>()?.into_iter();
+        let exterior = rings.next().ok_or("GeoJSON polygon has no rings")?;
+        Ok(Polygon::new(exterior, rings.collect()))
+    };
+    let coordinates = || value.get("coordinates").ok_or("GeoJSON geometry has no coordinates");
+    match value.get("type").and_then(|t| t.as_str()) {
+        Some("Polygon") => out.push(polygon(coordinates()?)?),
+        Some("MultiPolygon") => {
+            for p in coordinates()?.as_array().ok_or("GeoJSON multipolygon is not an array")? {
+                out.push(polygon(p)?);
+            }
+        }
+        Some("Feature") => {
+            if let Some(geometry) = value.get("geometry") {
+                collect_geojson_polygons(geometry, out)?;
+            }
+        }
+        Some("FeatureCollection") => {
+            for feature in value.get("features").and_then(|f| f.as_array()).into_iter().flatten() {
+                collect_geojson_polygons(feature, out)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Read railway lines from GeoJSON: LineString or MultiLineString
+/// geometries, or Features and FeatureCollections of them
+pub fn lines_from_geojson(text: &str) -> Result<Vec<LineString<f64>>, String> {
+    let value: serde_json::Value = serde_json::from_str(text)
+        .map_err(|e| format!("Invalid GeoJSON lines: {}", e))?;
+    let mut lines = Vec::new();
+    collect_geojson_lines(&value, &mut lines)?;
+    Ok(lines)
+}
+
+fn collect_geojson_lines(value: &serde_json::Value, out: &mut Vec<LineString<f64>>) -> Result<(), String> {
+    let line = |value: &serde_json::Value| -> Result<LineString<f64>, String> {
+        let coords = value.as_array().ok_or("GeoJSON line is not an array")?;
+        coords
+            .iter()
+            .map(|c| match (c.get(0).and_then(|v| v.as_f64()), c.get(1).and_then(|v| v.as_f64())) {
+                (Some(x), Some(y)) => Ok(Coord { x, y }),
+                _ => Err("GeoJSON position is not a coordinate pair".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(LineString::new)
+    };
+    let coordinates = || value.get("coordinates").ok_or("GeoJSON geometry has no coordinates");
+    match value.get("type").and_then(|t| t.as_str()) {
+        Some("LineString") => out.push(line(coordinates()?)?),
+        Some("MultiLineString") => {
+            for l in coordinates()?.as_array().ok_or("GeoJSON multilinestring is not an array")? {
+                out.push(line(l)?);
+            }
+        }
+        Some("Feature") => {
+            if let Some(geometry) = value.get("geometry") {
+                collect_geojson_lines(geometry, out)?;
+            }
+        }
+        Some("FeatureCollection") => {
+            for feature in value.get("features").and_then(|f| f.as_array()).into_iter().flatten() {
+                collect_geojson_lines(feature, out)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Railway lines in an R-tree, for finding where roads cross them
+#[derive(Debug, Clone)]
+pub struct RailIndex {
+    tree: RTree<rstar::primitives::Line<[f64; 2]>>,
+}
+
+impl RailIndex {
+    pub fn new(lines: &[LineString<f64>]) -> Self {
+        let edges = lines
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(|edge| rstar::primitives::Line::new([edge.start.x, edge.start.y], [edge.end.x, edge.end.y]))
+            .collect();
+        Self { tree: RTree::bulk_load(edges) }
+    }
+
+    /// First point along `line` where it crosses a railway, with the index
+    /// of the line segment it lies on
+    ///
+    /// The point is rounded to the 7 decimals of the input coordinates.
+    /// Stretches where a road runs along a railway are not crossings.
+    pub fn first_crossing(&self, line: &LineString<f64>) -> Option<(usize, Coord)> {
+        for (i, seg) in line.lines().enumerate() {
+            let envelope = AABB::from_corners([seg.start.x, seg.start.y], [seg.end.x, seg.end.y]);
+            let mut first: Option<f64> = None;
+            for rail in self.tree.locate_in_envelope_intersecting(&envelope) {
+                let rail = Line::new(Coord { x: rail.from[0], y: rail.from[1] }, Coord { x: rail.to[0], y: rail.to[1] });
+                if let Some(LineIntersection::SinglePoint { intersection, .. }) = line_intersection(seg, rail) {
+                    let t = position_on(&seg, intersection);
+                    if first.is_none_or(|f| t < f) {
+                        first = Some(t);
+                    }
+                }
+            }
+            if let Some(t) = first {
+                return Some((i, point_at(&seg, t)));
+            }
+        }
+        None
+    }
+}
+
+/// Metres per degree of latitude (and of longitude at the equator)
+pub(crate) const METRES_PER_DEGREE: f64 = 111_320.0;
+
+/// Parallel of `line` at `distance` metres to its left (negative: right),
+/// such as a noise barrier beside the carriageway
+///
+/// Vertices move along the bisector of their two edges, as far as needed to
+/// keep both edges at `distance` but at most twice that at sharp turns.
+/// Coordinates are rounded to 7 decimals; a line with fewer than two
+/// distinct vertices is returned unchanged.
+pub fn offset_line(line: &LineString<f64>, distance: f64) -> LineString<f64> {
+    let coords = &line.0;
+    if coords.len() < 2 {
+        return line.clone();
+    }
+    // Local equirectangular projection in metres around the first vertex
+    let origin = coords[0];
+    let x_scale = METRES_PER_DEGREE * origin.y.to_radians().cos();
+    let local: Vec<Coord> = coords
+        .iter()
+        .map(|c| Coord { x: (c.x - origin.x) * x_scale, y: (c.y - origin.y) * METRES_PER_DEGREE })
+        .collect();
+    // Unit normal to the left of each edge; zero-length edges take their neighbour's
+    let mut normals: Vec<Option<Coord>> = local
+        .windows(2)
+        .map(|w| {
+            let (dx, dy) = (w[1].x - w[0].x, w[1].y - w[0].y);
+            let len = dx.hypot(dy);
+            (len > 0.0).then(|| Coord { x: -dy / len, y: dx / len })
+        })
+        .collect();
+    for i in 1..normals.len() {
+        if normals[i].is_none() {
+            normals[i] = normals[i - 1];
+        }
+    }
+    for i in (0..normals.len().saturating_sub(1)).rev() {
+        if normals[i].is_none() {
+            normals[i] = normals[i + 1];
+        }
+    }
+    let normals: Vec<Coord> = match normals.into_iter().collect::<Option<Vec<_>>>() {
+        Some(normals) => normals,
+        None => return line.clone(),
+    };
+    let round = |v: f64| (v * 10_000_000.0).round() / 10_000_000.0;
+    local
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let before = normals[i.saturating_sub(1)];
+            let after = normals[i.min(normals.len() - 1)];
+            let (bx, by) = (before.x + after.x, before.y + after.y);
+            let len = bx.hypot(by);
+            let shift = if len < 1e-9 {
+                // Reversal: keep the normal of the incoming edge
+                Coord { x: before.x * distance, y: before.y * distance }
+            } else {
+                let (ux, uy) = (bx / len, by / len);
+                let cos_half = (ux * after.x + uy * after.y).max(0.5);
+                Coord { x: ux * distance / cos_half, y: uy * distance / cos_half }
+            };
+            Coord {
+                x: round(origin.x + (p.x + shift.x) / x_scale),
+                y: round(origin.y + (p.y + shift.y) / METRES_PER_DEGREE),
+            }
+        })
+        .collect()
+}
+
+/// Point at `fraction` (0 to 1) of the length of `line`, measured in
+/// metres rather than degrees, rounded to 7 decimals (linear referencing)
+pub fn point_along(line: &LineString<f64>, fraction: f64) -> Option<Coord> {
+    let first = *line.0.first()?;
+    let x_scale = first.y.to_radians().cos();
+    let lengths: Vec<f64> = line
+        .lines()
+        .map(|edge| ((edge.dx() * x_scale).powi(2) + edge.dy().powi(2)).sqrt())
+        .collect();
+    let mut remaining = fraction.clamp(0.0, 1.0) * lengths.iter().sum::<f64>();
+    for (edge, length) in line.lines().zip(&lengths) {
+        if remaining <= *length && *length > 0.0 {
+            return Some(point_at(&edge, remaining / length));
+        }
+        remaining -= length;
+    }
+    line.0.last().copied()
+}
+
+/// Fraction of the way from `seg.start` to `seg.end` at which `c` lies
+fn position_on(seg: &Line<f64>, c: Coord) -> f64 {
+    let d = seg.delta();
+    let t = if d.x.abs() >= d.y.abs() {
+        (c.x - seg.start.x) / d.x
+    } else {
+        (c.y - seg.start.y) / d.y
+    };
+    if t.is_finite() { t.clamp(0.0, 1.0) } else { 0.0 }
+}
+
+/// Point at fraction `t` of `seg`; new points are rounded to the 7 decimals
+/// of the input coordinates, original vertices are returned unchanged
+fn point_at(seg: &Line<f64>, t: f64) -> Coord {
+    if t <= 0.0 {
+        return seg.start;
+    }
+    if t >= 1.0 {
+        return seg.end;
+    }
+    let round = |v: f64| (v * 10_000_000.0).round() / 10_000_000.0;
+    Coord {
+        x: round(seg.start.x + t * seg.dx()),
+        y: round(seg.start.y + t * seg.dy()),
+    }
+}
+
+/// Encode a 2D LineString as little-endian WKB
+pub fn linestring_to_wkb(line: &LineString<f64>) -> Vec<u8> {
+    let mut wkb = Vec::with_capacity(9 + line.0.len() * 16);
+    wkb.push(1u8);
+    wkb.extend_from_slice(&2u32.to_le_bytes());
+    wkb.extend_from_slice(&(line.0.len() as u32).to_le_bytes());
+    for c in &line.0 {
+        wkb.extend_from_slice(&c.x.to_le_bytes());
+        wkb.extend_from_slice(&c.y.to_le_bytes());
+    }
+    wkb
+}
+
+/// Encode a 2D Point as little-endian WKB
+pub fn point_to_wkb(x: f64, y: f64) -> Vec<u8> {
+    let mut wkb = Vec::with_capacity(21);
+    wkb.push(1u8);
+    wkb.extend_from_slice(&1u32.to_le_bytes());
+    wkb.extend_from_slice(&x.to_le_bytes());
+    wkb.extend_from_slice(&y.to_le_bytes());
+    wkb
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{Coord, MultiPolygon, line_string, polygon};
+
+    use super::{RailIndex, clip_line, compute_junction_angle, offset_line, point_along};
+    use crate::test_support::segment;
+
+    #[test]
+    fn clip_line_keeps_the_inside_part() {
+        let square = MultiPolygon::new(vec![polygon![
+            (x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0), (x: 0.0, y: 0.0),
+        ]]);
+        let parts = clip_line(&line_string![(x: -1.0, y: 0.5), (x: 0.5, y: 0.5)], &square);
+        assert_eq!(parts, vec![line_string![(x: 0.0, y: 0.5), (x: 0.5, y: 0.5)]]);
+    }
+
+    #[test]
+    fn first_crossing_finds_the_segment_and_point() {
+        let rails = RailIndex::new(&[line_string![(x: 18.0015, y: 58.99), (x: 18.0015, y: 59.01)]]);
+        let road = line_string![(x: 18.0, y: 59.0), (x: 18.001, y: 59.0), (x: 18.002, y: 59.0)];
+        assert_eq!(rails.first_crossing(&road), Some((1, Coord { x: 18.0015, y: 59.0 })));
+        assert_eq!(rails.first_crossing(&line_string![(x: 18.0, y: 59.0), (x: 18.001, y: 59.0)]), None);
+    }
+
+    #[test]
+    fn offset_line_moves_left_for_positive_distances() {
+        let road = line_string![(x: 18.0, y: 59.0), (x: 18.01, y: 59.0)];
+        let left = offset_line(&road, 10.0);
+        assert_eq!(left.0[0].x, 18.0);
+        assert!((left.0[0].y - 59.0 - 10.0 / 111_320.0).abs() < 1e-7);
+        assert!(offset_line(&road, -10.0).0[1].y < 59.0);
+    }
+
+    #[test]
+    fn point_along_measures_in_metres() {
+        let road = line_string![(x: 18.0, y: 59.0), (x: 18.01, y: 59.0), (x: 18.01, y: 59.01)];
+        assert_eq!(point_along(&road, 0.0), Some(Coord { x: 18.0, y: 59.0 }));
+        // The north-south leg is the longer one in metres
+        assert!(point_along(&road, 0.5).unwrap().y > 59.0);
+    }
+
+    #[test]
+    fn junction_angle_follows_segment_geometry() {
+        let east = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+        // Simplified to a straight line to the north-east
+        let mut next = segment(vec![(18.001, 59.0), (18.002, 59.001)]);
+        assert!(compute_junction_angle(&east, &next).abs() > 45.0);
+
+        // Before the simplification it left the junction heading east
+        next.end_neighbours = Some((Coord { x: 18.0015, y: 59.0 }, Coord { x: 18.0015, y: 59.0 }));
+        assert!(compute_junction_angle(&east, &next).abs() < 1.0);
+    }
+}
@@ -0,0 +1,107 @@
+use rustc_hash::FxHashMap;
+use crate::models::{Segment, SimplifyMethod, UnnamedGroups};
+use crate::schema::Field;
+
+/// Group segments for simplification
+/// 
+/// EXACT port of Python's grouping logic (lines 1769-1793)
+pub fn group_segments(
+    segments: &[Segment],
+    method: SimplifyMethod,
+    unnamed: UnnamedGroups,
+) -> FxHashMap<String, Vec<usize>> {
+    let mut groups: FxHashMap<String, Vec<usize>> = FxHashMap::default();
+    
+    for (idx, segment) in segments.iter().enumerate() {
+        let mut group_id = match method {
+            SimplifyMethod::Route => group_by_route(segment),
+            // Python: refname and recursive use same grouping (line 1778-1788)
+            SimplifyMethod::Refname | SimplifyMethod::Recursive | SimplifyMethod::Linear => {
+                group_by_refname(segment)
+            }
+            SimplifyMethod::Municipality => group_by_municipality(segment),
+            SimplifyMethod::Rlid => group_by_rlid(segment),
+            SimplifyMethod::Segment => String::new(), // All in one group
+        };
+        if method != SimplifyMethod::Route && method != SimplifyMethod::Segment {
+            if let Some(key) = unnamed_key(segment, unnamed) {
+                group_id.push('|');
+                group_id.push_str(&key);
+            }
+        }
+
+        groups.entry(group_id).or_default().push(idx);
+    }
+    
+    groups
+}
+
+/// Group by ROUTE_ID property
+/// Port of Python line 1773-1776
+fn group_by_route(segment: &Segment) -> String {
+    segment.prop(Field::RouteId)
+        .map(|v| v.as_string())
+        .unwrap_or_default()
+}
+
+/// Group by municipality code, then ref + name + highway: ways end at
+/// municipality borders
+fn group_by_municipality(segment: &Segment) -> String {
+    let municipality = segment.prop(Field::Municipality).map(|v| v.as_string()).unwrap_or_default();
+    format!("{}|{}", municipality, group_by_refname(segment))
+}
+
+/// Group by NVDB link identity (RLID, the ROUTE_ID of the delivery), then
+/// ref + name + highway: ways never leave one NVDB link
+fn group_by_rlid(segment: &Segment) -> String {
+    format!("{}|{}", group_by_route(segment), group_by_refname(segment))
+}
+
+/// Size of the grid cells of `UnnamedGroups::Cell` (degrees)
+const CELL_DEGREES: f64 = 0.01;
+
+/// Extra group key of a segment without `ref` and `name`
+fn unnamed_key(segment: &Segment, unnamed: UnnamedGroups) -> Option<String> {
+    if segment.tags.contains_key("ref") || segment.tags.contains_key("name") || segment.prop(Field::GrantRoadNumber).is_some() {
+        return None;
+    }
+    match unnamed {
+        UnnamedGroups::Class => None,
+        UnnamedGroups::Rlid => Some(group_by_route(segment)),
+        UnnamedGroups::Cell => {
+            let start = segment.start_coord();
+            Some(format!("{}:{}", (start.x / CELL_DEGREES).floor(), (start.y / CELL_DEGREES).floor()))
+        }
+    }
+}
+
+/// Group by ref + name + highway
+/// 
+/// EXACT port of Python lines 1778-1788:
+/// - ref or Vagnr_10370 (road number for countryside)
+/// - name
+/// - highway
+fn group_by_refname(segment: &Segment) -> String {
+    let mut group_id = String::new();
+    
+    // ref or Vagnr_10370 (line 1779-1784)
+    if let Some(ref_val) = segment.tags.get("ref") {
+        group_id.push_str(ref_val);
+    } else if let Some(vagnr) = segment.prop(Field::GrantRoadNumber) {
+        group_id.push_str(&vagnr.as_string());
+    }
+    
+    // name (line 1785-1786)
+    if let Some(name) = segment.tags.get("name") {
+        group_id.push_str(name);
+    }
+    
+    // highway (line 1787-1788)
+    if let Some(highway) = segment.tags.get("highway") {
+        group_id.push_str(highway);
+    }
+    
+    group_id
+}
+
+
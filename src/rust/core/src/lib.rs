@@ -0,0 +1,59 @@
+//! NVDB to OpenStreetMap conversion engine
+//!
+//! Rust port of the NVDB (Swedish national road database) to OSM conversion:
+//! segments are parsed from WKB (`wkb`), WKT (`wkt`) or Arrow and GeoParquet
+//! files (`arrow_input`), their attributes normalized to the fields of
+//! `schema` (translating other countries' databases via `profile`), tagged
+//! (`tag_mapper`), merged into ways (`topology`) and written as PBF or OPL
+//! (`writer`). The R package nvdb2osmr is a thin binding over this crate.
+//!
+//! `Pipeline` chains all stages:
+//!
+//! ```no_run
+//! use nvdb2osm_core::{wkb, ConversionStats, Pipeline};
+//! use rustc_hash::FxHashMap;
+//!
+//! # let blobs: Vec<Vec<u8>> = Vec::new();
+//! let segments = blobs
+//!     .iter()
+//!     .filter_map(|blob| wkb::segment_from_wkb(blob, FxHashMap::default()).ok())
+//!     .collect();
+//!
+//! let mut stats = ConversionStats::default();
+//! Pipeline::new().run(segments, "out.osm.pbf", &mut stats).unwrap();
+//! println!("{} ways in {:.1}s", stats.ways_out, stats.total_secs());
+//! ```
+
+pub mod models;
+pub mod schema;
+pub mod geometry;
+pub mod elevation;
+mod grouping;
+pub mod tag_mapper;
+pub mod topology;
+pub mod stats;
+pub mod writer;
+pub mod wkb;
+pub mod wkt;
+pub mod arrow_input;
+pub mod profile;
+pub mod compare;
+pub mod coverage;
+pub mod tag_stats;
+pub mod routing;
+pub mod synthetic;
+pub mod checkpoint;
+mod pipeline;
+#[cfg(test)]
+mod test_support;
+
+pub use checkpoint::Checkpoint;
+pub use models::{Annotation, AnnotationKind, NodeFeature, NullTokens, PropertyValue, RelationFeature, Segment, SimplifyMethod, UnnamedGroups, Way};
+pub use pipeline::{Conversion, Pipeline, INTERRUPTED};
+pub use profile::Profile;
+pub use routing::RoutingPreset;
+pub use schema::{Field, Schema};
+pub use stats::{AnnotationRecord, ConversionStats, PhaseTimer};
+pub use tag_mapper::{access::AccessDefaults, dates::DateTag, lay_bys::LayBys, node_directions::NodeDirections, noise_barriers::NoiseBarriers, non_road::NonRoadTags, planned_roads::PlannedRoads, private_roads::PrivateRoads, reverse::OnewayGeometry, roundabout::{RoundaboutRef, RoundaboutTags}, tag_network, tag_network_with, units::UnitFormat};
+pub use topology::{duplicates::DuplicateWays, junction_degrees, simplify_network, splitting::WaySplitting};
+pub use writer::{write_pbf_parts, write_pbf_three_pass, write_pbf_tiles, write_ways_geojson, BaseExtract, ElementIds, HeaderBbox, PbfEmitter, WriteOptions};
@@ -0,0 +1,554 @@
+use rustc_hash::FxHashMap;
+use geo_types::{Coord, LineString};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Field;
+
+/// Coordinate hash for fast lookups (8 bytes)
+pub type CoordHash = u64;
+
+/// Hash a coordinate to u64 for use as map keys
+pub fn hash_coord(coord: &Coord) -> CoordHash {
+    let lat = (coord.y * 10_000_000.0).round() as i64;
+    let lon = (coord.x * 10_000_000.0).round() as i64;
+    ((lat as u64) << 32) | (lon as u64)
+}
+
+/// NVDB Property value (can be int, float, or string)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PropertyValue {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    /// Several values of a multi-valued attribute (e.g. several banned
+    /// vehicle types on one link); the scalar accessors read the first value
+    Array(Vec<PropertyValue>),
+    Null,
+}
+
+impl PropertyValue {
+    pub fn as_string(&self) -> String {
+        match self {
+            PropertyValue::Integer(i) => i.to_string(),
+            PropertyValue::Float(f) => f.to_string(),
+            PropertyValue::String(s) => s.clone(),
+            PropertyValue::Boolean(b) => b.to_string(),
+            // OSM separates multiple values with semicolons
+            PropertyValue::Array(values) => values.iter().map(|v| v.as_string()).collect::<Vec<_>>().join(";"),
+            PropertyValue::Null => String::new(),
+        }
+    }
+    
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            PropertyValue::Integer(i) => Some(*i),
+            PropertyValue::Float(f) => Some(*f as i64),
+            PropertyValue::Array(values) => values.first().and_then(|v| v.as_i64()),
+            _ => None,
+        }
+    }
+    
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            PropertyValue::Integer(i) => Some(*i as f64),
+            PropertyValue::Float(f) => Some(*f),
+            PropertyValue::Array(values) => values.first().and_then(|v| v.as_f64()),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> bool {
+        match self {
+            PropertyValue::Boolean(b) => *b,
+            PropertyValue::Integer(i) => *i == 1 || *i == -1,
+            PropertyValue::Float(f) => (*f - 1.0).abs() < f64::EPSILON || (*f - -1.0).abs() < f64::EPSILON,
+            PropertyValue::String(s) => s.eq_ignore_ascii_case("true") || s == "1" || s == "-1",
+            PropertyValue::Array(values) => values.first().map(|v| v.as_bool()).unwrap_or(false),
+            PropertyValue::Null => false,
+        }
+    }
+
+    /// All values of the property: the elements of an array, the value
+    /// itself for a scalar, none for null
+    pub fn values(&self) -> &[PropertyValue] {
+        match self {
+            PropertyValue::Array(values) => values,
+            PropertyValue::Null => &[],
+            scalar => std::slice::from_ref(scalar),
+        }
+    }
+}
+
+/// Strings that stand for a missing value in NVDB extracts
+///
+/// Depending on how an extract was exported, missing attributes arrive as
+/// "NA", "NULL" or ESRI's "<Null>" rather than as real nulls. Such strings
+/// and whitespace-only ones are normalized to `PropertyValue::Null` before
+/// tagging, so the tag mapper only has to check for absent values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullTokens {
+    tokens: Vec<String>,
+}
+
+impl Default for NullTokens {
+    fn default() -> Self {
+        Self::new(["NA", "NULL", "<Null>"])
+    }
+}
+
+impl NullTokens {
+    /// Treat these strings (compared ignoring case and surrounding
+    /// whitespace) as missing, in addition to empty ones
+    pub fn new<I, S>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self { tokens: tokens.into_iter().map(|t| t.as_ref().trim().to_string()).collect() }
+    }
+
+    pub fn is_null(&self, s: &str) -> bool {
+        let s = s.trim();
+        s.is_empty() || self.tokens.iter().any(|t| t.eq_ignore_ascii_case(s))
+    }
+
+    /// Replace a null string by `PropertyValue::Null`; arrays lose their
+    /// null elements and collapse to a scalar or null when one or none is left
+    pub fn normalize(&self, value: &mut PropertyValue) {
+        match value {
+            PropertyValue::String(s) if self.is_null(s) => *value = PropertyValue::Null,
+            PropertyValue::Array(values) => {
+                for v in values.iter_mut() {
+                    self.normalize(v);
+                }
+                values.retain(|v| !matches!(v, PropertyValue::Null));
+                match values.len() {
+                    0 => *value = PropertyValue::Null,
+                    1 => *value = values.pop().unwrap(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Normalize all properties of the segments
+    pub fn apply(&self, segments: &mut [Segment]) {
+        for segment in segments.iter_mut() {
+            for value in segment.properties.values_mut() {
+                self.normalize(value);
+            }
+        }
+    }
+}
+
+/// Oneway direction (matches Python's oneway variable)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OnewayDirection {
+    None,
+    Forward,   // B_ForbjudenFardriktning=1 → backward forbidden → traffic goes forward
+    Backward,  // F_ForbjudenFardriktning=1 → forward forbidden → geometry reversed, traffic goes "backward" (original direction)
+}
+
+/// Kind of problem a mapping function found in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnnotationKind {
+    /// The attributes do not determine the OSM class; a default was used
+    AmbiguousClass,
+    /// Attributes contradict each other; one of them was ignored
+    ConflictingAttributes,
+    /// A value outside the plausible range was dropped
+    OutOfRange,
+}
+
+impl AnnotationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotationKind::AmbiguousClass => "ambiguous_class",
+            AnnotationKind::ConflictingAttributes => "conflicting_attributes",
+            AnnotationKind::OutOfRange => "out_of_range",
+        }
+    }
+
+    /// Review tag the annotation becomes: `fixme` when the tagging is likely
+    /// wrong, `note` when only a source value was dropped
+    pub fn tag_key(&self) -> &'static str {
+        match self {
+            AnnotationKind::AmbiguousClass | AnnotationKind::ConflictingAttributes => "fixme",
+            AnnotationKind::OutOfRange => "note",
+        }
+    }
+}
+
+/// Machine-readable warning attached to a segment during tagging
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    pub message: String,
+}
+
+/// Road segment from NVDB
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segment {
+    pub start_node: CoordHash,
+    pub end_node: CoordHash,
+    pub global_start_node_id: Option<i64>,
+    pub global_end_node_id: Option<i64>,
+    pub global_start_owned: bool,
+    pub global_end_owned: bool,
+    #[serde(with = "crate::checkpoint::coords")]
+    pub geometry: LineString<f64>,
+    pub tags: FxHashMap<String, String>,
+    pub properties: FxHashMap<String, PropertyValue>,
+    pub shape_length: f64,
+    // Node IDs for internal coordinates (for PBF output)
+    pub internal_node_ids: Vec<i64>,
+    /// Oneway direction after map_oneway() — used by tag_direction() helper
+    pub oneway_direction: OnewayDirection,
+    /// Zero-based row of the input this segment was parsed from
+    pub source_row: usize,
+    /// Warnings from the tag mapper (see `Segment::annotate()`)
+    pub annotations: Vec<Annotation>,
+    /// Elevation in metres of the segment ends, by coordinate, where the
+    /// input has Z values (see `WkbOptions::keep_elevation`)
+    pub elevations: Vec<(CoordHash, f64)>,
+    /// Second and penultimate vertex before Douglas-Peucker, for the
+    /// junction angles of the merging (see `second_coord()`); cleared when
+    /// the segment is cut
+    #[serde(with = "crate::checkpoint::coords::pair")]
+    pub end_neighbours: Option<(Coord, Coord)>,
+}
+
+impl Segment {
+    pub fn new(_id: String, geometry: LineString<f64>) -> Self {
+        let shape_length = geometry_length(&geometry);
+        let start_node = hash_coord(geometry.0.first().unwrap());
+        let end_node = hash_coord(geometry.0.last().unwrap());
+        
+        Self {
+            start_node,
+            end_node,
+            global_start_node_id: None,
+            global_end_node_id: None,
+            global_start_owned: false,
+            global_end_owned: false,
+            geometry,
+            tags: FxHashMap::default(),
+            properties: FxHashMap::default(),
+            shape_length,
+            internal_node_ids: Vec::new(),
+            oneway_direction: OnewayDirection::None,
+            source_row: 0,
+            annotations: Vec::new(),
+            elevations: Vec::new(),
+            end_neighbours: None,
+        }
+    }
+    
+    /// Cut the segment at interior vertex `k`, keeping the first part and
+    /// returning the rest with the same tags and properties
+    pub fn split_at_vertex(&mut self, k: usize) -> Segment {
+        let mut tail = self.clone();
+        tail.geometry.0.drain(..k);
+        tail.start_node = hash_coord(&tail.geometry.0[0]);
+        tail.global_start_node_id = None;
+        tail.global_start_owned = false;
+
+        self.geometry.0.truncate(k + 1);
+        self.end_node = hash_coord(&self.geometry.0[k]);
+        self.global_end_node_id = None;
+        self.global_end_owned = false;
+        self.shape_length = geometry_length(&self.geometry);
+        tail.shape_length = geometry_length(&tail.geometry);
+        self.end_neighbours = None;
+        tail.end_neighbours = None;
+        tail
+    }
+
+    /// Attach a warning for review (written as `fixme`/`note` in review mode)
+    pub fn annotate(&mut self, kind: AnnotationKind, message: impl Into<String>) {
+        self.annotations.push(Annotation { kind, message: message.into() });
+    }
+
+    /// Property value of a schema field, `None` if missing or null
+    pub fn prop(&self, field: Field) -> Option<&PropertyValue> {
+        self.properties.get(field.column()).filter(|v| !matches!(v, PropertyValue::Null))
+    }
+
+    /// Get the coordinate at start or end
+    pub fn start_coord(&self) -> &Coord {
+        self.geometry.0.first().unwrap()
+    }
+    
+    pub fn end_coord(&self) -> &Coord {
+        self.geometry.0.last().unwrap()
+    }
+
+    /// Vertex after the start, as before Douglas-Peucker if recorded
+    /// (`end_neighbours`), else of the current geometry
+    pub fn second_coord(&self) -> &Coord {
+        match &self.end_neighbours {
+            Some((second, _)) => second,
+            None => self.geometry.0.get(1).unwrap_or(self.end_coord()),
+        }
+    }
+
+    /// Vertex before the end, see `second_coord()`
+    pub fn penultimate_coord(&self) -> &Coord {
+        match &self.end_neighbours {
+            Some((_, penultimate)) => penultimate,
+            None => self.geometry.0.get(self.geometry.0.len().saturating_sub(2)).unwrap_or(self.start_coord()),
+        }
+    }
+    
+    /// Get internal coordinates (excluding start and end)
+    pub fn internal_coords(&self) -> &[Coord] {
+        let coords = &self.geometry.0;
+        if coords.len() <= 2 {
+            &[]
+        } else {
+            &coords[1..coords.len()-1]
+        }
+    }
+}
+
+/// Junction node where segments connect
+#[derive(Debug, Clone, Default)]
+pub struct Junction {
+    pub segment_indices: Vec<usize>,
+}
+
+/// Merged way (collection of connected segments)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Way {
+    pub segment_indices: Vec<usize>,
+    /// Orientation of each segment of `segment_indices`: `true` where the
+    /// way runs through it against its digitised direction, from its end
+    /// node to its start node. Empty when every segment runs forward.
+    pub reversed: Vec<bool>,
+    pub tags: FxHashMap<String, String>,
+}
+
+impl Way {
+    /// Way through `segment_indices`, each in its digitised direction
+    pub fn new(segment_indices: Vec<usize>, tags: FxHashMap<String, String>) -> Self {
+        Self { segment_indices, reversed: Vec::new(), tags }
+    }
+
+    /// Way through segments of the given orientations, `(index, reversed)`
+    pub fn from_oriented(segments: Vec<(usize, bool)>, tags: FxHashMap<String, String>) -> Self {
+        let reversed = if segments.iter().any(|&(_, r)| r) { segments.iter().map(|&(_, r)| r).collect() } else { Vec::new() };
+        Self { segment_indices: segments.into_iter().map(|(i, _)| i).collect(), reversed, tags }
+    }
+
+    /// Whether the `k`th segment of the way is run through end to start
+    pub fn is_reversed(&self, k: usize) -> bool {
+        self.reversed.get(k).copied().unwrap_or(false)
+    }
+
+    /// Segments in way order with their orientation, `(index, reversed)`
+    pub fn oriented(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+        self.segment_indices.iter().enumerate().map(|(k, &i)| (i, self.is_reversed(k)))
+    }
+
+    /// Node where the way enters and node where it leaves its `k`th segment
+    pub fn segment_ends(&self, k: usize, segments: &[Segment]) -> (CoordHash, CoordHash) {
+        let segment = &segments[self.segment_indices[k]];
+        if self.is_reversed(k) {
+            (segment.end_node, segment.start_node)
+        } else {
+            (segment.start_node, segment.end_node)
+        }
+    }
+
+    /// First node of the way
+    pub fn first_node(&self, segments: &[Segment]) -> Option<CoordHash> {
+        (!self.segment_indices.is_empty()).then(|| self.segment_ends(0, segments).0)
+    }
+
+    /// Last node of the way
+    pub fn last_node(&self, segments: &[Segment]) -> Option<CoordHash> {
+        self.segment_indices.len().checked_sub(1).map(|k| self.segment_ends(k, segments).1)
+    }
+
+    /// Geometry of the way: the coordinates of its segments in way order and
+    /// orientation, without the duplicated coordinate at the segment
+    /// junctions
+    pub fn line(&self, segments: &[Segment]) -> LineString<f64> {
+        let mut coords: Vec<Coord> = Vec::new();
+        let mut push = |c: &Coord| {
+            if coords.last() != Some(c) {
+                coords.push(*c);
+            }
+        };
+        for (seg_idx, reversed) in self.oriented() {
+            let geometry = &segments[seg_idx].geometry.0;
+            if reversed {
+                geometry.iter().rev().for_each(&mut push);
+            } else {
+                geometry.iter().for_each(&mut push);
+            }
+        }
+        LineString::new(coords)
+    }
+}
+
+/// Bridge/tunnel structure
+#[derive(Debug, Clone)]
+pub struct Bridge {
+    pub car_count: i32,
+    pub cycle_count: i32,
+    pub length: f64,
+    pub layer: String,
+    pub tag: String,  // "bridge" or "tunnel" - Python logic
+}
+
+/// Relation over merged ways, e.g. a `type=bridge` relation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationFeature {
+    pub tags: FxHashMap<String, String>,
+    /// Members as (index into the way list, role)
+    pub members: Vec<(usize, String)>,
+}
+
+/// Node feature (POI like crossings, speed cameras, barriers, etc.)
+/// Ported from Python create_node() function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeFeature {
+    pub id: i64,
+    pub lat: f64,
+    pub lon: f64,
+    pub tags: FxHashMap<String, String>,
+}
+
+impl NodeFeature {
+    pub fn new(id: i64, lat: f64, lon: f64) -> Self {
+        Self {
+            id,
+            lat,
+            lon,
+            tags: FxHashMap::default(),
+        }
+    }
+    
+    pub fn add_tag(&mut self, key: &str, value: &str) {
+        if !value.is_empty() {
+            self.tags.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Simplification method
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimplifyMethod {
+    Recursive,
+    Route,
+    Refname,
+    Linear,
+    /// As `Refname`, within one municipality (Kommu_141)
+    Municipality,
+    /// As `Refname`, within one NVDB link (RLID, ROUTE_ID)
+    Rlid,
+    Segment, // No simplification
+}
+
+impl From<&str> for SimplifyMethod {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "recursive" => SimplifyMethod::Recursive,
+            "route" => SimplifyMethod::Route,
+            "refname" => SimplifyMethod::Refname,
+            "linear" => SimplifyMethod::Linear,
+            "kommun" | "municipality" => SimplifyMethod::Municipality,
+            "rlid" => SimplifyMethod::Rlid,
+            "segment" => SimplifyMethod::Segment,
+            _ => SimplifyMethod::Refname, // Default
+        }
+    }
+}
+
+/// Extra grouping key of unnamed roads (no `ref` and no `name`) when
+/// merging, which otherwise share one group per `highway` class and can be
+/// chained across unrelated streets meeting at junctions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnnamedGroups {
+    /// One group per `highway` class (the default, as in the Python script)
+    #[default]
+    Class,
+    /// Also by NVDB link (RLID, ROUTE_ID): unnamed ways follow one link
+    Rlid,
+    /// Also by the grid cell of about 1 km of the segment start: unnamed
+    /// ways stay local, and end at cell borders
+    Cell,
+}
+
+impl From<&str> for UnnamedGroups {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "rlid" => UnnamedGroups::Rlid,
+            "cell" | "grid" => UnnamedGroups::Cell,
+            _ => UnnamedGroups::Class,
+        }
+    }
+}
+
+/// Compute length of a LineString in meters
+pub(crate) fn geometry_length(geometry: &LineString<f64>) -> f64 {
+    use geo::algorithm::euclidean_length::EuclideanLength;
+    geometry.euclidean_length()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::LineString;
+
+    use crate::{NullTokens, PropertyValue, SimplifyMethod, UnnamedGroups, Way};
+    use crate::test_support::segment;
+
+    #[test]
+    fn values_lists_array_elements() {
+        let types = PropertyValue::Array(vec![PropertyValue::Integer(90), PropertyValue::Integer(150)]);
+        let codes: Vec<i64> = types.values().iter().filter_map(|v| v.as_i64()).collect();
+        assert_eq!(codes, vec![90, 150]);
+        assert_eq!(PropertyValue::Integer(90).values().len(), 1);
+        assert!(PropertyValue::Null.values().is_empty());
+    }
+
+    #[test]
+    fn null_tokens_normalize_values() {
+        let tokens = NullTokens::default();
+        assert!(tokens.is_null("<Null>"));
+        assert!(tokens.is_null("  "));
+        assert!(!tokens.is_null("Storgatan"));
+        assert!(NullTokens::new(["-"]).is_null("-"));
+
+        let mut value = PropertyValue::String("NA".into());
+        tokens.normalize(&mut value);
+        assert!(matches!(value, PropertyValue::Null));
+    }
+
+    #[test]
+    fn line_follows_segment_orientation() {
+        // The second segment is digitised against the way
+        let segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.002, 59.0), (18.001, 59.0)]),
+        ];
+        let way = Way::from_oriented(vec![(0, false), (1, true)], Default::default());
+        assert_eq!(way.line(&segments), LineString::from(vec![(18.0, 59.0), (18.001, 59.0), (18.002, 59.0)]));
+    }
+
+    #[test]
+    fn simplify_method_from_name() {
+        assert_eq!(SimplifyMethod::from("kommun"), SimplifyMethod::Municipality);
+        assert_eq!(SimplifyMethod::from("RLID"), SimplifyMethod::Rlid);
+        assert_eq!(SimplifyMethod::from("anything else"), SimplifyMethod::Refname);
+    }
+
+    #[test]
+    fn unnamed_groups_from_name() {
+        assert_eq!(UnnamedGroups::from("rlid"), UnnamedGroups::Rlid);
+        assert_eq!(UnnamedGroups::from("cell"), UnnamedGroups::Cell);
+        assert_eq!(UnnamedGroups::from("anything else"), UnnamedGroups::Class);
+    }
+}
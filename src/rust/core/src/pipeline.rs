@@ -0,0 +1,1038 @@
+//! Builder-style conversion pipeline
+//!
+//! Chains the stages used by the R bindings: tagging, feature node generation,
+//! network simplification and writing. Phase timings and counts are added to a
+//! caller-owned `ConversionStats`, so parsing (done by the caller) and several
+//! runs can be accumulated into one summary.
+
+use std::fmt::{Debug, Write as _};
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use geo_types::{Coord, LineString, MultiPolygon};
+use rustc_hash::{FxHashMap, FxHasher};
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::{self, Checkpoint};
+use crate::elevation::{sample_segment_ends, tag_node_elevations, ElevationProvider};
+use crate::geometry::{self, RailIndex};
+use crate::models::{self, AnnotationKind, CoordHash, NodeFeature, NullTokens, PropertyValue, RelationFeature, Segment, SimplifyMethod, UnnamedGroups, Way};
+use crate::profile::Profile;
+use crate::routing::RoutingPreset;
+use crate::stats::{AnnotationRecord, ConversionStats, PhaseTimer};
+use crate::tag_mapper::{self, access::AccessDefaults, bridges::bridge_relations, dates::{iso_date, retain_valid, DateTag}, nodes::{generate_feature_nodes, railway_crossings, split_at_railway_crossings, NodeCollection}, roundabout::RoundaboutTags};
+use crate::tag_mapper::{lay_bys::LayBys, incline::tag_incline, layers::harmonize_layers, lengths::tag_lengths, node_directions::NodeDirections, noise_barriers::NoiseBarriers, non_road::NonRoadTags, planned_roads::PlannedRoads, private_roads::PrivateRoads, quays::connect_ferries, reverse::OnewayGeometry, review::write_review_tags, speed::tag_implicit_maxspeed, tunnels::tag_tunnels, units::UnitFormat};
+use crate::topology::{self, duplicates::DuplicateWays, loops::split_loops, splitting::WaySplitting};
+use crate::writer::{is_xml_path, review_layer_path, write_josm_session, write_pbf_parts, write_pbf_three_pass, write_pbf_tiles, write_ways_geojson, BaseExtract, ElementIds, HeaderBbox, WriteOptions};
+
+/// Error of a conversion stopped by `Pipeline::interrupt_check()`
+pub const INTERRUPTED: &str = "Conversion interrupted";
+
+/// Conversion settings
+///
+/// ```no_run
+/// use nvdb2osm_core::{ConversionStats, Pipeline, SimplifyMethod};
+///
+/// # let segments = Vec::new();
+/// let mut stats = ConversionStats::default();
+/// Pipeline::new()
+///     .simplify_method(SimplifyMethod::Refname)
+///     .node_id_start(1)
+///     .way_id_start(1)
+///     .run(segments, "out.osm.pbf", &mut stats)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    simplify_method: SimplifyMethod,
+    unnamed_groups: UnnamedGroups,
+    way_splitting: WaySplitting,
+    split_loops: bool,
+    duplicate_ways: DuplicateWays,
+    node_id_start: i64,
+    way_id_start: i64,
+    relation_id_start: i64,
+    debug_geojson: Option<String>,
+    profile: Profile,
+    access_defaults: AccessDefaults,
+    non_road_tags: NonRoadTags,
+    private_roads: PrivateRoads,
+    lay_bys: LayBys,
+    noise_barriers: NoiseBarriers,
+    planned_roads: PlannedRoads,
+    oneway_geometry: OnewayGeometry,
+    node_directions: NodeDirections,
+    unit_format: UnitFormat,
+    implicit_maxspeed: bool,
+    roundabout_tags: RoundaboutTags,
+    review_mode: bool,
+    date_tag: DateTag,
+    null_tokens: NullTokens,
+    base: Option<BaseExtract>,
+    tile_zoom: Option<u8>,
+    max_file_elements: Option<usize>,
+    boundary: Option<MultiPolygon<f64>>,
+    railways: Option<RailIndex>,
+    as_of: Option<String>,
+    fidelity: bool,
+    bridge_relations: bool,
+    length_tags: bool,
+    incline: bool,
+    elevation: Option<Arc<dyn ElevationProvider>>,
+    routing_preset: RoutingPreset,
+    write_options: WriteOptions,
+    interrupt: Option<fn() -> bool>,
+    checkpoint: Option<String>,
+    input_fingerprint: u64,
+    josm_session: Option<String>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            simplify_method: SimplifyMethod::Refname,
+            unnamed_groups: UnnamedGroups::default(),
+            way_splitting: WaySplitting::default(),
+            split_loops: false,
+            duplicate_ways: DuplicateWays::default(),
+            node_id_start: 1,
+            way_id_start: 1,
+            relation_id_start: 1,
+            debug_geojson: None,
+            profile: Profile::default(),
+            access_defaults: AccessDefaults::default(),
+            non_road_tags: NonRoadTags::default(),
+            private_roads: PrivateRoads::default(),
+            lay_bys: LayBys::default(),
+            noise_barriers: NoiseBarriers::default(),
+            planned_roads: PlannedRoads::default(),
+            oneway_geometry: OnewayGeometry::default(),
+            node_directions: NodeDirections::default(),
+            unit_format: UnitFormat::default(),
+            implicit_maxspeed: false,
+            roundabout_tags: RoundaboutTags::default(),
+            review_mode: false,
+            date_tag: DateTag::default(),
+            null_tokens: NullTokens::default(),
+            base: None,
+            tile_zoom: None,
+            max_file_elements: None,
+            boundary: None,
+            railways: None,
+            as_of: None,
+            fidelity: false,
+            bridge_relations: false,
+            length_tags: false,
+            incline: false,
+            elevation: None,
+            routing_preset: RoutingPreset::default(),
+            write_options: WriteOptions::default(),
+            interrupt: None,
+            checkpoint: None,
+            input_fingerprint: 0,
+            josm_session: None,
+        }
+    }
+}
+
+/// Result of `Pipeline::convert()`: tagged segments, the merged ways built
+/// from them, the generated feature nodes and relations over the ways
+#[derive(Serialize, Deserialize)]
+pub struct Conversion {
+    pub segments: Vec<Segment>,
+    pub ways: Vec<Way>,
+    pub feature_nodes: Vec<NodeFeature>,
+    pub relations: Vec<RelationFeature>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Way merging strategy (default: `SimplifyMethod::Refname`)
+    pub fn simplify_method(mut self, method: SimplifyMethod) -> Self {
+        self.simplify_method = method;
+        self
+    }
+
+    /// Extra grouping key of roads without `ref` and `name` when merging
+    /// (default: `UnnamedGroups::Class`, as in the Python script)
+    pub fn unnamed_groups(mut self, groups: UnnamedGroups) -> Self {
+        self.unnamed_groups = groups;
+        self
+    }
+
+    /// Extra points where merged ways end, besides tag changes (default:
+    /// none, ways run through junctions as in the Python script)
+    pub fn way_splitting(mut self, splitting: WaySplitting) -> Self {
+        self.way_splitting = splitting;
+        self
+    }
+
+    /// Split closed ways that are not roundabouts into two ways at their
+    /// farthest vertex (default: false)
+    pub fn split_loops(mut self, enabled: bool) -> Self {
+        self.split_loops = enabled;
+        self
+    }
+
+    /// Ways with identical node sequences (default: `DuplicateWays::Keep`,
+    /// written unchecked)
+    pub fn duplicate_ways(mut self, handling: DuplicateWays) -> Self {
+        self.duplicate_ways = handling;
+        self
+    }
+
+    /// First ID for generated nodes (default: 1)
+    pub fn node_id_start(mut self, id: i64) -> Self {
+        self.node_id_start = id;
+        self
+    }
+
+    /// First ID for written ways (default: 1)
+    pub fn way_id_start(mut self, id: i64) -> Self {
+        self.way_id_start = id;
+        self
+    }
+
+    /// Copy the elements of an existing extract into the output, numbering
+    /// the converted nodes and ways after its highest IDs
+    pub fn append_to(mut self, base: BaseExtract) -> Self {
+        self.base = Some(base);
+        self
+    }
+
+    /// Write every NVDB segment as its own way with all its vertices: no
+    /// Douglas-Peucker and no merging, regardless of `simplify_method`
+    /// (default: false). Tagging is unchanged. The caller parses the input
+    /// without rounding (`wkb::segment_from_wkb_exact()`).
+    pub fn fidelity(mut self, enabled: bool) -> Self {
+        self.fidelity = enabled;
+        self
+    }
+
+    /// Group the ways of bridges split into several ways into `type=bridge`
+    /// relations (default: false, see `tag_mapper::bridges`); `validate()`
+    /// refuses them for `tile_zoom()` and `max_file_elements()` output
+    pub fn bridge_relations(mut self, enabled: bool) -> Self {
+        self.bridge_relations = enabled;
+        self
+    }
+
+    /// Tag each way with its `length` in metres and each ferry with its
+    /// `duration`, from the final geometry (default: false, see
+    /// `tag_mapper::lengths`)
+    pub fn length_tags(mut self, enabled: bool) -> Self {
+        self.length_tags = enabled;
+        self
+    }
+
+    /// Tag each way with its average gradient as `incline`, from the
+    /// elevation of its ends (default: false, see `tag_mapper::incline`);
+    /// the input is parsed with `WkbOptions::keep_elevation`
+    pub fn incline(mut self, enabled: bool) -> Self {
+        self.incline = enabled;
+        self
+    }
+
+    /// Terrain heights for the segment ends without Z values and the `ele`
+    /// of the feature nodes (default: none, see `elevation`); with
+    /// `incline()`, this gives 2D input its gradients
+    pub fn elevation(mut self, provider: Option<Arc<dyn ElevationProvider>>) -> Self {
+        self.elevation = provider;
+        self
+    }
+
+    /// Prepare the output for a routing engine's import (default:
+    /// `RoutingPreset::None`, see `routing`)
+    pub fn routing_preset(mut self, preset: RoutingPreset) -> Self {
+        self.routing_preset = preset;
+        self
+    }
+
+    /// Clip the network to a boundary polygon in `convert()`, splitting
+    /// segments that cross it (see `geometry::clip_segments()`)
+    pub fn clip_boundary(mut self, boundary: Option<MultiPolygon<f64>>) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Railway lines at which level crossings are placed (default: none)
+    ///
+    /// A level crossing node goes where the road crosses a railway, splitting
+    /// the segment there so the node is part of the way, rather than at the
+    /// segment's first vertex.
+    pub fn railways(mut self, lines: Option<Vec<LineString<f64>>>) -> Self {
+        self.railways = lines.map(|lines| RailIndex::new(&lines));
+        self
+    }
+
+    /// Convert only the segments valid on this date (default: all), for
+    /// historical snapshots of the network; segments are kept or dropped as
+    /// a whole (see `dates::retain_valid()`); the date is read like
+    /// `FROM_DATE` values, and one that cannot be read is ignored
+    pub fn as_of(mut self, date: Option<String>) -> Self {
+        self.as_of = date.and_then(|date| iso_date(&PropertyValue::String(date)));
+        self
+    }
+
+    /// Drop the segments not valid on the `as_of()` date
+    pub fn retain_valid(&self, segments: &mut Vec<Segment>) {
+        if let Some(as_of) = &self.as_of {
+            retain_valid(segments, as_of);
+        }
+    }
+
+    /// Header bbox of the output (default: `HeaderBbox::All`)
+    pub fn header_bbox(mut self, bbox: HeaderBbox) -> Self {
+        self.write_options.bbox = bbox;
+        self
+    }
+
+    /// zlib level of PBF output, 0 (fastest) to 9 (smallest) (default: 6)
+    pub fn compression_level(mut self, level: u32) -> Self {
+        self.write_options.compression_level = level.min(9);
+        self
+    }
+
+    /// Maximum number of elements per PBF block (default: 8000)
+    pub fn block_size(mut self, size: usize) -> Self {
+        self.write_options.block_size = size.max(1);
+        self
+    }
+
+    /// Write PBF nodes as DenseNodes (default: true)
+    pub fn dense_nodes(mut self, enabled: bool) -> Self {
+        self.write_options.dense_nodes = enabled;
+        self
+    }
+
+    /// Encode PBF blocks on all rayon threads (default: true); the output
+    /// is identical to sequential encoding
+    pub fn parallel_encoding(mut self, enabled: bool) -> Self {
+        self.write_options.parallel_encoding = enabled;
+        self
+    }
+
+    /// Changeset referenced by the rows of PostGIS output (default: 1)
+    pub fn changeset_id(mut self, id: i64) -> Self {
+        self.write_options.changeset_id = id;
+        self
+    }
+
+    /// Schema of the `current_*` tables of PostGIS output (default: `None`,
+    /// the connection's `search_path`)
+    pub fn db_schema(mut self, schema: Option<String>) -> Self {
+        self.write_options.db_schema = schema;
+        self
+    }
+
+    /// Poll `check` between the conversion phases, on the thread running
+    /// the pipeline (default: never); once it returns true, `convert()`,
+    /// `run()` and `write()` stop with `Err(INTERRUPTED)`
+    pub fn interrupt_check(mut self, check: fn() -> bool) -> Self {
+        self.interrupt = Some(check);
+        self
+    }
+
+    /// Write one file per z/x/y tile at this zoom instead of a single file;
+    /// the output path is then a template or directory (see `writer::tile_path()`)
+    pub fn tile_zoom(mut self, zoom: Option<u8>) -> Self {
+        self.tile_zoom = zoom;
+        self
+    }
+
+    /// Split the output into files of at most this many nodes and ways, for
+    /// uploading in changesets of limited size (default: `None`, one file);
+    /// the output path is then a template or directory (see
+    /// `writer::part_path()`)
+    pub fn max_file_elements(mut self, max: Option<usize>) -> Self {
+        self.max_file_elements = max;
+        self
+    }
+
+    /// First node ID actually used: `node_id_start`, raised above the base
+    /// extract's nodes when appending
+    pub fn first_node_id(&self) -> i64 {
+        match &self.base {
+            Some(base) => self.node_id_start.max(base.next_node_id()),
+            None => self.node_id_start,
+        }
+    }
+
+    /// First way ID actually used, see `first_node_id()`
+    pub fn first_way_id(&self) -> i64 {
+        match &self.base {
+            Some(base) => self.way_id_start.max(base.next_way_id()),
+            None => self.way_id_start,
+        }
+    }
+
+    /// First node, way and relation IDs actually used
+    pub fn first_ids(&self) -> ElementIds {
+        let relation = match &self.base {
+            Some(base) => self.relation_id_start.max(base.next_relation_id()),
+            None => self.relation_id_start,
+        };
+        ElementIds { node: self.first_node_id(), way: self.first_way_id(), relation }
+    }
+
+    /// Output settings, for writing with a `PbfEmitter` directly
+    pub fn write_options(&self) -> WriteOptions {
+        WriteOptions { ids: self.first_ids(), ..self.write_options.clone() }
+    }
+
+    /// Hash of the options that shape the converted state and of the
+    /// `input_fingerprint()`; a checkpoint saved under another fingerprint
+    /// is not resumed
+    ///
+    /// Options only used for writing (`write_options()`, `tile_zoom()`,
+    /// `max_file_elements()`, the debug GeoJSON, the JOSM session) are left
+    /// out, as a resumed run writes with its own; a DEM counts by its
+    /// `ElevationProvider::identity()`.
+    pub fn fingerprint(&self) -> u64 {
+        // Destructured in full, so that a new option has to be placed here
+        let Pipeline {
+            simplify_method, unnamed_groups, way_splitting, split_loops, duplicate_ways,
+            node_id_start, way_id_start, relation_id_start, debug_geojson: _, profile,
+            access_defaults, non_road_tags, private_roads, lay_bys, noise_barriers, planned_roads,
+            oneway_geometry, node_directions, unit_format, implicit_maxspeed, roundabout_tags,
+            review_mode, date_tag, null_tokens, base, tile_zoom: _, max_file_elements: _, boundary,
+            railways, as_of, fidelity, bridge_relations, length_tags, incline, elevation,
+            routing_preset, write_options: _, interrupt: _, checkpoint: _, input_fingerprint,
+            josm_session: _,
+        } = self;
+        let options: &[&dyn Debug] = &[
+            simplify_method, unnamed_groups, way_splitting, split_loops, duplicate_ways,
+            node_id_start, way_id_start, relation_id_start, profile, access_defaults,
+            non_road_tags, private_roads, lay_bys, noise_barriers, planned_roads, oneway_geometry,
+            node_directions, unit_format, implicit_maxspeed, roundabout_tags, review_mode,
+            date_tag, null_tokens, base, boundary, railways, as_of, fidelity, bridge_relations,
+            length_tags, incline, &elevation.as_ref().map(|dem| dem.identity()), routing_preset,
+            input_fingerprint,
+        ];
+        // The Debug output is fed to the hasher as it is formatted
+        struct HashWriter(FxHasher);
+        impl std::fmt::Write for HashWriter {
+            fn write_str(&mut self, s: &str) -> std::fmt::Result {
+                self.0.write(s.as_bytes());
+                Ok(())
+            }
+        }
+        let mut writer = HashWriter(FxHasher::default());
+        for option in options {
+            let _ = write!(writer, "{:?};", option);
+        }
+        writer.0.finish()
+    }
+
+    /// First ID for written relations (default: 1)
+    pub fn relation_id_start(mut self, id: i64) -> Self {
+        self.relation_id_start = id;
+        self
+    }
+
+    /// Save the state after each phase of `run()` to `path` and remove it
+    /// once the output is written (default: none); see `checkpoint`
+    pub fn checkpoint(mut self, path: Option<String>) -> Self {
+        self.checkpoint = path;
+        self
+    }
+
+    /// Fingerprint of the input, such as a hash of its rows, saved in
+    /// checkpoints so that another input does not resume them (default: 0);
+    /// see `fingerprint()`
+    pub fn input_fingerprint(mut self, fingerprint: u64) -> Self {
+        self.input_fingerprint = fingerprint;
+        self
+    }
+
+    /// Also write a JOSM session to `path` opening the output, which must be
+    /// OSM XML (`.osm` or `.xml`), with a review layer of the annotations
+    /// (default: none); see `writer::write_josm_session()`
+    pub fn josm_session(mut self, path: Option<String>) -> Self {
+        self.josm_session = path;
+        self
+    }
+
+    /// Also write the simplified ways as GeoJSON to `path` in `run()`
+    pub fn debug_geojson(mut self, path: impl Into<String>) -> Self {
+        self.debug_geojson = Some(path.into());
+        self
+    }
+
+    /// Source database of the input (default: `Profile::Sweden`)
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Access tags implied by highway class (default: `AccessDefaults::sweden()`)
+    pub fn access_defaults(mut self, defaults: AccessDefaults) -> Self {
+        self.access_defaults = defaults;
+        self
+    }
+
+    /// Tags of the non-road GCM types, cable cars and funiculars
+    /// (default: `NonRoadTags::sweden()`)
+    pub fn non_road_tags(mut self, tags: NonRoadTags) -> Self {
+        self.non_road_tags = tags;
+        self
+    }
+
+    /// Access policy for privately maintained roads (default:
+    /// `PrivateRoads::Permissive`, access left open)
+    pub fn private_roads(mut self, policy: PrivateRoads) -> Self {
+        self.private_roads = policy;
+        self
+    }
+
+    /// Mapping of lay-bys (default: `LayBys::Node`, a detached parking node)
+    pub fn lay_bys(mut self, mapping: LayBys) -> Self {
+        self.lay_bys = mapping;
+        self
+    }
+
+    /// Mapping of noise barriers (default: `NoiseBarriers::Tags`, a
+    /// `noise_barrier` tag on the road way)
+    pub fn noise_barriers(mut self, mapping: NoiseBarriers) -> Self {
+        self.noise_barriers = mapping;
+        self
+    }
+
+    /// Roads under construction and planned roads (default:
+    /// `PlannedRoads::Tag`, `highway=construction` and `highway=proposed`)
+    pub fn planned_roads(mut self, policy: PlannedRoads) -> Self {
+        self.planned_roads = policy;
+        self
+    }
+
+    /// Geometry of oneways against the digitised direction (default:
+    /// `OnewayGeometry::Reverse`, reversed with `oneway=yes`)
+    pub fn oneway_geometry(mut self, policy: OnewayGeometry) -> Self {
+        self.oneway_geometry = policy;
+        self
+    }
+
+    /// `direction` on speed cameras for one direction of traffic (default:
+    /// `NodeDirections::None`)
+    pub fn node_directions(mut self, format: NodeDirections) -> Self {
+        self.node_directions = format;
+        self
+    }
+
+    /// Restriction value format (default: `UnitFormat::plain()`)
+    pub fn unit_format(mut self, format: UnitFormat) -> Self {
+        self.unit_format = format;
+        self
+    }
+
+    /// Tag the Swedish general speed limits where NVDB has no signed limit
+    /// (default: false). Ignored for the Norwegian and Finnish profiles.
+    pub fn implicit_maxspeed(mut self, enabled: bool) -> Self {
+        self.implicit_maxspeed = enabled;
+        self
+    }
+
+    /// Ref and name handling on roundabouts (default: refs kept, no names)
+    pub fn roundabout_tags(mut self, tags: RoundaboutTags) -> Self {
+        self.roundabout_tags = tags;
+        self
+    }
+
+    /// Write the tag mapper's annotations as `fixme`/`note` tags (default:
+    /// false). They are collected in `ConversionStats::annotations` either way.
+    pub fn review_mode(mut self, enabled: bool) -> Self {
+        self.review_mode = enabled;
+        self
+    }
+
+    /// Tag ways with the newest validity date of their segments (default:
+    /// `DateTag::None`)
+    pub fn date_tag(mut self, tag: DateTag) -> Self {
+        self.date_tag = tag;
+        self
+    }
+
+    /// Strings read as missing values (default: "NA", "NULL", "<Null>" and
+    /// whitespace-only strings)
+    pub fn null_tokens(mut self, tokens: NullTokens) -> Self {
+        self.null_tokens = tokens;
+        self
+    }
+
+    /// Translate the segment properties with the profile, tag them, adjust
+    /// the roundabouts, add the access defaults and implicit speed limits,
+    /// format the restriction values and collect the annotations
+    pub fn tag(&self, segments: &mut [Segment], stats: &mut ConversionStats) {
+        let timer = PhaseTimer::start();
+        self.null_tokens.apply(segments);
+        self.profile.apply_all(segments);
+        tag_mapper::tag_network_with(segments, &self.non_road_tags);
+        self.roundabout_tags.apply(segments);
+        self.private_roads.apply(segments);
+        self.lay_bys.apply(segments);
+        self.noise_barriers.apply(segments);
+        self.access_defaults.apply(segments);
+        self.planned_roads.apply(segments);
+        if self.implicit_maxspeed && self.profile == Profile::Sweden {
+            tag_implicit_maxspeed(segments);
+        }
+        self.unit_format.apply(segments);
+        self.oneway_geometry.apply(segments);
+        stats.record_annotations(segments);
+        if self.review_mode {
+            write_review_tags(segments);
+        }
+        stats.tag_secs += timer.secs();
+    }
+
+    /// Check that the options can be used together, before anything is
+    /// converted: bridge relations cannot be written to tiled or split output
+    pub fn validate(&self) -> Result<(), String> {
+        if self.bridge_relations && (self.tile_zoom.is_some() || self.max_file_elements.is_some()) {
+            return Err("Bridge relations cannot be written to tiled or split output".to_string());
+        }
+        Ok(())
+    }
+
+    /// Tag the segments, generate feature nodes and simplify, without
+    /// writing; fails when interrupted (see `interrupt_check()`) and for
+    /// options that `validate()` refuses
+    pub fn convert(&self, mut segments: Vec<Segment>, stats: &mut ConversionStats) -> Result<Conversion, String> {
+        self.validate()?;
+        self.interrupted()?;
+        self.retain_valid(&mut segments);
+        let mut segments = match &self.boundary {
+            Some(boundary) => {
+                let timer = PhaseTimer::start();
+                let clipped = geometry::clip_segments(segments, boundary);
+                stats.clip_secs += timer.secs();
+                clipped
+            }
+            None => segments,
+        };
+        self.tag(&mut segments, stats);
+        self.interrupted()?;
+        self.planned_roads.retain(&mut segments);
+        if let Some(provider) = &self.elevation {
+            sample_segment_ends(&mut segments, provider.as_ref());
+        }
+        connect_ferries(&mut segments);
+        let walls = self.noise_barriers.barrier_segments(&segments);
+        segments.extend(walls);
+
+        // Generate nodes from segment properties (POIs like crossings, cameras, etc.)
+        let timer = PhaseTimer::start();
+        let crossings = match &self.railways {
+            Some(rails) => railway_crossings(&segments, rails),
+            None => FxHashMap::default(),
+        };
+        let mut feature_nodes = generate_feature_nodes(&segments, self.first_node_id(), &crossings, self.node_directions);
+        self.lay_bys.retain_nodes(&mut feature_nodes);
+        let mut collection = NodeCollection::from(feature_nodes);
+        split_at_railway_crossings(&mut segments, &crossings);
+        let conflicts = collection.merge_coincident();
+        if !conflicts.is_empty() {
+            // Feature nodes sit at the first vertex of the segment they come from
+            let rows: FxHashMap<CoordHash, usize> = segments.iter().rev().map(|s| (s.start_node, s.source_row)).collect();
+            for conflict in conflicts {
+                let Some(node) = collection.nodes().iter().find(|node| node.id == conflict.kept_id) else { continue };
+                let hash = models::hash_coord(&Coord { x: node.lon, y: node.lat });
+                stats.annotations.push(AnnotationRecord {
+                    source_row: rows.get(&hash).copied().unwrap_or_default(),
+                    kind: AnnotationKind::ConflictingAttributes.as_str(),
+                    message: conflict.to_string(),
+                });
+            }
+        }
+        let mut feature_nodes = collection.into_nodes();
+        for node in &mut feature_nodes {
+            self.unit_format.apply_tags(&mut node.tags);
+        }
+        if let Some(provider) = &self.elevation {
+            tag_node_elevations(&mut feature_nodes, provider.as_ref());
+        }
+        stats.feature_nodes_out += feature_nodes.len();
+        stats.nodes_secs += timer.secs();
+        self.interrupted()?;
+
+        let timer = PhaseTimer::start();
+        self.routing_preset.prepare_segments(&mut segments);
+        let mut ways = if self.fidelity {
+            topology::unmerged_ways(&segments)
+        } else {
+            topology::simplify_network(&mut segments, self.simplify_method, self.unnamed_groups)
+        };
+        self.way_splitting.apply(&mut ways, &segments);
+        if self.split_loops {
+            split_loops(&mut ways, &mut segments);
+        }
+        self.routing_preset.prepare_ways(&mut ways, &segments);
+        self.date_tag.apply(&mut ways, &segments);
+        harmonize_layers(&mut ways, &segments);
+        tag_tunnels(&mut ways, &segments);
+        // Last pass changing the way list; relations refer to ways by index
+        self.duplicate_ways.apply(&mut ways, &segments, stats);
+        let relations = if self.bridge_relations {
+            bridge_relations(&mut ways, &segments)
+        } else {
+            Vec::new()
+        };
+        self.routing_preset.finish_tags(&mut ways, &segments);
+        if self.length_tags {
+            tag_lengths(&mut ways, &segments);
+        }
+        if self.incline {
+            tag_incline(&mut ways, &segments);
+        }
+        stats.ways_out += ways.len();
+        stats.relations_out += relations.len();
+        stats.lengths.add_ways(&ways, &segments);
+        stats.simplify_secs += timer.secs();
+
+        self.interrupted()?;
+
+        Ok(Conversion { segments, ways, feature_nodes, relations })
+    }
+
+    /// Convert and write the result to `output_path` (PBF, or OPL for `.opl`
+    /// and OSM XML for `.osm`); returns the written conversion for further
+    /// outputs
+    pub fn run(&self, segments: Vec<Segment>, output_path: &str, stats: &mut ConversionStats) -> Result<Conversion, String> {
+        let parsed = Checkpoint::Parsed(segments);
+        self.save_checkpoint(&parsed, stats)?;
+        self.resume(parsed, output_path, stats)
+    }
+
+    /// The saved state of an earlier `run()` with this `checkpoint()`, to
+    /// pass to `resume()` in place of parsing the input again; `None` if
+    /// there is none
+    pub fn load_checkpoint(&self, stats: &mut ConversionStats) -> Result<Option<Checkpoint>, String> {
+        match &self.checkpoint {
+            Some(path) => checkpoint::load(path, self.first_ids(), self.fingerprint(), stats),
+            None => Ok(None),
+        }
+    }
+
+    /// Finish `run()` from a checkpoint: convert parsed segments, saving the
+    /// result, then write it and remove the checkpoint; returns the written
+    /// conversion like `run()`
+    pub fn resume(&self, checkpoint: Checkpoint, output_path: &str, stats: &mut ConversionStats) -> Result<Conversion, String> {
+        let mut conversion = match checkpoint {
+            Checkpoint::Parsed(segments) => {
+                let converted = Checkpoint::Converted(self.convert(segments, stats)?);
+                self.save_checkpoint(&converted, stats)?;
+                let Checkpoint::Converted(conversion) = converted else { unreachable!() };
+                conversion
+            }
+            Checkpoint::Converted(conversion) => {
+                stats.lengths.add_ways(&conversion.ways, &conversion.segments);
+                conversion
+            }
+        };
+        self.write(&mut conversion, output_path, stats)?;
+        if let Some(path) = &self.checkpoint {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(conversion)
+    }
+
+    /// Save `checkpoint` if a `checkpoint()` path is set
+    fn save_checkpoint(&self, state: &Checkpoint, stats: &ConversionStats) -> Result<(), String> {
+        match &self.checkpoint {
+            Some(path) => checkpoint::save(path, state, self.first_ids(), self.fingerprint(), stats),
+            None => Ok(()),
+        }
+    }
+
+    /// Write a finished conversion to `output_path`; can be called several
+    /// times on one conversion to produce several outputs
+    ///
+    /// When interrupted (see `interrupt_check()`), the debug GeoJSON, a
+    /// single output file and its JOSM session are removed again; tiles and
+    /// parts are not.
+    pub fn write(&self, conversion: &mut Conversion, output_path: &str, stats: &mut ConversionStats) -> Result<(), String> {
+        self.interrupted()?;
+        // Optional secondary output for inspecting the tagged ways
+        if let Some(geojson_path) = &self.debug_geojson {
+            if let Err(e) = write_ways_geojson(&conversion.ways, &conversion.segments, geojson_path, self.first_way_id()) {
+                eprintln!("Failed to write debug GeoJSON: {}", e);
+            }
+            self.interrupted().inspect_err(|_| self.remove_outputs(None))?;
+        }
+
+        let split = self.tile_zoom.is_some() || self.max_file_elements.is_some();
+        if self.josm_session.is_some() && (split || !is_xml_path(output_path)) {
+            return Err("JOSM sessions need a single OSM XML (.osm or .xml) output".to_string());
+        }
+
+        // Write PBF using three-pass approach (nodes first, then ways)
+        // Feature nodes are written before junction nodes
+        let timer = PhaseTimer::start();
+        if let Some(zoom) = self.tile_zoom {
+            if self.max_file_elements.is_some() {
+                return Err("Tiled output cannot also be split by element count".to_string());
+            }
+            if self.base.is_some() {
+                return Err("Tiled output cannot be appended to an existing extract".to_string());
+            }
+            let result = write_pbf_tiles(
+                &conversion.ways,
+                &mut conversion.segments,
+                &conversion.feature_nodes,
+                output_path,
+                zoom,
+                &self.write_options(),
+            );
+            stats.write_secs += timer.secs();
+            stats.record_peak_memory(&conversion.segments);
+            stats.nodes_out += result?.0;
+            return Ok(());
+        }
+        if let Some(max_elements) = self.max_file_elements {
+            if self.base.is_some() {
+                return Err("Split output cannot be appended to an existing extract".to_string());
+            }
+            let result = write_pbf_parts(
+                &conversion.ways,
+                &mut conversion.segments,
+                &conversion.feature_nodes,
+                output_path,
+                max_elements,
+                &self.write_options(),
+            );
+            stats.write_secs += timer.secs();
+            stats.record_peak_memory(&conversion.segments);
+            stats.nodes_out += result?.0;
+            return Ok(());
+        }
+        let result = write_pbf_three_pass(
+            &conversion.ways,
+            &mut conversion.segments,
+            &conversion.feature_nodes,
+            &conversion.relations,
+            output_path,
+            self.base.as_ref(),
+            &self.write_options(),
+        );
+        stats.write_secs += timer.secs();
+        stats.record_peak_memory(&conversion.segments);
+
+        stats.nodes_out += result?;
+        if let Some(session) = &self.josm_session {
+            write_josm_session(session, output_path, &stats.annotations, &conversion.segments)?;
+        }
+        self.interrupted().inspect_err(|_| self.remove_outputs(Some(output_path)))
+    }
+
+    /// `Err(INTERRUPTED)` once the `interrupt_check()` reports an interrupt
+    fn interrupted(&self) -> Result<(), String> {
+        match self.interrupt {
+            Some(check) if check() => Err(INTERRUPTED.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove the debug GeoJSON, `output_path` and its JOSM session after an
+    /// interrupt, if they are files (not a database URL)
+    fn remove_outputs(&self, output_path: Option<&str>) {
+        let session = output_path.and(self.josm_session.as_deref());
+        let review = session.map(review_layer_path);
+        let paths = self.debug_geojson.as_deref().into_iter().chain(output_path).chain(session).chain(review.as_deref());
+        for path in paths {
+            if std::path::Path::new(path).is_file() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use geo_types::LineString;
+
+    use crate::{Checkpoint, ConversionStats, HeaderBbox, INTERRUPTED, Pipeline, PropertyValue};
+    use crate::compare::{compare_pbf, MatchBy};
+    use crate::elevation::ElevationProvider;
+    use crate::synthetic::{Layout, SyntheticNetwork};
+    use crate::test_support::segment;
+
+    #[test]
+    fn bridge_relations_are_refused_for_tiles_and_parts() {
+        let mut stats = ConversionStats::default();
+        for pipeline in [Pipeline::new().tile_zoom(Some(14)), Pipeline::new().max_file_elements(Some(10))] {
+            assert!(pipeline.validate().is_ok());
+            let pipeline = pipeline.bridge_relations(true);
+            let error = pipeline.validate().unwrap_err();
+            assert_eq!(error, "Bridge relations cannot be written to tiled or split output");
+            let segments = vec![segment(vec![(18.0, 59.0), (18.001, 59.0)])];
+            assert_eq!(pipeline.convert(segments, &mut stats).err(), Some(error));
+        }
+    }
+
+    #[test]
+    fn exact_fidelity_keeps_segments_and_vertices() {
+        // One street in three segments, the first with a vertex barely off the line
+        let street = |coords: Vec<(f64, f64)>| {
+            let mut segment = segment(coords);
+            segment.properties.insert("Namn_130".to_string(), PropertyValue::String("Storgatan".to_string()));
+            segment
+        };
+        let segments = || vec![
+            street(vec![(18.0, 59.0), (18.0005, 59.000000_1), (18.001, 59.0)]),
+            street(vec![(18.001, 59.0), (18.002, 59.0)]),
+            street(vec![(18.002, 59.0), (18.003, 59.0)]),
+        ];
+
+        let mut stats = ConversionStats::default();
+        let merged = Pipeline::new().convert(segments(), &mut stats).unwrap();
+        assert_eq!(merged.ways.len(), 1);
+
+        let exact = Pipeline::new().fidelity(true).convert(segments(), &mut stats).unwrap();
+        assert_eq!(exact.ways.len(), 3);
+        assert_eq!(exact.ways[0].tags["name"], "Storgatan");
+        assert_eq!(exact.segments[0].geometry.0.len(), 3);
+    }
+
+    #[test]
+    fn planned_roads_get_no_implicit_maxspeed() {
+        let road = |status: i64| {
+            let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+            segment.properties.insert("Kateg_380".to_string(), PropertyValue::Integer(3));
+            segment.properties.insert("Lankstatus".to_string(), PropertyValue::Integer(status));
+            segment
+        };
+        let mut segments = vec![road(1), road(3)];
+        Pipeline::new().implicit_maxspeed(true).tag(&mut segments, &mut ConversionStats::default());
+
+        assert_eq!(segments[0].tags["maxspeed"], "70");
+        assert_eq!(segments[1].tags["highway"], "proposed");
+        assert!(!segments[1].tags.contains_key("maxspeed"));
+    }
+
+    #[test]
+    fn header_bbox_can_be_fixed_or_omitted() {
+        let path = std::env::temp_dir().join("nvdb2osm_header_bbox.osm");
+        let path = path.to_str().unwrap();
+        let segments = SyntheticNetwork::new(Layout::Grid, 3).segments();
+
+        let pipeline = Pipeline::new().header_bbox(HeaderBbox::Fixed(20.0, 63.5, 20.5, 64.0));
+        pipeline.run(segments.clone(), path, &mut ConversionStats::default()).unwrap();
+        assert!(std::fs::read_to_string(path).unwrap().contains(r#"minlat="63.5000000""#));
+
+        let pipeline = Pipeline::new().header_bbox(HeaderBbox::Omit);
+        pipeline.run(segments, path, &mut ConversionStats::default()).unwrap();
+        assert!(!std::fs::read_to_string(path).unwrap().contains("<bounds"));
+    }
+
+    #[test]
+    fn block_settings_keep_the_content() {
+        let dir = std::env::temp_dir();
+        let (a, b) = (dir.join("nvdb2osm_blocks_a.osm.pbf"), dir.join("nvdb2osm_blocks_b.osm.pbf"));
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        let segments = SyntheticNetwork::new(Layout::Grid, 6).segments();
+        Pipeline::new().run(segments.clone(), a, &mut ConversionStats::default()).unwrap();
+        Pipeline::new()
+            .compression_level(0)
+            .block_size(10)
+            .dense_nodes(false)
+            .run(segments, b, &mut ConversionStats::default())
+            .unwrap();
+
+        assert!(std::fs::metadata(b).unwrap().len() > std::fs::metadata(a).unwrap().len());
+        let diff = compare_pbf(a, b, MatchBy::Id).unwrap();
+        assert_eq!(diff.ways.matched.len(), diff.ways.count_a);
+        assert!(diff.ways.retagged.is_empty() && diff.ways.added.is_empty());
+    }
+
+    #[test]
+    fn parallel_encoding_writes_the_same_file() {
+        let dir = std::env::temp_dir();
+        let (a, b) = (dir.join("nvdb2osm_parallel.osm.pbf"), dir.join("nvdb2osm_sequential.osm.pbf"));
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        let segments = SyntheticNetwork::new(Layout::Radial, 8).segments();
+        let pipeline = Pipeline::new().block_size(50);
+        pipeline.clone().run(segments.clone(), a, &mut ConversionStats::default()).unwrap();
+        pipeline.parallel_encoding(false).run(segments, b, &mut ConversionStats::default()).unwrap();
+        assert_eq!(std::fs::read(a).unwrap(), std::fs::read(b).unwrap());
+    }
+
+    #[test]
+    fn railways_add_level_crossings() {
+        let mut road = segment(vec![(20.26, 63.83), (20.262, 63.83)]);
+        road.properties.insert("Vagsk_100".to_string(), PropertyValue::Integer(1));
+        let rail = LineString::from(vec![(20.2615, 63.82), (20.2615, 63.84)]);
+
+        let pipeline = Pipeline::new().railways(Some(vec![rail]));
+        let conversion = pipeline.convert(vec![road], &mut ConversionStats::default()).unwrap();
+        let crossing = &conversion.feature_nodes[0];
+        assert_eq!((crossing.lon, crossing.lat), (20.2615, 63.83));
+        assert_eq!(crossing.tags["crossing:barrier"], "full");
+        assert!(conversion.segments.iter().any(|s| s.start_coord().x == 20.2615));
+    }
+
+    #[test]
+    fn as_of_drops_future_segments() {
+        let mut road = segment(vec![(20.26, 63.83), (20.262, 63.83)]);
+        road.properties.insert("FROM_DATE".to_string(), PropertyValue::String("2031-01-01".to_string()));
+
+        let pipeline = Pipeline::new().as_of(Some("2030-06-30".to_string()));
+        assert!(pipeline.convert(vec![road], &mut ConversionStats::default()).unwrap().ways.is_empty());
+    }
+
+    #[test]
+    fn interrupt_check_stops_the_conversion() {
+        let segments = SyntheticNetwork::new(Layout::Grid, 3).segments();
+        let pipeline = Pipeline::new().interrupt_check(|| true);
+        let result = pipeline.convert(segments, &mut ConversionStats::default());
+        assert_eq!(result.err().as_deref(), Some(INTERRUPTED));
+    }
+
+    #[test]
+    fn fingerprint_ignores_output_settings() {
+        let pipeline = Pipeline::new().input_fingerprint(42);
+        assert_eq!(pipeline.clone().compression_level(0).fingerprint(), pipeline.fingerprint());
+        assert_ne!(pipeline.clone().split_loops(true).fingerprint(), pipeline.fingerprint());
+        assert_ne!(pipeline.clone().input_fingerprint(43).fingerprint(), pipeline.fingerprint());
+
+        /// A DEM that only has a file identity
+        #[derive(Debug)]
+        struct Dem(&'static str);
+        impl ElevationProvider for Dem {
+            fn elevation(&self, _lon: f64, _lat: f64) -> Option<f64> {
+                None
+            }
+            fn identity(&self) -> Option<String> {
+                Some(self.0.to_string())
+            }
+        }
+        let with_dem = |identity| pipeline.clone().elevation(Some(Arc::new(Dem(identity)))).fingerprint();
+        assert_eq!(with_dem("dem.tif (10 bytes)"), with_dem("dem.tif (10 bytes)"));
+        assert_ne!(with_dem("dem.tif (10 bytes)"), with_dem("dem.tif (12 bytes)"));
+    }
+
+    #[test]
+    fn resume_writes_a_converted_checkpoint() {
+        let dir = std::env::temp_dir();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let pipeline = Pipeline::new().checkpoint(Some(path("nvdb2osm-resume-test.bin")));
+
+        // Crashed while writing: the converted state is saved
+        let segments = SyntheticNetwork::new(Layout::Grid, 3).segments();
+        let mut stats = ConversionStats::default();
+        let conversion = pipeline.convert(segments, &mut stats).unwrap();
+        crate::checkpoint::save(&path("nvdb2osm-resume-test.bin"), &Checkpoint::Converted(conversion), pipeline.first_ids(), pipeline.fingerprint(), &stats).unwrap();
+
+        let mut stats = ConversionStats::default();
+        let checkpoint = pipeline.load_checkpoint(&mut stats).unwrap().unwrap();
+        assert_eq!(checkpoint.phase(), "converted");
+        pipeline.resume(checkpoint, &path("nvdb2osm-resume-test.opl"), &mut stats).unwrap();
+        assert!(stats.ways_out > 0 && stats.nodes_out > 0);
+        assert!(pipeline.load_checkpoint(&mut stats).unwrap().is_none());
+        std::fs::remove_file(path("nvdb2osm-resume-test.opl")).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+//! Finnish Digiroad (Väylävirasto) road link profile
+//!
+//! Expects the attribute names of the Digiroad K export (upper case):
+//! `TOIMINN_LK` (functional class 1-8), `LINKKITYYP` (link type),
+//! `HALLINN_LK` (administrative class), `AJOSUUNTA` (2 = both, 3 = against
+//! and 4 = along the digitised direction), `TIENUMERO`, `TIENIMI_SU` /
+//! `TIENIMI_RU`, `SILTA_ALIK`, `PAALLYSTE` (pavement class),
+//! `NOPEUSRAJOITUS` (km/h), the dimension limits `MAX_KORKEUS`, `MAX_LEVEYS`,
+//! `MAX_PITUUS` (cm) and `MAX_PAINO` (kg), and `AJONEUVORAJ` (vehicle
+//! restriction class, 3 = motor vehicles). Missing fields are simply not
+//! translated.
+
+use rustc_hash::FxHashMap;
+
+use super::{integer, text};
+use crate::models::PropertyValue;
+use crate::schema::Field;
+
+/// Translate the properties of one Finnish road link
+pub fn translate(p: &mut FxHashMap<String, PropertyValue>) {
+    let mut out: Vec<(Field, PropertyValue)> = Vec::new();
+    let flag = |out: &mut Vec<(Field, PropertyValue)>, key| out.push((key, PropertyValue::Boolean(true)));
+    let int = |out: &mut Vec<(Field, PropertyValue)>, key, v: i64| out.push((key, PropertyValue::Integer(v)));
+    let float = |out: &mut Vec<(Field, PropertyValue)>, key, v: f64| out.push((key, PropertyValue::Float(v)));
+
+    // Road owner: state, municipality or private
+    if let Some(owner @ 1..=3) = integer(p, "HALLINN_LK") {
+        int(&mut out, Field::RoadOwner, owner);
+    }
+
+    // Functional class: valtatie/kantatie/seututie map onto the Swedish road
+    // categories, connecting roads and streets onto the functional road class
+    let mut net_type = 1;
+    match integer(p, "TOIMINN_LK") {
+        Some(1) => int(&mut out, Field::RoadCategory, 2),
+        Some(2) => int(&mut out, Field::RoadCategory, 3),
+        Some(3) => int(&mut out, Field::RoadCategory, 4),
+        Some(4) => int(&mut out, Field::FunctionalClass, 5),
+        Some(5) => int(&mut out, Field::FunctionalClass, 6),
+        Some(6) => int(&mut out, Field::FunctionalClass, 8),
+        Some(7) => { int(&mut out, Field::FunctionalClass, 8); int(&mut out, Field::Accessibility, 1); }
+        Some(8) => { net_type = 2; int(&mut out, Field::GcmType, 1); }
+        _ => {}
+    }
+    match integer(p, "LINKKITYYP") {
+        Some(1) => flag(&mut out, Field::Motorway),
+        Some(4) => flag(&mut out, Field::Motorroad),
+        Some(5) => flag(&mut out, Field::RoundaboutForward),
+        Some(8) => net_type = 2,
+        Some(9) => flag(&mut out, Field::PedestrianStreetLeft),
+        Some(21) => flag(&mut out, Field::Ferry),
+        _ => {}
+    }
+    int(&mut out, Field::NetworkType, net_type);
+
+    match integer(p, "AJOSUUNTA") {
+        Some(3) => flag(&mut out, Field::NoEntryForward),
+        Some(4) => flag(&mut out, Field::NoEntryBackward),
+        _ => {}
+    }
+
+    if let Some(number) = integer(p, "TIENUMERO").filter(|&n| n > 0) {
+        out.push((Field::RoadNumber, PropertyValue::String(number.to_string())));
+    }
+    if let Some(name) = text(p, "TIENIMI_SU").or_else(|| text(p, "TIENIMI_RU")) {
+        out.push((Field::StreetName, PropertyValue::String(name)));
+    }
+
+    // 1 = bridge, -1 = underpass/tunnel
+    match integer(p, "SILTA_ALIK") {
+        Some(1) => int(&mut out, Field::StructureType, 1),
+        Some(-1) => int(&mut out, Field::StructureType, 3),
+        _ => {}
+    }
+
+    // Pavement classes 1-30 are bound surfaces, 40 is gravel
+    match integer(p, "PAALLYSTE") {
+        Some(1..=30) => int(&mut out, Field::Surface, 1),
+        Some(40) => int(&mut out, Field::Surface, 2),
+        _ => {}
+    }
+
+    if let Some(speed) = integer(p, "NOPEUSRAJOITUS").filter(|&v| v > 0) {
+        int(&mut out, Field::SpeedLimitForward, speed);
+        int(&mut out, Field::SpeedLimitBackward, speed);
+    }
+
+    // Dimension limits: Digiroad uses cm and kg, the tag mapper m and tonnes
+    if let Some(height) = integer(p, "MAX_KORKEUS").filter(|&v| v > 0) {
+        float(&mut out, Field::MaxHeight, height as f64 / 100.0);
+    }
+    if let Some(width) = integer(p, "MAX_LEVEYS").filter(|&v| v > 0) {
+        float(&mut out, Field::MaxWidth, width as f64 / 100.0);
+    }
+    if let Some(length) = integer(p, "MAX_PITUUS").filter(|&v| v > 0) {
+        float(&mut out, Field::MaxLength, length as f64 / 100.0);
+    }
+    if let Some(weight) = integer(p, "MAX_PAINO").filter(|&v| v > 0) {
+        float(&mut out, Field::MaxWeightForward, weight as f64 / 1000.0);
+        float(&mut out, Field::MaxWeightBackward, weight as f64 / 1000.0);
+    }
+    if integer(p, "AJONEUVORAJ") == Some(3) {
+        int(&mut out, Field::TrafficBanForward, 1);
+        int(&mut out, Field::TrafficBanBackward, 1);
+    }
+
+    // Never overwrite columns that are already present in the input
+    for (key, value) in out {
+        p.entry(key.column().to_string()).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Profile, PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn finnish_attributes_translate_to_osm_tags() {
+        let link = |properties: &[(&str, i64)]| {
+            let mut segment = segment(vec![(25.0, 60.2), (25.001, 60.2)]);
+            for (key, value) in properties {
+                segment.properties.insert(key.to_string(), PropertyValue::Integer(*value));
+            }
+            segment
+        };
+        let mut segments = vec![
+            link(&[("TOIMINN_LK", 1), ("NOPEUSRAJOITUS", 80), ("AJOSUUNTA", 4)]),
+            link(&[("TOIMINN_LK", 5), ("MAX_KORKEUS", 380), ("MAX_PAINO", 12000), ("AJONEUVORAJ", 3)]),
+            link(&[("TOIMINN_LK", 8)]),
+        ];
+        Profile::Finland.apply_all(&mut segments);
+        tag_network(&mut segments);
+
+        assert_eq!(segments[0].tags["highway"], "trunk");
+        assert_eq!(segments[0].tags["maxspeed"], "80");
+        assert_eq!(segments[0].tags["oneway"], "yes");
+        assert_eq!(segments[1].tags["highway"], "unclassified");
+        assert_eq!(segments[1].tags["maxheight"], "3.8");
+        assert_eq!(segments[1].tags["maxweight"], "12");
+        assert_eq!(segments[1].tags["motor_vehicle"], "no");
+        assert_eq!(segments[2].tags["highway"], "cycleway");
+    }
+}
@@ -0,0 +1,199 @@
+//! Attribute-schema profiles
+//!
+//! The tag mapper is written against the Swedish NVDB vocabulary (field names
+//! such as `Kateg_380` and their value codes). A profile translates the
+//! properties of another road database into that vocabulary before tagging, so
+//! tagging, topology and writing are shared by all countries.
+
+use std::str::FromStr;
+
+use geo_types::Coord;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::models::{PropertyValue, Segment};
+
+mod finland;
+mod norway;
+
+/// Source database of the input attributes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Profile {
+    /// Swedish NVDB (Trafikverket), the native vocabulary
+    #[default]
+    Sweden,
+    /// Norwegian NVDB (Statens vegvesen) road network
+    Norway,
+    /// Finnish Digiroad (Väylävirasto) road links
+    Finland,
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    /// Parse a country code or name, e.g. "se" or "norway"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "se" | "swe" | "sweden" => Ok(Profile::Sweden),
+            "no" | "nor" | "norway" => Ok(Profile::Norway),
+            "fi" | "fin" | "finland" => Ok(Profile::Finland),
+            _ => Err(format!("Unknown country '{}': expected \"se\", \"no\" or \"fi\"", s)),
+        }
+    }
+}
+
+impl Profile {
+    /// Translate one segment's properties into the Swedish NVDB vocabulary
+    pub fn apply(self, properties: &mut FxHashMap<String, PropertyValue>) {
+        match self {
+            Profile::Sweden => {}
+            Profile::Norway => norway::translate(properties),
+            Profile::Finland => finland::translate(properties),
+        }
+    }
+
+    /// Translate the properties of all segments
+    pub fn apply_all(self, segments: &mut [Segment]) {
+        if self == Profile::Sweden {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            self.apply(&mut segment.properties);
+        }
+    }
+}
+
+/// Why the input coordinates are not plausible for the profile
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundsError {
+    /// Vertex outside the longitude/latitude range: projected input
+    NotLonLat { row: usize, coord: Coord<f64>, crs: &'static str },
+    /// Vertex with latitude and longitude in the wrong order
+    Swapped { row: usize, coord: Coord<f64> },
+    /// Vertex outside the country's extent
+    OutsideCountry { row: usize, coord: Coord<f64>, country: &'static str },
+}
+
+impl std::fmt::Display for BoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Rows are reported 1-based, as in R
+        match self {
+            BoundsError::NotLonLat { row, coord, crs } => write!(
+                f,
+                "coordinate ({}, {}) in row {} is not a longitude/latitude; the input looks like it is still in {}, transform it to WGS84 (EPSG:4326) first",
+                coord.x, coord.y, row + 1, crs
+            ),
+            BoundsError::Swapped { row, coord } => write!(
+                f,
+                "coordinate ({}, {}) in row {} has latitude and longitude swapped; x must be the longitude",
+                coord.x, coord.y, row + 1
+            ),
+            BoundsError::OutsideCountry { row, coord, country } => write!(
+                f,
+                "coordinate ({}, {}) in row {} is outside {}; check the CRS of the input",
+                coord.x, coord.y, row + 1, country
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoundsError {}
+
+impl Profile {
+    /// Longitude/latitude extent of the country's road network with a
+    /// margin, as (west, south, east, north)
+    pub fn extent(self) -> (f64, f64, f64, f64) {
+        match self {
+            Profile::Sweden => (10.0, 55.0, 24.5, 69.5),
+            // Including Svalbard
+            Profile::Norway => (4.0, 57.5, 35.0, 81.0),
+            Profile::Finland => (19.0, 59.5, 32.0, 70.5),
+        }
+    }
+
+    fn country(self) -> &'static str {
+        match self {
+            Profile::Sweden => "Sweden",
+            Profile::Norway => "Norway",
+            Profile::Finland => "Finland",
+        }
+    }
+
+    /// Projected CRS the road database is delivered in
+    fn national_crs(self) -> &'static str {
+        match self {
+            Profile::Sweden => "SWEREF99 TM (EPSG:3006)",
+            Profile::Norway => "UTM zone 33N (EPSG:25833)",
+            Profile::Finland => "ETRS-TM35FIN (EPSG:3067)",
+        }
+    }
+
+    /// Check that all vertices are WGS84 longitude/latitude within `extent()`
+    ///
+    /// Projected input would otherwise be written as nonsense nanodegree
+    /// coordinates under a corrupt header bbox. The first offending vertex
+    /// in input order is reported.
+    pub fn check_bounds(self, segments: &[Segment]) -> Result<(), BoundsError> {
+        let (west, south, east, north) = self.extent();
+        let inside = |x: f64, y: f64| (west..=east).contains(&x) && (south..=north).contains(&y);
+        let error = segments.par_iter().find_map_first(|segment| {
+            let row = segment.source_row;
+            segment.geometry.0.iter().find_map(|&coord| {
+                if !(-180.0..=180.0).contains(&coord.x) || !(-90.0..=90.0).contains(&coord.y) {
+                    Some(BoundsError::NotLonLat { row, coord, crs: self.national_crs() })
+                } else if inside(coord.x, coord.y) {
+                    None
+                } else if inside(coord.y, coord.x) {
+                    Some(BoundsError::Swapped { row, coord })
+                } else {
+                    Some(BoundsError::OutsideCountry { row, coord, country: self.country() })
+                }
+            })
+        });
+        error.map_or(Ok(()), Err)
+    }
+}
+
+/// Read a property as text (null strings are already normalized away)
+fn text(properties: &FxHashMap<String, PropertyValue>, key: &str) -> Option<String> {
+    properties
+        .get(key)
+        .map(|v| v.as_string().trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Read a property as an integer, also accepting numeric strings
+fn integer(properties: &FxHashMap<String, PropertyValue>, key: &str) -> Option<i64> {
+    properties.get(key).and_then(|v| match v {
+        PropertyValue::String(s) => s.trim().parse::<f64>().ok().map(|f| f as i64),
+        other => other.as_i64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundsError, Profile};
+    use crate::test_support::segment;
+
+    #[test]
+    fn check_bounds_detects_projected_swapped_and_foreign_coordinates() {
+        let umea = segment(vec![(20.26, 63.83), (20.27, 63.83)]);
+        assert_eq!(Profile::Sweden.check_bounds(std::slice::from_ref(&umea)), Ok(()));
+
+        let sweref = segment(vec![(759_000.0, 7_085_000.0), (759_100.0, 7_085_000.0)]);
+        let error = Profile::Sweden.check_bounds(&[umea, sweref]).unwrap_err();
+        assert!(matches!(error, BoundsError::NotLonLat { .. }));
+        assert!(error.to_string().contains("SWEREF99 TM"));
+
+        let swapped = segment(vec![(63.83, 20.26), (63.83, 20.27)]);
+        assert!(matches!(Profile::Sweden.check_bounds(&[swapped]), Err(BoundsError::Swapped { .. })));
+        let malmo = segment(vec![(13.0, 55.6), (13.01, 55.6)]);
+        assert!(matches!(Profile::Finland.check_bounds(&[malmo]), Err(BoundsError::OutsideCountry { .. })));
+    }
+
+    #[test]
+    fn profile_from_country_code() {
+        assert_eq!("NO".parse::<Profile>(), Ok(Profile::Norway));
+        assert!("dk".parse::<Profile>().is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Norwegian NVDB (Statens vegvesen) road network profile
+//!
+//! Expects the attribute names of the NVDB vegnett export (lower case):
+//! `vegkategori` (E/R/F/K/P/S), `vegnummer`, `typeveg`, `trafikantgruppe`
+//! (K/G), `medium`, `feltoversikt` (e.g. "1#2"), `fartsgrense`, `gatenavn`,
+//! `motorvegtype` and `dekketype`. Missing fields are simply not translated.
+
+use rustc_hash::FxHashMap;
+
+use super::{integer, text};
+use crate::models::PropertyValue;
+use crate::schema::Field;
+
+/// Translate the properties of one Norwegian segment
+///
+/// Lanes in `feltoversikt` are numbered odd with the digitised direction and
+/// even against it, so "1#2K" is a two-way road whose backward lane is a
+/// public transport lane, while "1#3" only runs forward.
+pub fn translate(p: &mut FxHashMap<String, PropertyValue>) {
+    let mut out: Vec<(Field, PropertyValue)> = Vec::new();
+    let flag = |out: &mut Vec<(Field, PropertyValue)>, key| out.push((key, PropertyValue::Boolean(true)));
+    let int = |out: &mut Vec<(Field, PropertyValue)>, key, v: i64| out.push((key, PropertyValue::Integer(v)));
+
+    // Road category and road owner: E/R roads are trunk, fylkesveg secondary
+    match text(p, "vegkategori").map(|s| s.to_uppercase()).as_deref() {
+        Some("E") => { int(&mut out, Field::RoadCategory, 1); int(&mut out, Field::RoadOwner, 1); }
+        Some("R") => { int(&mut out, Field::RoadCategory, 2); int(&mut out, Field::RoadOwner, 1); }
+        Some("F") => { int(&mut out, Field::RoadCategory, 4); int(&mut out, Field::RoadOwner, 1); }
+        Some("K") => int(&mut out, Field::RoadOwner, 2),
+        Some("P") => int(&mut out, Field::RoadOwner, 3),
+        Some("S") => { int(&mut out, Field::RoadOwner, 3); int(&mut out, Field::Accessibility, 1); }
+        _ => {}
+    }
+    if let Some(number) = text(p, "vegnummer") {
+        out.push((Field::RoadNumber, PropertyValue::String(number)));
+    }
+
+    // Network type (1 = car, 2 = cycle/pedestrian, 4 = pedestrian) and GCM type
+    let mut net_type = match text(p, "trafikantgruppe").map(|s| s.to_uppercase()).as_deref() {
+        Some("G") => 2,
+        _ => 1,
+    };
+    match text(p, "typeveg").map(|s| s.to_lowercase()).as_deref() {
+        Some("rundkjøring") => flag(&mut out, Field::RoundaboutForward),
+        Some("bilferje") => { flag(&mut out, Field::Ferry); net_type = 1; }
+        Some("passasjerferje") => { flag(&mut out, Field::Ferry); net_type = 2; }
+        Some("gang- og sykkelveg") => { net_type = 2; int(&mut out, Field::GcmType, 1); }
+        Some("sykkelveg") => { net_type = 2; int(&mut out, Field::GcmType, 29); }
+        Some("fortau") => { net_type = 2; int(&mut out, Field::GcmType, 12); }
+        Some("trapp") => { net_type = 4; int(&mut out, Field::GcmType, 17); }
+        Some("gangveg") | Some("gangfelt") | Some("sti") => net_type = 4,
+        Some("gågate") => { net_type = 1; flag(&mut out, Field::PedestrianStreetLeft); }
+        Some("gatetun") => flag(&mut out, Field::LivingStreetLeft),
+        Some("traktorveg") => { int(&mut out, Field::RoadOwner, 3); int(&mut out, Field::Accessibility, 1); }
+        _ => {}
+    }
+    int(&mut out, Field::NetworkType, net_type);
+
+    // Bridges (in the air) and tunnels (under the terrain)
+    match text(p, "medium").map(|s| s.to_uppercase()).as_deref() {
+        Some("L") => int(&mut out, Field::StructureType, 1),
+        Some("U") => int(&mut out, Field::StructureType, 3),
+        _ => {}
+    }
+
+    // Lanes: odd numbers run with the digitised direction, even against it;
+    // a "K" suffix marks a public transport lane, "S" a cycle lane
+    if let Some(lanes) = text(p, "feltoversikt") {
+        let (mut forward, mut backward, mut count) = (false, false, 0);
+        for lane in lanes.split('#') {
+            let lane = lane.trim().to_uppercase();
+            let digits: String = lane.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let Ok(number) = digits.parse::<i64>() else { continue };
+            let suffix = &lane[digits.len()..];
+            if suffix.contains('S') {
+                continue;
+            }
+            count += 1;
+            let odd = number % 2 == 1;
+            if odd { forward = true } else { backward = true }
+            if suffix.contains('K') {
+                int(&mut out, if odd { Field::BusLaneForward } else { Field::BusLaneBackward }, 1);
+            }
+        }
+        if forward && !backward {
+            flag(&mut out, Field::NoEntryBackward);
+        } else if backward && !forward {
+            flag(&mut out, Field::NoEntryForward);
+        }
+        if count > 0 {
+            int(&mut out, Field::LaneCount, count);
+        }
+    }
+
+    if let Some(speed) = integer(p, "fartsgrense") {
+        int(&mut out, Field::SpeedLimitForward, speed);
+        int(&mut out, Field::SpeedLimitBackward, speed);
+    }
+    if let Some(name) = text(p, "gatenavn") {
+        out.push((Field::StreetName, PropertyValue::String(name)));
+    }
+    match text(p, "motorvegtype").map(|s| s.to_lowercase()).as_deref() {
+        Some("motorveg") => flag(&mut out, Field::Motorway),
+        Some("motortrafikkveg") => flag(&mut out, Field::Motorroad),
+        _ => {}
+    }
+    if let Some(surface) = text(p, "dekketype").map(|s| s.to_lowercase()) {
+        let paved = surface.contains("asfalt") || surface.contains("betong") || surface.contains("brostein");
+        int(&mut out, Field::Surface, if paved { 1 } else { 2 });
+    }
+
+    // Never overwrite columns that are already present in the input
+    for (key, value) in out {
+        p.entry(key.column().to_string()).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tag_network, Profile, PropertyValue};
+    use crate::test_support::segment;
+
+    #[test]
+    fn norwegian_attributes_translate_to_osm_tags() {
+        let road = |lanes: &str| {
+            let mut segment = segment(vec![(10.0, 63.0), (10.001, 63.0)]);
+            segment.properties.insert("vegkategori".to_string(), PropertyValue::String("K".to_string()));
+            segment.properties.insert("feltoversikt".to_string(), PropertyValue::String(lanes.to_string()));
+            segment
+        };
+        let mut segments = vec![road("1#2K"), road("1#3")];
+        Profile::Norway.apply_all(&mut segments);
+        tag_network(&mut segments);
+
+        assert!(!segments[0].tags.contains_key("oneway"));
+        assert_eq!(segments[0].tags["lanes:psv:backward"], "1");
+        assert_eq!(segments[1].tags["oneway"], "yes");
+        assert_eq!(segments[1].tags["lanes"], "2");
+    }
+}
@@ -0,0 +1,250 @@
+//! Routing engine presets
+//!
+//! The default output follows the OSM tagging of the Python script. Routing
+//! engines have further requirements on the graph they import; a preset
+//! adjusts the conversion so that the output goes through the engine's
+//! import without surprises:
+//!
+//! - `Osrm` (osrm-extract): a shared node wherever two ways cross at a common
+//!   vertex on the same layer, not only at segment ends; no way longer than
+//!   `MAX_WAY_NODES` nodes; `oneway` values normalized to `yes`, `no` and
+//!   `-1`; and no review tags (`fixme`, `note`), which the car profile would
+//!   otherwise copy into way names of some builds.
+//! - `Valhalla` and `GraphHopper`: the same, plus a `duration` on ferries
+//!   (both take the crossing time from it rather than from the length),
+//!   explicit `foot=no` and `bicycle=no` on motor roads (their pedestrian
+//!   and bicycle profiles do not read `motorroad`), and only the
+//!   conditional restrictions their parsers accept: Valhalla reads time
+//!   conditions but not vehicle properties (`no @ (weight>3.5)`),
+//!   GraphHopper only date ranges.
+
+use geo::HaversineLength;
+use rustc_hash::FxHashMap;
+
+use crate::models::{hash_coord, CoordHash, Segment, Way};
+
+/// Most nodes in one way (the OSM API limit)
+pub const MAX_WAY_NODES: usize = 2000;
+
+/// Ferry speed for the estimated crossing `duration`, about 10 knots
+pub const FERRY_SPEED_KMH: f64 = 18.0;
+
+/// Output preset for a routing engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoutingPreset {
+    /// Plain OSM output (the default)
+    #[default]
+    None,
+    /// Output for osrm-extract
+    Osrm,
+    /// Output for valhalla_build_tiles
+    Valhalla,
+    /// Output for the GraphHopper import
+    GraphHopper,
+}
+
+impl From<&str> for RoutingPreset {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "osrm" => RoutingPreset::Osrm,
+            "valhalla" => RoutingPreset::Valhalla,
+            "graphhopper" => RoutingPreset::GraphHopper,
+            _ => RoutingPreset::None, // Default
+        }
+    }
+}
+
+impl RoutingPreset {
+    /// Adjust the tagged segments before merging
+    pub fn prepare_segments(&self, segments: &mut Vec<Segment>) {
+        if *self != RoutingPreset::None {
+            split_at_shared_vertices(segments);
+        }
+    }
+
+    /// Adjust the merged ways; runs before any pass that refers to ways by
+    /// index (relations)
+    pub fn prepare_ways(&self, ways: &mut Vec<Way>, segments: &[Segment]) {
+        if *self != RoutingPreset::None {
+            split_long_ways(ways, segments, MAX_WAY_NODES);
+        }
+    }
+
+    /// Final tag cleanup of the ways
+    pub fn finish_tags(&self, ways: &mut [Way], segments: &[Segment]) {
+        if *self == RoutingPreset::None {
+            return;
+        }
+        for way in ways.iter_mut() {
+            way.tags.remove("fixme");
+            way.tags.remove("note");
+            if let Some(oneway) = way.tags.get("oneway") {
+                match normalized_oneway(oneway) {
+                    Some(value) => { way.tags.insert("oneway".to_string(), value.to_string()); }
+                    None => { way.tags.remove("oneway"); }
+                }
+            }
+            if *self == RoutingPreset::Osrm {
+                continue;
+            }
+
+            let ferry = way.tags.get("route").map(String::as_str) == Some("ferry");
+            if ferry && !way.tags.contains_key("duration") {
+                let metres: f64 = way.segment_indices.iter().map(|&s| segments[s].geometry.haversine_length()).sum();
+                way.tags.insert("duration".to_string(), ferry_duration(metres));
+            }
+            if way.tags.get("motorroad").map(String::as_str) == Some("yes") {
+                way.tags.entry("foot".to_string()).or_insert_with(|| "no".to_string());
+                way.tags.entry("bicycle".to_string()).or_insert_with(|| "no".to_string());
+            }
+            self.filter_conditionals(way);
+        }
+    }
+
+    /// Drop the conditions the engine cannot parse from `*:conditional` tags
+    fn filter_conditionals(&self, way: &mut Way) {
+        let keys: Vec<String> = way.tags.keys().filter(|k| k.ends_with(":conditional")).cloned().collect();
+        for key in keys {
+            let kept: Vec<&str> = way.tags[&key]
+                .split("; ")
+                .filter(|part| {
+                    let condition = part.split_once('@').map_or("", |(_, c)| c);
+                    let comparison = condition.contains(['<', '>', '=']);
+                    let time_of_day = condition.contains(':');
+                    match self {
+                        RoutingPreset::Valhalla => !comparison,
+                        RoutingPreset::GraphHopper => !comparison && !time_of_day,
+                        _ => true,
+                    }
+                })
+                .collect();
+            if kept.is_empty() {
+                way.tags.remove(&key);
+            } else {
+                let value = kept.join("; ");
+                way.tags.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Crossing time "H:MM" of a ferry line of `metres` at `FERRY_SPEED_KMH`,
+/// at least one minute
+pub(crate) fn ferry_duration(metres: f64) -> String {
+    let minutes = ((metres / 1000.0 / FERRY_SPEED_KMH * 60.0).round() as i64).max(1);
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// `yes`, `no` or `-1` for the common spellings of a oneway value
+fn normalized_oneway(value: &str) -> Option<&'static str> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" | "1" => Some("yes"),
+        "no" | "false" | "0" => Some("no"),
+        "-1" | "reverse" => Some("-1"),
+        _ => None,
+    }
+}
+
+/// Layer a segment is on (0 unless tagged)
+fn layer(segment: &Segment) -> i64 {
+    segment.tags.get("layer").and_then(|l| l.parse().ok()).unwrap_or(0)
+}
+
+/// Split segments at interior vertices that another segment on the same
+/// layer also passes through, so that the writer gives them one node
+fn split_at_shared_vertices(segments: &mut Vec<Segment>) {
+    let mut uses: FxHashMap<(CoordHash, i64), usize> = FxHashMap::default();
+    for segment in segments.iter() {
+        let layer = layer(segment);
+        for coord in &segment.geometry.0 {
+            *uses.entry((hash_coord(coord), layer)).or_default() += 1;
+        }
+    }
+
+    let mut pieces = Vec::new();
+    for segment in segments.iter_mut() {
+        let layer = layer(segment);
+        // Cut from the back so that earlier vertex indices stay valid
+        let cuts: Vec<usize> = (1..segment.geometry.0.len().saturating_sub(1))
+            .filter(|&k| uses.get(&(hash_coord(&segment.geometry.0[k]), layer)).copied().unwrap_or(0) > 1)
+            .collect();
+        for &k in cuts.iter().rev() {
+            pieces.push(segment.split_at_vertex(k));
+        }
+    }
+    segments.extend(pieces);
+}
+
+/// Split ways into runs of whole segments with at most `max_nodes` nodes
+fn split_long_ways(ways: &mut Vec<Way>, segments: &[Segment], max_nodes: usize) {
+    let mut out = Vec::with_capacity(ways.len());
+    for way in ways.drain(..) {
+        let mut current: Vec<(usize, bool)> = Vec::new();
+        let mut nodes = 1;
+        for (s, reversed) in way.oriented() {
+            let added = segments[s].geometry.0.len().saturating_sub(1);
+            if !current.is_empty() && nodes + added > max_nodes {
+                out.push(Way::from_oriented(std::mem::take(&mut current), way.tags.clone()));
+                nodes = 1;
+            }
+            current.push((s, reversed));
+            nodes += added;
+        }
+        out.push(Way::from_oriented(current, way.tags));
+    }
+    *ways = out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RoutingPreset, Way};
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn prepare_segments_splits_at_shared_vertices() {
+        // Two roads crossing at a vertex that is interior to both
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0), (18.002, 59.0)]),
+            segment(vec![(18.001, 58.999), (18.001, 59.0), (18.001, 59.001)]),
+        ];
+        RoutingPreset::None.prepare_segments(&mut segments);
+        assert_eq!(segments.len(), 2);
+
+        RoutingPreset::Osrm.prepare_segments(&mut segments);
+        assert_eq!(segments.len(), 4);
+        assert!(segments.iter().all(|s| s.geometry.0.len() == 2));
+        assert_eq!(segments[0].end_node, segments[1].end_node);
+    }
+
+    #[test]
+    fn routing_preset_from_name() {
+        assert_eq!(RoutingPreset::from("OSRM"), RoutingPreset::Osrm);
+        assert_eq!(RoutingPreset::from("graphhopper"), RoutingPreset::GraphHopper);
+        assert_eq!(RoutingPreset::from("none"), RoutingPreset::None);
+    }
+
+    #[test]
+    fn finish_tags_sets_routing_tags() {
+        let way = |pairs: &[(&str, &str)]| Way::new(vec![0], tags(pairs));
+        // A 9 km ferry line takes half an hour at 18 km/h
+        let segments = vec![segment(vec![(18.0, 59.0), (18.0, 59.081)])];
+        let mut ways = vec![
+            way(&[("oneway", "true"), ("fixme", "check")]),
+            way(&[("oneway", "reversible")]),
+            way(&[("route", "ferry"), ("motor_vehicle:conditional", "no @ (weight>3.5); no @ (22:00-06:00)")]),
+        ];
+
+        RoutingPreset::Osrm.finish_tags(&mut ways, &segments);
+        assert_eq!(ways[0].tags["oneway"], "yes");
+        assert!(!ways[0].tags.contains_key("fixme"));
+        assert!(!ways[1].tags.contains_key("oneway"));
+        assert!(!ways[2].tags.contains_key("duration"));
+
+        RoutingPreset::Valhalla.finish_tags(&mut ways, &segments);
+        assert_eq!(ways[2].tags["duration"], "0:30");
+        assert_eq!(ways[2].tags["motor_vehicle:conditional"], "no @ (22:00-06:00)");
+
+        RoutingPreset::GraphHopper.finish_tags(&mut ways, &segments);
+        assert!(!ways[2].tags.contains_key("motor_vehicle:conditional"));
+    }
+}
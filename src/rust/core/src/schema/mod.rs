@@ -0,0 +1,765 @@
+//! Attribute schema: semantic fields and their NVDB column names
+//!
+//! The tag mapper looks up segment properties by `Field` instead of by column
+//! name, so the mapping logic does not depend on how a particular NVDB release
+//! or delivery names its columns. `Field::column()` is the canonical name (the
+//! truncated name of the Vägnät GDB delivery); a `Schema` holds the alias
+//! tables of other deliveries, e.g. the full Swedish attribute names of the
+//! GeoPackage delivery, and renames input columns to the canonical names once
+//! per input, before any segment is built.
+
+use rustc_hash::FxHashMap;
+
+
+/// Semantic NVDB attribute used by the tag mapper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// Road category (Vägkategori): 1 E road, 2 national, 3 primary county, 4 other county
+    RoadCategory,
+    /// Main road number (Vägnummer/Huvudnummer)
+    RoadNumber,
+    /// Road number of state-subsidised private roads (Driftbidrag statligt/Vägnr)
+    GrantRoadNumber,
+    /// Road owner (Väghållare): 1 state, 2 municipal, 3 private
+    RoadOwner,
+    /// Functional road class (Funktionell vägklass) 0-9
+    FunctionalClass,
+    /// Network type (Vägtrafiknät): 1 car, 2 cycle, 4 pedestrian
+    NetworkType,
+    /// Municipality code (Kommunnummer)
+    Municipality,
+    /// Route identifier of the reference line
+    RouteId,
+    /// Date from which the record is valid, i.e. of its last change
+    ValidFrom,
+    /// Motorway (Motorväg)
+    Motorway,
+    /// Motor road (Motortrafikled)
+    Motorroad,
+    /// Ferry route (Färjeled)
+    Ferry,
+    /// Ferry route name (Färjeled/Namn)
+    FerryName,
+    /// Street name (Gatunamn/Namn)
+    StreetName,
+    /// Other road name (Övrigt vägnamn/Namn)
+    OtherName,
+    /// Bridge or tunnel name (Bro och tunnel/Namn)
+    StructureName,
+    /// Bridge or tunnel identity (Bro och tunnel/Identitet)
+    StructureId,
+    /// Bridge or tunnel construction (Bro och tunnel/Konstruktion)
+    StructureType,
+    /// Bridge load class (Bärighet/Bärighetsklass)
+    BridgeLoadClass,
+    /// Surface layer (Slitlager): 1 paved, 2 unpaved
+    Surface,
+    /// Road width (Vägbredd/Bredd)
+    Width,
+    /// Built-up area (Tättbebyggt område)
+    UrbanArea,
+    /// Accessibility class (Tillgänglighet)
+    Accessibility,
+    /// Trafficability class (Framkomlighet för vissa fordonskombinationer)
+    Trafficability,
+    /// Delivery quality class (Leveranskvalitet DoU 2017)
+    DeliveryQuality,
+    /// Road class for public transport and heavy traffic (FPV-klass)
+    FpvClass,
+    /// Prioritised public transport road (FPV kollektivtrafik)
+    FpvPublicTransport,
+    /// Speed limit with the digitised direction (Hastighetsgräns)
+    SpeedLimitForward,
+    /// Speed limit against the digitised direction (Hastighetsgräns)
+    SpeedLimitBackward,
+    /// Travel with the digitised direction forbidden (Förbjuden färdriktning)
+    NoEntryForward,
+    /// Travel against the digitised direction forbidden (Förbjuden färdriktning)
+    NoEntryBackward,
+    /// Number of lanes (Antal körfält/Körfältsantal)
+    LaneCount,
+    /// Lane usage (Körfältsanvändning)
+    LaneUsage,
+    /// Public transport lane with the digitised direction (Kollektivkörfält)
+    BusLaneForward,
+    /// Public transport lane against the digitised direction (Kollektivkörfält)
+    BusLaneBackward,
+    /// Roundabout with the digitised direction (Cirkulationsplats)
+    RoundaboutForward,
+    /// Roundabout against the digitised direction (Cirkulationsplats)
+    RoundaboutBackward,
+    /// No overtaking with the digitised direction (Omkörningsförbud)
+    NoOvertakingForward,
+    /// No overtaking against the digitised direction (Omkörningsförbud)
+    NoOvertakingBackward,
+    /// Traffic ban with the digitised direction (Förbud mot trafik)
+    TrafficBanForward,
+    /// Traffic ban against the digitised direction (Förbud mot trafik)
+    TrafficBanBackward,
+    /// Vehicle type of the forward traffic ban (Förbud mot trafik/Gäller fordon)
+    BanVehicleForward,
+    /// Vehicle type of the backward traffic ban (Förbud mot trafik/Gäller fordon)
+    BanVehicleBackward,
+    /// Total weight of the forward traffic ban (Förbud mot trafik/Totalvikt)
+    BanWeightForward,
+    /// Total weight of the backward traffic ban (Förbud mot trafik/Totalvikt)
+    BanWeightBackward,
+    /// Height obstacle (Höjdhinder/Fri höjd)
+    MaxHeight,
+    /// Maximum vehicle width (Begränsad fordonsbredd)
+    MaxWidth,
+    /// Maximum vehicle length (Begränsad fordonslängd)
+    MaxLength,
+    /// Maximum axle load (Begränsat axel-boggitryck)
+    MaxAxleLoad,
+    /// Maximum gross weight with the digitised direction (Begränsad bruttovikt)
+    MaxWeightForward,
+    /// Maximum gross weight against the digitised direction (Begränsad bruttovikt)
+    MaxWeightBackward,
+    /// Dangerous goods restriction with the digitised direction (Farligt gods)
+    HazmatForward,
+    /// Dangerous goods restriction against the digitised direction (Farligt gods)
+    HazmatBackward,
+    /// Recommended road for dangerous goods (Rekommenderad väg för farligt gods)
+    HazmatRecommended,
+    /// Low emission zone (Miljözon)
+    LowEmissionZone,
+    /// Lit cycle/pedestrian way (GCM-belysning)
+    Lit,
+    /// Cycle/pedestrian way type (GCM-vägtyp)
+    GcmType,
+    /// Separated cycle/pedestrian way, left side (GCM-separation)
+    SeparatedLeft,
+    /// Separated cycle/pedestrian way, right side (GCM-separation)
+    SeparatedRight,
+    /// Pedestrian street, left side (Gågata)
+    PedestrianStreetLeft,
+    /// Pedestrian street, right side (Gågata)
+    PedestrianStreetRight,
+    /// Living street, left side (Gångfartsområde)
+    LivingStreetLeft,
+    /// Living street, right side (Gångfartsområde)
+    LivingStreetRight,
+    /// Recommended road for cycle traffic (Rekommenderad bilväg för cykeltrafik)
+    CycleRouteRecommended,
+    /// Cycle route name (Cykelled/Namn)
+    CycleRouteName,
+    /// Cycle/pedestrian crossing type (GCM-passage/Passagetyp)
+    CrossingType,
+    /// Railway crossing protection (Järnvägskorsning/Vägskydd)
+    RailwayCrossing,
+    /// Traffic calming type (Farthinder/Typ)
+    TrafficCalming,
+    /// Barrier type (Väghinder/Hindertyp)
+    Barrier,
+    /// Passage width at a barrier (Väghinder/Passerbar bredd)
+    BarrierPassage,
+    /// Speed camera with the digitised direction (ATK-Mätplats)
+    SpeedCameraForward,
+    /// Speed camera against the digitised direction (ATK-Mätplats)
+    SpeedCameraBackward,
+    /// Rest area (Rastplats)
+    RestArea,
+    /// Rest area name (Rastplats/Rastplatsnamn)
+    RestAreaName,
+    /// Rest area parking spaces for cars (Rastplats/Antal parkeringsplatser)
+    RestAreaCarSpaces,
+    /// Rest area parking spaces for lorries (Rastplats/Antal parkeringsplatser för lastbil)
+    RestAreaHgvSpaces,
+    /// Toilets at a rest area (Rastplats/Toalett)
+    RestAreaToilets,
+    /// Restaurant or café at a rest area (Rastplats/Servering)
+    RestAreaRestaurant,
+    /// Fuel station at a rest area (Rastplats/Drivmedel)
+    RestAreaFuel,
+    /// Lay-by, left side (Rastficka)
+    LayByLeft,
+    /// Lay-by, right side (Rastficka)
+    LayByRight,
+    /// Number of steps of a stairway (Trappa/Antal steg)
+    StepCount,
+    /// Stairway handrail (Trappa/Ledstång)
+    Handrail,
+    /// Incline in percent along the digitised direction (Lutning)
+    Incline,
+    /// Levels served by an elevator (Hiss/Våningsplan)
+    ElevatorLevel,
+    /// Platform or stop number (Plattform/Plattformsnummer)
+    PlatformRef,
+    /// Start of the hours a pedestrian street applies (Gågata/Gäller från klockan)
+    PedestrianStreetFrom,
+    /// End of the hours a pedestrian street applies (Gågata/Gäller till klockan)
+    PedestrianStreetTo,
+    /// Turning place at a road end: 1 turning circle, 2 turning loop (Vändmöjlighet)
+    TurningPlace,
+    /// Heavy vehicle control site: 1 weighing, 2 checkpoint, 3 both (Kontrollplats/Typ)
+    ControlSite,
+    /// Authority running a control site (Kontrollplats/Huvudman)
+    ControlSiteOperator,
+    /// Ford: 1 along the segment, 2 at a point (Vadställe/Typ)
+    Ford,
+    /// Passability of a ford: 1 all year, 2 winter only (Vadställe/Farbarhet)
+    FordPassability,
+    /// Guard rail, left side (Räcke)
+    GuardRailLeft,
+    /// Guard rail, right side (Räcke)
+    GuardRailRight,
+    /// Noise barrier, left side (Bullerskydd)
+    NoiseBarrierLeft,
+    /// Noise barrier, right side (Bullerskydd)
+    NoiseBarrierRight,
+    /// Link status: 1 open, 2 under construction, 3 planned (Länkstatus)
+    LinkStatus,
+    /// Date until which the record is valid (exclusive), if it has ended
+    ValidTo,
+    /// Chainage at the start of the segment, in km (Längdmätning/Från km)
+    ChainageFrom,
+    /// Chainage at the end of the segment, in km (Längdmätning/Till km)
+    ChainageTo,
+    /// Wildlife fence, left side (Viltstängsel)
+    WildlifeFenceLeft,
+    /// Wildlife fence, right side (Viltstängsel)
+    WildlifeFenceRight,
+    /// At-grade wildlife crossing (Viltpassage i plan)
+    WildlifeCrossing,
+    /// School zone (Skolområde)
+    SchoolZone,
+    /// Speed limit in a school zone during school hours (Skolområde/Hastighet)
+    SchoolZoneSpeed,
+    /// Start of the school hours (Skolområde/Från kl)
+    SchoolZoneFrom,
+    /// End of the school hours (Skolområde/Till kl)
+    SchoolZoneTo,
+}
+
+impl Field {
+    pub const ALL: [Field; 108] = [
+        Field::RoadCategory,
+        Field::RoadNumber,
+        Field::GrantRoadNumber,
+        Field::RoadOwner,
+        Field::FunctionalClass,
+        Field::NetworkType,
+        Field::Municipality,
+        Field::RouteId,
+        Field::ValidFrom,
+        Field::Motorway,
+        Field::Motorroad,
+        Field::Ferry,
+        Field::FerryName,
+        Field::StreetName,
+        Field::OtherName,
+        Field::StructureName,
+        Field::StructureId,
+        Field::StructureType,
+        Field::BridgeLoadClass,
+        Field::Surface,
+        Field::Width,
+        Field::UrbanArea,
+        Field::Accessibility,
+        Field::Trafficability,
+        Field::DeliveryQuality,
+        Field::FpvClass,
+        Field::FpvPublicTransport,
+        Field::SpeedLimitForward,
+        Field::SpeedLimitBackward,
+        Field::NoEntryForward,
+        Field::NoEntryBackward,
+        Field::LaneCount,
+        Field::LaneUsage,
+        Field::BusLaneForward,
+        Field::BusLaneBackward,
+        Field::RoundaboutForward,
+        Field::RoundaboutBackward,
+        Field::NoOvertakingForward,
+        Field::NoOvertakingBackward,
+        Field::TrafficBanForward,
+        Field::TrafficBanBackward,
+        Field::BanVehicleForward,
+        Field::BanVehicleBackward,
+        Field::BanWeightForward,
+        Field::BanWeightBackward,
+        Field::MaxHeight,
+        Field::MaxWidth,
+        Field::MaxLength,
+        Field::MaxAxleLoad,
+        Field::MaxWeightForward,
+        Field::MaxWeightBackward,
+        Field::HazmatForward,
+        Field::HazmatBackward,
+        Field::HazmatRecommended,
+        Field::LowEmissionZone,
+        Field::Lit,
+        Field::GcmType,
+        Field::SeparatedLeft,
+        Field::SeparatedRight,
+        Field::PedestrianStreetLeft,
+        Field::PedestrianStreetRight,
+        Field::LivingStreetLeft,
+        Field::LivingStreetRight,
+        Field::CycleRouteRecommended,
+        Field::CycleRouteName,
+        Field::CrossingType,
+        Field::RailwayCrossing,
+        Field::TrafficCalming,
+        Field::Barrier,
+        Field::BarrierPassage,
+        Field::SpeedCameraForward,
+        Field::SpeedCameraBackward,
+        Field::RestArea,
+        Field::RestAreaName,
+        Field::RestAreaCarSpaces,
+        Field::RestAreaHgvSpaces,
+        Field::RestAreaToilets,
+        Field::RestAreaRestaurant,
+        Field::RestAreaFuel,
+        Field::LayByLeft,
+        Field::LayByRight,
+        Field::StepCount,
+        Field::Handrail,
+        Field::Incline,
+        Field::ElevatorLevel,
+        Field::PlatformRef,
+        Field::PedestrianStreetFrom,
+        Field::PedestrianStreetTo,
+        Field::TurningPlace,
+        Field::ControlSite,
+        Field::ControlSiteOperator,
+        Field::Ford,
+        Field::FordPassability,
+        Field::GuardRailLeft,
+        Field::GuardRailRight,
+        Field::NoiseBarrierLeft,
+        Field::NoiseBarrierRight,
+        Field::LinkStatus,
+        Field::ValidTo,
+        Field::ChainageFrom,
+        Field::ChainageTo,
+        Field::WildlifeFenceLeft,
+        Field::WildlifeFenceRight,
+        Field::WildlifeCrossing,
+        Field::SchoolZone,
+        Field::SchoolZoneSpeed,
+        Field::SchoolZoneFrom,
+        Field::SchoolZoneTo,
+    ];
+
+    /// Fields only some deliveries have (accessibility data of stairs, ramps
+    /// and elevators, platform numbers, pedestrian street hours, turning
+    /// places, rest area facilities, control sites, fords, guard rails,
+    /// noise barriers, link status, validity end, chainage, wildlife,
+    /// school zones); their absence is not reported by `resolve_columns()`
+    pub const OPTIONAL: [Field; 30] = [
+        Field::StepCount,
+        Field::Handrail,
+        Field::Incline,
+        Field::ElevatorLevel,
+        Field::PlatformRef,
+        Field::PedestrianStreetFrom,
+        Field::PedestrianStreetTo,
+        Field::TurningPlace,
+        Field::RestAreaToilets,
+        Field::RestAreaRestaurant,
+        Field::RestAreaFuel,
+        Field::ControlSite,
+        Field::ControlSiteOperator,
+        Field::Ford,
+        Field::FordPassability,
+        Field::GuardRailLeft,
+        Field::GuardRailRight,
+        Field::NoiseBarrierLeft,
+        Field::NoiseBarrierRight,
+        Field::LinkStatus,
+        Field::ValidTo,
+        Field::ChainageFrom,
+        Field::ChainageTo,
+        Field::WildlifeFenceLeft,
+        Field::WildlifeFenceRight,
+        Field::WildlifeCrossing,
+        Field::SchoolZone,
+        Field::SchoolZoneSpeed,
+        Field::SchoolZoneFrom,
+        Field::SchoolZoneTo,
+    ];
+
+    /// Canonical column name
+    pub fn column(self) -> &'static str {
+        match self {
+            Field::RoadCategory => "Kateg_380",
+            Field::RoadNumber => "Huvnr_556_1",
+            Field::GrantRoadNumber => "Vagnr_10370",
+            Field::RoadOwner => "Vagha_6",
+            Field::FunctionalClass => "Klass_181",
+            Field::NetworkType => "Vagtr_474",
+            Field::Municipality => "Kommu_141",
+            Field::RouteId => "ROUTE_ID",
+            Field::ValidFrom => "FROM_DATE",
+            Field::Motorway => "Motorvag",
+            Field::Motorroad => "Motortrafikled",
+            Field::Ferry => "Farjeled",
+            Field::FerryName => "Farje_139",
+            Field::StreetName => "Namn_130",
+            Field::OtherName => "Namn_132",
+            Field::StructureName => "Namn_193",
+            Field::StructureId => "Ident_191",
+            Field::StructureType => "Konst_190",
+            Field::BridgeLoadClass => "Barig_64",
+            Field::Surface => "Slitl_152",
+            Field::Width => "Bredd_156",
+            Field::UrbanArea => "TattbebyggtOmrade",
+            Field::Accessibility => "Tillg_169",
+            Field::Trafficability => "Framk_161",
+            Field::DeliveryQuality => "Lever_292",
+            Field::FpvClass => "FPV_k_309",
+            Field::FpvPublicTransport => "FPV_kollektivtrafik",
+            Field::SpeedLimitForward => "F_Hogst_225",
+            Field::SpeedLimitBackward => "B_Hogst_225",
+            Field::NoEntryForward => "F_ForbjudenFardriktning",
+            Field::NoEntryBackward => "B_ForbjudenFardriktning",
+            Field::LaneCount => "Korfa_497",
+            Field::LaneUsage => "Korfa_524",
+            Field::BusLaneForward => "F_Korfa_517",
+            Field::BusLaneBackward => "B_Korfa_517",
+            Field::RoundaboutForward => "F_Cirkulationsplats",
+            Field::RoundaboutBackward => "B_Cirkulationsplats",
+            Field::NoOvertakingForward => "F_Omkorningsforbud",
+            Field::NoOvertakingBackward => "B_Omkorningsforbud",
+            Field::TrafficBanForward => "F_ForbudTrafik",
+            Field::TrafficBanBackward => "B_ForbudTrafik",
+            Field::BanVehicleForward => "Typ_512",
+            Field::BanVehicleBackward => "Typ_369",
+            Field::BanWeightForward => "F_Total_136",
+            Field::BanWeightBackward => "B_Total_136",
+            Field::MaxHeight => "Fri_h_143",
+            Field::MaxWidth => "Hogst_36",
+            Field::MaxLength => "Hogst_46",
+            Field::MaxAxleLoad => "Hogst_55_30",
+            Field::MaxWeightForward => "F_Hogst_24",
+            Field::MaxWeightBackward => "B_Hogst_24",
+            Field::HazmatForward => "F_Beskr_124",
+            Field::HazmatBackward => "B_Beskr_124",
+            Field::HazmatRecommended => "Rekom_185",
+            Field::LowEmissionZone => "Miljozon",
+            Field::Lit => "GCM_belyst",
+            Field::GcmType => "GCM_t_502",
+            Field::SeparatedLeft => "L_Separ_500",
+            Field::SeparatedRight => "R_Separ_500",
+            Field::PedestrianStreetLeft => "L_Gagata",
+            Field::PedestrianStreetRight => "R_Gagata",
+            Field::LivingStreetLeft => "L_Gangfartsomrade",
+            Field::LivingStreetRight => "R_Gangfartsomrade",
+            Field::CycleRouteRecommended => "C_Rekbilvagcykeltrafik",
+            Field::CycleRouteName => "Namn_457",
+            Field::CrossingType => "Passa_85",
+            Field::RailwayCrossing => "Vagsk_100",
+            Field::TrafficCalming => "TypAv_82",
+            Field::Barrier => "Hinde_72",
+            Field::BarrierPassage => "Passe_73",
+            Field::SpeedCameraForward => "F_ATK_Matplats",
+            Field::SpeedCameraBackward => "B_ATK_Matplats",
+            Field::RestArea => "Rastplats",
+            Field::RestAreaName => "Rastp_118",
+            Field::RestAreaCarSpaces => "Antal_119",
+            Field::RestAreaHgvSpaces => "Antal_122",
+            Field::RestAreaToilets => "Rastplats_Toalett",
+            Field::RestAreaRestaurant => "Rastplats_Servering",
+            Field::RestAreaFuel => "Rastplats_Drivmedel",
+            Field::LayByLeft => "L_Rastficka_2",
+            Field::LayByRight => "R_Rastficka_2",
+            Field::StepCount => "Antal_steg",
+            Field::Handrail => "Ledstang",
+            Field::Incline => "Lutning",
+            Field::ElevatorLevel => "Vaningsplan",
+            Field::PlatformRef => "Plattformsnummer",
+            Field::PedestrianStreetFrom => "Gagata_Fran_kl",
+            Field::PedestrianStreetTo => "Gagata_Till_kl",
+            Field::TurningPlace => "Vandmojlighet",
+            Field::ControlSite => "Kontrollplats_Typ",
+            Field::ControlSiteOperator => "Kontrollplats_Huvudman",
+            Field::Ford => "Vadstalle",
+            Field::FordPassability => "Vadstalle_Farbarhet",
+            Field::GuardRailLeft => "L_Racke",
+            Field::GuardRailRight => "R_Racke",
+            Field::NoiseBarrierLeft => "L_Bullerskydd",
+            Field::NoiseBarrierRight => "R_Bullerskydd",
+            Field::LinkStatus => "Lankstatus",
+            Field::ValidTo => "TO_DATE",
+            Field::ChainageFrom => "Langdmatning_Fran",
+            Field::ChainageTo => "Langdmatning_Till",
+            Field::WildlifeFenceLeft => "L_Viltstangsel",
+            Field::WildlifeFenceRight => "R_Viltstangsel",
+            Field::WildlifeCrossing => "Viltpassage_i_plan",
+            Field::SchoolZone => "Skolomrade",
+            Field::SchoolZoneSpeed => "Skolomrade_Hastighet",
+            Field::SchoolZoneFrom => "Skolomrade_Fran_kl",
+            Field::SchoolZoneTo => "Skolomrade_Till_kl",
+        }
+    }
+}
+
+/// Full attribute names of the GeoPackage delivery
+const GPKG_COLUMNS: [(Field, &str); 103] = [
+    (Field::RoadCategory, "Vägkategori/Kategori"),
+    (Field::RoadNumber, "Vägnummer/Huvudnummer"),
+    (Field::GrantRoadNumber, "Driftbidrag statligt/Vägnr"),
+    (Field::RoadOwner, "Väghållare/Väghållartyp"),
+    (Field::FunctionalClass, "Funktionell vägklass/Klass"),
+    (Field::NetworkType, "Vägtrafiknät/Nättyp"),
+    (Field::Municipality, "Kommunnr"),
+    (Field::Motorway, "Motorväg"),
+    (Field::Ferry, "Färjeled"),
+    (Field::FerryName, "Färjeled/Färjeledsnamn"),
+    (Field::StreetName, "Gatunamn/Namn"),
+    (Field::OtherName, "Övrigt vägnamn/Namn"),
+    (Field::StructureName, "Bro och tunnel/Namn"),
+    (Field::StructureId, "Bro och tunnel/Identitet"),
+    (Field::StructureType, "Bro och tunnel/Konstruktion"),
+    (Field::BridgeLoadClass, "Bärighet/Bärighetsklass"),
+    (Field::Surface, "Slitlager/Slitlagertyp"),
+    (Field::Width, "Vägbredd/Bredd"),
+    (Field::UrbanArea, "Tättbebyggt område"),
+    (Field::Accessibility, "Tillgänglighet/Tillgänglighetsklass"),
+    (Field::Trafficability, "Framkomlighet för vissa fordonskombinationer/Framkomlighetsklass"),
+    (Field::DeliveryQuality, "Leveranskvalitet DoU 2017/Leveranskvalitetsklass DoU 2017"),
+    (Field::FpvClass, "Funktionellt prioriterat vägnät/FPV-klass"),
+    (Field::FpvPublicTransport, "FPV-Kollektivtrafik"),
+    (Field::SpeedLimitForward, "Hastighetsgräns/Högsta tillåtna hastighet(F)"),
+    (Field::SpeedLimitBackward, "Hastighetsgräns/Högsta tillåtna hastighet(B)"),
+    (Field::NoEntryForward, "Förbjuden färdriktning(F)"),
+    (Field::NoEntryBackward, "Förbjuden färdriktning(B)"),
+    (Field::LaneCount, "Antal körfält/Körfältsantal"),
+    (Field::LaneUsage, "Körfältsinformation"),
+    (Field::BusLaneForward, "Kollektivkörfält/Körfält-Körbana(F)"),
+    (Field::BusLaneBackward, "Kollektivkörfält/Körfält-Körbana(B)"),
+    (Field::RoundaboutForward, "Cirkulationsplats(F)"),
+    (Field::RoundaboutBackward, "Cirkulationsplats(B)"),
+    (Field::NoOvertakingForward, "Omkörningsförbud(F)"),
+    (Field::NoOvertakingBackward, "Omkörningsförbud(B)"),
+    (Field::TrafficBanForward, "Förbud mot trafik(F)"),
+    (Field::TrafficBanBackward, "Förbud mot trafik(B)"),
+    (Field::BanVehicleForward, "Förbud mot trafik/Gäller fordon(F)"),
+    (Field::BanVehicleBackward, "Förbud mot trafik/Gäller fordon(B)"),
+    (Field::BanWeightForward, "Förbud mot trafik/Totalvikt(F)"),
+    (Field::BanWeightBackward, "Förbud mot trafik/Totalvikt(B)"),
+    (Field::MaxHeight, "Höjdhinder upp till 4,5 m/Fri höjd"),
+    (Field::MaxWidth, "Begränsad fordonsbredd/Högsta tillåtna fordonsbredd"),
+    (Field::MaxLength, "Begränsad fordonslängd/Högsta tillåtna fordonslängd"),
+    (Field::MaxAxleLoad, "Begränsat axel-boggitryck/Högsta tillåtna tryck"),
+    (Field::MaxWeightForward, "Begränsad bruttovikt/Högsta tillåtna bruttovikt(F)"),
+    (Field::MaxWeightBackward, "Begränsad bruttovikt/Högsta tillåtna bruttovikt(B)"),
+    (Field::HazmatForward, "Inskränkningar för transport av farligt gods/Beskrivning(F)"),
+    (Field::HazmatBackward, "Inskränkningar för transport av farligt gods/Beskrivning(B)"),
+    (Field::HazmatRecommended, "Rekommenderad väg för farligt gods/Rekommendation"),
+    (Field::LowEmissionZone, "Miljözon"),
+    (Field::Lit, "GCM-belyst"),
+    (Field::GcmType, "GCM-vägtyp/GCM-typ"),
+    (Field::SeparatedLeft, "GCM-separation/Separation(V)"),
+    (Field::SeparatedRight, "GCM-separation/Separation(H)"),
+    (Field::PedestrianStreetLeft, "Gågata(V)"),
+    (Field::PedestrianStreetRight, "Gågata(H)"),
+    (Field::LivingStreetLeft, "Gångfartsområde(V)"),
+    (Field::LivingStreetRight, "Gångfartsområde(H)"),
+    (Field::CycleRouteRecommended, "C-Rekommenderad bilväg for cykel"),
+    (Field::CycleRouteName, "C-Cykelled/Namn"),
+    (Field::CrossingType, "GCM-passage/Passagetyp"),
+    (Field::RailwayCrossing, "Järnvägskorsning/Vägskydd"),
+    (Field::TrafficCalming, "Farthinder/Typ"),
+    (Field::Barrier, "Väghinder/Hindertyp"),
+    (Field::BarrierPassage, "Väghinder/Passerbar bredd"),
+    (Field::SpeedCameraForward, "ATK-Mätplats(F)"),
+    (Field::SpeedCameraBackward, "ATK-Mätplats(B)"),
+    (Field::RestAreaName, "Rastplats/Rastplatsnamn"),
+    (Field::RestAreaCarSpaces, "Rastplats/Antal markerade parkeringsplatser för personbil"),
+    (Field::RestAreaHgvSpaces, "Rastplats/Antal markerade parkeringsplatser för lastbil+släp"),
+    (Field::RestAreaToilets, "Rastplats/Toalett"),
+    (Field::RestAreaRestaurant, "Rastplats/Servering"),
+    (Field::RestAreaFuel, "Rastplats/Drivmedel"),
+    (Field::LayByLeft, "Rastficka(V)"),
+    (Field::LayByRight, "Rastficka(H)"),
+    (Field::StepCount, "Trappa/Antal steg"),
+    (Field::Handrail, "Trappa/Ledstång"),
+    (Field::Incline, "Lutning/Lutning"),
+    (Field::ElevatorLevel, "Hiss/Våningsplan"),
+    (Field::PlatformRef, "Plattform/Plattformsnummer"),
+    (Field::PedestrianStreetFrom, "Gågata/Gäller från klockan"),
+    (Field::PedestrianStreetTo, "Gågata/Gäller till klockan"),
+    (Field::TurningPlace, "Vändmöjlighet/Typ"),
+    (Field::ControlSite, "Kontrollplats/Typ"),
+    (Field::ControlSiteOperator, "Kontrollplats/Huvudman"),
+    (Field::Ford, "Vadställe/Typ"),
+    (Field::FordPassability, "Vadställe/Farbarhet"),
+    (Field::GuardRailLeft, "Räcke(V)"),
+    (Field::GuardRailRight, "Räcke(H)"),
+    (Field::NoiseBarrierLeft, "Bullerskydd(V)"),
+    (Field::NoiseBarrierRight, "Bullerskydd(H)"),
+    (Field::LinkStatus, "Länkstatus/Status"),
+    (Field::ChainageFrom, "Längdmätning/Från km"),
+    (Field::ChainageTo, "Längdmätning/Till km"),
+    (Field::WildlifeFenceLeft, "Viltstängsel(V)"),
+    (Field::WildlifeFenceRight, "Viltstängsel(H)"),
+    (Field::WildlifeCrossing, "Viltpassage i plan"),
+    (Field::SchoolZone, "Skolområde"),
+    (Field::SchoolZoneSpeed, "Skolområde/Hastighet"),
+    (Field::SchoolZoneFrom, "Skolområde/Från kl"),
+    (Field::SchoolZoneTo, "Skolområde/Till kl"),
+];
+
+/// Column aliases of one NVDB product version
+///
+/// Aliases are matched ignoring case, Swedish letters and punctuation, so
+/// "Vägkategori/Kategori" and "vagkategori_kategori" both resolve to
+/// `Field::RoadCategory`.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// Folded alias name → field
+    aliases: FxHashMap<String, Field>,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::nvdb()
+    }
+}
+
+impl Schema {
+    /// Current NVDB Vägnät deliveries (GDB and GeoPackage)
+    pub fn nvdb() -> Self {
+        let mut schema = Self { aliases: FxHashMap::default() };
+        for field in Field::ALL {
+            schema = schema.column(field, field.column());
+        }
+        for (field, name) in GPKG_COLUMNS {
+            schema = schema.column(field, name);
+        }
+        // Older extracts suffix the speed camera columns with the attribute id
+        schema
+            .column(Field::SpeedCameraForward, "F_ATK_Matplats_117")
+            .column(Field::SpeedCameraBackward, "B_ATK_Matplats_117")
+    }
+
+    /// Declare that this version stores `field` in column `name`
+    pub fn column(mut self, field: Field, name: impl AsRef<str>) -> Self {
+        self.aliases.insert(fold(name.as_ref()), field);
+        self
+    }
+
+    /// Field stored in column `name`, if any
+    pub fn field(&self, name: &str) -> Option<Field> {
+        self.aliases.get(&fold(name)).copied()
+    }
+
+    /// Rename input columns to their canonical names in place
+    ///
+    /// Returns the fields that no column resolved to, in `Field::ALL` order,
+    /// leaving out `Field::OPTIONAL` ones.
+    /// Columns that are not NVDB fields are left untouched, and a column that
+    /// already has the canonical name wins over its aliases.
+    pub fn resolve_columns(&self, names: &mut [String]) -> Vec<Field> {
+        let mut found: FxHashMap<Field, usize> = FxHashMap::default();
+        for (i, name) in names.iter().enumerate() {
+            if let Some(field) = self.field(name) {
+                let canonical = name == field.column();
+                match found.get(&field) {
+                    Some(&j) if !canonical || names[j] == field.column() => {}
+                    _ => {
+                        found.insert(field, i);
+                    }
+                }
+            }
+        }
+        for (field, &i) in &found {
+            names[i] = field.column().to_string();
+        }
+        Field::ALL.into_iter()
+            .filter(|f| !found.contains_key(f) && !Field::OPTIONAL.contains(f))
+            .collect()
+    }
+
+    /// Whether the conversion reads column `name` once the columns are
+    /// resolved: an NVDB field under its canonical name, or one of the
+    /// `GLOBAL_NODE_COLUMNS` of tiled conversions
+    ///
+    /// Segment properties are only built for these columns, so the other
+    /// columns of a wide input cost no memory per segment.
+    pub fn uses_column(&self, name: &str) -> bool {
+        GLOBAL_NODE_COLUMNS.contains(&name) || self.field(name).is_some_and(|field| field.column() == name)
+    }
+}
+
+/// Columns with the global node IDs and ownership of segment ends, set when
+/// a country is converted in tiles (see `process_nvdb_fast()` in R)
+pub const GLOBAL_NODE_COLUMNS: [&str; 4] =
+    ["global_start_node_id", "global_end_node_id", "global_start_owned", "global_end_owned"];
+
+/// NVDB GDB boolean fields that use -1 for true (ESRI convention), by
+/// canonical column name
+/// Matches Python load_file() boolean_fields list (lines 2237-2277)
+pub fn is_esri_boolean(name: &str) -> bool {
+    matches!(name,
+        "F_ForbudTrafik" | "B_ForbudTrafik" |
+        "F_ForbjudenFardriktning" | "B_ForbjudenFardriktning" |
+        "F_Cirkulationsplats" | "B_Cirkulationsplats" |
+        "TattbebyggtOmrade" |
+        "Farjeled" |
+        "Motorvag" | "Motortrafikled" |
+        "GCM_belyst" | "GCM_passage" |
+        "F_Omkorningsforbud" | "B_Omkorningsforbud" |
+        "L_Gagata" | "R_Gagata" |
+        "L_Gangfartsomrade" | "R_Gangfartsomrade" |
+        "Miljozon" |
+        "C_Rekbilvagcykeltrafik" |
+        "Rastplats" |
+        "L_Rastficka_2" | "R_Rastficka_2" |
+        "L_Racke" | "R_Racke" |
+        "L_Bullerskydd" | "R_Bullerskydd" |
+        "F_ATK_Matplats" | "B_ATK_Matplats" |
+        "Provisorisk_vag" | "F_Stigningsfalt" | "B_Stigningsfalt" |
+        "Katastrofoverfart" | "Viltpassage_i_plan" |
+        "L_Viltuthopp" | "R_Viltuthopp" |
+        "L_Viltstangsel" | "R_Viltstangsel" | "Skolomrade" |
+        "L_P_ficka" | "R_P_ficka" | "M_P_ficka" |
+        "Driftvandplats_2" | "Brunn___Slamsugning" | "Hallplats"
+    )
+}
+
+/// Lower-case ASCII letters and digits only, with Swedish letters folded
+fn fold(name: &str) -> String {
+    name.chars()
+        .filter_map(|c| match c {
+            'å' | 'ä' | 'Å' | 'Ä' => Some('a'),
+            'ö' | 'Ö' => Some('o'),
+            'é' | 'É' => Some('e'),
+            c if c.is_ascii_alphanumeric() => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, Schema};
+
+    #[test]
+    fn uses_column_after_resolving_aliases() {
+        let schema = Schema::nvdb();
+        assert!(schema.uses_column("Namn_130"));
+        assert!(schema.uses_column("global_start_node_id"));
+        // Aliases are renamed by resolve_columns() first
+        assert!(!schema.uses_column("Vägkategori/Kategori"));
+        assert!(!schema.uses_column("Shape_Length"));
+    }
+
+    #[test]
+    fn renamed_and_aliased_columns_resolve() {
+        // A release that renamed the speed limit columns
+        let schema = Schema::nvdb()
+            .column(Field::SpeedLimitForward, "F_Hastighet")
+            .column(Field::SpeedLimitBackward, "B_Hastighet");
+        assert_eq!(schema.field("F_Hastighet"), Some(Field::SpeedLimitForward));
+
+        let mut columns = vec!["Vägkategori/Kategori".to_string(), "Namn_130".to_string()];
+        let unresolved = schema.resolve_columns(&mut columns);
+        assert_eq!(columns, ["Kateg_380", "Namn_130"]);
+        assert!(unresolved.contains(&Field::RoadNumber));
+    }
+}
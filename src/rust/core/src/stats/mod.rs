@@ -0,0 +1,217 @@
+//! Conversion statistics
+//!
+//! Per-phase wall time and element counts collected during a conversion and
+//! handed back to R, so parameter choices can be benchmarked and performance
+//! issues reported with numbers attached.
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use geo::GeodesicLength;
+
+use crate::models::{Segment, Way};
+
+/// Summary of a single conversion run
+#[derive(Debug, Clone, Default)]
+pub struct ConversionStats {
+    // Wall time per phase, in seconds
+    pub parse_secs: f64,
+    /// Clipping to the boundary polygon, zero without a boundary
+    pub clip_secs: f64,
+    pub tag_secs: f64,
+    pub nodes_secs: f64,
+    pub simplify_secs: f64,
+    pub write_secs: f64,
+    // Counts
+    pub segments_in: usize,
+    pub parse_failures: usize,
+    pub ways_out: usize,
+    pub nodes_out: usize,
+    pub feature_nodes_out: usize,
+    pub relations_out: usize,
+    /// Peak resident memory in MB (VmHWM on Linux, structure-size estimate elsewhere)
+    pub peak_memory_mb: f64,
+    /// Memory of the parsed segments in MB, estimated from their sizes
+    pub segment_memory_mb: f64,
+    /// Canonical names of schema fields missing from the input columns
+    pub unresolved_fields: Vec<&'static str>,
+    /// Tag mapper warnings, one per segment and problem
+    pub annotations: Vec<AnnotationRecord>,
+    /// Length of the merged ways per highway class, surface and speed limit
+    pub lengths: LengthReport,
+}
+
+/// A segment annotation together with the input row it belongs to
+#[derive(Debug, Clone)]
+pub struct AnnotationRecord {
+    pub source_row: usize,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl ConversionStats {
+    pub fn total_secs(&self) -> f64 {
+        self.parse_secs + self.clip_secs + self.tag_secs + self.nodes_secs + self.simplify_secs + self.write_secs
+    }
+
+    /// Record peak memory, preferring the OS high-water mark over the
+    /// estimate, and the estimated memory of `segments`
+    pub fn record_peak_memory(&mut self, segments: &[Segment]) {
+        self.segment_memory_mb = estimate_segment_memory_mb(segments);
+        self.peak_memory_mb = peak_rss_mb().unwrap_or(self.segment_memory_mb);
+    }
+
+    /// Collect the annotations of tagged segments
+    pub fn record_annotations(&mut self, segments: &[Segment]) {
+        for segment in segments {
+            for annotation in &segment.annotations {
+                self.annotations.push(AnnotationRecord {
+                    source_row: segment.source_row,
+                    kind: annotation.kind.as_str(),
+                    message: annotation.message.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Way count and length per highway class, surface and maxspeed bucket, the
+/// usual figures for comparing the coverage of NVDB and OSM
+///
+/// Ways without `highway` (ferries, barriers) are left out; ways without a
+/// `surface` or `maxspeed` count as "(missing)".
+#[derive(Debug, Clone, Default)]
+pub struct LengthReport {
+    /// (group index in `LENGTH_GROUPS`, value) to (ways, metres)
+    totals: BTreeMap<(usize, String), (usize, f64)>,
+}
+
+/// Groups of `LengthReport`, in report order
+pub const LENGTH_GROUPS: [&str; 3] = ["highway", "surface", "maxspeed"];
+
+/// One value of a `LengthReport` group
+#[derive(Debug, Clone, PartialEq)]
+pub struct LengthRow {
+    /// "highway", "surface" or "maxspeed"
+    pub group: &'static str,
+    pub value: String,
+    pub ways: usize,
+    pub length_m: f64,
+}
+
+impl LengthReport {
+    /// Add the lengths of `ways`
+    pub fn add_ways(&mut self, ways: &[Way], segments: &[Segment]) {
+        for way in ways {
+            let Some(highway) = way.tags.get("highway") else { continue };
+            let metres = way.line(segments).geodesic_length();
+            let missing = || "(missing)".to_string();
+            let values = [
+                highway.clone(),
+                way.tags.get("surface").cloned().unwrap_or_else(missing),
+                way.tags.get("maxspeed").map_or_else(missing, |v| maxspeed_bucket(v).to_string()),
+            ];
+            for (group, value) in values.into_iter().enumerate() {
+                let total = self.totals.entry((group, value)).or_insert((0, 0.0));
+                total.0 += 1;
+                total.1 += metres;
+            }
+        }
+    }
+
+    /// Totals by group, longest first within each group
+    pub fn rows(&self) -> Vec<LengthRow> {
+        let mut rows: Vec<LengthRow> = self.totals
+            .iter()
+            .map(|((group, value), &(ways, length_m))| LengthRow { group: LENGTH_GROUPS[*group], value: value.clone(), ways, length_m })
+            .collect();
+        let order = |row: &LengthRow| LENGTH_GROUPS.iter().position(|g| *g == row.group);
+        rows.sort_by(|a, b| order(a).cmp(&order(b)).then(b.length_m.total_cmp(&a.length_m)));
+        rows
+    }
+}
+
+/// Speed limit range of a `maxspeed` value in km/h ("mph" values converted),
+/// or "other" for values without a number (`signals`, `none`)
+pub fn maxspeed_bucket(value: &str) -> &'static str {
+    let mut parts = value.split_whitespace();
+    let Some(number) = parts.next().and_then(|n| n.parse::<f64>().ok()) else { return "other" };
+    let kmh = if parts.next() == Some("mph") { number * 1.609344 } else { number };
+    match kmh.round() as i64 {
+        ..=30 => "1-30",
+        31..=50 => "31-50",
+        51..=70 => "51-70",
+        71..=90 => "71-90",
+        91..=110 => "91-110",
+        _ => "111+",
+    }
+}
+
+/// Simple stopwatch for phase timing
+pub struct PhaseTimer {
+    start: Instant,
+}
+
+impl PhaseTimer {
+    pub fn start() -> Self {
+        Self { start: Instant::now() }
+    }
+
+    /// Seconds elapsed since the timer was started
+    pub fn secs(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+}
+
+/// Peak resident set size of this process from /proc (Linux only)
+fn peak_rss_mb() -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: f64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024.0)
+}
+
+/// Rough lower bound of segment memory when the OS does not report a peak
+fn estimate_segment_memory_mb(segments: &[Segment]) -> f64 {
+    let mut bytes = std::mem::size_of_val(segments);
+    for seg in segments {
+        bytes += seg.geometry.0.len() * std::mem::size_of::<geo_types::Coord>();
+        for (k, v) in &seg.properties {
+            bytes += k.len() + v.as_string().len() + 32;
+        }
+        for (k, v) in &seg.tags {
+            bytes += k.len() + v.len() + 48;
+        }
+        bytes += seg.internal_node_ids.len() * 8;
+    }
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LengthReport, maxspeed_bucket};
+    use crate::Way;
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn length_report_groups_ways_by_tag_value() {
+        let segments = vec![segment(vec![(18.0, 59.0), (18.0, 59.009)])];
+        let primary = |maxspeed: &str| Way::new(vec![0], tags(&[("highway", "primary"), ("maxspeed", maxspeed)]));
+        let ways = vec![primary("80"), primary("90")];
+
+        let mut report = LengthReport::default();
+        report.add_ways(&ways, &segments);
+        let rows = report.rows();
+        assert_eq!((rows[0].group, rows[0].value.as_str(), rows[0].ways), ("highway", "primary", 2));
+        assert!(rows[0].length_m > 1900.0 && rows[0].length_m < 2100.0);
+        assert_eq!(rows.last().unwrap().value, "71-90");
+    }
+
+    #[test]
+    fn maxspeed_buckets() {
+        assert_eq!(maxspeed_bucket("50"), "31-50");
+        assert_eq!(maxspeed_bucket("120"), "111+");
+        assert_eq!(maxspeed_bucket("30 mph"), "31-50");
+        assert_eq!(maxspeed_bucket("signals"), "other");
+    }
+}
@@ -0,0 +1,266 @@
+//! Synthetic NVDB input
+//!
+//! Generates fake NVDB road links, as WKB plus attribute columns under their
+//! canonical NVDB names, so that the pipeline can be benchmarked and fuzzed
+//! at any scale without distributing real NVDB data. Two layouts are
+//! available: a square street grid and a radial town (spokes and ring
+//! roads). Every link has a network type, owner, functional class, speed
+//! limit and route ID; the other attributes are filled for a share
+//! (`coverage`) of the links, the rest are null as in sparse real data.
+//! Output is reproducible for a given `seed`.
+
+use std::f64::consts::PI;
+
+use geo_types::{Coord, LineString};
+use rustc_hash::FxHashMap;
+
+use crate::geometry::linestring_to_wkb;
+use crate::models::{PropertyValue, Segment};
+use crate::schema::Field;
+use crate::wkb::segment_from_wkb;
+
+/// Metres per degree of latitude
+const METRES_PER_DEGREE: f64 = 111_320.0;
+
+/// Spokes of the radial layout
+const SPOKES: usize = 8;
+
+/// Street names drawn for the covered links
+const STREET_NAMES: [&str; 6] = ["Storgatan", "Kyrkogatan", "Skolgatan", "Parkvägen", "Björkvägen", "Strandvägen"];
+
+/// Street layout of the generated network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// `size` x `size` junctions, every fifth street a county road
+    #[default]
+    Grid,
+    /// `size` ring roads crossed by 8 spokes, the spokes county roads
+    Radial,
+}
+
+impl From<&str> for Layout {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "radial" => Layout::Radial,
+            _ => Layout::Grid,
+        }
+    }
+}
+
+/// Generator settings
+#[derive(Debug, Clone)]
+pub struct SyntheticNetwork {
+    pub layout: Layout,
+    /// Junctions per side (grid) or number of rings (radial)
+    pub size: usize,
+    /// Distance between neighbouring junctions or rings, in metres
+    pub spacing_m: f64,
+    /// South-west corner (grid) or centre (radial), lon/lat
+    pub origin: (f64, f64),
+    /// Share of links (0-1) with the optional attributes filled
+    pub coverage: f64,
+    pub seed: u64,
+}
+
+impl Default for SyntheticNetwork {
+    fn default() -> Self {
+        Self {
+            layout: Layout::Grid,
+            size: 10,
+            spacing_m: 100.0,
+            origin: (20.26, 63.83), // Umeå
+            coverage: 0.5,
+            seed: 1,
+        }
+    }
+}
+
+/// Generated links: one WKB LineString per link and one value per link in
+/// each column
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticInput {
+    pub wkb: Vec<Vec<u8>>,
+    pub columns: Vec<(Field, Vec<PropertyValue>)>,
+}
+
+/// Attributes of one link before it is added to the columns
+struct Link {
+    geometry: LineString<f64>,
+    /// County road rather than municipal street
+    main: bool,
+}
+
+impl SyntheticNetwork {
+    pub fn new(layout: Layout, size: usize) -> Self {
+        Self { layout, size, ..Self::default() }
+    }
+
+    /// Generate the links
+    pub fn generate(&self) -> SyntheticInput {
+        let links = match self.layout {
+            Layout::Grid => self.grid_links(),
+            Layout::Radial => self.radial_links(),
+        };
+
+        // xorshift64*, as in `topology::graph::path_check()`
+        let mut state = self.seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        let mut random = move || {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            (state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        let mut columns: Vec<(Field, Vec<PropertyValue>)> = [
+            Field::RouteId,
+            Field::NetworkType,
+            Field::RoadOwner,
+            Field::FunctionalClass,
+            Field::SpeedLimitForward,
+            Field::SpeedLimitBackward,
+            Field::RoadCategory,
+            Field::RoadNumber,
+            Field::StreetName,
+            Field::Municipality,
+            Field::Surface,
+            Field::Width,
+            Field::LaneCount,
+            Field::UrbanArea,
+            Field::Lit,
+        ]
+        .into_iter()
+        .map(|field| (field, Vec::with_capacity(links.len())))
+        .collect();
+
+        let mut wkb = Vec::with_capacity(links.len());
+        for (i, link) in links.iter().enumerate() {
+            let speed = if link.main { 70 } else { [30, 40, 50][(random() * 3.0) as usize] };
+            let covered = random() < self.coverage;
+            let optional = |value: PropertyValue| if covered { value } else { PropertyValue::Null };
+            let values = [
+                PropertyValue::String(format!("synthetic-{}", i + 1)),
+                PropertyValue::Integer(1),
+                PropertyValue::Integer(if link.main { 1 } else { 2 }),
+                PropertyValue::Integer(if link.main { 3 } else { 6 + (random() * 2.0) as i64 }),
+                PropertyValue::Integer(speed),
+                optional(PropertyValue::Integer(speed)),
+                if link.main { PropertyValue::Integer(4) } else { PropertyValue::Null },
+                if link.main { PropertyValue::Integer(500 + (i % 7) as i64) } else { PropertyValue::Null },
+                if link.main {
+                    PropertyValue::Null
+                } else {
+                    optional(PropertyValue::String(STREET_NAMES[(random() * 6.0) as usize].to_string()))
+                },
+                optional(PropertyValue::Integer(2480)),
+                optional(PropertyValue::Integer(if random() < 0.9 { 1 } else { 2 })),
+                optional(PropertyValue::Float(if link.main { 8.0 } else { 5.5 })),
+                optional(PropertyValue::Integer(2)),
+                optional(PropertyValue::Integer(1)),
+                optional(PropertyValue::Integer(1)),
+            ];
+            for ((_, column), value) in columns.iter_mut().zip(values) {
+                column.push(value);
+            }
+            wkb.push(linestring_to_wkb(&link.geometry));
+        }
+        SyntheticInput { wkb, columns }
+    }
+
+    /// Generate the links as segments, for benchmarks that skip WKB
+    pub fn segments(&self) -> Vec<Segment> {
+        let input = self.generate();
+        input
+            .wkb
+            .iter()
+            .enumerate()
+            .filter_map(|(i, blob)| {
+                let properties: FxHashMap<String, PropertyValue> = input
+                    .columns
+                    .iter()
+                    .filter(|(_, values)| !matches!(values[i], PropertyValue::Null))
+                    .map(|(field, values)| (field.column().to_string(), values[i].clone()))
+                    .collect();
+                segment_from_wkb(blob, properties).ok()
+            })
+            .collect()
+    }
+
+    /// Offset of `origin` by `(east, north)` metres
+    fn coord(&self, east: f64, north: f64) -> Coord<f64> {
+        let (lon, lat) = self.origin;
+        let round = |v: f64| (v * 10_000_000.0).round() / 10_000_000.0;
+        Coord {
+            x: round(lon + east / (METRES_PER_DEGREE * lat.to_radians().cos())),
+            y: round(lat + north / METRES_PER_DEGREE),
+        }
+    }
+
+    fn grid_links(&self) -> Vec<Link> {
+        let n = self.size;
+        let s = self.spacing_m;
+        let mut links = Vec::with_capacity(2 * n * n.saturating_sub(1));
+        for row in 0..n {
+            for col in 0..n.saturating_sub(1) {
+                // East-west link along street `row`, north-south along `col`
+                let (a, b) = (col as f64 * s, (col + 1) as f64 * s);
+                let along = row as f64 * s;
+                links.push(Link {
+                    geometry: LineString::from(vec![self.coord(a, along), self.coord(b, along)]),
+                    main: row % 5 == 0,
+                });
+                links.push(Link {
+                    geometry: LineString::from(vec![self.coord(along, a), self.coord(along, b)]),
+                    main: row % 5 == 0,
+                });
+            }
+        }
+        links
+    }
+
+    fn radial_links(&self) -> Vec<Link> {
+        let s = self.spacing_m;
+        let mut links = Vec::with_capacity(2 * SPOKES * self.size);
+        let point = |radius: f64, angle: f64| self.coord(radius * angle.cos(), radius * angle.sin());
+        for spoke in 0..SPOKES {
+            let angle = 2.0 * PI * spoke as f64 / SPOKES as f64;
+            for ring in 0..self.size {
+                links.push(Link {
+                    geometry: LineString::from(vec![point(ring as f64 * s, angle), point((ring + 1) as f64 * s, angle)]),
+                    main: true,
+                });
+            }
+        }
+        // Ring roads as curved arcs between neighbouring spokes
+        for ring in 1..=self.size {
+            let radius = ring as f64 * s;
+            let steps = 2 + ring * 2;
+            for spoke in 0..SPOKES {
+                let start = 2.0 * PI * spoke as f64 / SPOKES as f64;
+                let arc = (0..=steps)
+                    .map(|k| point(radius, start + 2.0 * PI / SPOKES as f64 * k as f64 / steps as f64))
+                    .collect::<Vec<_>>();
+                links.push(Link { geometry: LineString::from(arc), main: false });
+            }
+        }
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, SyntheticNetwork};
+    use crate::{ConversionStats, Pipeline, Field};
+
+    #[test]
+    fn synthetic_networks_convert() {
+        let input = SyntheticNetwork::new(Layout::Grid, 4).generate();
+        assert_eq!(input.wkb.len(), 24); // 2 x 4 x 3 links
+        assert!(input.columns.iter().all(|(_, values)| values.len() == 24));
+        assert!(input.columns.iter().any(|(field, _)| *field == Field::SpeedLimitForward));
+
+        let segments = SyntheticNetwork::new(Layout::Radial, 3).segments();
+        assert_eq!(segments.len(), 48); // 8 spokes x 3 + 3 rings x 8
+        let conversion = Pipeline::new().convert(segments, &mut ConversionStats::default()).unwrap();
+        assert!(conversion.ways.iter().all(|way| way.tags.contains_key("highway")));
+    }
+}
@@ -0,0 +1,112 @@
+//! Legal access defaults per highway class
+//!
+//! NVDB only records deviations from the traffic rules, so a motorway carries
+//! no `foot=no` and a cycleway no `motor_vehicle=no`. Routing engines that do
+//! not know the Swedish defaults would route pedestrians onto motorways; this
+//! post-tagging pass writes the implied access tags out explicitly.
+
+use crate::models::Segment;
+
+/// Access tags implied by a tag (usually a highway class)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessRule {
+    /// Tag that selects the segments, as key and value
+    pub when: (String, String),
+    /// Access tag written when the segment has no value for it yet
+    pub key: String,
+    pub value: String,
+}
+
+/// Table of access defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessDefaults {
+    rules: Vec<AccessRule>,
+}
+
+impl Default for AccessDefaults {
+    fn default() -> Self {
+        Self::sweden()
+    }
+}
+
+impl AccessDefaults {
+    /// No defaults: leave access tags as mapped from NVDB
+    pub fn none() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Swedish defaults (Trafikförordningen)
+    ///
+    /// Pedestrians, cyclists, mopeds (`moped` and `mofa`) and riders are
+    /// banned from motorways and motor roads; motor vehicles from cycleways,
+    /// footways and pedestrian streets, and cyclists from footways.
+    pub fn sweden() -> Self {
+        let mut defaults = Self::none();
+        for (key, value) in [("highway", "motorway"), ("motorroad", "yes")] {
+            for banned in ["foot", "bicycle", "moped", "mofa", "horse"] {
+                defaults = defaults.rule(key, value, banned, "no");
+            }
+        }
+        defaults
+            .rule("highway", "cycleway", "motor_vehicle", "no")
+            .rule("highway", "footway", "motor_vehicle", "no")
+            .rule("highway", "footway", "bicycle", "no")
+            .rule("highway", "pedestrian", "motor_vehicle", "no")
+    }
+
+    /// Add a rule, replacing an existing one for the same selector and key
+    pub fn rule(mut self, when_key: &str, when_value: &str, key: &str, value: &str) -> Self {
+        self = self.without(when_key, when_value, key);
+        self.rules.push(AccessRule {
+            when: (when_key.to_string(), when_value.to_string()),
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// Remove the rule for a selector and key
+    pub fn without(mut self, when_key: &str, when_value: &str, key: &str) -> Self {
+        self.rules.retain(|r| !(r.when.0 == when_key && r.when.1 == when_value && r.key == key));
+        self
+    }
+
+    pub fn rules(&self) -> &[AccessRule] {
+        &self.rules
+    }
+
+    /// Write the defaults into the tags of all segments
+    ///
+    /// A default is skipped when the segment already has the key, or a
+    /// directional or conditional variant of it (`motor_vehicle:forward`,
+    /// `bicycle:conditional`), so mapped restrictions always win.
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if self.rules.is_empty() {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            for rule in &self.rules {
+                if segment.tags.get(&rule.when.0) != Some(&rule.when.1) {
+                    continue;
+                }
+                let prefix = format!("{}:", rule.key);
+                let mapped = segment.tags.keys().any(|k| *k == rule.key || k.starts_with(&prefix));
+                if !mapped {
+                    segment.tags.insert(rule.key.clone(), rule.value.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccessDefaults;
+
+    #[test]
+    fn access_defaults_without_a_rule() {
+        // Swedish defaults, but horses are allowed on motor roads
+        let defaults = AccessDefaults::sweden().without("motorroad", "yes", "horse");
+        assert!(defaults.rules().iter().all(|r| !(r.when.0 == "motorroad" && r.key == "horse")));
+    }
+}
@@ -0,0 +1,98 @@
+//! Pedestrian accessibility of stairs, ramps and elevators
+//!
+//! Where a delivery carries the accessibility attributes of the GCM network
+//! (see `Field::OPTIONAL`), steps get `step_count` and `handrail`, inclined
+//! paths (ramps) get `incline` and a `wheelchair` hint, and elevators the
+//! `level`s they serve. Without these columns nothing is added.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// Steepest incline (percent) tagged `wheelchair=yes`; ramps up to 1:12
+/// (8.3 %) are `wheelchair=limited`, steeper ones `wheelchair=no`
+const WHEELCHAIR_MAX_INCLINE: f64 = 5.0;
+const WHEELCHAIR_LIMITED_INCLINE: f64 = 100.0 / 12.0;
+
+/// Add the accessibility tags of a tagged GCM segment
+pub fn map_accessibility(segment: &mut Segment) {
+    let highway = match segment.tags.get("highway") {
+        Some(highway) => highway.clone(),
+        None => return,
+    };
+    match highway.as_str() {
+        "steps" => {
+            if let Some(count) = segment.prop(Field::StepCount).and_then(|v| v.as_i64()).filter(|&n| n > 0) {
+                segment.tags.insert("step_count".to_string(), count.to_string());
+            }
+            if let Some(handrail) = segment.prop(Field::Handrail).map(|v| v.as_bool()) {
+                segment.tags.insert("handrail".to_string(), if handrail { "yes" } else { "no" }.to_string());
+            }
+        }
+        "elevator" => {
+            if let Some(levels) = segment.prop(Field::ElevatorLevel).map(|v| v.as_string()) {
+                let levels = levels.trim();
+                if !levels.is_empty() {
+                    segment.tags.insert("level".to_string(), levels.to_string());
+                }
+            }
+        }
+        "footway" | "cycleway" | "pedestrian" | "path" => {
+            let incline = match segment.prop(Field::Incline).and_then(|v| v.as_f64()).filter(|v| v.is_finite()) {
+                Some(incline) => incline,
+                None => return,
+            };
+            segment.tags.insert("incline".to_string(), format_incline(incline));
+            let steepness = incline.abs();
+            let wheelchair = if steepness <= WHEELCHAIR_MAX_INCLINE {
+                "yes"
+            } else if steepness <= WHEELCHAIR_LIMITED_INCLINE {
+                "limited"
+            } else {
+                "no"
+            };
+            segment.tags.entry("wheelchair".to_string()).or_insert_with(|| wheelchair.to_string());
+        }
+        _ => {}
+    }
+}
+
+/// `incline` value in percent, e.g. "6%" or "-4.5%"
+pub(crate) fn format_incline(percent: f64) -> String {
+    let rounded = (percent * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{}%", rounded as i64)
+    } else {
+        format!("{:.1}%", rounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn map_accessibility_tags_steps_levels_and_inclines() {
+        let gcm = |gcm_type: i64, properties: &[(&str, PropertyValue)]| {
+            let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+            segment.properties.insert("Vagtr_474".to_string(), PropertyValue::Integer(2));
+            segment.properties.insert("GCM_t_502".to_string(), PropertyValue::Integer(gcm_type));
+            for (key, value) in properties {
+                segment.properties.insert(key.to_string(), value.clone());
+            }
+            segment
+        };
+        let mut segments = vec![
+            gcm(17, &[("Antal_steg", PropertyValue::Integer(12)), ("Ledstang", PropertyValue::Boolean(true))]),
+            gcm(21, &[("Vaningsplan", PropertyValue::String("0;1".to_string()))]),
+            gcm(4, &[("Lutning", PropertyValue::Float(6.0))]),
+            gcm(4, &[("Lutning", PropertyValue::Float(-4.54))]),
+        ];
+        tag_network(&mut segments);
+
+        assert_eq!((&*segments[0].tags["step_count"], &*segments[0].tags["handrail"]), ("12", "yes"));
+        assert_eq!(segments[1].tags["level"], "0;1");
+        assert_eq!((&*segments[2].tags["incline"], &*segments[2].tags["wheelchair"]), ("6%", "limited"));
+        assert_eq!((&*segments[3].tags["incline"], &*segments[3].tags["wheelchair"]), ("-4.5%", "yes"));
+    }
+}
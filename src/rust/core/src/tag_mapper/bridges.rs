@@ -0,0 +1,98 @@
+//! Bridge relations
+//!
+//! A named bridge is often split into several ways where its attributes
+//! change (lanes, speed limit, a footway alongside). NVDB identifies the
+//! structure itself (`Ident_191`), so after merging the ways of one bridge
+//! are grouped into a `type=bridge` relation carrying the bridge name, and
+//! a `bridge:name` found on one of them is copied to the others. Bridges
+//! that ended up as a single way need no relation.
+
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+
+use crate::models::{RelationFeature, Segment, Way};
+use crate::schema::Field;
+
+/// Group the bridge ways by NVDB structure identity into `type=bridge`
+/// relations (role `across`)
+pub fn bridge_relations(ways: &mut [Way], segments: &[Segment]) -> Vec<RelationFeature> {
+    // Sorted by identity so that the relation order is reproducible
+    let mut structures: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (way_idx, way) in ways.iter().enumerate() {
+        if !way.tags.contains_key("bridge") {
+            continue;
+        }
+        let ident = way.segment_indices.iter()
+            .find_map(|&i| segments[i].prop(Field::StructureId))
+            .map(|v| v.as_string().trim().to_string());
+        if let Some(ident) = ident.filter(|id| !id.is_empty()) {
+            structures.entry(ident).or_default().push(way_idx);
+        }
+    }
+
+    let mut relations = Vec::new();
+    for (_, members) in structures {
+        if members.len() < 2 {
+            continue;
+        }
+        let bridge_name = members.iter().find_map(|&w| ways[w].tags.get("bridge:name").cloned());
+        let structure_name = members.iter()
+            .flat_map(|&w| ways[w].segment_indices.iter())
+            .find_map(|&i| segments[i].prop(Field::StructureName))
+            .map(|v| v.as_string().trim().to_string());
+
+        if let Some(name) = &bridge_name {
+            for &w in &members {
+                ways[w].tags.entry("bridge:name".to_string()).or_insert_with(|| name.clone());
+            }
+        }
+
+        let mut tags = FxHashMap::default();
+        tags.insert("type".to_string(), "bridge".to_string());
+        if let Some(name) = bridge_name.or(structure_name) {
+            tags.insert("name".to_string(), name);
+        }
+        relations.push(RelationFeature {
+            tags,
+            members: members.into_iter().map(|w| (w, "across".to_string())).collect(),
+        });
+    }
+    relations
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ConversionStats, Pipeline, PropertyValue};
+    use crate::test_support::segment;
+
+    #[test]
+    fn bridge_relations_group_split_bridge_ways() {
+        // One bridge split where the speed limit changes
+        let bridge = |coords: Vec<(f64, f64)>, maxspeed: i64| {
+            let mut segment = segment(coords);
+            for (key, value) in [
+                ("Kateg_380", PropertyValue::Integer(3)),
+                ("Konst_190", PropertyValue::Integer(1)),
+                ("Ident_191", PropertyValue::String("1-1234-1".to_string())),
+                ("Namn_193", PropertyValue::String("Tranebergsbron".to_string())),
+                ("F_Hogst_225", PropertyValue::Integer(maxspeed)),
+            ] {
+                segment.properties.insert(key.to_string(), value);
+            }
+            segment
+        };
+        let segments = vec![
+            bridge(vec![(18.0, 59.0), (18.001, 59.0)], 50),
+            bridge(vec![(18.001, 59.0), (18.002, 59.0)], 70),
+        ];
+
+        let mut stats = ConversionStats::default();
+        let conversion = Pipeline::new().bridge_relations(true).convert(segments, &mut stats).unwrap();
+        assert_eq!(conversion.ways.len(), 2);
+        assert_eq!(conversion.relations.len(), 1);
+        let relation = &conversion.relations[0];
+        assert_eq!((&*relation.tags["type"], &*relation.tags["name"]), ("bridge", "Tranebergsbron"));
+        assert_eq!(relation.members, [(0, "across".to_string()), (1, "across".to_string())]);
+    }
+}
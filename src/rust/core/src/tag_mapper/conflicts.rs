@@ -0,0 +1,55 @@
+//! Conflicts between mappers
+//!
+//! Each mapper reads its own NVDB attributes, so tags written by one can
+//! contradict the implications of another. This step runs last on each
+//! segment and settles them: a living street (gångfartsområde) is driven at
+//! walking pace (Trafikförordningen 8 kap. 2 §), so a numeric speed limit
+//! from the speed limit layer gives way to `maxspeed=walk`.
+
+use crate::models::{AnnotationKind, Segment};
+
+const MAXSPEED_KEYS: [&str; 3] = ["maxspeed", "maxspeed:forward", "maxspeed:backward"];
+
+/// Resolve contradicting tags of a tagged segment
+pub fn resolve_conflicts(segment: &mut Segment) {
+    if segment.tags.get("highway").map(|s| s.as_str()) == Some("living_street") {
+        walking_pace(segment);
+    }
+}
+
+/// Replace numeric speed limits by `maxspeed=walk`
+fn walking_pace(segment: &mut Segment) {
+    for key in MAXSPEED_KEYS {
+        if let Some(speed) = segment.tags.remove(key).filter(|v| v != "walk") {
+            segment.annotate(
+                AnnotationKind::ConflictingAttributes,
+                format!("{}={} dropped on living street", key, speed),
+            );
+        }
+    }
+    segment.tags.insert("maxspeed".to_string(), "walk".to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn living_street_drops_maxspeed() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+        for (key, value) in [
+            ("L_Gangfartsomrade", PropertyValue::Boolean(true)),
+            ("F_Hogst_225", PropertyValue::Integer(30)),
+            ("B_Hogst_225", PropertyValue::Integer(30)),
+        ] {
+            segment.properties.insert(key.to_string(), value);
+        }
+        let mut segments = vec![segment];
+        tag_network(&mut segments);
+
+        assert_eq!(segments[0].tags["highway"], "living_street");
+        assert_eq!(segments[0].tags["maxspeed"], "walk");
+        assert_eq!(segments[0].annotations[0].message, "maxspeed=30 dropped on living street");
+    }
+}
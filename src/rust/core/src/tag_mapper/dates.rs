@@ -0,0 +1,151 @@
+//! Survey dates from NVDB validity dates
+//!
+//! Every NVDB record carries the date from which it is valid, i.e. the date
+//! of its last change. Writing the newest of these per way as `check_date`
+//! (or `source:date`) lets editors see how fresh the imported data is and
+//! prioritize stale roads. The date is only known per segment, so this pass
+//! runs after merging: tagging segments would keep them from merging.
+
+use crate::models::{PropertyValue, Segment, Way};
+use crate::schema::Field;
+
+/// Tag the newest validity date is written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateTag {
+    /// No date tag (the default)
+    #[default]
+    None,
+    /// `check_date`, the date the data was last verified
+    CheckDate,
+    /// `source:date`, the date of the source data
+    SourceDate,
+}
+
+impl From<&str> for DateTag {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "check_date" => DateTag::CheckDate,
+            "source_date" | "source:date" => DateTag::SourceDate,
+            _ => DateTag::None, // Default
+        }
+    }
+}
+
+impl DateTag {
+    pub fn key(&self) -> Option<&'static str> {
+        match self {
+            DateTag::None => None,
+            DateTag::CheckDate => Some("check_date"),
+            DateTag::SourceDate => Some("source:date"),
+        }
+    }
+
+    /// Tag each way with the newest validity date of its segments
+    pub fn apply(&self, ways: &mut [Way], segments: &[Segment]) {
+        let key = match self.key() {
+            Some(key) => key,
+            None => return,
+        };
+        for way in ways.iter_mut() {
+            let newest = way.segment_indices.iter()
+                .filter_map(|&i| segments[i].prop(Field::ValidFrom))
+                .filter_map(iso_date)
+                .max();
+            if let Some(date) = newest {
+                way.tags.insert(key.to_string(), date);
+            }
+        }
+    }
+}
+
+/// Keep the segments valid on `as_of` (`YYYY-MM-DD`): valid from
+/// `FROM_DATE` on and before `TO_DATE`; a missing or unreadable date does
+/// not limit validity
+///
+/// The dates are those of the segment: the input has one row per segment
+/// with the attributes of all its NVDB records flattened into it, and the
+/// validity dates of the single records are not kept. A segment is
+/// therefore kept or dropped as a whole; this gives historical snapshots of
+/// the network, but a segment with a speed limit that changes in the future
+/// is dropped rather than given its current limit.
+pub fn retain_valid(segments: &mut Vec<Segment>, as_of: &str) {
+    segments.retain(|segment| {
+        let from = segment.prop(Field::ValidFrom).and_then(iso_date);
+        let to = segment.prop(Field::ValidTo).and_then(iso_date);
+        from.is_none_or(|from| from.as_str() <= as_of) && to.is_none_or(|to| as_of < to.as_str())
+    });
+}
+
+/// `YYYY-MM-DD` of a day count since 1970-01-01 (proleptic Gregorian calendar)
+pub fn date_from_days(days: i64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` of a count of seconds since 1970-01-01 00:00:00 UTC
+pub fn datetime_from_seconds(seconds: i64) -> String {
+    let secs = seconds.rem_euclid(86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        date_from_days(seconds.div_euclid(86_400)),
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// `YYYY-MM-DD` date of a property, from date strings with or without a time
+/// ("2021-03-04", "2021-03-04 00:00:00", "2021/03/04") or compact "20210304"
+pub fn iso_date(value: &PropertyValue) -> Option<String> {
+    let text = value.as_string();
+    let text = text.trim();
+    let digits: String = text.chars().take(10).filter(|c| c.is_ascii_digit()).collect();
+    let compact = text.len() == 8 && digits.len() == 8;
+    let separated = text.len() >= 10 && digits.len() == 8 && matches!(text.as_bytes()[4], b'-' | b'/');
+    if !compact && !separated {
+        return None;
+    }
+    let (year, month, day) = (&digits[0..4], &digits[4..6], &digits[6..8]);
+    let valid_month = matches!(month.parse::<u32>(), Ok(1..=12));
+    let valid_day = matches!(day.parse::<u32>(), Ok(1..=31));
+    if !valid_month || !valid_day || year < "1900" {
+        return None;
+    }
+    Some(format!("{}-{}-{}", year, month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{iso_date, retain_valid};
+    use crate::PropertyValue;
+    use crate::test_support::segment;
+
+    #[test]
+    fn iso_date_from_strings_and_integers() {
+        assert_eq!(iso_date(&PropertyValue::String("2021-03-04 00:00:00".into())), Some("2021-03-04".into()));
+        assert_eq!(iso_date(&PropertyValue::Integer(20210304)), Some("2021-03-04".into()));
+        assert_eq!(iso_date(&PropertyValue::String("NA".into())), None);
+    }
+
+    #[test]
+    fn retain_valid_drops_records_outside_the_date() {
+        let segment = |from: &str, to: &str| {
+            let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+            segment.properties.insert("FROM_DATE".to_string(), PropertyValue::String(from.to_string()));
+            segment.properties.insert("TO_DATE".to_string(), PropertyValue::String(to.to_string()));
+            segment
+        };
+        let mut segments = vec![segment("2020-01-01", "2024-05-01"), segment("2024-05-01", ""), segment("2030-01-01", "")];
+        retain_valid(&mut segments, "2024-05-01");
+        assert_eq!(segments.len(), 1);
+    }
+}
@@ -0,0 +1,77 @@
+//! Fords (Vadställe)
+//!
+//! A ford along the road is tagged `ford=yes` on its way; a ford located at
+//! a point of the segment becomes a `ford=yes` node of the way instead (see
+//! `generate_nodes_for_segment()`). Where the flow data says the ford is
+//! only passable in winter, when the watercourse is frozen, access is closed
+//! the rest of the year with `access:conditional`.
+
+use rustc_hash::FxHashMap;
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// Months a winter-only ford is closed (April to November)
+const WINTER_ONLY_CLOSED: &str = "no @ (Apr-Nov)";
+
+/// Where a ford is located
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FordKind {
+    /// Along the segment: tagged on the way (Vadställe/Typ 1)
+    Way,
+    /// At a point of the segment: a node (Vadställe/Typ 2)
+    Node,
+}
+
+/// The ford of a segment, if any
+pub fn ford_kind(segment: &Segment) -> Option<FordKind> {
+    match segment.prop(Field::Ford).and_then(|v| v.as_i64()) {
+        Some(1) => Some(FordKind::Way),
+        Some(2) => Some(FordKind::Node),
+        _ => None,
+    }
+}
+
+/// Tags of the ford of a segment, on its way or its node
+pub fn ford_tags(segment: &Segment) -> Option<FxHashMap<String, String>> {
+    ford_kind(segment)?;
+    let mut tags = FxHashMap::default();
+    tags.insert("ford".to_string(), "yes".to_string());
+    // Passability: 1 all year, 2 winter only (frozen)
+    if segment.prop(Field::FordPassability).and_then(|v| v.as_i64()) == Some(2) {
+        tags.insert("access:conditional".to_string(), WINTER_ONLY_CLOSED.to_string());
+    }
+    Some(tags)
+}
+
+/// Tag a ford along a segment on its way
+pub fn map_ford(segment: &mut Segment) {
+    if ford_kind(segment) != Some(FordKind::Way) || !segment.tags.contains_key("highway") {
+        return;
+    }
+    let Some(tags) = ford_tags(segment) else { return };
+    for (key, value) in tags {
+        if key == "access:conditional" {
+            super::add_conditional(&mut segment.tags, key, &value);
+        } else {
+            segment.tags.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ford_tags;
+    use crate::PropertyValue;
+    use crate::test_support::segment;
+
+    #[test]
+    fn ford_tags_include_seasonal_access() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.properties.insert("Vadstalle".to_string(), PropertyValue::Integer(1));
+        segment.properties.insert("Vadstalle_Farbarhet".to_string(), PropertyValue::Integer(2));
+        let tags = ford_tags(&segment).unwrap();
+        assert_eq!(tags["ford"], "yes");
+        assert_eq!(tags["access:conditional"], "no @ (Apr-Nov)");
+    }
+}
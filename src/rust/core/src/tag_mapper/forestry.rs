@@ -0,0 +1,67 @@
+//! Forestry roads (skogsbilvägar)
+//!
+//! Private gravel roads with an accessibility class (Tillgänglighetsklass)
+//! are forestry roads, classified `highway=track` by the tag mapper. The
+//! class says how well the road carries timber lorries through the year, from
+//! A (all year) to D (only on frozen or dry ground), and becomes the
+//! `tracktype`. Their access follows the private road policy
+//! (`private_roads::PrivateRoads`), and the turning places at their ends
+//! become turning circle nodes (`nodes::turning_nodes`).
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// `tracktype` of an accessibility class; a paved track is always grade1
+fn tracktype(class: i64, paved: bool) -> Option<&'static str> {
+    if paved {
+        return Some("grade1");
+    }
+    match class {
+        1 => Some("grade2"), // A: all year
+        2 => Some("grade3"), // B: all year except during the spring thaw
+        3 => Some("grade4"), // C: not during the thaw or long rainy periods
+        4 => Some("grade5"), // D: only on frozen or dry ground
+        _ => None,
+    }
+}
+
+/// Add `tracktype` to tracks with an accessibility class
+pub fn tag_forestry_roads(segments: &mut [Segment]) {
+    for segment in segments.iter_mut() {
+        if segment.tags.get("highway").map(|s| s.as_str()) != Some("track") {
+            continue;
+        }
+        let Some(class) = segment.prop(Field::Accessibility).and_then(|v| v.as_i64()) else { continue };
+        let paved = segment.prop(Field::Surface).and_then(|v| v.as_i64()) == Some(1);
+        if let Some(grade) = tracktype(class, paved) {
+            segment.tags.entry("tracktype".to_string()).or_insert_with(|| grade.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn forestry_roads_become_tracks() {
+        let forestry_road = |class: i64| {
+            let mut segment = segment(vec![(16.0, 62.0), (16.001, 62.0)]);
+            for (key, value) in [
+                ("Vagha_6", PropertyValue::Integer(3)),
+                ("Klass_181", PropertyValue::Integer(9)),
+                ("Tillg_169", PropertyValue::Integer(class)),
+                ("Slitl_152", PropertyValue::Integer(2)),
+            ] {
+                segment.properties.insert(key.to_string(), value);
+            }
+            segment
+        };
+        let mut segments = vec![forestry_road(1), forestry_road(3)];
+        tag_network(&mut segments);
+
+        assert_eq!((&*segments[0].tags["highway"], &*segments[0].tags["tracktype"]), ("track", "grade2"));
+        assert_eq!(segments[1].tags["tracktype"], "grade4");
+    }
+}
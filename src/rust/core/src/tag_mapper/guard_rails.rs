@@ -0,0 +1,43 @@
+//! Guard rails (Räcke)
+//!
+//! NVDB records safety barriers for the left and right side of the road in
+//! the digitised direction, like lay-bys and pedestrian streets. They are
+//! written as `barrier:left/right/both=guard_rail` on the way.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// Tag the guard rails of a road segment on its way
+pub fn map_guard_rails(segment: &mut Segment) {
+    if !segment.tags.contains_key("highway") {
+        return;
+    }
+    let left = segment.prop(Field::GuardRailLeft).is_some_and(|v| v.as_bool());
+    let right = segment.prop(Field::GuardRailRight).is_some_and(|v| v.as_bool());
+    if let Some(side) = super::side_of(segment.oneway_direction, left, right) {
+        segment.tags.insert(format!("barrier:{}", side), "guard_rail".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::map_guard_rails;
+    use crate::PropertyValue;
+    use crate::models::OnewayDirection;
+    use crate::test_support::segment;
+
+    #[test]
+    fn guard_rails_follow_way_direction() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.tags.insert("highway".to_string(), "primary".to_string());
+        segment.properties.insert("L_Racke".to_string(), PropertyValue::Integer(-1));
+        map_guard_rails(&mut segment);
+        assert_eq!(segment.tags["barrier:left"], "guard_rail");
+
+        // Reversed oneway: the left rail is on the right of the way
+        segment.tags.remove("barrier:left");
+        segment.oneway_direction = OnewayDirection::Backward;
+        map_guard_rails(&mut segment);
+        assert_eq!(segment.tags["barrier:right"], "guard_rail");
+    }
+}
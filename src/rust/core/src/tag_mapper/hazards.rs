@@ -0,0 +1,101 @@
+//! Hazards: wildlife fences, animal crossings and school zones
+//!
+//! Roads through forest are often fenced against elk and deer (Viltstängsel),
+//! with gaps where animals may cross the road at grade (Viltpassage i plan).
+//! Fences are written as side tags of the way, `barrier:<side>=fence` with
+//! `fence_type:<side>=wildlife`; the crossing sections get
+//! `hazard=animal_crossing`, which navigation devices warn about. School
+//! zones (Skolområde) get `hazard=school_zone` and their reduced speed limit
+//! as `maxspeed:conditional` during school hours.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// School hours of a school zone whose delivery has none
+const DEFAULT_SCHOOL_HOURS: &str = "07:00-17:00";
+
+/// Add the wildlife fence and animal crossing tags of a road segment
+///
+/// A fence on a side that already has a barrier (a guard rail) is added to
+/// it as a list, `guard_rail;fence`.
+pub fn map_hazards(segment: &mut Segment) {
+    if !segment.tags.contains_key("highway") {
+        return;
+    }
+    let left = segment.prop(Field::WildlifeFenceLeft).is_some_and(|v| v.as_bool());
+    let right = segment.prop(Field::WildlifeFenceRight).is_some_and(|v| v.as_bool());
+    if let Some(side) = super::side_of(segment.oneway_direction, left, right) {
+        segment.tags
+            .entry(format!("barrier:{}", side))
+            .and_modify(|barrier| {
+                if !barrier.split(';').any(|v| v == "fence") {
+                    barrier.push_str(";fence");
+                }
+            })
+            .or_insert_with(|| "fence".to_string());
+        segment.tags.insert(format!("fence_type:{}", side), "wildlife".to_string());
+    }
+    if segment.prop(Field::WildlifeCrossing).is_some_and(|v| v.as_bool()) {
+        segment.tags.insert("hazard".to_string(), "animal_crossing".to_string());
+    }
+    map_school_zone(segment);
+}
+
+/// Tag a school zone, with its reduced speed limit on school days
+fn map_school_zone(segment: &mut Segment) {
+    if !segment.prop(Field::SchoolZone).is_some_and(|v| v.as_bool()) {
+        return;
+    }
+    // An animal crossing is the more specific warning
+    segment.tags.entry("hazard".to_string()).or_insert_with(|| "school_zone".to_string());
+
+    let Some(speed) = segment.prop(Field::SchoolZoneSpeed).and_then(|v| v.as_i64()).filter(|&s| s > 0) else {
+        return;
+    };
+    let maxspeed = segment.tags.get("maxspeed").and_then(|v| v.parse::<i64>().ok());
+    if maxspeed.is_some_and(|maxspeed| maxspeed <= speed) {
+        return;
+    }
+    let from = segment.prop(Field::SchoolZoneFrom).and_then(super::clock_time);
+    let to = segment.prop(Field::SchoolZoneTo).and_then(super::clock_time);
+    let hours = match (from, to) {
+        (Some(from), Some(to)) if from != to => format!("{}-{}", from, to),
+        _ => DEFAULT_SCHOOL_HOURS.to_string(),
+    };
+    super::add_conditional(&mut segment.tags, "maxspeed:conditional".to_string(), &format!("{} @ (Mo-Fr {})", speed, hours));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::map_hazards;
+    use crate::PropertyValue;
+    use crate::test_support::segment;
+
+    #[test]
+    fn hazards_combine_barriers_and_wildlife_crossing() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.tags.insert("highway".to_string(), "primary".to_string());
+        segment.tags.insert("barrier:both".to_string(), "guard_rail".to_string());
+        segment.properties.insert("L_Viltstangsel".to_string(), PropertyValue::Integer(-1));
+        segment.properties.insert("R_Viltstangsel".to_string(), PropertyValue::Integer(-1));
+        segment.properties.insert("Viltpassage_i_plan".to_string(), PropertyValue::Integer(-1));
+        map_hazards(&mut segment);
+        assert_eq!(segment.tags["barrier:both"], "guard_rail;fence");
+        assert_eq!(segment.tags["fence_type:both"], "wildlife");
+        assert_eq!(segment.tags["hazard"], "animal_crossing");
+    }
+
+    #[test]
+    fn school_zone_adds_conditional_maxspeed() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.tags.insert("highway".to_string(), "residential".to_string());
+        segment.tags.insert("maxspeed".to_string(), "50".to_string());
+        segment.properties.insert("Skolomrade".to_string(), PropertyValue::Integer(-1));
+        segment.properties.insert("Skolomrade_Hastighet".to_string(), PropertyValue::Integer(30));
+        segment.properties.insert("Skolomrade_Fran_kl".to_string(), PropertyValue::String("0730".into()));
+        segment.properties.insert("Skolomrade_Till_kl".to_string(), PropertyValue::Integer(16));
+        map_hazards(&mut segment);
+        assert_eq!(segment.tags["hazard"], "school_zone");
+        assert_eq!(segment.tags["maxspeed:conditional"], "30 @ (Mo-Fr 07:30-16:00)");
+    }
+}
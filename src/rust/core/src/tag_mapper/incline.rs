@@ -0,0 +1,63 @@
+//! Incline from elevation
+//!
+//! Where the input geometries have Z values (see
+//! `WkbOptions::keep_elevation`), each merged way gets its average gradient
+//! as `incline`, from the elevation of its two ends over its length.
+//! `incline` follows the way direction: positive uphill from the first node
+//! to the last, negative downhill.
+
+use geo::GeodesicLength;
+use rustc_hash::FxHashMap;
+
+use crate::models::{hash_coord, CoordHash, Segment, Way};
+use super::accessibility::format_incline;
+
+/// Shortest way whose gradient is tagged (metres); the elevation of shorter
+/// ways is too coarse for a meaningful gradient
+const MIN_LENGTH: f64 = 20.0;
+
+/// Tag each way whose ends have an elevation with its average `incline`;
+/// ways already tagged (ramps of the GCM network) and flat ways are left
+/// alone
+pub fn tag_incline(ways: &mut [Way], segments: &[Segment]) {
+    let elevations: FxHashMap<CoordHash, f64> = segments.iter().flat_map(|s| s.elevations.iter().copied()).collect();
+    if elevations.is_empty() {
+        return;
+    }
+    for way in ways.iter_mut() {
+        if way.tags.contains_key("incline") {
+            continue;
+        }
+        let line = way.line(segments);
+        let (Some(first), Some(last)) = (line.0.first(), line.0.last()) else { continue };
+        let (Some(start), Some(end)) = (elevations.get(&hash_coord(first)), elevations.get(&hash_coord(last))) else {
+            continue;
+        };
+        let length = line.geodesic_length();
+        if length < MIN_LENGTH {
+            continue;
+        }
+        let incline = format_incline((end - start) / length * 100.0);
+        if incline != "0%" {
+            way.tags.insert("incline".to_string(), incline);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tag_incline;
+    use crate::Way;
+    use crate::test_support::segment;
+
+    #[test]
+    fn incline_from_elevations() {
+        // About 111 m north, 5.55 m down
+        let mut segment = segment(vec![(18.0, 59.0), (18.0, 59.001)]);
+        segment.elevations = vec![(segment.start_node, 20.0), (segment.end_node, 14.45)];
+        let mut ways = vec![Way::new(vec![0], Default::default())];
+
+        tag_incline(&mut ways, &[segment]);
+        assert_eq!(ways[0].tags["incline"], "-5%");
+    }
+}
@@ -0,0 +1,97 @@
+//! Lay-bys (Rastficka)
+//!
+//! The Python script turns NVDB's left and right lay-by attributes into an
+//! `amenity=parking` node at the first vertex of the segment. Navigation
+//! data is more useful with the lay-by on the road itself, as parking tags
+//! of the way, in the older `parking:lane:*` scheme or the current
+//! `parking:*=street_side` one.
+
+use crate::models::{NodeFeature, Segment};
+use crate::schema::Field;
+
+/// How lay-bys are mapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayBys {
+    /// A detached `amenity=parking` node (the default, as in the Python script)
+    #[default]
+    Node,
+    /// `parking:lane:left/right/both=yes` on the way
+    Lane,
+    /// `parking:left/right/both=street_side` with
+    /// `parking:<side>:street_side=lay_by` on the way
+    StreetSide,
+}
+
+impl From<&str> for LayBys {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "lane" | "parking:lane" => LayBys::Lane,
+            "street_side" | "parking" => LayBys::StreetSide,
+            _ => LayBys::Node, // Default
+        }
+    }
+}
+
+impl LayBys {
+    /// Tag the lay-bys of the road network on their ways
+    ///
+    /// Sides refer to the direction of the geometry, which is reversed on
+    /// oneways against the digitised direction (see `map_oneway()`).
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if *self == LayBys::Node {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            if !segment.tags.contains_key("highway") {
+                continue;
+            }
+            let left = segment.prop(Field::LayByLeft).is_some_and(|v| v.as_bool());
+            let right = segment.prop(Field::LayByRight).is_some_and(|v| v.as_bool());
+            let Some(side) = super::side_of(segment.oneway_direction, left, right) else { continue };
+            match self {
+                LayBys::Node => {}
+                LayBys::Lane => {
+                    segment.tags.insert(format!("parking:lane:{}", side), "yes".to_string());
+                }
+                LayBys::StreetSide => {
+                    segment.tags.insert(format!("parking:{}", side), "street_side".to_string());
+                    segment.tags.insert(format!("parking:{}:street_side", side), "lay_by".to_string());
+                }
+            }
+        }
+    }
+
+    /// Drop the lay-by nodes of `generate_feature_nodes()` when lay-bys are
+    /// tagged on the ways instead
+    pub fn retain_nodes(&self, nodes: &mut Vec<NodeFeature>) {
+        if *self != LayBys::Node {
+            nodes.retain(|node| node.tags.get("amenity").map(String::as_str) != Some("parking"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayBys;
+    use crate::PropertyValue;
+    use crate::test_support::segment;
+
+    #[test]
+    fn lay_bys_tag_street_side_parking() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.tags.insert("highway".to_string(), "primary".to_string());
+        segment.properties.insert("R_Rastficka_2".to_string(), PropertyValue::Boolean(true));
+        let mut segments = vec![segment];
+
+        LayBys::StreetSide.apply(&mut segments);
+        assert_eq!(segments[0].tags["parking:right"], "street_side");
+        assert_eq!(segments[0].tags["parking:right:street_side"], "lay_by");
+    }
+
+    #[test]
+    fn lay_bys_from_name() {
+        assert_eq!(LayBys::from("lane"), LayBys::Lane);
+        assert_eq!(LayBys::from("street_side"), LayBys::StreetSide);
+        assert_eq!(LayBys::from("anything else"), LayBys::Node);
+    }
+}
@@ -0,0 +1,85 @@
+//! Layer continuity across merged ways
+//!
+//! The ways of one bridge or tunnel can end up with different `layer`
+//! values (one segment tagged from the bridge register, its neighbour from
+//! the default), which routers and renderers read as a step up or down in
+//! the middle of the structure. After merging, the ways sharing an NVDB
+//! structure identity (`Ident_191`) get the same `layer`, `bridge` and
+//! `tunnel` values: the one carried by most of their length.
+
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+
+use crate::models::{Segment, Way};
+use crate::schema::Field;
+
+const HARMONIZED_KEYS: [&str; 3] = ["layer", "bridge", "tunnel"];
+
+/// Give all ways of one structure the same layer, bridge and tunnel tags
+pub fn harmonize_layers(ways: &mut [Way], segments: &[Segment]) {
+    let mut structures: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (way_idx, way) in ways.iter().enumerate() {
+        if !way.tags.contains_key("bridge") && !way.tags.contains_key("tunnel") {
+            continue;
+        }
+        let ident = way.segment_indices.iter()
+            .find_map(|&i| segments[i].prop(Field::StructureId))
+            .map(|v| v.as_string().trim().to_string());
+        if let Some(ident) = ident.filter(|id| !id.is_empty()) {
+            structures.entry(ident).or_default().push(way_idx);
+        }
+    }
+
+    for members in structures.values().filter(|m| m.len() > 1) {
+        let lengths: Vec<f64> = members.iter()
+            .map(|&w| ways[w].segment_indices.iter().map(|&i| segments[i].shape_length).sum())
+            .collect();
+        for key in HARMONIZED_KEYS {
+            // Length carried by each value; ways without the tag count as a value too
+            let mut weights: FxHashMap<Option<&String>, f64> = FxHashMap::default();
+            for (k, &w) in members.iter().enumerate() {
+                *weights.entry(ways[w].tags.get(key)).or_default() += lengths[k];
+            }
+            if weights.len() < 2 {
+                continue;
+            }
+            // Ties go to the smaller value so that the result does not depend on hashing
+            let winner = weights.into_iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .and_then(|(value, _)| value.cloned());
+            for &w in members {
+                match &winner {
+                    Some(value) => { ways[w].tags.insert(key.to_string(), value.clone()); }
+                    None => { ways[w].tags.remove(key); }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::harmonize_layers;
+    use crate::{PropertyValue, Way};
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn layers_follow_the_longest_way() {
+        // A long and a short way of one bridge, tagged with different layers
+        let part = |to: f64| {
+            let mut segment = segment(vec![(18.0, 59.0), (to, 59.0)]);
+            segment.properties.insert("Ident_191".to_string(), PropertyValue::String("1-1234-1".to_string()));
+            segment
+        };
+        let segments = vec![part(18.002), part(18.0005)];
+        let way = |idx: usize, layer: &str| {
+            Way::new(vec![idx], tags(&[("bridge", "yes"), ("layer", layer)]))
+        };
+        let mut ways = vec![way(0, "2"), way(1, "1")];
+        harmonize_layers(&mut ways, &segments);
+
+        assert_eq!(ways[0].tags["layer"], "2");
+        assert_eq!(ways[1].tags["layer"], "2");
+    }
+}
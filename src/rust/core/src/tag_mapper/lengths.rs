@@ -0,0 +1,43 @@
+//! Way lengths and ferry durations
+//!
+//! Analyses of the exported network often need the length of each way, or
+//! the crossing time of ferries, and would otherwise have to rebuild the
+//! geometry to get them. This pass writes them as tags of the merged and
+//! simplified ways: `length` in metres on the WGS84 ellipsoid, and a
+//! `duration` on ferries estimated at `routing::FERRY_SPEED_KMH`.
+
+use geo::GeodesicLength;
+
+use crate::models::{Segment, Way};
+use crate::routing::ferry_duration;
+
+/// Tag each way with its `length` and each ferry with its `duration`;
+/// values already set (the `length` of a whole tunnel, a signed ferry
+/// duration) are kept
+pub fn tag_lengths(ways: &mut [Way], segments: &[Segment]) {
+    for way in ways.iter_mut() {
+        let metres = way.line(segments).geodesic_length();
+        way.tags.entry("length".to_string()).or_insert_with(|| format!("{:.1}", metres));
+        if way.tags.get("route").map(String::as_str) == Some("ferry") {
+            way.tags.entry("duration".to_string()).or_insert_with(|| ferry_duration(metres));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tag_lengths;
+    use crate::Way;
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn lengths_and_ferry_durations() {
+        let segments = vec![segment(vec![(18.0, 59.0), (18.0, 59.081)])];
+        let tags = tags(&[("route", "ferry")]);
+        let mut ways = vec![Way::new(vec![0], tags)];
+
+        tag_lengths(&mut ways, &segments);
+        assert_eq!(ways[0].tags["length"], "9023.1");
+        assert_eq!(ways[0].tags["duration"], "0:30");
+    }
+}
@@ -1,13 +1,42 @@
 use rustc_hash::FxHashMap;
-use crate::models::{Segment, Bridge, OnewayDirection};
+use crate::models::{AnnotationKind, PropertyValue, Segment, Bridge, OnewayDirection};
+use crate::schema::Field;
 use std::sync::OnceLock;
-
+use non_road::NonRoadTags;
+use units::number;
+
+pub mod access;
+pub mod accessibility;
+pub mod bridges;
+pub mod conflicts;
+pub mod dates;
+pub mod fords;
+pub mod forestry;
+pub mod guard_rails;
+pub mod hazards;
+pub mod incline;
+pub mod lay_bys;
+pub mod layers;
+pub mod lengths;
+pub mod noise_barriers;
+pub mod node_directions;
 pub mod nodes;
+pub mod planned_roads;
+pub mod non_road;
+pub mod private_roads;
+pub mod quays;
+pub mod review;
+pub mod reverse;
+pub mod roundabout;
+pub mod speed;
+pub mod transit;
+pub mod tunnels;
+pub mod units;
 
 // Static lookup tables for tag mapping
 static HIGHWAY_CLASSES: OnceLock<FxHashMap<i64, &'static str>> = OnceLock::new();
 static COUNTY_CODES: OnceLock<FxHashMap<i64, &'static str>> = OnceLock::new();
-static VEHICLE_TYPE_MAP: OnceLock<FxHashMap<i64, &'static str>> = OnceLock::new();
+static VEHICLE_TYPE_MAP: OnceLock<FxHashMap<i64, &'static [&'static str]>> = OnceLock::new();
 
 fn init_highway_classes() -> FxHashMap<i64, &'static str> {
     let mut map = FxHashMap::default();
@@ -55,24 +84,27 @@ fn init_county_codes() -> FxHashMap<i64, &'static str> {
 
 /// NVDB vehicle type codes to OSM access tags
 /// From "Förbud mot trafik/Gäller fordon"
-fn init_vehicle_type_map() -> FxHashMap<i64, &'static str> {
-    let mut map = FxHashMap::default();
-    map.insert(10, "motorcar");       // bil
-    map.insert(20, "bus");            // buss
-    map.insert(30, "bicycle");        // cykel
-    map.insert(40, "vehicle");        // fordon (all vehicles)
-    map.insert(90, "hgv");            // lastbil (heavy goods vehicle)
-    map.insert(100, "goods");         // lätt lastbil (light truck)
-    map.insert(120, "moped");         // moped
-    map.insert(130, "moped");         // moped klass I
-    map.insert(140, "moped");         // moped klass II
-    map.insert(150, "motorcycle");    // motorcykel
-    map.insert(170, "motor_vehicle"); // motordrivna fordon
-    map.insert(180, "motor_vehicle"); // motorredskap
-    map.insert(210, "motorcar");      // personbil (passenger car)
-    map.insert(230, "atv");           // terrängmotorfordon
-    map.insert(270, "tractor");       // traktor
-    map.insert(280, "hgv");           // tung lastbil (heavy truck)
+///
+/// Swedish tagging practice uses `moped` for moped klass I (45 km/h) and
+/// `mofa` for moped klass II (25 km/h); a ban on all mopeds sets both.
+fn init_vehicle_type_map() -> FxHashMap<i64, &'static [&'static str]> {
+    let mut map: FxHashMap<i64, &'static [&'static str]> = FxHashMap::default();
+    map.insert(10, &["motorcar"]);       // bil
+    map.insert(20, &["bus"]);            // buss
+    map.insert(30, &["bicycle"]);        // cykel
+    map.insert(40, &["vehicle"]);        // fordon (all vehicles)
+    map.insert(90, &["hgv"]);            // lastbil (heavy goods vehicle)
+    map.insert(100, &["goods"]);         // lätt lastbil (light truck)
+    map.insert(120, &["moped", "mofa"]); // moped (both classes)
+    map.insert(130, &["moped"]);         // moped klass I
+    map.insert(140, &["mofa"]);          // moped klass II
+    map.insert(150, &["motorcycle"]);    // motorcykel
+    map.insert(170, &["motor_vehicle"]); // motordrivna fordon
+    map.insert(180, &["motor_vehicle"]); // motorredskap
+    map.insert(210, &["motorcar"]);      // personbil (passenger car)
+    map.insert(230, &["atv"]);           // terrängmotorfordon
+    map.insert(270, &["tractor"]);       // traktor
+    map.insert(280, &["hgv"]);           // tung lastbil (heavy truck)
     map
 }
 
@@ -80,6 +112,11 @@ fn init_vehicle_type_map() -> FxHashMap<i64, &'static str> {
 /// 
 /// Port of tag_network() from Python
 pub fn tag_network(segments: &mut [Segment]) {
+    tag_network_with(segments, &NonRoadTags::default());
+}
+
+/// `tag_network()` with the tags of the non-road GCM types from `non_road`
+pub fn tag_network_with(segments: &mut [Segment], non_road: &NonRoadTags) {
     // Initialize lookup tables
     let _ = HIGHWAY_CLASSES.get_or_init(init_highway_classes);
     let _ = COUNTY_CODES.get_or_init(init_county_codes);
@@ -144,6 +181,16 @@ pub fn tag_network(segments: &mut [Segment]) {
         // Width (Python line 914-915)
         map_width(segment);
 
+        // Fords along the segment
+        fords::map_ford(segment);
+
+        // Guard rails (Räcke) on either side
+        guard_rails::map_guard_rails(segment);
+
+        // Wildlife fences and animal crossings, after the guard rails they
+        // share the barrier side tags with
+        hazards::map_hazards(segment);
+
         // Priority road (Python line 917-918)
         map_priority_road(segment);
 
@@ -163,13 +210,23 @@ pub fn tag_network(segments: &mut [Segment]) {
         // Lit (from GCM_belyst, Python line 598-599)
         map_lit(segment);
 
+        // Steps, ramps and elevators of the cycle/pedestrian network
+        accessibility::map_accessibility(segment);
+
         // Layer fallback
         map_layer(segment);
+
+        // Settle tags that contradict each other (living street speeds)
+        conflicts::resolve_conflicts(segment);
     }
     
     // 4. Post-processing
     tag_isolated_tracks(segments);
+    forestry::tag_forestry_roads(segments);
     tag_urban_vs_rural(segments);
+    non_road.apply(segments);
+    // After the non-road tags, so that platforms at funiculars see the rail
+    transit::tag_platforms(segments);
 }
 
 /// Detect bridges and build bridge dictionary
@@ -184,8 +241,8 @@ fn detect_bridges(segments: &[Segment]) -> FxHashMap<String, Bridge> {
     for segment in segments {
         // Check for bridge/tunnel identity and construction
         if let (Some(id_prop), Some(constr_prop)) = (
-            segment.properties.get("Ident_191"),
-            segment.properties.get("Konst_190")
+            segment.prop(Field::StructureId),
+            segment.prop(Field::StructureType)
         ) {
             let bridge_id = id_prop.as_string();
             let construction = constr_prop.as_i64().unwrap_or(0);
@@ -206,7 +263,7 @@ fn detect_bridges(segments: &[Segment]) -> FxHashMap<String, Bridge> {
             match construction {
                 2 | 4 => {
                     // Under bridge
-                    if let Some(net_type) = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()) {
+                    if let Some(net_type) = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()) {
                         // Line 1113-1118: net_type 1 and not construction 3 = car
                         if net_type == 1 && construction != 3 {
                             bridge.car_count += 1;
@@ -215,11 +272,9 @@ fn detect_bridges(segments: &[Segment]) -> FxHashMap<String, Bridge> {
                         }
                     }
                 }
-                1 => {
-                    // Over bridge - track length (line 1120-1125)
-                    if segment.shape_length > bridge.length {
-                        bridge.length = segment.shape_length;
-                    }
+                // Over bridge - track length (line 1120-1125)
+                1 if segment.shape_length > bridge.length => {
+                    bridge.length = segment.shape_length;
                 }
                 _ => {}
             }
@@ -262,11 +317,11 @@ fn detect_missing_bridges(_segments: &mut [Segment], _bridges: &FxHashMap<String
 /// 4. Private roads / Service / Track
 fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<String>) {
     // STEP 0: Check for ferry first (Python lines 452-480)
-    if segment.properties.get("Farjeled").map(|v| v.as_bool()).unwrap_or(false) {
+    if segment.prop(Field::Ferry).map(|v| v.as_bool()).unwrap_or(false) {
         segment.tags.insert("route".to_string(), "ferry".to_string());
         segment.tags.insert("foot".to_string(), "yes".to_string());
         // Motor vehicle access depends on network type
-        let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(0);
+        let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(0);
         if net_type == 1 {
             segment.tags.insert("motor_vehicle".to_string(), "yes".to_string());
         } else {
@@ -274,7 +329,7 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
         }
 
         // P3 FIX: Ferry category (Python lines 461-469)
-        if let Some(kateg) = segment.properties.get("Kateg_380").and_then(|v| v.as_i64()) {
+        if let Some(kateg) = segment.prop(Field::RoadCategory).and_then(|v| v.as_i64()) {
             let ferry_cat = match kateg {
                 1 | 2 => "trunk",
                 3 => "primary",
@@ -287,10 +342,10 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
         }
 
         // P3 FIX: Ferry ref (Python lines 471-475)
-        if let Some(huvnr) = segment.properties.get("Huvnr_556_1") {
+        if let Some(huvnr) = segment.prop(Field::RoadNumber) {
             let huvnr_str = huvnr.as_string();
-            if !huvnr_str.is_empty() && huvnr_str != "0" {
-                let kateg = segment.properties.get("Kateg_380").and_then(|v| v.as_i64()).unwrap_or(0);
+            if huvnr_str != "0" {
+                let kateg = segment.prop(Field::RoadCategory).and_then(|v| v.as_i64()).unwrap_or(0);
                 if kateg == 1 {
                     segment.tags.insert("ref".to_string(), format!("E {}", huvnr_str));
                 } else {
@@ -300,29 +355,25 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
         }
 
         // P3 FIX: Ferry name (Python lines 477-478)
-        if let Some(name) = segment.properties.get("Farje_139") {
-            let name_str = name.as_string();
-            let name_str = name_str.trim();
-            if !name_str.is_empty() && name_str != "NA" {
-                segment.tags.insert("name".to_string(), name_str.to_string());
-            }
+        if let Some(name) = segment.prop(Field::FerryName) {
+            segment.tags.insert("name".to_string(), name.as_string().trim().to_string());
         }
 
         return; // Fixed: Needs to return here so ferries don't get mapped to other highway types
     }
 
     // 1. Check for pedestrian/living street (original Python 638-643) needed for cycleway/footway detection
-    let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(1);
+    let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
 
     // STEP 1: Check for cycleway/footway based on network type (Python lines 528-619)
     if net_type == 2 || net_type == 4 {
         // Check for sidewalk FIRST (Python lines 563-570) — overrides GCM type
-        let l_separ = segment.properties.get("L_Separ_500").map(|v| v.as_bool()).unwrap_or(false);
-        let r_separ = segment.properties.get("R_Separ_500").map(|v| v.as_bool()).unwrap_or(false);
+        let l_separ = segment.prop(Field::SeparatedLeft).map(|v| v.as_bool()).unwrap_or(false);
+        let r_separ = segment.prop(Field::SeparatedRight).map(|v| v.as_bool()).unwrap_or(false);
         if l_separ || r_separ {
             segment.tags.insert("highway".to_string(), "footway".to_string());
             segment.tags.insert("footway".to_string(), "sidewalk".to_string());
-        } else if let Some(gcm_typ) = segment.properties.get("GCM_t_502").and_then(|v| v.as_i64()) {
+        } else if let Some(gcm_typ) = segment.prop(Field::GcmType).and_then(|v| v.as_i64()) {
             // P6 FIX: Full GCM type mapping (Python lines 529-561)
             match gcm_typ {
                 1 | 2 | 3 | 5 | 8 | 9 | 13 | 15 => {
@@ -352,17 +403,20 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
                 20 | 21 => {
                     segment.tags.insert("highway".to_string(), "elevator".to_string());
                 }
-                22 => { // P6 FIX: linbana (cable car)
+                // Linbana (cable car) and bergbana (funicular) are not roads
+                // and get no `highway` tag; `non_road::NonRoadTags` can
+                // replace these tags
+                22 => {
                     segment.tags.insert("aerialway".to_string(), "cable_car".to_string());
                 }
-                23 => { // P6 FIX: bergbana (funicular)
-                    segment.tags.insert("railway".to_string(), "furnicular".to_string());
+                23 => {
+                    segment.tags.insert("railway".to_string(), "funicular".to_string());
                 }
                 24 | 26 => {
                     segment.tags.insert("highway".to_string(), "pedestrian".to_string());
                 }
                 25 => { // P6 FIX: kaj (quay)
-                    segment.tags.insert("highway".to_string(), "footway".to_string());
+                    quays::map_quay(segment);
                 }
                 27 => { // P6 FIX: färja (GCM ferry)
                     segment.tags.insert("route".to_string(), "ferry".to_string());
@@ -378,11 +432,12 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
                 }
                 _ => {
                     // Default based on network type
-                    if net_type == 2 {
-                        segment.tags.insert("highway".to_string(), "cycleway".to_string());
-                    } else {
-                        segment.tags.insert("highway".to_string(), "footway".to_string());
-                    }
+                    let highway = if net_type == 2 { "cycleway" } else { "footway" };
+                    segment.tags.insert("highway".to_string(), highway.to_string());
+                    segment.annotate(
+                        AnnotationKind::AmbiguousClass,
+                        format!("unknown GCM type {}, tagged as {}", gcm_typ, highway),
+                    );
                 }
             }
         } else {
@@ -408,56 +463,45 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
         }
 
         // P11 FIX: Cycleway/footway name logic (Python lines 587-607)
-        if let Some(name) = segment.properties.get("Namn_130") {
+        if let Some(name) = segment.prop(Field::StreetName) {
             let name_str = name.as_string();
             let name_str = name_str.trim();
-            if !name_str.is_empty() && name_str != "NA" {
-                let highway = segment.tags.get("highway").map(|s| s.as_str()).unwrap_or("");
-                let name_lower = name_str.to_lowercase();
-                // Python: include name if pedestrian, or name contains stig/gång/park,
-                // or name is not a motor vehicle street name
-                if highway == "pedestrian"
-                    || name_lower.contains("stig")
-                    || name_lower.contains("gång")
-                    || name_lower.contains("park")
-                    || !street_names.contains(name_str)
-                {
-                    segment.tags.insert("name".to_string(), name_str.to_string());
-                }
+            let highway = segment.tags.get("highway").map(|s| s.as_str()).unwrap_or("");
+            let name_lower = name_str.to_lowercase();
+            // Python: include name if pedestrian, or name contains stig/gång/park,
+            // or name is not a motor vehicle street name
+            if highway == "pedestrian"
+                || name_lower.contains("stig")
+                || name_lower.contains("gång")
+                || name_lower.contains("park")
+                || !street_names.contains(name_str)
+            {
+                segment.tags.insert("name".to_string(), name_str.to_string());
             }
         }
 
         // GCM-belyst → lit=yes (Python line 598-599)
-        if segment.properties.get("GCM_belyst").map(|v| v.as_bool()).unwrap_or(false) {
-            if segment.tags.contains_key("highway") {
-                segment.tags.insert("lit".to_string(), "yes".to_string());
-            }
+        if segment.prop(Field::Lit).map(|v| v.as_bool()).unwrap_or(false) && segment.tags.contains_key("highway") {
+            segment.tags.insert("lit".to_string(), "yes".to_string());
         }
 
         // Cycleway route name (Python lines 602-607)
-        if let Some(cykel_namn) = segment.properties.get("Namn_457") {
-            let s = cykel_namn.as_string();
-            let s = s.trim();
-            if !s.is_empty() && s != "NA" {
-                if segment.tags.get("highway").map(|s| s.as_str()) == Some("cycleway") {
-                    segment.tags.insert("cycleway:name".to_string(), s.to_string());
-                }
+        if let Some(cykel_namn) = segment.prop(Field::CycleRouteName) {
+            if segment.tags.get("highway").map(|s| s.as_str()) == Some("cycleway") {
+                segment.tags.insert("cycleway:name".to_string(), cykel_namn.as_string().trim().to_string());
             }
         }
 
         // Bridge name for cycleways (Python lines 609-617)
         if segment.tags.contains_key("bridge") {
-            if let Some(namn_132) = segment.properties.get("Namn_132") {
+            if let Some(namn_132) = segment.prop(Field::OtherName) {
                 let s = namn_132.as_string();
-                if !s.is_empty() && s.contains("bron") {
+                if s.contains("bron") {
                     segment.tags.insert("bridge:name".to_string(), s.trim().to_string());
                 }
             }
-            if let Some(namn_193) = segment.properties.get("Namn_193") {
-                let s = namn_193.as_string();
-                if !s.is_empty() {
-                    segment.tags.insert("description".to_string(), s.trim().to_string());
-                }
+            if let Some(namn_193) = segment.prop(Field::StructureName) {
+                segment.tags.insert("description".to_string(), namn_193.as_string().trim().to_string());
             }
         }
 
@@ -466,7 +510,7 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
 
     // STEP 2: Check Vägkategori/Kategori (Kateg_380) for motor vehicle roads
     // NOTE: No early return here — motorway/motorroad override comes after (in map_motorway_override)
-    if let Some(kateg) = segment.properties.get("Kateg_380").and_then(|v| v.as_i64()) {
+    if let Some(kateg) = segment.prop(Field::RoadCategory).and_then(|v| v.as_i64()) {
         match kateg {
             1 => { // E road
                 segment.tags.insert("highway".to_string(), "trunk".to_string());
@@ -489,22 +533,28 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
     }
 
     // STEP 3: Check pedestrian streets and living streets
-    let l_gagata = segment.properties.get("L_Gagata").map(|v| v.as_bool()).unwrap_or(false);
-    let r_gagata = segment.properties.get("R_Gagata").map(|v| v.as_bool()).unwrap_or(false);
+    let l_gagata = segment.prop(Field::PedestrianStreetLeft).map(|v| v.as_bool()).unwrap_or(false);
+    let r_gagata = segment.prop(Field::PedestrianStreetRight).map(|v| v.as_bool()).unwrap_or(false);
     if l_gagata || r_gagata {
         segment.tags.insert("highway".to_string(), "pedestrian".to_string());
+        // A pedestrian street signed for certain hours is open to traffic
+        // outside them
+        if let Some(hours) = pedestrian_street_hours(segment) {
+            segment.tags.insert("motor_vehicle".to_string(), "yes".to_string());
+            add_conditional(&mut segment.tags, "motor_vehicle:conditional".to_string(), &format!("no @ ({})", hours));
+        }
         return;
     }
 
-    let l_gangfart = segment.properties.get("L_Gangfartsomrade").map(|v| v.as_bool()).unwrap_or(false);
-    let r_gangfart = segment.properties.get("R_Gangfartsomrade").map(|v| v.as_bool()).unwrap_or(false);
+    let l_gangfart = segment.prop(Field::LivingStreetLeft).map(|v| v.as_bool()).unwrap_or(false);
+    let r_gangfart = segment.prop(Field::LivingStreetRight).map(|v| v.as_bool()).unwrap_or(false);
     if l_gangfart || r_gangfart {
         segment.tags.insert("highway".to_string(), "living_street".to_string());
         return;
     }
 
     // STEP 4: Check Funktionell vägklass < 6 → tertiary
-    if let Some(klass) = segment.properties.get("Klass_181").and_then(|v| v.as_i64()) {
+    if let Some(klass) = segment.prop(Field::FunctionalClass).and_then(|v| v.as_i64()) {
         if klass > 0 && klass < 6 {
             segment.tags.insert("highway".to_string(), "tertiary".to_string());
             return;
@@ -512,22 +562,17 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
     }
 
     // STEP 5: Private road checks, service, and track classification
-    let vagha = segment.properties.get("Vagha_6").and_then(|v| v.as_i64()).unwrap_or(0);
-    let klass = segment.properties.get("Klass_181").and_then(|v| v.as_i64()).unwrap_or(0);
-    let tillg = segment.properties.get("Tillg_169").and_then(|v| v.as_i64()).unwrap_or(0);
-    let has_namn = segment.properties.get("Namn_130")
-        .map(|v| {
-            let s = v.as_string();
-            !s.is_empty() && s != "NA"
-        })
-        .unwrap_or(false);
-    let slitl = segment.properties.get("Slitl_152").and_then(|v| v.as_i64()).unwrap_or(0);
-    let tatt = segment.properties.get("TattbebyggtOmrade").map(|v| v.as_bool()).unwrap_or(false);
+    let vagha = segment.prop(Field::RoadOwner).and_then(|v| v.as_i64()).unwrap_or(0);
+    let klass = segment.prop(Field::FunctionalClass).and_then(|v| v.as_i64()).unwrap_or(0);
+    let tillg = segment.prop(Field::Accessibility).and_then(|v| v.as_i64()).unwrap_or(0);
+    let has_namn = segment.prop(Field::StreetName).is_some();
+    let slitl = segment.prop(Field::Surface).and_then(|v| v.as_i64()).unwrap_or(0);
+    let tatt = segment.prop(Field::UrbanArea).map(|v| v.as_bool()).unwrap_or(false);
     // P4 FIX: Check Driftbidrag statligt/Vägnr (Python line 658)
-    let has_vagnr = segment.properties.get("Vagnr_10370")
+    let has_vagnr = segment.prop(Field::GrantRoadNumber)
         .map(|v| {
             let s = v.as_string();
-            !s.is_empty() && s != "0" && s != "NA"
+            s != "0"
         })
         .unwrap_or(false);
 
@@ -573,9 +618,9 @@ fn map_highway(segment: &mut Segment, street_names: &std::collections::HashSet<S
 /// P1 FIX: Motorway/motorroad override (Python lines 684-688)
 /// Must run AFTER map_highway — overrides the category-based classification
 fn map_motorway_override(segment: &mut Segment) {
-    if segment.properties.get("Motorvag").map(|v| v.as_bool()).unwrap_or(false) {
+    if segment.prop(Field::Motorway).map(|v| v.as_bool()).unwrap_or(false) {
         segment.tags.insert("highway".to_string(), "motorway".to_string());
-    } else if segment.properties.get("Motortrafikled").map(|v| v.as_bool()).unwrap_or(false) {
+    } else if segment.prop(Field::Motorroad).map(|v| v.as_bool()).unwrap_or(false) {
         segment.tags.insert("motorroad".to_string(), "yes".to_string());
     }
 }
@@ -584,19 +629,19 @@ fn map_motorway_override(segment: &mut Segment) {
 /// Separate function so it applies to ALL motor vehicle highways, not just default
 fn map_bicycle_designated(segment: &mut Segment) {
     // Only for motor vehicle highways (not cycleways/footways)
-    let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(1);
+    let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
     if net_type == 2 || net_type == 4 {
         return;
     }
-    if segment.properties.get("C_Rekbilvagcykeltrafik").map(|v| v.as_bool()).unwrap_or(false) {
+    if segment.prop(Field::CycleRouteRecommended).map(|v| v.as_bool()).unwrap_or(false) {
         segment.tags.insert("bicycle".to_string(), "designated".to_string());
     }
 }
 
 /// P13 FIX: Roundabout via tag_direction (Python lines 749-756)
 fn map_roundabout(segment: &mut Segment) {
-    let f_cirk = segment.properties.get("F_Cirkulationsplats").and_then(|v| if v.as_bool() { Some(1) } else { None });
-    let b_cirk = segment.properties.get("B_Cirkulationsplats").and_then(|v| if v.as_bool() { Some(1) } else { None });
+    let f_cirk = segment.prop(Field::RoundaboutForward).and_then(|v| if v.as_bool() { Some(1) } else { None });
+    let b_cirk = segment.prop(Field::RoundaboutBackward).and_then(|v| if v.as_bool() { Some(1) } else { None });
     tag_direction(
         &mut segment.tags,
         segment.oneway_direction,
@@ -623,13 +668,13 @@ fn map_highway_links(segment: &mut Segment) {
     }
     
     // Check FPV class - must be None (not on priority network)
-    let fpv_class = segment.properties.get("FPV_k_309").and_then(|v| v.as_i64());
+    let fpv_class = segment.prop(Field::FpvClass).and_then(|v| v.as_i64());
     if fpv_class.is_some() {
         return;
     }
     
     // Check delivery quality class - must be < 4
-    let delivery_class = segment.properties.get("Lever_292").and_then(|v| v.as_i64());
+    let delivery_class = segment.prop(Field::DeliveryQuality).and_then(|v| v.as_i64());
     if let Some(dc) = delivery_class {
         if dc >= 4 {
             return;
@@ -639,8 +684,8 @@ fn map_highway_links(segment: &mut Segment) {
     }
     
     // Check not a roundabout
-    let f_cirk = segment.properties.get("F_Cirkulationsplats").map(|v| v.as_bool()).unwrap_or(false);
-    let b_cirk = segment.properties.get("B_Cirkulationsplats").map(|v| v.as_bool()).unwrap_or(false);
+    let f_cirk = segment.prop(Field::RoundaboutForward).map(|v| v.as_bool()).unwrap_or(false);
+    let b_cirk = segment.prop(Field::RoundaboutBackward).map(|v| v.as_bool()).unwrap_or(false);
     if f_cirk || b_cirk {
         return;
     }
@@ -655,7 +700,7 @@ fn map_highway_links(segment: &mut Segment) {
 /// Only cycleways/footways are excluded (they return early in Python's osm_tags).
 fn map_surface(segment: &mut Segment) {
     // Cycleways/footways already returned in Python — they never reach this code
-    let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(1);
+    let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
     if net_type == 2 || net_type == 4 {
         return;  // Already handled in cycleway section
     }
@@ -664,7 +709,7 @@ fn map_surface(segment: &mut Segment) {
         return;
     }
 
-    if let Some(surface_code) = segment.properties.get("Slitl_152").and_then(|v| v.as_i64()) {
+    if let Some(surface_code) = segment.prop(Field::Surface).and_then(|v| v.as_i64()) {
         let surface = match surface_code {
             1 => "paved",
             2 => "unpaved",
@@ -680,13 +725,17 @@ fn map_surface(segment: &mut Segment) {
 fn map_maxspeed(segment: &mut Segment) {
     // Check if this is a track with 70/70 speeds (excluded in Python, lines 758-762)
     let highway = segment.tags.get("highway").map(|s| s.as_str()).unwrap_or("");
-    let speed_f = segment.properties.get("F_Hogst_225").and_then(|v| v.as_i64());
-    let speed_b = segment.properties.get("B_Hogst_225").and_then(|v| v.as_i64());
+    let speed_f = segment.prop(Field::SpeedLimitForward).and_then(|v| v.as_i64());
+    let speed_b = segment.prop(Field::SpeedLimitBackward).and_then(|v| v.as_i64());
 
     if highway == "track" && speed_f == Some(70) && speed_b == Some(70) {
         return;
     }
 
+    for speed in [speed_f, speed_b].into_iter().flatten().filter(|&v| v <= 0 || v > 120) {
+        segment.annotate(AnnotationKind::OutOfRange, format!("speed limit {} dropped", speed));
+    }
+
     // Use tag_direction for maxspeed — value=None means use the property value directly
     tag_direction(
         &mut segment.tags,
@@ -707,14 +756,12 @@ fn map_maxspeed(segment: &mut Segment) {
 /// CRITICAL: Must run BEFORE any directional tags (maxspeed, motor_vehicle, etc.)
 /// because they all depend on segment.oneway_direction via tag_direction()
 fn map_oneway(segment: &mut Segment) {
-    use crate::models::hash_coord;
-
     // Check direction of travel restrictions (takes priority)
     // Python: if prop["Förbjuden färdriktning(B)"]: oneway = "forward"
     //         elif prop["Förbjuden färdriktning(F)"]: oneway = "backward"; reverse_segment
-    let f_forbidden = segment.properties.get("F_ForbjudenFardriktning")
+    let f_forbidden = segment.prop(Field::NoEntryForward)
         .map(|v| v.as_bool()).unwrap_or(false);
-    let b_forbidden = segment.properties.get("B_ForbjudenFardriktning")
+    let b_forbidden = segment.prop(Field::NoEntryBackward)
         .map(|v| v.as_bool()).unwrap_or(false);
 
     if b_forbidden && !f_forbidden {
@@ -732,11 +779,16 @@ fn map_oneway(segment: &mut Segment) {
         
         segment.tags.insert("oneway".to_string(), "yes".to_string());
         segment.oneway_direction = OnewayDirection::Backward;
+    } else if f_forbidden && b_forbidden {
+        segment.annotate(
+            AnnotationKind::ConflictingAttributes,
+            "travel forbidden in both directions, oneway ignored",
+        );
     }
 
     // Check Korfa_524 (Körfältsanvändning) only if oneway not already set
     if segment.oneway_direction == OnewayDirection::None {
-        if let Some(korfa) = segment.properties.get("Korfa_524").and_then(|v| v.as_i64()) {
+        if let Some(korfa) = segment.prop(Field::LaneUsage).and_then(|v| v.as_i64()) {
             if korfa == 1 {
                 segment.tags.insert("oneway".to_string(), "yes".to_string());
                 segment.oneway_direction = OnewayDirection::Forward;
@@ -745,6 +797,19 @@ fn map_oneway(segment: &mut Segment) {
     }
 }
 
+/// Side key ("left", "right" or "both") of an attribute given for the left
+/// and right side in the digitised direction, relative to the way, which is
+/// reversed on oneways against the digitised direction (see `map_oneway()`)
+pub(crate) fn side_of(oneway: OnewayDirection, left: bool, right: bool) -> Option<&'static str> {
+    let (left, right) = if oneway == OnewayDirection::Backward { (right, left) } else { (left, right) };
+    match (left, right) {
+        (true, true) => Some("both"),
+        (true, false) => Some("left"),
+        (false, true) => Some("right"),
+        (false, false) => None,
+    }
+}
+
 /// Port of Python tag_direction() helper (lines 1040-1072)
 ///
 /// Handles forward/backward directional tag application with oneway awareness.
@@ -788,8 +853,8 @@ fn tag_direction(
     });
 
     // Python: if prop_forward == prop_backward: tags[tag] = str(prop_forward)
-    if val_f.is_some() && val_b.is_some() && val_f == val_b {
-        tags.insert(tag.to_string(), val_f.unwrap());
+    if let Some(v) = val_f.as_ref().filter(|&v| val_b.as_ref() == Some(v)) {
+        tags.insert(tag.to_string(), v.clone());
         return;
     }
 
@@ -835,14 +900,10 @@ fn tag_direction(
 fn build_street_names(segments: &[Segment]) -> std::collections::HashSet<String> {
     let mut names = std::collections::HashSet::new();
     for segment in segments {
-        let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(0);
+        let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(0);
         if net_type == 1 {
-            if let Some(name) = segment.properties.get("Namn_130") {
-                let s = name.as_string();
-                let s = s.trim();
-                if !s.is_empty() && s != "NA" {
-                    names.insert(s.to_string());
-                }
+            if let Some(name) = segment.prop(Field::StreetName) {
+                names.insert(name.as_string().trim().to_string());
             }
         }
     }
@@ -856,7 +917,7 @@ fn build_street_names(segments: &[Segment]) -> std::collections::HashSet<String>
 /// - Construction 2,3: tunnel IF bridge tag is "tunnel" or no bridge ID + conditions
 fn map_bridge_tunnel(segment: &mut Segment, bridges: &FxHashMap<String, Bridge>) {
     // Check for bridge/tunnel by construction type (Konst_190)
-    if let Some(constr_prop) = segment.properties.get("Konst_190") {
+    if let Some(constr_prop) = segment.prop(Field::StructureType) {
         let construction = constr_prop.as_i64().unwrap_or(0);
         let bridge_margin = 50.0;
         
@@ -866,7 +927,7 @@ fn map_bridge_tunnel(segment: &mut Segment, bridges: &FxHashMap<String, Bridge>)
                 segment.tags.insert("bridge".to_string(), "yes".to_string());
                 
                 // Add layer tag if we have bridge ID
-                if let Some(id_prop) = segment.properties.get("Ident_191") {
+                if let Some(id_prop) = segment.prop(Field::StructureId) {
                     let bridge_id = id_prop.as_string();
                     if let Some(bridge) = bridges.get(&bridge_id) {
                         segment.tags.insert("layer".to_string(), bridge.layer.clone());
@@ -879,11 +940,11 @@ fn map_bridge_tunnel(segment: &mut Segment, bridges: &FxHashMap<String, Bridge>)
             }
             2 | 3 => {
                 // Under bridge - check if should be marked as tunnel (lines 497-510)
-                let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(0);
+                let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(0);
                 let is_long = segment.shape_length > bridge_margin;
                 
                 // Check if we have a bridge ID and what its tag is
-                let bridge_tag = segment.properties.get("Ident_191")
+                let bridge_tag = segment.prop(Field::StructureId)
                     .and_then(|id| bridges.get(&id.as_string()))
                     .map(|b| b.tag.as_str());
                 
@@ -918,7 +979,7 @@ fn map_bridge_tunnel(segment: &mut Segment, bridges: &FxHashMap<String, Bridge>)
 /// Uses Namn_130 with Namn_132 fallback.
 fn map_name(segment: &mut Segment) {
     // Cycleways/footways already got their names in map_highway
-    let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(1);
+    let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
     if net_type == 2 || net_type == 4 {
         return;
     }
@@ -927,47 +988,47 @@ fn map_name(segment: &mut Segment) {
         return;
     }
 
-    // Skip if roundabout (Python lines 931-932)
-    let f_cirk = segment.properties.get("F_Cirkulationsplats").map(|v| v.as_bool()).unwrap_or(false);
-    let b_cirk = segment.properties.get("B_Cirkulationsplats").map(|v| v.as_bool()).unwrap_or(false);
+    // Skip if roundabout (Python lines 931-932); roundabout::RoundaboutTags
+    // can add the names back afterwards
+    let f_cirk = segment.prop(Field::RoundaboutForward).map(|v| v.as_bool()).unwrap_or(false);
+    let b_cirk = segment.prop(Field::RoundaboutBackward).map(|v| v.as_bool()).unwrap_or(false);
     if f_cirk || b_cirk {
         return;
     }
 
-    // Python: Gatunamn/Namn first, then Övrigt vägnamn/Namn fallback (lines 934-937)
-    let name_str = segment.properties.get("Namn_130")
-        .map(|v| v.as_string())
-        .filter(|s| {
-            let t = s.trim();
-            !t.is_empty() && t != "NA" && t != "-1"
-        })
-        .or_else(|| {
-            segment.properties.get("Namn_132")
-                .map(|v| v.as_string())
-                .filter(|s| {
-                    let t = s.trim();
-                    !t.is_empty() && t != "NA" && t != "-1"
-                })
-        });
-
-    if let Some(name) = name_str {
-        segment.tags.insert("name".to_string(), name.trim().to_string());
+    if let Some(name) = road_name(segment) {
+        segment.tags.insert("name".to_string(), name);
     }
 }
 
+/// Street name of a road segment, if any
+///
+/// Python: Gatunamn/Namn first, then Övrigt vägnamn/Namn fallback (lines 934-937)
+pub(crate) fn road_name(segment: &Segment) -> Option<String> {
+    let valid = |s: &String| s.trim() != "-1";
+    segment.prop(Field::StreetName)
+        .map(|v| v.as_string())
+        .filter(valid)
+        .or_else(|| segment.prop(Field::OtherName).map(|v| v.as_string()).filter(valid))
+        .map(|name| name.trim().to_string())
+}
+
 /// Map reference number
 /// 
 /// Python logic (lines 732-745):
 /// - Category 1 (E road): "E " + Huvudnummer
 /// - Category 2,3 (Trunk, Primary): Huvudnummer
 /// - Category 4 (Secondary): County letter + " " + Huvudnummer
+///
+/// Roundabouts get the ref too; see roundabout::RoundaboutTags to drop it
+/// or move it to `junction:ref`.
 fn map_ref(segment: &mut Segment) {
-    let kateg = segment.properties.get("Kateg_380").and_then(|v| v.as_i64());
-    let huvnr = segment.properties.get("Huvnr_556_1");
+    let kateg = segment.prop(Field::RoadCategory).and_then(|v| v.as_i64());
+    let huvnr = segment.prop(Field::RoadNumber);
     
     if let (Some(kat), Some(huvnr_val)) = (kateg, huvnr) {
         let huvnr_str = huvnr_val.as_string();
-        if huvnr_str.is_empty() || huvnr_str == "0" || huvnr_str == "-1" {
+        if huvnr_str == "0" || huvnr_str == "-1" {
             return;
         }
         
@@ -982,7 +1043,7 @@ fn map_ref(segment: &mut Segment) {
             }
             4 => {
                 // Secondary county road: county letter + number
-                if let Some(kommun) = segment.properties.get("Kommu_141").and_then(|v| v.as_i64()) {
+                if let Some(kommun) = segment.prop(Field::Municipality).and_then(|v| v.as_i64()) {
                     let county_num = kommun / 100;
                     let county_codes = COUNTY_CODES.get_or_init(init_county_codes);
                     
@@ -1003,7 +1064,7 @@ fn map_ref(segment: &mut Segment) {
 /// PSV lanes use tag_direction via F/B_Korfa_517.
 fn map_lanes(segment: &mut Segment) {
     // P5 FIX: Use Korfa_497 for lane count (Python line 873-878)
-    if let Some(lane_count) = segment.properties.get("Korfa_497").and_then(|v| v.as_i64()) {
+    if let Some(lane_count) = segment.prop(Field::LaneCount).and_then(|v| v.as_i64()) {
         let is_oneway = segment.oneway_direction != OnewayDirection::None;
         // Python: only tag if > 2, or oneway and > 1
         if lane_count > 2 || (is_oneway && lane_count > 1) {
@@ -1012,8 +1073,8 @@ fn map_lanes(segment: &mut Segment) {
     }
 
     // PSV lanes via tag_direction (Python lines 880-905)
-    let f_psv = segment.properties.get("F_Korfa_517").and_then(|v| v.as_i64()).unwrap_or(0);
-    let b_psv = segment.properties.get("B_Korfa_517").and_then(|v| v.as_i64()).unwrap_or(0);
+    let f_psv = segment.prop(Field::BusLaneForward).and_then(|v| v.as_i64()).unwrap_or(0);
+    let b_psv = segment.prop(Field::BusLaneBackward).and_then(|v| v.as_i64()).unwrap_or(0);
 
     // PSV=yes + motor_vehicle=no for bus-only lanes (value 2)
     let f_bus = if f_psv == 2 { Some(1i64) } else { None };
@@ -1032,7 +1093,7 @@ fn map_lanes(segment: &mut Segment) {
 /// P8 FIX: Python applies width to ALL motor vehicle highways (no type filter)
 fn map_width(segment: &mut Segment) {
     // Cycleways/footways already returned in Python
-    let net_type = segment.properties.get("Vagtr_474").and_then(|v| v.as_i64()).unwrap_or(1);
+    let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
     if net_type == 2 || net_type == 4 {
         return;
     }
@@ -1040,7 +1101,7 @@ fn map_width(segment: &mut Segment) {
         return;  // No width for ferries
     }
 
-    if let Some(width) = segment.properties.get("Bredd_156").and_then(|v| v.as_f64()) {
+    if let Some(width) = segment.prop(Field::Width).and_then(|v| v.as_f64()) {
         if width > 0.0 && width < 50.0 {
             segment.tags.insert("width".to_string(), format!("{:.1}", width));
         }
@@ -1067,14 +1128,9 @@ fn tag_isolated_tracks(segments: &mut [Segment]) {
                 // - Tillgänglighet/Tillgänglighetsklass exists (> 0)
                 // - No street name
                 // - Unpaved surface
-                let tillg = segment.properties.get("Tillg_169").and_then(|v| v.as_i64()).unwrap_or(0);
-                let has_namn = segment.properties.get("Namn_130")
-                    .map(|v| {
-                        let s = v.as_string();
-                        !s.is_empty() && s != "NA"
-                    })
-                    .unwrap_or(false);
-                let slitl = segment.properties.get("Slitl_152").and_then(|v| v.as_i64()).unwrap_or(0);
+                let tillg = segment.prop(Field::Accessibility).and_then(|v| v.as_i64()).unwrap_or(0);
+                let has_namn = segment.prop(Field::StreetName).is_some();
+                let slitl = segment.prop(Field::Surface).and_then(|v| v.as_i64()).unwrap_or(0);
                 
                 if tillg > 0 && !has_namn && slitl != 1 {
                     segment.tags.insert("highway".to_string(), "track".to_string());
@@ -1093,9 +1149,9 @@ fn tag_urban_vs_rural(_segments: &mut [Segment]) {
 /// Set for roads with official road numbers
 fn map_priority_road(segment: &mut Segment) {
     // Check if road has an official number (Vägnummer/Huvudnummer = Huvnr_556_1)
-    if let Some(huvnr) = segment.properties.get("Huvnr_556_1") {
+    if let Some(huvnr) = segment.prop(Field::RoadNumber) {
         let huvnr_str = huvnr.as_string();
-        if !huvnr_str.is_empty() && huvnr_str != "0" {
+        if huvnr_str != "0" {
             segment.tags.insert("priority_road".to_string(), "designated".to_string());
         }
     }
@@ -1104,7 +1160,7 @@ fn map_priority_road(segment: &mut Segment) {
 /// Map lit tag (street lighting)
 /// GCM-belyst = 1 means lit
 fn map_lit(segment: &mut Segment) {
-    if let Some(belyst) = segment.properties.get("GCM_belyst") {
+    if let Some(belyst) = segment.prop(Field::Lit) {
         if belyst.as_bool() {
             segment.tags.insert("lit".to_string(), "yes".to_string());
         }
@@ -1114,13 +1170,13 @@ fn map_lit(segment: &mut Segment) {
 /// Motor vehicle access restriction — Python lines 772-779
 /// tag_direction(tags, "motor_vehicle", "no", F_ForbudTrafik, B_ForbudTrafik, oneway)
 fn map_motor_vehicle_access(segment: &mut Segment) {
-    let f = segment.properties.get("F_ForbudTrafik").and_then(|v| v.as_i64());
-    let b = segment.properties.get("B_ForbudTrafik").and_then(|v| v.as_i64());
+    let f = segment.prop(Field::TrafficBanForward).and_then(|v| v.as_i64());
+    let b = segment.prop(Field::TrafficBanBackward).and_then(|v| v.as_i64());
     
     // Only apply if Typ_512 is 40 (vehicle) or NULL
     // If Typ_512 is 10 (car) or 20 (bus), we handle it in map_vehicle_restrictions
-    let f_typ = segment.properties.get("Typ_512").and_then(|v| v.as_i64()).unwrap_or(40);
-    let b_typ = segment.properties.get("Typ_369").and_then(|v| v.as_i64()).unwrap_or(40);
+    let f_typ = segment.prop(Field::BanVehicleForward).and_then(|v| v.as_i64()).unwrap_or(40);
+    let b_typ = segment.prop(Field::BanVehicleBackward).and_then(|v| v.as_i64()).unwrap_or(40);
 
     let f_val = if f_typ == 40 { f } else { None };
     let b_val = if b_typ == 40 { b } else { None };
@@ -1131,7 +1187,7 @@ fn map_motor_vehicle_access(segment: &mut Segment) {
 /// Map PSV lanes — port from Python lines 880-896
 fn map_psv_lanes(segment: &mut Segment) {
     // 2024 schema uses FPV_kollektivtrafik = -1 for bus lanes/routes
-    if let Some(prop) = segment.properties.get("FPV_kollektivtrafik").and_then(|v| v.as_i64()) {
+    if let Some(prop) = segment.prop(Field::FpvPublicTransport).and_then(|v| v.as_i64()) {
         if prop == -1 || prop == 1 {
             // Apply psv=yes and motor_vehicle=no
             // Usually psv lanes are directional, but FPV_kollektivtrafik seems to be a general flag here
@@ -1151,74 +1207,125 @@ fn map_psv_lanes(segment: &mut Segment) {
 /// Now uses tag_direction for proper oneway handling
 fn map_hazmat(segment: &mut Segment) {
     // Check if recommended for hazardous goods (Python line 847-848)
-    if segment.properties.get("Rekom_185").map(|v| v.as_bool()).unwrap_or(false) {
+    if segment.prop(Field::HazmatRecommended).map(|v| v.as_bool()).unwrap_or(false) {
         segment.tags.insert("hazmat".to_string(), "designated".to_string());
     }
 
     // Check for restrictions (Python lines 850-860)
-    let hazmat_f = segment.properties.get("F_Beskr_124").and_then(|v| v.as_i64()).unwrap_or(0);
-    let hazmat_b = segment.properties.get("B_Beskr_124").and_then(|v| v.as_i64()).unwrap_or(0);
+    let hazmat_f = segment.prop(Field::HazmatForward).and_then(|v| v.as_i64()).unwrap_or(0);
+    let hazmat_b = segment.prop(Field::HazmatBackward).and_then(|v| v.as_i64()).unwrap_or(0);
     // Python converts truthy to 1 for tag_direction: `1 if hazmat_f else None`
     let hf = if hazmat_f > 0 { Some(1i64) } else { None };
     let hb = if hazmat_b > 0 { Some(1i64) } else { None };
     tag_direction(&mut segment.tags, segment.oneway_direction, "hazmat", Some("no"), hf, hb);
 }
 
+/// Hours a pedestrian street applies as an opening_hours range ("11:00-06:00")
+fn pedestrian_street_hours(segment: &Segment) -> Option<String> {
+    let from = segment.prop(Field::PedestrianStreetFrom).and_then(clock_time)?;
+    let to = segment.prop(Field::PedestrianStreetTo).and_then(clock_time)?;
+    (from != to).then(|| format!("{}-{}", from, to))
+}
+
+/// Time of day as "HH:MM" from "11", "1100", "11:00", "11.30" or a number
+fn clock_time(value: &PropertyValue) -> Option<String> {
+    let text = value.as_string();
+    let text = text.trim();
+    let (hours, minutes) = match text.split_once([':', '.']) {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        None if text.len() <= 2 => (text.parse::<u32>().ok()?, 0),
+        None if text.len() <= 4 => {
+            let (h, m) = text.split_at(text.len() - 2);
+            (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)
+        }
+        None => return None,
+    };
+    (hours < 24 && minutes < 60 || hours == 24 && minutes == 0)
+        .then(|| format!("{:02}:{:02}", hours, minutes))
+}
+
+/// Add a conditional restriction, combining it with one already set for the
+/// same key (several banned vehicle types can map to one OSM tag)
+fn add_conditional(tags: &mut FxHashMap<String, String>, key: String, value: &str) {
+    match tags.get_mut(&key) {
+        Some(existing) => {
+            if !existing.split("; ").any(|v| v == value) {
+                existing.push_str("; ");
+                existing.push_str(value);
+            }
+        }
+        None => {
+            tags.insert(key, value.to_string());
+        }
+    }
+}
+
 /// Map vehicle size and weight restrictions
 /// 
 /// UPDATED: Added full vehicle type restrictions from "Förbud mot trafik"
 /// Python lines 781-845
 fn map_vehicle_restrictions(segment: &mut Segment) {
     // Max height (Höjdhinder upp till 4,5 m/Fri höjd)
-    if let Some(height) = segment.properties.get("Fri_h_143").and_then(|v| v.as_f64()) {
+    if let Some(height) = segment.prop(Field::MaxHeight).and_then(|v| v.as_f64()) {
         if height > 0.0 && height < 10.0 {
-            segment.tags.insert("maxheight".to_string(), format!("{:.1}", height));
+            segment.tags.insert("maxheight".to_string(), number(height));
+        } else if height >= 10.0 {
+            segment.annotate(AnnotationKind::OutOfRange, format!("maxheight {} dropped", number(height)));
         }
     }
     
     // Max length (Begränsad fordonslängd)
-    if let Some(length) = segment.properties.get("Hogst_46").and_then(|v| v.as_f64()) {
+    if let Some(length) = segment.prop(Field::MaxLength).and_then(|v| v.as_f64()) {
         if length > 0.0 && length < 50.0 {
-            segment.tags.insert("maxlength".to_string(), format!("{:.1}", length));
+            segment.tags.insert("maxlength".to_string(), number(length));
+        } else if length >= 50.0 {
+            segment.annotate(AnnotationKind::OutOfRange, format!("maxlength {} dropped", number(length)));
         }
     }
     
     // Max width (Begränsad fordonsbredd)
-    if let Some(width) = segment.properties.get("Hogst_36").and_then(|v| v.as_f64()) {
+    if let Some(width) = segment.prop(Field::MaxWidth).and_then(|v| v.as_f64()) {
         if width > 0.0 && width < 10.0 {
-            segment.tags.insert("maxwidth".to_string(), format!("{:.1}", width));
+            segment.tags.insert("maxwidth".to_string(), number(width));
+        } else if width >= 10.0 {
+            segment.annotate(AnnotationKind::OutOfRange, format!("maxwidth {} dropped", number(width)));
         }
     }
     
     // Max axle load (Begränsat axel-boggitryck)
-    if let Some(axleload) = segment.properties.get("Hogst_55_30").and_then(|v| v.as_f64()) {
+    if let Some(axleload) = segment.prop(Field::MaxAxleLoad).and_then(|v| v.as_f64()) {
         if axleload > 0.0 && axleload < 100.0 {
-            segment.tags.insert("maxaxleload".to_string(), format!("{:.1}", axleload));
+            segment.tags.insert("maxaxleload".to_string(), number(axleload));
+        } else if axleload >= 100.0 {
+            segment.annotate(AnnotationKind::OutOfRange, format!("maxaxleload {} dropped", number(axleload)));
         }
     }
     
     // Max weight - directional (Begränsad bruttovikt)
-    let weight_f = segment.properties.get("F_Hogst_24").and_then(|v| v.as_f64());
-    let weight_b = segment.properties.get("B_Hogst_24").and_then(|v| v.as_f64());
+    let weight_f = segment.prop(Field::MaxWeightForward).and_then(|v| v.as_f64());
+    let weight_b = segment.prop(Field::MaxWeightBackward).and_then(|v| v.as_f64());
     
     let wf = weight_f.filter(|&v| v > 0.0 && v < 100.0);
     let wb = weight_b.filter(|&v| v > 0.0 && v < 100.0);
+    for weight in [weight_f, weight_b].into_iter().flatten().filter(|&v| v >= 100.0) {
+        segment.annotate(AnnotationKind::OutOfRange, format!("maxweight {} dropped", number(weight)));
+    }
     
     if let (Some(wf_val), Some(wb_val)) = (wf, wb) {
         if (wf_val - wb_val).abs() < 0.1 {
-            segment.tags.insert("maxweight".to_string(), format!("{:.1}", wf_val));
+            segment.tags.insert("maxweight".to_string(), number(wf_val));
         } else {
-            segment.tags.insert("maxweight:forward".to_string(), format!("{:.1}", wf_val));
-            segment.tags.insert("maxweight:backward".to_string(), format!("{:.1}", wb_val));
+            segment.tags.insert("maxweight:forward".to_string(), number(wf_val));
+            segment.tags.insert("maxweight:backward".to_string(), number(wb_val));
         }
     } else if let Some(wf_val) = wf {
-        segment.tags.insert("maxweight:forward".to_string(), format!("{:.1}", wf_val));
+        segment.tags.insert("maxweight:forward".to_string(), number(wf_val));
     } else if let Some(wb_val) = wb {
-        segment.tags.insert("maxweight:backward".to_string(), format!("{:.1}", wb_val));
+        segment.tags.insert("maxweight:backward".to_string(), number(wb_val));
     }
     
     // HGV restriction for forest roads (Framkomlighetsklass = 4)
-    if let Some(framk) = segment.properties.get("Framk_161").and_then(|v| v.as_i64()) {
+    if let Some(framk) = segment.prop(Field::Trafficability).and_then(|v| v.as_i64()) {
         if framk == 4 {
             segment.tags.insert("hgv".to_string(), "no".to_string());
         }
@@ -1226,13 +1333,13 @@ fn map_vehicle_restrictions(segment: &mut Segment) {
     
     // Bridge weight limit fallback (Python lines 994-998)
     if segment.tags.contains_key("bridge") && !segment.tags.contains_key("maxweight") {
-        if let Some(barig) = segment.properties.get("Barig_64").and_then(|v| v.as_i64()) {
+        if let Some(barig) = segment.prop(Field::BridgeLoadClass).and_then(|v| v.as_i64()) {
             let maxweight = match barig {
-                1 => "64.0",  // BK1
+                1 => "64",    // BK1
                 2 => "51.4",  // BK2
                 3 => "37.5",  // BK3
-                4 => "74.0",  // BK4
-                5 => "74.0",  // BK4 särskilda villkor
+                4 => "74",    // BK4
+                5 => "74",    // BK4 särskilda villkor
                 _ => "",
             };
             if !maxweight.is_empty() {
@@ -1255,24 +1362,31 @@ fn map_vehicle_restrictions(segment: &mut Segment) {
     let mut restrictions: Vec<VehicleRestriction> = Vec::new();
 
     for is_forward in [true, false] {
-        let forbud_key = if is_forward { "F_ForbudTrafik" } else { "B_ForbudTrafik" };
-        let typ_key = if is_forward { "Typ_512" } else { "Typ_369" };
+        let forbud_key = if is_forward { Field::TrafficBanForward } else { Field::TrafficBanBackward };
+        let typ_key = if is_forward { Field::BanVehicleForward } else { Field::BanVehicleBackward };
         // Weight limit column F_Hogst_24 is already handled above for maxweight,
         // but Gäller fordon might have a specific weight limit F_Total_136 in some schemas.
         // In 2024 schema, we only see Typ_512 for now.
-        let total_key = if is_forward { "F_Total_136" } else { "B_Total_136" };
+        let total_key = if is_forward { Field::BanWeightForward } else { Field::BanWeightBackward };
 
-        if let Some(forbud) = segment.properties.get(forbud_key).and_then(|v| v.as_i64()) {
+        if let Some(forbud) = segment.prop(forbud_key).and_then(|v| v.as_i64()) {
             if forbud == -1 || forbud == 1 {
-                if let Some(vehicle_type) = segment.properties.get(typ_key).and_then(|v| v.as_i64()) {
-                    if let Some(&osm_tag) = vehicle_type_map.get(&vehicle_type) {
-                        // Skip if it is "vehicle" since that is handled in map_motor_vehicle_access
-                        if osm_tag != "vehicle" {
-                            let weight_limit = segment.properties.get(total_key)
-                                .and_then(|v| v.as_f64())
-                                .filter(|&w| w > 0.0);
-                            restrictions.push(VehicleRestriction { is_forward, osm_tag, weight_limit });
-                        }
+                // A link can ban several vehicle types; a multi-valued weight
+                // column pairs up with the types, a single weight applies to all
+                let vehicle_types = segment.prop(typ_key).map(|v| v.values()).unwrap_or(&[]);
+                let weights = segment.prop(total_key).map(|v| v.values()).unwrap_or(&[]);
+                for (k, vehicle_type) in vehicle_types.iter().enumerate() {
+                    let osm_tags = match vehicle_type.as_i64().and_then(|t| vehicle_type_map.get(&t)) {
+                        Some(&osm_tags) => osm_tags,
+                        None => continue,
+                    };
+                    let weight = if weights.len() > 1 { weights.get(k) } else { weights.first() };
+                    let weight_limit = weight
+                        .and_then(|v| v.as_f64())
+                        .filter(|&w| w > 0.0);
+                    // Skip if it is "vehicle" since that is handled in map_motor_vehicle_access
+                    for &osm_tag in osm_tags.iter().filter(|&&t| t != "vehicle") {
+                        restrictions.push(VehicleRestriction { is_forward, osm_tag, weight_limit });
                     }
                 }
             }
@@ -1285,19 +1399,19 @@ fn map_vehicle_restrictions(segment: &mut Segment) {
             if r.osm_tag == "hgv" {
                 // Python line 812: maxweight:(F)/(B) — use :forward/:backward
                 let suffix = if r.is_forward { ":forward" } else { ":backward" };
-                segment.tags.insert(format!("maxweight{}", suffix), format!("{}", weight));
+                segment.tags.insert(format!("maxweight{}", suffix), number(weight));
             } else {
                 // Python lines 817-830: conditional restriction with direction handling
-                let tag_value = format!("no @ (weight>{})", weight);
+                let tag_value = format!("no @ (weight>{})", number(weight));
                 if r.is_forward {
                     // Python line 820: if oneway != "backward"
                     if oneway != OnewayDirection::Backward {
                         if oneway == OnewayDirection::Forward {
                             // Python line 822: tags[tag_key] = tag_value (no direction suffix)
-                            segment.tags.insert(format!("{}:conditional", r.osm_tag), tag_value);
+                            add_conditional(&mut segment.tags, format!("{}:conditional", r.osm_tag), &tag_value);
                         } else {
                             // Python line 824
-                            segment.tags.insert(format!("{}:forward:conditional", r.osm_tag), tag_value);
+                            add_conditional(&mut segment.tags, format!("{}:forward:conditional", r.osm_tag), &tag_value);
                         }
                     }
                 } else {
@@ -1305,10 +1419,10 @@ fn map_vehicle_restrictions(segment: &mut Segment) {
                     if oneway != OnewayDirection::Forward {
                         if oneway == OnewayDirection::Backward {
                             // Python line 828
-                            segment.tags.insert(format!("{}:conditional", r.osm_tag), tag_value);
+                            add_conditional(&mut segment.tags, format!("{}:conditional", r.osm_tag), &tag_value);
                         } else {
                             // Python line 830
-                            segment.tags.insert(format!("{}:backward:conditional", r.osm_tag), tag_value);
+                            add_conditional(&mut segment.tags, format!("{}:backward:conditional", r.osm_tag), &tag_value);
                         }
                     }
                 }
@@ -1345,14 +1459,14 @@ fn map_vehicle_restrictions(segment: &mut Segment) {
 /// Map overtaking restrictions — Python lines 862-869
 /// Uses tag_direction() for proper oneway handling
 fn map_overtaking_restrictions(segment: &mut Segment) {
-    let f = segment.properties.get("F_Omkorningsforbud").and_then(|v| v.as_i64());
-    let b = segment.properties.get("B_Omkorningsforbud").and_then(|v| v.as_i64());
+    let f = segment.prop(Field::NoOvertakingForward).and_then(|v| v.as_i64());
+    let b = segment.prop(Field::NoOvertakingBackward).and_then(|v| v.as_i64());
     tag_direction(&mut segment.tags, segment.oneway_direction, "overtaking", Some("no"), f, b);
 }
 
 /// Map low emission zone
 fn map_low_emission_zone(segment: &mut Segment) {
-    if let Some(miljozon_val) = segment.properties.get("Miljozon") {
+    if let Some(miljozon_val) = segment.prop(Field::LowEmissionZone) {
         if miljozon_val.as_bool() {
             segment.tags.insert("low_emission_zone".to_string(), "yes".to_string());
         } else if let Some(miljozon) = miljozon_val.as_i64() {
@@ -1370,15 +1484,13 @@ fn map_bridge_tunnel_names(segment: &mut Segment) {
 
     // P10 FIX: Check Namn_132 (Övrigt vägnamn/Namn) for bridge/tunnel name substrings
     // Python lines 939-943
-    if let Some(namn_132) = segment.properties.get("Namn_132") {
+    if let Some(namn_132) = segment.prop(Field::OtherName) {
         let name_str = namn_132.as_string();
         let name_str = name_str.trim();
-        if !name_str.is_empty() {
-            if is_tunnel && name_str.to_lowercase().contains("tunneln") {
-                segment.tags.insert("tunnel:name".to_string(), name_str.to_string());
-            } else if is_bridge && name_str.to_lowercase().contains("bron") {
-                segment.tags.insert("bridge:name".to_string(), name_str.to_string());
-            }
+        if is_tunnel && name_str.to_lowercase().contains("tunneln") {
+            segment.tags.insert("tunnel:name".to_string(), name_str.to_string());
+        } else if is_bridge && name_str.to_lowercase().contains("bron") {
+            segment.tags.insert("bridge:name".to_string(), name_str.to_string());
         }
     }
 
@@ -1387,11 +1499,45 @@ fn map_bridge_tunnel_names(segment: &mut Segment) {
     }
 
     // Bridge/tunnel description from Namn_193 (Bro och tunnel/Namn) — Python lines 945-948
-    if let Some(name) = segment.properties.get("Namn_193") {
-        let name_str = name.as_string();
-        let name_str = name_str.trim();
-        if !name_str.is_empty() {
-            segment.tags.insert("description".to_string(), name_str.to_string());
+    if let Some(name) = segment.prop(Field::StructureName) {
+        segment.tags.insert("description".to_string(), name.as_string().trim().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn moped_restrictions_by_direction() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+        for (key, value) in [("F_ForbudTrafik", 1), ("Typ_512", 120), ("B_ForbudTrafik", 1), ("Typ_369", 140)] {
+            segment.properties.insert(key.to_string(), PropertyValue::Integer(value));
         }
+        let mut segments = vec![segment];
+        tag_network(&mut segments);
+
+        assert_eq!(segments[0].tags["moped:forward"], "no");
+        assert_eq!(segments[0].tags["mofa:forward"], "no");
+        assert_eq!(segments[0].tags["mofa:backward"], "no");
+        assert!(!segments[0].tags.contains_key("moped:backward"));
+    }
+
+    #[test]
+    fn pedestrian_street_hours_allow_motor_vehicles() {
+        let street = |from: &str, to: &str| {
+            let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+            segment.properties.insert("L_Gagata".to_string(), PropertyValue::Boolean(true));
+            segment.properties.insert("Gagata_Fran_kl".to_string(), PropertyValue::String(from.to_string()));
+            segment.properties.insert("Gagata_Till_kl".to_string(), PropertyValue::String(to.to_string()));
+            segment
+        };
+        let mut segments = vec![street("11", "0600"), street("10.30", "10:30")];
+        tag_network(&mut segments);
+
+        assert_eq!(segments[0].tags["motor_vehicle"], "yes");
+        assert_eq!(segments[0].tags["motor_vehicle:conditional"], "no @ (11:00-06:00)");
+        assert!(!segments[1].tags.contains_key("motor_vehicle:conditional"));
     }
 }
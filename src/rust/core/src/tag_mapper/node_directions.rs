@@ -0,0 +1,106 @@
+//! Direction of directional feature nodes
+//!
+//! NVDB records a speed camera (ATK-Mätplats) for the traffic in the
+//! digitised direction (`F_`) or against it (`B_`). The Python script drops
+//! the side; this pass keeps it as `direction=` on the camera node: the
+//! bearing of the monitored traffic at the node, from the segment geometry.
+//! Traffic signals and stop signs would follow the same rule, but are not
+//! in the NVDB layers read here.
+
+use crate::geometry::compute_bearing;
+use crate::models::{NodeFeature, OnewayDirection, Segment};
+use crate::schema::Field;
+
+/// Format of `direction` on directional nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeDirections {
+    /// No `direction` (the default, as in the Python script)
+    #[default]
+    None,
+    /// Whole degrees clockwise from north, "0" to "359"
+    Degrees,
+    /// One of the 16 compass points, "N", "NNE" to "NNW"
+    Cardinal,
+}
+
+impl From<&str> for NodeDirections {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "degrees" | "bearing" => NodeDirections::Degrees,
+            "cardinal" | "compass" => NodeDirections::Cardinal,
+            _ => NodeDirections::None, // Default
+        }
+    }
+}
+
+/// The 16 compass points, clockwise from north
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+    "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+];
+
+impl NodeDirections {
+    /// Tag the speed cameras among the feature nodes of `segment` with the
+    /// direction of the traffic they monitor; cameras for both directions
+    /// get none
+    pub fn apply(&self, segment: &Segment, nodes: &mut [NodeFeature]) {
+        if *self == NodeDirections::None {
+            return;
+        }
+        let has = |field: Field| segment.prop(field).is_some_and(|v| v.as_bool());
+        let (forward, backward) = (has(Field::SpeedCameraForward), has(Field::SpeedCameraBackward));
+        if forward == backward {
+            return;
+        }
+        let Some(bearing) = traffic_bearing(segment, forward) else { return };
+        let value = match self {
+            NodeDirections::Degrees => format!("{}", bearing.round() as i64 % 360),
+            _ => COMPASS_POINTS[((bearing / 22.5).round() as usize) % 16].to_string(),
+        };
+        for node in nodes.iter_mut().filter(|n| n.tags.get("highway").map(String::as_str) == Some("speed_camera")) {
+            node.tags.insert("direction".to_string(), value.clone());
+        }
+    }
+}
+
+/// Bearing at the first vertex of the traffic in the digitised direction
+/// (`forward`) or against it
+fn traffic_bearing(segment: &Segment, forward: bool) -> Option<f64> {
+    let coords = &segment.geometry.0;
+    let (first, second) = (coords.first()?, coords.get(1)?);
+    // Reversed oneways run against their digitised direction (see
+    // `map_oneway()`)
+    let along_geometry = forward != (segment.oneway_direction == OnewayDirection::Backward);
+    Some(match along_geometry {
+        true => compute_bearing(first, second),
+        false => compute_bearing(second, first),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeDirections;
+    use crate::PropertyValue;
+    use crate::tag_mapper::nodes::generate_nodes_for_segment;
+    use crate::test_support::segment;
+
+    #[test]
+    fn camera_direction_against_digitisation() {
+        // Digitised eastwards, camera for the traffic against it
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.properties.insert("B_ATK_Matplats".to_string(), PropertyValue::Integer(1));
+        let (mut nodes, _) = generate_nodes_for_segment(&segment, 1);
+
+        NodeDirections::Cardinal.apply(&segment, &mut nodes);
+        assert_eq!(nodes[0].tags["direction"], "W");
+        NodeDirections::Degrees.apply(&segment, &mut nodes);
+        assert_eq!(nodes[0].tags["direction"], "270");
+    }
+
+    #[test]
+    fn node_directions_from_name() {
+        assert_eq!(NodeDirections::from("degrees"), NodeDirections::Degrees);
+        assert_eq!(NodeDirections::from("cardinal"), NodeDirections::Cardinal);
+        assert_eq!(NodeDirections::from("anything else"), NodeDirections::None);
+    }
+}
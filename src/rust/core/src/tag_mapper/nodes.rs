@@ -0,0 +1,618 @@
+//! Node generation for NVDB point features
+//! 
+//! This module handles generation of OSM nodes (POIs) from NVDB segment data.
+//! Ported from Python create_node() function (py-script.py lines 1006-1027).
+
+use geo_types::Coord;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use crate::geometry::{self, RailIndex};
+use crate::models::{self, CoordHash, NodeFeature, PropertyValue, Segment};
+use crate::schema::Field;
+use crate::topology::junction_degrees;
+use super::units::number;
+use super::fords;
+use super::node_directions::NodeDirections;
+
+/// Container for all generated nodes during tagging
+#[derive(Debug, Default)]
+pub struct NodeCollection {
+    nodes: Vec<NodeFeature>,
+    next_id: i64,
+}
+
+impl NodeCollection {
+    pub fn new(starting_id: i64) -> Self {
+        Self {
+            nodes: Vec::new(),
+            next_id: starting_id,
+        }
+    }
+    
+    pub fn add_node(&mut self, lat: f64, lon: f64, tags: FxHashMap<String, String>) -> i64 {
+        let mut node = NodeFeature::new(self.next_id, lat, lon);
+        node.tags = tags;
+        let id = self.next_id;
+        self.nodes.push(node);
+        self.next_id += 1;
+        id
+    }
+    
+    pub fn into_nodes(self) -> Vec<NodeFeature> {
+        self.nodes
+    }
+    
+    pub fn nodes(&self) -> &[NodeFeature] {
+        &self.nodes
+    }
+
+    /// Combine nodes at the same coordinate whose tags agree
+    ///
+    /// A node is merged into the first earlier node at its coordinate that has
+    /// no key with a different value; the merged node keeps the lower ID. A
+    /// node that disagrees with all of them stays separate, and the first
+    /// disagreement is returned so it can be reviewed.
+    pub fn merge_coincident(&mut self) -> Vec<NodeConflict> {
+        let mut merged: Vec<NodeFeature> = Vec::with_capacity(self.nodes.len());
+        let mut at: FxHashMap<CoordHash, Vec<usize>> = FxHashMap::default();
+        let mut conflicts = Vec::new();
+
+        for node in std::mem::take(&mut self.nodes) {
+            let hash = models::hash_coord(&Coord { x: node.lon, y: node.lat });
+            let earlier = at.entry(hash).or_default();
+            let target = earlier.iter().copied().find(|&i| first_conflict(&merged[i], &node).is_none());
+            match target {
+                Some(i) => merged[i].tags.extend(node.tags),
+                None => {
+                    if let Some(&first) = earlier.first() {
+                        if let Some(key) = first_conflict(&merged[first], &node) {
+                            conflicts.push(NodeConflict {
+                                kept_id: merged[first].id,
+                                other_id: node.id,
+                                kept_value: merged[first].tags[&key].clone(),
+                                other_value: node.tags[&key].clone(),
+                                key,
+                            });
+                        }
+                    }
+                    earlier.push(merged.len());
+                    merged.push(node);
+                }
+            }
+        }
+        self.nodes = merged;
+        conflicts
+    }
+}
+
+impl From<Vec<NodeFeature>> for NodeCollection {
+    fn from(nodes: Vec<NodeFeature>) -> Self {
+        let next_id = nodes.iter().map(|node| node.id + 1).max().unwrap_or(0);
+        Self { nodes, next_id }
+    }
+}
+
+/// Feature nodes at the same coordinate whose tags could not be combined
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeConflict {
+    pub kept_id: i64,
+    pub other_id: i64,
+    pub key: String,
+    pub kept_value: String,
+    pub other_value: String,
+}
+
+impl std::fmt::Display for NodeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "coincident feature nodes disagree on {}: {} and {} (kept as separate nodes)",
+            self.key, self.kept_value, self.other_value
+        )
+    }
+}
+
+/// Alphabetically first key that both nodes have with different values
+fn first_conflict(a: &NodeFeature, b: &NodeFeature) -> Option<String> {
+    b.tags
+        .iter()
+        .filter(|(k, v)| a.tags.get(*k).is_some_and(|value| value != *v))
+        .map(|(k, _)| k)
+        .min()
+        .cloned()
+}
+
+/// Generate nodes for a segment based on NVDB properties
+/// 
+/// This function checks various NVDB properties and creates appropriate
+/// OSM nodes (crossings, cameras, barriers, etc.)
+/// 
+/// Python equivalent: osm_tags() lines 319-446
+pub fn generate_nodes_for_segment(segment: &Segment, next_id: i64) -> (Vec<NodeFeature>, i64) {
+    let mut nodes = Vec::new();
+    let mut id = next_id;
+    
+    // Get the first coordinate of the segment (used for node position)
+    // Python uses: way["geometry"]["coordinates"][0][0]
+    let coord = segment.geometry.0.first();
+    if coord.is_none() {
+        return (nodes, id);
+    }
+    let coord = coord.unwrap();
+    let (lon, lat) = (coord.x, coord.y);
+    
+    // 1. Pedestrian/Cycle Crossings (GCM-passage)
+    // Python lines 321-336
+    if let Some(passage_type) = segment.prop(Field::CrossingType).and_then(|v| v.as_i64()) {
+        let mut tags = FxHashMap::default();
+        
+        match passage_type {
+            3 => {
+                // övergångsställe och/eller cykelpassage
+                tags.insert("highway".to_string(), "crossing".to_string());
+            }
+            4 => {
+                // signalreglerat övergångsställe
+                tags.insert("highway".to_string(), "crossing".to_string());
+                tags.insert("crossing".to_string(), "traffic_signals".to_string());
+            }
+            5 => {
+                // annan ordnad passage
+                tags.insert("highway".to_string(), "crossing".to_string());
+            }
+            _ => {}
+        }
+        
+        if !tags.is_empty() {
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+    
+    // 2. Railway Crossings (Järnvägskorsning)
+    // Python lines 338-354
+    if let Some(skydd) = segment.prop(Field::RailwayCrossing).and_then(|v| v.as_i64()) {
+        let mut tags = FxHashMap::default();
+        
+        // Determine railway tag based on network type
+        let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(0);
+        if net_type == 1 {
+            tags.insert("railway".to_string(), "level_crossing".to_string());
+        } else {
+            tags.insert("railway".to_string(), "crossing".to_string());
+        }
+        
+        // Add protection details
+        match skydd {
+            1 => { tags.insert("crossing:barrier".to_string(), "full".to_string()); }    // Helbom
+            2 => { tags.insert("crossing:barrier".to_string(), "half".to_string()); }    // Halvbom
+            3 => {
+                tags.insert("crossing:bell".to_string(), "yes".to_string());
+                tags.insert("crossing:light".to_string(), "yes".to_string());
+            }
+            4 => { tags.insert("crossing:light".to_string(), "yes".to_string()); }       // Ljussignal
+            5 => { tags.insert("crossing:bell".to_string(), "yes".to_string()); }        // Ljudsignal
+            6 => { tags.insert("crossing:saltire".to_string(), "yes".to_string()); }      // Kryssmärke
+            7 => { tags.insert("crossing".to_string(), "uncontrolled".to_string()); }     // Utan skydd
+            _ => {}
+        }
+        
+        if tags.len() > 1 || tags.contains_key("railway") {
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+    
+    // 3. Traffic Calming (Farthinder)
+    // Python lines 356-372
+    if let Some(farthinder_typ) = segment.prop(Field::TrafficCalming).and_then(|v| v.as_i64()) {
+        let mut tags = FxHashMap::default();
+        
+        let calming_type = match farthinder_typ {
+            1 => "choker",     // avsmalning till ett körfält
+            2 => "hump",       // gupp
+            3 => "chicane",    // sidoförskjutning
+            4 => "island",     // sidoförskjutning - refug
+            5 => "dip",        // väghåla
+            6 => "cushion",    // vägkudde
+            7 => "table",      // förhöjd genomgående gcm-passage
+            8 => "table",      // förhöjd korsning
+            9 => "yes",        // övrigt farthinder
+            _ => "",
+        };
+        
+        if !calming_type.is_empty() {
+            tags.insert("traffic_calming".to_string(), calming_type.to_string());
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+    
+    // 4. Barriers (Väghinder)
+    // Python lines 374-388
+    if let Some(hinder_typ) = segment.prop(Field::Barrier).and_then(|v| v.as_i64()) {
+        let mut tags = FxHashMap::default();
+        
+        let barrier_type = match hinder_typ {
+            1 => "bollard",       // pollare
+            2 => "swing_gate",    // eftergivlig grind
+            3 => "cycle_barrier", // cykelfålla
+            4 => "lift_gate",     // låst grind/bom
+            5 => "jersey_barrier",// betonghinder
+            6 => "bus_trap",      // spårviddshinder
+            99 => "yes",          // övrigt
+            _ => "",
+        };
+        
+        if !barrier_type.is_empty() {
+            tags.insert("barrier".to_string(), barrier_type.to_string());
+            
+            // Add maxwidth:physical if available
+            if let Some(pass_width) = segment.prop(Field::BarrierPassage).and_then(|v| v.as_f64()) {
+                if pass_width > 0.0 {
+                    tags.insert("maxwidth:physical".to_string(), number(pass_width));
+                }
+            }
+            
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+    
+    // 5. Speed Cameras (ATK-Mätplats)
+    // Python lines 390-415
+    let f_atk = segment.prop(Field::SpeedCameraForward)
+        .map(|v| v.as_bool()).unwrap_or(false);
+    let b_atk = segment.prop(Field::SpeedCameraBackward)
+        .map(|v| v.as_bool()).unwrap_or(false);
+    
+    if f_atk || b_atk {
+        let mut tags = FxHashMap::default();
+        tags.insert("highway".to_string(), "speed_camera".to_string());
+        
+        // Add maxspeed from the corresponding direction
+        if f_atk {
+            if let Some(speed) = segment.prop(Field::SpeedLimitForward).and_then(|v| v.as_i64()) {
+                if speed > 0 && speed <= 120 {
+                    tags.insert("maxspeed".to_string(), speed.to_string());
+                }
+            }
+        } else if b_atk {
+            if let Some(speed) = segment.prop(Field::SpeedLimitBackward).and_then(|v| v.as_i64()) {
+                if speed > 0 && speed <= 120 {
+                    tags.insert("maxspeed".to_string(), speed.to_string());
+                }
+            }
+        }
+        
+        nodes.push(NodeFeature { id, lat, lon, tags });
+        id += 1;
+    }
+    
+    // 6. Rest Areas (Rastplats)
+    // Python lines 417-440, plus the facility attributes: a rest area with
+    // fuel or food is a service area (highway=services)
+    if let Some(rastplats_val) = segment.prop(Field::RestArea) {
+        if rastplats_val.as_bool() {
+            let mut tags = FxHashMap::default();
+            let has = |field: Field| segment.prop(field).is_some_and(|v| v.as_bool());
+            let (toilets, restaurant, fuel) = (has(Field::RestAreaToilets), has(Field::RestAreaRestaurant), has(Field::RestAreaFuel));
+            let highway = if restaurant || fuel { "services" } else { "rest_area" };
+            tags.insert("highway".to_string(), highway.to_string());
+            for (present, key) in [(toilets, "toilets"), (restaurant, "restaurant"), (fuel, "fuel")] {
+                if present {
+                    tags.insert(key.to_string(), "yes".to_string());
+                }
+            }
+            
+            // Add name if available
+            if let Some(name) = segment.prop(Field::RestAreaName) {
+                tags.insert("name".to_string(), name.as_string().trim().to_string());
+            }
+            
+            // Add capacity for cars
+            if let Some(cap) = segment.prop(Field::RestAreaCarSpaces).and_then(|v| v.as_i64()) {
+                if cap > 0 {
+                    tags.insert("capacity".to_string(), cap.to_string());
+                }
+            }
+            
+            // Add capacity for HGVs
+            if let Some(cap_hgv) = segment.prop(Field::RestAreaHgvSpaces).and_then(|v| v.as_i64()) {
+                if cap_hgv > 0 {
+                    tags.insert("capacity:hgv".to_string(), cap_hgv.to_string());
+                }
+            }
+            
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+    
+    // 7. Parking Along Highway (Rastficka)
+    // Python lines 442-446
+    let l_rastficka = segment.prop(Field::LayByLeft).map(|v| v.as_bool()).unwrap_or(false);
+    let r_rastficka = segment.prop(Field::LayByRight).map(|v| v.as_bool()).unwrap_or(false);
+    
+    if l_rastficka || r_rastficka {
+        let mut tags = FxHashMap::default();
+        tags.insert("amenity".to_string(), "parking".to_string());
+        
+        // Add parking type if we know which side
+        if l_rastficka && !r_rastficka {
+            tags.insert("parking:lane:left".to_string(), "yes".to_string());
+        } else if r_rastficka && !l_rastficka {
+            tags.insert("parking:lane:right".to_string(), "yes".to_string());
+        }
+        
+        nodes.push(NodeFeature { id, lat, lon, tags });
+        id += 1;
+    }
+
+    // 8. Heavy vehicle control sites (Kontrollplats): weigh stations and
+    // roadside checkpoints
+    if let Some(site) = segment.prop(Field::ControlSite).and_then(|v| v.as_i64()) {
+        let mut tags = FxHashMap::default();
+        if site == 1 || site == 3 {
+            tags.insert("amenity".to_string(), "weighbridge".to_string());
+        }
+        if site == 2 || site == 3 {
+            tags.insert("barrier".to_string(), "checkpoint".to_string());
+        }
+        if !tags.is_empty() {
+            let operator = match segment.prop(Field::ControlSiteOperator) {
+                Some(PropertyValue::Integer(1)) => Some("Trafikverket".to_string()),
+                Some(PropertyValue::Integer(2)) => Some("Polismyndigheten".to_string()),
+                Some(PropertyValue::String(name)) if !name.trim().is_empty() => Some(name.trim().to_string()),
+                _ => None,
+            };
+            if let Some(operator) = operator {
+                tags.insert("operator".to_string(), operator);
+            }
+            tags.insert("hgv".to_string(), "designated".to_string());
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+
+    // 9. Fords (Vadställe) located at a point of the segment
+    if fords::ford_kind(segment) == Some(fords::FordKind::Node) {
+        if let Some(tags) = fords::ford_tags(segment) {
+            nodes.push(NodeFeature { id, lat, lon, tags });
+            id += 1;
+        }
+    }
+
+    // 10. Kilometre posts (Längdmätning), placed by linear referencing
+    for (km, at) in milestones(segment) {
+        let mut tags = FxHashMap::default();
+        tags.insert("highway".to_string(), "milestone".to_string());
+        tags.insert("distance".to_string(), km.to_string());
+        nodes.push(NodeFeature { id, lat: at.y, lon: at.x, tags });
+        id += 1;
+    }
+
+    (nodes, id)
+}
+
+/// Whole kilometres of the segment's chainage, with their position along
+/// it; a kilometre at the higher end belongs to the next segment
+fn milestones(segment: &Segment) -> Vec<(i64, Coord)> {
+    let chainage = |field| segment.prop(field).and_then(|v| v.as_f64()).filter(|v| v.is_finite() && *v >= 0.0);
+    let (Some(from), Some(to)) = (chainage(Field::ChainageFrom), chainage(Field::ChainageTo)) else {
+        return Vec::new();
+    };
+    if from == to {
+        return Vec::new();
+    }
+    let (low, high) = (from.min(to), from.max(to));
+    // Chainage follows the digitised direction; reversed oneways run the
+    // other way (see `map_oneway()`)
+    let reversed = segment.oneway_direction == models::OnewayDirection::Backward;
+    (low.ceil() as i64..)
+        .take_while(|&km| (km as f64) < high)
+        .filter_map(|km| {
+            let fraction = (km as f64 - from) / (to - from);
+            let fraction = if reversed { 1.0 - fraction } else { fraction };
+            geometry::point_along(&segment.geometry, fraction).map(|at| (km, at))
+        })
+        .collect()
+}
+
+/// Generate feature nodes (crossings, cameras, etc.) with consecutive IDs
+///
+/// Level crossings of the segments in `railway_crossings` (see
+/// `railway_crossings()`) are placed where the road crosses the railway
+/// instead of at the segment's first vertex. Speed cameras get a
+/// `direction` as chosen by `directions`.
+pub fn generate_feature_nodes(
+    segments: &[Segment],
+    node_id_start: i64,
+    railway_crossings: &FxHashMap<usize, (usize, Coord)>,
+    directions: NodeDirections,
+) -> Vec<NodeFeature> {
+    let mut nodes: Vec<NodeFeature> = Vec::new();
+    let mut next_node_id = node_id_start;
+    
+    for (i, segment) in segments.iter().enumerate() {
+        let (mut segment_nodes, new_id) = generate_nodes_for_segment(segment, next_node_id);
+        directions.apply(segment, &mut segment_nodes);
+        if let Some((_, at)) = railway_crossings.get(&i) {
+            for node in segment_nodes.iter_mut().filter(|node| node.tags.contains_key("railway")) {
+                node.lat = at.y;
+                node.lon = at.x;
+            }
+        }
+        nodes.extend(segment_nodes);
+        next_node_id = new_id;
+    }
+    nodes.extend(turning_nodes(segments, &junction_degrees(segments), next_node_id));
+
+    nodes
+}
+
+/// Where the segments with a level crossing (Järnvägskorsning) cross one of
+/// the `rails`, by segment index (see `RailIndex::first_crossing()`)
+pub fn railway_crossings(segments: &[Segment], rails: &RailIndex) -> FxHashMap<usize, (usize, Coord)> {
+    segments
+        .par_iter()
+        .enumerate()
+        .filter(|(_, segment)| segment.prop(Field::RailwayCrossing).is_some())
+        .filter_map(|(i, segment)| rails.first_crossing(&segment.geometry).map(|crossing| (i, crossing)))
+        .collect()
+}
+
+/// Split the segments of `railway_crossings` at the crossing point, so that
+/// the level crossing node becomes a junction of the way
+///
+/// The parts after the crossing are appended to `segments`; call this after
+/// `generate_feature_nodes()`, which would otherwise give them nodes too.
+pub fn split_at_railway_crossings(segments: &mut Vec<Segment>, railway_crossings: &FxHashMap<usize, (usize, Coord)>) {
+    let mut crossings: Vec<_> = railway_crossings.iter().collect();
+    crossings.sort_unstable_by_key(|(&i, _)| i);
+    for (&i, &(k, at)) in crossings {
+        let coords = &mut segments[i].geometry.0;
+        let vertex = if coords[k] == at {
+            k
+        } else if coords[k + 1] == at {
+            k + 1
+        } else {
+            coords.insert(k + 1, at);
+            k + 1
+        };
+        if vertex == 0 || vertex == coords.len() - 1 {
+            continue;
+        }
+        let tail = segments[i].split_at_vertex(vertex);
+        segments.push(tail);
+    }
+}
+
+/// Turning places at the dead ends of the road network, numbered from
+/// `next_id`
+///
+/// A dead end (junction degree 1) of a segment with NVDB turning place data
+/// (Vändmöjlighet) becomes a `highway=turning_circle` or `turning_loop`
+/// node. A segment that loops back to its start where only one other road
+/// joins (degree 3) is a turning loop by its geometry.
+pub fn turning_nodes(segments: &[Segment], degrees: &FxHashMap<CoordHash, usize>, next_id: i64) -> Vec<NodeFeature> {
+    let mut nodes = Vec::new();
+    let mut placed: FxHashSet<CoordHash> = FxHashSet::default();
+    let degree = |hash: CoordHash| degrees.get(&hash).copied().unwrap_or(0);
+
+    for segment in segments {
+        let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
+        if net_type != 1 || !segment.tags.contains_key("highway") {
+            continue;
+        }
+        let mut ends: Vec<(CoordHash, &'static str)> = Vec::new();
+        if segment.start_node == segment.end_node {
+            if degree(segment.start_node) == 3 {
+                ends.push((segment.start_node, "turning_loop"));
+            }
+        } else {
+            let highway = match segment.prop(Field::TurningPlace).and_then(|v| v.as_i64()) {
+                Some(1) => "turning_circle",
+                Some(2) => "turning_loop",
+                _ => continue,
+            };
+            ends.extend(
+                [segment.start_node, segment.end_node].into_iter()
+                    .filter(|&hash| degree(hash) == 1)
+                    .map(|hash| (hash, highway)),
+            );
+        }
+
+        for (hash, highway) in ends {
+            if !placed.insert(hash) {
+                continue;
+            }
+            let coord = if hash == segment.start_node { segment.geometry.0.first() } else { segment.geometry.0.last() };
+            let Some(coord) = coord else { continue };
+            let mut node = NodeFeature::new(next_id + nodes.len() as i64, coord.y, coord.x);
+            node.tags.insert("highway".to_string(), highway.to_string());
+            nodes.push(node);
+        }
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeCollection, generate_nodes_for_segment, turning_nodes};
+    use crate::{junction_degrees, PropertyValue, tag_network};
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn turning_nodes_at_road_ends() {
+        let road = |coords: Vec<(f64, f64)>| {
+            let mut segment = segment(coords);
+            segment.properties.insert("Kateg_380".to_string(), PropertyValue::Integer(5));
+            segment
+        };
+        // A road whose last segment has a turning circle, and a second road
+        // ending in a loop
+        let mut end = road(vec![(16.001, 62.0), (16.002, 62.0)]);
+        end.properties.insert("Vandmojlighet".to_string(), PropertyValue::Integer(1));
+        let mut segments = vec![
+            road(vec![(16.0, 62.0), (16.001, 62.0)]),
+            end,
+            road(vec![(16.0, 62.0), (16.0, 62.001)]),
+            road(vec![(16.0, 62.001), (16.0005, 62.0015), (15.9995, 62.0015), (16.0, 62.001)]),
+        ];
+        tag_network(&mut segments);
+
+        let nodes = turning_nodes(&segments, &junction_degrees(&segments), 100);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!((nodes[0].id, nodes[0].lon, nodes[0].lat), (100, 16.002, 62.0));
+        assert_eq!(nodes[0].tags["highway"], "turning_circle");
+        assert_eq!((nodes[1].lon, nodes[1].lat), (16.0, 62.001));
+        assert_eq!(nodes[1].tags["highway"], "turning_loop");
+    }
+
+    #[test]
+    fn merge_coincident_reports_conflicts() {
+        let mut nodes = NodeCollection::new(1);
+        nodes.add_node(59.0, 18.0, tags(&[("highway", "crossing")]));
+        nodes.add_node(59.0, 18.0, tags(&[("traffic_calming", "table")]));
+        nodes.add_node(59.0, 18.0, tags(&[("highway", "speed_camera")]));
+
+        let conflicts = nodes.merge_coincident();
+        assert_eq!(nodes.nodes().len(), 2);
+        assert_eq!(nodes.nodes()[0].tags["traffic_calming"], "table");
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!((conflicts[0].kept_id, conflicts[0].other_id), (1, 3));
+        assert_eq!(conflicts[0].key, "highway");
+    }
+
+    #[test]
+    fn point_features_along_segments() {
+        let mut rest_area = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        rest_area.properties.insert("Rastplats".to_string(), PropertyValue::Integer(1));
+        rest_area.properties.insert("Rastplats_Toalett".to_string(), PropertyValue::Integer(1));
+        let (nodes, _) = generate_nodes_for_segment(&rest_area, 1);
+        assert_eq!(nodes[0].tags["highway"], "rest_area");
+        assert_eq!(nodes[0].tags["toilets"], "yes");
+
+        rest_area.properties.insert("Rastplats_Drivmedel".to_string(), PropertyValue::Boolean(true));
+        let (nodes, _) = generate_nodes_for_segment(&rest_area, 1);
+        assert_eq!(nodes[0].tags["highway"], "services");
+        assert_eq!(nodes[0].tags["fuel"], "yes");
+
+        // Weigh station run by the police
+        let mut weigh_station = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        weigh_station.properties.insert("Kontrollplats_Typ".to_string(), PropertyValue::Integer(1));
+        weigh_station.properties.insert("Kontrollplats_Huvudman".to_string(), PropertyValue::Integer(2));
+        let (nodes, _) = generate_nodes_for_segment(&weigh_station, 1);
+        assert_eq!(nodes[0].tags["amenity"], "weighbridge");
+        assert_eq!(nodes[0].tags["operator"], "Polismyndigheten");
+
+        // Kilometre post 12 halfway along a segment from km 11.5 to 12.5
+        let mut post = segment(vec![(18.0, 59.0), (18.02, 59.0)]);
+        post.properties.insert("Langdmatning_Fran".to_string(), PropertyValue::Float(11.5));
+        post.properties.insert("Langdmatning_Till".to_string(), PropertyValue::Float(12.5));
+        let (nodes, _) = generate_nodes_for_segment(&post, 1);
+        assert_eq!(nodes[0].tags["distance"], "12");
+        assert_eq!(nodes[0].lon, 18.01);
+    }
+}
@@ -0,0 +1,122 @@
+//! Noise barriers (Bullerskydd)
+//!
+//! NVDB records noise barriers for the left and right side of the road in
+//! the digitised direction. They are either tagged on the road way
+//! (`noise_barrier=left/right/both`), or drawn as `barrier=wall` ways of
+//! their own, parallel to the carriageway (see `geometry::offset_line()`).
+
+use crate::geometry::offset_line;
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// Road width assumed where NVDB has none (metres)
+const DEFAULT_ROAD_WIDTH: f64 = 7.0;
+
+/// Distance of a barrier wall from the edge of the carriageway (metres)
+const WALL_MARGIN: f64 = 3.0;
+
+/// How noise barriers are mapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseBarriers {
+    /// `noise_barrier=left/right/both` on the road way (the default)
+    #[default]
+    Tags,
+    /// Separate `barrier=wall` + `wall=noise_barrier` ways beside the road
+    Ways,
+}
+
+impl From<&str> for NoiseBarriers {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "ways" | "wall" | "walls" => NoiseBarriers::Ways,
+            _ => NoiseBarriers::Tags, // Default
+        }
+    }
+}
+
+impl NoiseBarriers {
+    /// Tag the noise barriers of the road network on their ways
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if *self != NoiseBarriers::Tags {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            if !segment.tags.contains_key("highway") {
+                continue;
+            }
+            let (left, right) = sides(segment);
+            if let Some(side) = super::side_of(segment.oneway_direction, left, right) {
+                segment.tags.insert("noise_barrier".to_string(), side.to_string());
+            }
+        }
+    }
+
+    /// Barrier walls beside the tagged road segments, as segments without
+    /// `highway` that become ways of their own
+    pub fn barrier_segments(&self, segments: &[Segment]) -> Vec<Segment> {
+        if *self != NoiseBarriers::Ways {
+            return Vec::new();
+        }
+        let mut walls = Vec::new();
+        for segment in segments {
+            if !segment.tags.contains_key("highway") {
+                continue;
+            }
+            let (mut left, mut right) = sides(segment);
+            // Sides are given in the digitised direction, the geometry of
+            // reversed oneways runs the other way (see `map_oneway()`)
+            if segment.oneway_direction == crate::models::OnewayDirection::Backward {
+                std::mem::swap(&mut left, &mut right);
+            }
+            let width = segment.prop(Field::Width)
+                .and_then(|v| v.as_f64())
+                .filter(|&w| w > 0.0 && w < 50.0)
+                .unwrap_or(DEFAULT_ROAD_WIDTH);
+            let distance = width / 2.0 + WALL_MARGIN;
+            for (present, offset) in [(left, distance), (right, -distance)] {
+                if !present {
+                    continue;
+                }
+                let mut wall = Segment::new(String::new(), offset_line(&segment.geometry, offset));
+                wall.tags.insert("barrier".to_string(), "wall".to_string());
+                wall.tags.insert("wall".to_string(), "noise_barrier".to_string());
+                wall.source_row = segment.source_row;
+                walls.push(wall);
+            }
+        }
+        walls
+    }
+}
+
+/// Noise barrier on the (left, right) side in the digitised direction
+fn sides(segment: &Segment) -> (bool, bool) {
+    (
+        segment.prop(Field::NoiseBarrierLeft).is_some_and(|v| v.as_bool()),
+        segment.prop(Field::NoiseBarrierRight).is_some_and(|v| v.as_bool()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoiseBarriers;
+    use crate::PropertyValue;
+    use crate::test_support::segment;
+
+    #[test]
+    fn barrier_segments_offset_walls() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.tags.insert("highway".to_string(), "motorway".to_string());
+        segment.properties.insert("R_Bullerskydd".to_string(), PropertyValue::Boolean(true));
+
+        let walls = NoiseBarriers::Ways.barrier_segments(&[segment]);
+        assert_eq!(walls.len(), 1);
+        assert_eq!(walls[0].tags["wall"], "noise_barrier");
+        assert!(walls[0].geometry.0[0].y < 59.0);
+    }
+
+    #[test]
+    fn noise_barriers_from_name() {
+        assert_eq!(NoiseBarriers::from("ways"), NoiseBarriers::Ways);
+        assert_eq!(NoiseBarriers::from("anything else"), NoiseBarriers::Tags);
+    }
+}
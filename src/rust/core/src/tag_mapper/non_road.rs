@@ -0,0 +1,147 @@
+//! Non-road ways of the cycle/pedestrian network
+//!
+//! The GCM network includes cable cars (GCM type 22, linbana) and funiculars
+//! (23, bergbana). They are not roads: the road tagging gives them
+//! `aerialway=cable_car` and `railway=funicular` and never a `highway` tag,
+//! this pass replaces those tags with the ones of a configurable table
+//! (keeping only the name), and `topology` keeps them out of road merging
+//! (`is_non_road()`).
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// Tags written for each non-road GCM type
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonRoadTags {
+    /// (GCM type, key, value)
+    rules: Vec<(i64, String, String)>,
+}
+
+impl Default for NonRoadTags {
+    fn default() -> Self {
+        Self::sweden()
+    }
+}
+
+impl NonRoadTags {
+    /// No replacement tags: the non-road types keep the tags of the road
+    /// tagging
+    pub fn none() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Cable cars as `aerialway=cable_car`, funiculars as `railway=funicular`
+    pub fn sweden() -> Self {
+        Self::none()
+            .rule(22, "aerialway", "cable_car")
+            .rule(23, "railway", "funicular")
+    }
+
+    /// Add a tag for a GCM type, replacing an existing one with the same key
+    pub fn rule(mut self, gcm_type: i64, key: &str, value: &str) -> Self {
+        self.rules.retain(|(t, k, _)| !(*t == gcm_type && k == key));
+        self.rules.push((gcm_type, key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Remove all tags of a GCM type, leaving it to the road tagging (which
+    /// tags cable cars and funiculars like `sweden()`)
+    pub fn without(mut self, gcm_type: i64) -> Self {
+        self.rules.retain(|(t, _, _)| *t != gcm_type);
+        self
+    }
+
+    pub fn rules(&self) -> &[(i64, String, String)] {
+        &self.rules
+    }
+
+    /// Tags of one GCM type (none if it is a road type)
+    pub fn tags_for(&self, gcm_type: i64) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.rules.iter()
+            .filter(move |(t, _, _)| *t == gcm_type)
+            .map(|(_, k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Retag the non-road segments of the GCM network
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if self.rules.is_empty() {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            let Some(gcm_type) = non_road_type(segment) else { continue };
+            let mut tags = self.tags_for(gcm_type).peekable();
+            if tags.peek().is_none() {
+                continue;
+            }
+            let name = segment.tags.remove("name");
+            segment.tags.clear();
+            for (key, value) in tags {
+                segment.tags.insert(key.to_string(), value.to_string());
+            }
+            if let Some(name) = name {
+                segment.tags.insert("name".to_string(), name);
+            }
+        }
+    }
+}
+
+/// Whether the segment is a cable car or funicular, which is kept out of
+/// road merging
+pub fn is_non_road(segment: &Segment) -> bool {
+    non_road_type(segment).is_some()
+}
+
+/// GCM type of a cable car or funicular of the cycle/pedestrian network
+fn non_road_type(segment: &Segment) -> Option<i64> {
+    let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
+    if net_type != 2 && net_type != 4 {
+        return None;
+    }
+    segment.prop(Field::GcmType).and_then(|v| v.as_i64()).filter(|t| matches!(t, 22 | 23))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_non_road, NonRoadTags};
+    use crate::models::Segment;
+    use crate::tag_mapper::tag_network_with;
+    use crate::test_support::segment;
+    use crate::PropertyValue;
+
+    fn gcm(gcm_type: i64) -> Segment {
+        let mut segment = segment(vec![(13.0, 55.6), (13.001, 55.6)]);
+        segment.properties.insert("Vagtr_474".to_string(), PropertyValue::Integer(2));
+        segment.properties.insert("GCM_t_502".to_string(), PropertyValue::Integer(gcm_type));
+        segment
+    }
+
+    #[test]
+    fn cable_cars_and_funiculars_never_get_a_highway_tag() {
+        for table in [NonRoadTags::sweden(), NonRoadTags::sweden().without(22).without(23), NonRoadTags::none()] {
+            let mut segments = vec![gcm(22), gcm(23)];
+            tag_network_with(&mut segments, &table);
+            assert_eq!(segments[0].tags.get("aerialway").map(String::as_str), Some("cable_car"));
+            assert_eq!(segments[1].tags.get("railway").map(String::as_str), Some("funicular"));
+            assert!(segments.iter().all(|s| !s.tags.contains_key("highway")));
+        }
+
+        let mut segments = vec![gcm(22)];
+        tag_network_with(&mut segments, &NonRoadTags::sweden().without(22).rule(22, "aerialway", "gondola"));
+        assert_eq!(segments[0].tags.get("aerialway").map(String::as_str), Some("gondola"));
+    }
+
+    #[test]
+    fn only_cable_cars_and_funiculars_are_non_road() {
+        assert!(is_non_road(&gcm(22)) && is_non_road(&gcm(23)));
+        assert!(!is_non_road(&gcm(4)));
+        // Untagged road segments are still merged
+        assert!(!is_non_road(&segment(vec![(13.0, 55.6), (13.001, 55.6)])));
+    }
+
+    #[test]
+    fn non_road_rules_can_be_replaced() {
+        // Map cable cars as gondolas instead
+        let tags = NonRoadTags::sweden().without(22).rule(22, "aerialway", "gondola");
+        assert!(tags.tags_for(22).any(|(k, v)| k == "aerialway" && v == "gondola"));
+    }
+}
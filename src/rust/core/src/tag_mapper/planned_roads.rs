@@ -0,0 +1,87 @@
+//! Roads under construction and planned roads
+//!
+//! Some deliveries include links that are not open yet, with their status
+//! (Länkstatus: 2 under construction, 3 planned). They keep the class the
+//! tag mapper gave them as `construction=<class>` or `proposed=<class>`,
+//! with `highway=construction` or `highway=proposed`, so that no router
+//! uses them. Routing extracts can leave them out altogether.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// What to do with roads that are not open to traffic yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlannedRoads {
+    /// `highway=construction` and `highway=proposed` (the default)
+    #[default]
+    Tag,
+    /// Drop them from the output
+    Exclude,
+}
+
+impl From<&str> for PlannedRoads {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "exclude" | "drop" => PlannedRoads::Exclude,
+            _ => PlannedRoads::Tag, // Default
+        }
+    }
+}
+
+/// Lifecycle key of a segment's link status, if it is not open yet
+fn lifecycle(segment: &Segment) -> Option<&'static str> {
+    match segment.prop(Field::LinkStatus).and_then(|v| v.as_i64()) {
+        Some(2) => Some("construction"),
+        Some(3) => Some("proposed"),
+        _ => None,
+    }
+}
+
+impl PlannedRoads {
+    /// Retag the roads under construction and planned roads; runs after
+    /// the access defaults, which depend on the highway class, and before
+    /// the implicit speed limits, which must not apply to them
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if *self != PlannedRoads::Tag {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            let Some(key) = lifecycle(segment) else { continue };
+            let Some(class) = segment.tags.remove("highway") else { continue };
+            segment.tags.insert(key.to_string(), class);
+            segment.tags.insert("highway".to_string(), key.to_string());
+        }
+    }
+
+    /// Drop the roads under construction and planned roads when excluded
+    pub fn retain(&self, segments: &mut Vec<Segment>) {
+        if *self == PlannedRoads::Exclude {
+            segments.retain(|segment| lifecycle(segment).is_none());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlannedRoads;
+    use crate::PropertyValue;
+    use crate::test_support::segment;
+
+    #[test]
+    fn planned_roads_become_proposed() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.tags.insert("highway".to_string(), "primary".to_string());
+        segment.properties.insert("Lankstatus".to_string(), PropertyValue::Integer(3));
+        let mut segments = vec![segment];
+
+        PlannedRoads::Tag.apply(&mut segments);
+        assert_eq!(segments[0].tags["highway"], "proposed");
+        assert_eq!(segments[0].tags["proposed"], "primary");
+    }
+
+    #[test]
+    fn planned_roads_from_name() {
+        assert_eq!(PlannedRoads::from("exclude"), PlannedRoads::Exclude);
+        assert_eq!(PlannedRoads::from("anything else"), PlannedRoads::Tag);
+    }
+}
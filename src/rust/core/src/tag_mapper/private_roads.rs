@@ -0,0 +1,65 @@
+//! Access on private roads
+//!
+//! Most of Sweden's road network is private (enskild väg, väghållare 3).
+//! The Python script classifies these roads but leaves their access open,
+//! which suits routing users: many are open to the public, and all roads
+//! with state grants must be. Import communities often prefer to mark them
+//! `access=private` until someone has checked the signs. This post-tagging
+//! pass applies the chosen policy to the car network.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// How roads with a private road owner are tagged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrivateRoads {
+    /// Access left open (the default, as in the Python script)
+    #[default]
+    Permissive,
+    /// `access=private` on every private road
+    Conservative,
+    /// `motor_vehicle=destination` on every private road: open for reaching
+    /// properties and forest plots, not for through traffic
+    Destination,
+}
+
+impl From<&str> for PrivateRoads {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "conservative" | "private" => PrivateRoads::Conservative,
+            "destination" => PrivateRoads::Destination,
+            _ => PrivateRoads::Permissive, // Default
+        }
+    }
+}
+
+impl PrivateRoads {
+    /// Tag the private roads of the car network; segments that already have
+    /// the tag keep their value
+    pub fn apply(&self, segments: &mut [Segment]) {
+        let (key, value) = match self {
+            PrivateRoads::Permissive => return,
+            PrivateRoads::Conservative => ("access", "private"),
+            PrivateRoads::Destination => ("motor_vehicle", "destination"),
+        };
+        for segment in segments.iter_mut() {
+            let owner = segment.prop(Field::RoadOwner).and_then(|v| v.as_i64());
+            let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
+            if owner != Some(3) || net_type != 1 || !segment.tags.contains_key("highway") {
+                continue;
+            }
+            segment.tags.entry(key.to_string()).or_insert_with(|| value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrivateRoads;
+
+    #[test]
+    fn private_roads_from_name() {
+        assert_eq!(PrivateRoads::from("conservative"), PrivateRoads::Conservative);
+        assert_eq!(PrivateRoads::from("anything else"), PrivateRoads::Permissive);
+    }
+}
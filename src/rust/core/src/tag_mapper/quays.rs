@@ -0,0 +1,114 @@
+//! Quays and their ferry connections
+//!
+//! GCM type 25 (kaj) is tagged `man_made=pier` on a `highway=footway`, with
+//! `area=yes` when the quay is digitised as a closed ring around the pier.
+//! Ferry lines in NVDB often stop a few metres short of the quay they serve,
+//! leaving the ferry disconnected from the walking network. After tagging,
+//! each ferry end within `FERRY_SNAP_METRES` of a quay is moved onto the
+//! nearest quay vertex, and the quay is split there if needed so that the
+//! vertex becomes a shared junction node.
+
+use geo_types::Coord;
+
+use crate::models::{hash_coord, Segment};
+
+/// Largest gap between a ferry end and a quay that is closed
+pub const FERRY_SNAP_METRES: f64 = 25.0;
+
+/// Tag a kaj segment as a pier footway
+pub fn map_quay(segment: &mut Segment) {
+    segment.tags.insert("highway".to_string(), "footway".to_string());
+    segment.tags.insert("man_made".to_string(), "pier".to_string());
+    if segment.geometry.0.len() > 3 && segment.start_node == segment.end_node {
+        segment.tags.insert("area".to_string(), "yes".to_string());
+    }
+}
+
+/// Connect ferry ends to nearby quays, splitting quays at the connection
+pub fn connect_ferries(segments: &mut Vec<Segment>) {
+    let is_quay = |s: &Segment| s.tags.get("man_made").map(String::as_str) == Some("pier");
+    let is_ferry = |s: &Segment| s.tags.get("route").map(String::as_str) == Some("ferry");
+    if !segments.iter().any(is_quay) {
+        return;
+    }
+
+    let ferries: Vec<usize> = (0..segments.len()).filter(|&i| is_ferry(&segments[i])).collect();
+    for ferry in ferries {
+        for at_start in [true, false] {
+            let end = if at_start { *segments[ferry].start_coord() } else { *segments[ferry].end_coord() };
+
+            // Nearest quay vertex: (segment, vertex index, distance)
+            let mut nearest: Option<(usize, usize, f64)> = None;
+            for (q, quay) in segments.iter().enumerate().filter(|(_, s)| is_quay(s)) {
+                for (k, c) in quay.geometry.0.iter().enumerate() {
+                    let d = distance_m(&end, c);
+                    if d <= FERRY_SNAP_METRES && nearest.is_none_or(|(_, _, best)| d < best) {
+                        nearest = Some((q, k, d));
+                    }
+                }
+            }
+            let Some((quay, vertex, _)) = nearest else { continue };
+
+            let target = segments[quay].geometry.0[vertex];
+            let ferry_seg = &mut segments[ferry];
+            if at_start {
+                ferry_seg.geometry.0[0] = target;
+                ferry_seg.start_node = hash_coord(&target);
+            } else {
+                let last = ferry_seg.geometry.0.len() - 1;
+                ferry_seg.geometry.0[last] = target;
+                ferry_seg.end_node = hash_coord(&target);
+            }
+
+            let interior = vertex > 0 && vertex + 1 < segments[quay].geometry.0.len();
+            if interior {
+                let tail = segments[quay].split_at_vertex(vertex);
+                segments.push(tail);
+            }
+        }
+    }
+}
+
+/// Equirectangular distance in metres, accurate enough at quay scale
+fn distance_m(a: &Coord, b: &Coord) -> f64 {
+    let lat = ((a.y + b.y) / 2.0).to_radians();
+    let dx = (b.x - a.x).to_radians() * lat.cos();
+    let dy = (b.y - a.y).to_radians();
+    (dx * dx + dy * dy).sqrt() * 6_371_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::connect_ferries;
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn ferries_connect_through_quays() {
+        let segment = |coords: Vec<(f64, f64)>, properties: &[(&str, PropertyValue)]| {
+            let mut segment = segment(coords);
+            for (key, value) in properties {
+                segment.properties.insert(key.to_string(), value.clone());
+            }
+            segment
+        };
+        let mut segments = vec![
+            segment(
+                vec![(18.0, 59.0), (18.0002, 59.0), (18.0004, 59.0)],
+                &[("Vagtr_474", PropertyValue::Integer(2)), ("GCM_t_502", PropertyValue::Integer(25))],
+            ),
+            // Ferry ending about 11 m north of the middle of the quay
+            segment(
+                vec![(18.0002, 59.0001), (18.01, 59.01)],
+                &[("Farjeled", PropertyValue::Boolean(true)), ("Kateg_380", PropertyValue::Integer(3))],
+            ),
+        ];
+        tag_network(&mut segments);
+        assert_eq!((&*segments[0].tags["highway"], &*segments[0].tags["man_made"]), ("footway", "pier"));
+
+        connect_ferries(&mut segments);
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[1].start_node, segments[0].end_node);
+        assert_eq!(segments[2].start_node, segments[0].end_node);
+    }
+}
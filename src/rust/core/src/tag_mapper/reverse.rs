@@ -0,0 +1,267 @@
+//! Tags of a segment run through against its digitised direction
+//!
+//! The simplifier chains segments digitised head to head into one way
+//! (`Way::reversed`). Tags that depend on the direction then describe the
+//! reversed segment the wrong way round: `maxspeed:forward` is the limit
+//! against the way, a barrier on the `left` (`barrier:left`,
+//! `noise_barrier=left`) is on its right. This pass turns them to the way's
+//! direction, so that a reversed segment can be compared with, and merged
+//! into, the way.
+//!
+//! The same turn puts the oneways against the digitised direction back to
+//! their NVDB geometry, as `oneway=-1` (`OnewayGeometry::Keep`).
+
+use rustc_hash::FxHashMap;
+
+use crate::models::{AnnotationKind, OnewayDirection, Segment};
+
+/// Keys whose value is a side of the way (`left`, `right` or `both`)
+const SIDE_VALUED_KEYS: [&str; 2] = ["noise_barrier", "sidewalk"];
+
+/// Tags of a segment as seen from the opposite direction: `:forward` and
+/// `:backward`, `:left` and `:right` swapped in the keys, `left` and `right`
+/// in the values of side-valued keys such as `noise_barrier`, `incline` and
+/// `direction` turned around
+///
+/// `None` when the tags cannot be turned: a oneway (its way should follow
+/// the traffic), or an `incline` or `direction` value without a
+/// direction.
+pub fn reverse_tags(tags: &FxHashMap<String, String>) -> Option<FxHashMap<String, String>> {
+    tags.iter().map(|(key, value)| reverse_tag(key, value, false)).collect()
+}
+
+/// One tag seen from the opposite direction; with `oneway`, `yes` and `-1`
+/// are swapped instead of refusing a oneway, also in the rules of a
+/// conditional oneway
+fn reverse_tag(key: &str, value: &str, oneway: bool) -> Option<(String, String)> {
+    let value = match key {
+        "incline" => reverse_incline(value)?,
+        "direction" => match value {
+            "forward" => "backward".to_string(),
+            "backward" => "forward".to_string(),
+            "both" => value.to_string(),
+            _ => return None,
+        },
+        _ if key == "oneway" || key.starts_with("oneway:") => match value {
+            "no" => value.to_string(),
+            "yes" if oneway => "-1".to_string(),
+            "-1" if oneway => "yes".to_string(),
+            _ if oneway && key.ends_with(":conditional") => reverse_conditional(value)?,
+            _ => return None,
+        },
+        _ if SIDE_VALUED_KEYS.contains(&key) => match value {
+            "left" => "right".to_string(),
+            "right" => "left".to_string(),
+            _ => value.to_string(),
+        },
+        _ => value.to_string(),
+    };
+    Some((reverse_key(key), value))
+}
+
+/// Key with its direction and side parts swapped
+fn reverse_key(key: &str) -> String {
+    if !key.contains(':') {
+        return key.to_string();
+    }
+    key.split(':')
+        .map(|part| match part {
+            "forward" => "backward",
+            "backward" => "forward",
+            "left" => "right",
+            "right" => "left",
+            _ => part,
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Conditional oneway (`yes @ (Mo-Fr 07:00-09:00); no @ (Su)`) seen from
+/// the opposite direction: `yes` and `-1` swapped in every rule, the
+/// conditions unchanged
+fn reverse_conditional(value: &str) -> Option<String> {
+    // Rules are separated by `;` outside of the parenthesised conditions
+    let mut rules = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ';' if depth == 0 => {
+                rules.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    rules.push(&value[start..]);
+    let turned = rules
+        .into_iter()
+        .map(|rule| {
+            let (oneway, condition) = rule.split_once('@')?;
+            let turned = match oneway.trim() {
+                "yes" => "-1",
+                "-1" => "yes",
+                "no" => "no",
+                _ => return None,
+            };
+            Some(format!("{}@{}", oneway.replacen(oneway.trim(), turned, 1), condition))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(turned.join(";"))
+}
+
+/// `incline` seen from the other end: the sign of a gradient flipped, `up`
+/// and `down` swapped
+fn reverse_incline(value: &str) -> Option<String> {
+    match value {
+        "up" => return Some("down".to_string()),
+        "down" => return Some("up".to_string()),
+        _ => {}
+    }
+    let number = value.trim_end_matches(['%', '°']);
+    number.parse::<f64>().ok()?;
+    Some(match number.strip_prefix('-') {
+        Some(positive) => format!("{}{}", positive, &value[number.len()..]),
+        None if number.trim_start_matches(['0', '.']).is_empty() => value.to_string(),
+        None => format!("-{}", value.trim_start_matches('+')),
+    })
+}
+
+/// Direction of the geometry of oneways against the digitised direction
+/// (Förbjuden färdriktning framåt)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnewayGeometry {
+    /// Reverse the geometry to the direction of travel, `oneway=yes` (the
+    /// default, as in the Python script)
+    #[default]
+    Reverse,
+    /// Keep the NVDB geometry and direction, `oneway=-1`, so that the output
+    /// matches the source vertex for vertex (for QA and conflation)
+    Keep,
+}
+
+impl From<&str> for OnewayGeometry {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "keep" | "-1" => OnewayGeometry::Keep,
+            _ => OnewayGeometry::Reverse,
+        }
+    }
+}
+
+impl OnewayGeometry {
+    /// Turn the tagged oneways against the digitised direction back to
+    /// their NVDB geometry, with their direction-dependent tags (sides in
+    /// the values of keys such as `noise_barrier` included)
+    ///
+    /// Tagging works on the reversed geometry (see `map_oneway()`); this
+    /// runs after it. The segments then no longer count as reversed
+    /// (`OnewayDirection::None`): `oneway=-1` carries the direction. Tags
+    /// that cannot be turned, such as an `incline` without a direction,
+    /// are kept as they are and the segment is annotated.
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if *self == OnewayGeometry::Reverse {
+            return;
+        }
+        for segment in segments.iter_mut().filter(|s| s.oneway_direction == OnewayDirection::Backward) {
+            segment.geometry.0.reverse();
+            std::mem::swap(&mut segment.start_node, &mut segment.end_node);
+            std::mem::swap(&mut segment.global_start_node_id, &mut segment.global_end_node_id);
+            std::mem::swap(&mut segment.global_start_owned, &mut segment.global_end_owned);
+            let mut unturned = Vec::new();
+            segment.tags = segment.tags
+                .iter()
+                .map(|(key, value)| {
+                    reverse_tag(key, value, true).unwrap_or_else(|| {
+                        unturned.push(format!("{}={} not turned to the NVDB direction", key, value));
+                        (key.clone(), value.clone())
+                    })
+                })
+                .collect();
+            unturned.sort();
+            for message in unturned {
+                segment.annotate(AnnotationKind::ConflictingAttributes, message);
+            }
+            segment.oneway_direction = OnewayDirection::None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::LineString;
+    use rustc_hash::FxHashMap;
+
+    use super::{OnewayGeometry, reverse_tags};
+    use crate::{NoiseBarriers, PropertyValue, Segment, tag_network};
+
+    #[test]
+    fn backward_oneways_turn_sides_back_to_the_nvdb_direction() {
+        let mut segment = Segment::new(String::new(), LineString::from(vec![(18.0, 59.0), (18.01, 59.0)]));
+        segment.properties.insert("Klass_181".to_string(), PropertyValue::Integer(4));
+        segment.properties.insert("F_ForbjudenFardriktning".to_string(), PropertyValue::Integer(-1));
+        segment.properties.insert("L_Bullerskydd".to_string(), PropertyValue::Integer(1));
+        let mut segments = vec![segment];
+        tag_network(&mut segments);
+        NoiseBarriers::Tags.apply(&mut segments);
+        // Left of the NVDB direction is right of the reversed geometry
+        assert_eq!(segments[0].tags["noise_barrier"], "right");
+
+        OnewayGeometry::Keep.apply(&mut segments);
+        assert_eq!(segments[0].tags["noise_barrier"], "left");
+    }
+
+    #[test]
+    fn reverse_tags_turns_directions_and_sides() {
+        let tags = FxHashMap::from_iter([
+            ("maxspeed:forward".to_string(), "70".to_string()),
+            ("barrier:left".to_string(), "guard_rail".to_string()),
+            ("incline".to_string(), "-5%".to_string()),
+        ]);
+        let reversed = reverse_tags(&tags).unwrap();
+        assert_eq!(reversed["maxspeed:backward"], "70");
+        assert_eq!(reversed["barrier:right"], "guard_rail");
+        assert_eq!(reversed["incline"], "5%");
+
+        let oneway = FxHashMap::from_iter([("oneway".to_string(), "yes".to_string())]);
+        assert!(reverse_tags(&oneway).is_none());
+    }
+
+    #[test]
+    fn backward_oneways_are_reversed() {
+        let line = LineString::from(vec![(18.0, 59.0), (18.01, 59.0)]);
+        let mut segment = Segment::new(String::new(), line.clone());
+        segment.properties.insert("Klass_181".to_string(), PropertyValue::Integer(4));
+        segment.properties.insert("F_ForbjudenFardriktning".to_string(), PropertyValue::Integer(-1));
+        let mut segments = vec![segment];
+        tag_network(&mut segments);
+        assert_eq!(segments[0].tags["oneway"], "yes");
+
+        OnewayGeometry::Keep.apply(&mut segments);
+        assert_eq!(segments[0].geometry, line);
+        assert_eq!(segments[0].tags["oneway"], "-1");
+
+        let mut segment = Segment::new(String::new(), line.clone());
+        segment.properties.insert("Klass_181".to_string(), PropertyValue::Integer(4));
+        segment.properties.insert("F_ForbjudenFardriktning".to_string(), PropertyValue::Integer(-1));
+        let mut segments = vec![segment];
+        tag_network(&mut segments);
+        let conditional = "no @ (Mo-Fr 07:00-09:00; Sa 10:00-12:00); -1 @ (Su)";
+        segments[0].tags.insert("oneway:conditional".to_string(), conditional.to_string());
+        segments[0].tags.insert("incline".to_string(), "yes".to_string());
+        let annotations = segments[0].annotations.len();
+
+        OnewayGeometry::Keep.apply(&mut segments);
+        assert_eq!(segments[0].tags["oneway:conditional"], "no @ (Mo-Fr 07:00-09:00; Sa 10:00-12:00); yes @ (Su)");
+        assert_eq!(segments[0].tags["incline"], "yes");
+        assert_eq!(segments[0].annotations.len(), annotations + 1);
+        assert!(segments[0].annotations[annotations].message.contains("incline=yes"));
+    }
+
+    #[test]
+    fn oneway_geometry_from_name() {
+        assert_eq!(OnewayGeometry::from("keep"), OnewayGeometry::Keep);
+        assert_eq!(OnewayGeometry::from("anything else"), OnewayGeometry::Reverse);
+    }
+}
@@ -0,0 +1,59 @@
+//! Review tags from tag mapper annotations
+//!
+//! Mapping functions attach warnings to segments with `Segment::annotate()`.
+//! They always end up in `ConversionStats::annotations`; in review mode they
+//! are also written as `fixme`/`note` tags so they show up in the editor.
+
+use rustc_hash::FxHashMap;
+
+use crate::models::Segment;
+
+/// Write the annotations of all segments as `fixme`/`note` tags
+///
+/// Several messages for the same key are joined with "; ", after any value
+/// the key already has.
+pub fn write_review_tags(segments: &mut [Segment]) {
+    for segment in segments.iter_mut() {
+        if segment.annotations.is_empty() {
+            continue;
+        }
+        let mut messages: FxHashMap<&'static str, Vec<&str>> = FxHashMap::default();
+        for annotation in &segment.annotations {
+            messages.entry(annotation.kind.tag_key()).or_default().push(&annotation.message);
+        }
+        for (key, list) in messages {
+            let joined = list.join("; ");
+            let value = match segment.tags.get(key) {
+                Some(existing) => format!("{}; {}", existing, joined),
+                None => joined,
+            };
+            segment.tags.insert(key.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_review_tags;
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn review_tags_collect_dropped_values() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+        for (key, value) in [
+            ("F_ForbjudenFardriktning", PropertyValue::Boolean(true)),
+            ("B_ForbjudenFardriktning", PropertyValue::Boolean(true)),
+            ("Fri_h_143", PropertyValue::Float(12.0)),
+            ("F_Hogst_225", PropertyValue::Integer(200)),
+        ] {
+            segment.properties.insert(key.to_string(), value);
+        }
+        let mut segments = vec![segment];
+        tag_network(&mut segments);
+        write_review_tags(&mut segments);
+
+        assert_eq!(segments[0].tags["fixme"], "travel forbidden in both directions, oneway ignored");
+        assert_eq!(segments[0].tags["note"], "speed limit 200 dropped; maxheight 12 dropped");
+    }
+}
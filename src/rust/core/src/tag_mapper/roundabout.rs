@@ -0,0 +1,105 @@
+//! Ref and name tags on roundabouts
+//!
+//! The Python script leaves names off roundabout ways and gives them the ref
+//! of the road they belong to. Import communities disagree on both: some want
+//! named roundabouts, some keep the road ref off the roundabout or record it
+//! as `junction:ref` instead. This post-tagging pass adjusts the mapped tags.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+use super::road_name;
+
+/// What happens to the road ref on roundabout ways
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundaboutRef {
+    /// Keep `ref` (the default, as in the Python script)
+    #[default]
+    Keep,
+    /// Remove `ref`
+    Drop,
+    /// Move the value of `ref` to `junction:ref`
+    JunctionRef,
+}
+
+impl From<&str> for RoundaboutRef {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "drop" => RoundaboutRef::Drop,
+            "junction_ref" | "junction:ref" => RoundaboutRef::JunctionRef,
+            _ => RoundaboutRef::Keep, // Default
+        }
+    }
+}
+
+/// Ref and name handling for roundabout ways
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundaboutTags {
+    refs: RoundaboutRef,
+    names: bool,
+}
+
+impl RoundaboutTags {
+    /// Road ref handling (default: `RoundaboutRef::Keep`)
+    pub fn refs(mut self, mode: RoundaboutRef) -> Self {
+        self.refs = mode;
+        self
+    }
+
+    /// Also name roundabout ways after their street (default: false)
+    pub fn names(mut self, enabled: bool) -> Self {
+        self.names = enabled;
+        self
+    }
+
+    pub fn ref_mode(&self) -> RoundaboutRef {
+        self.refs
+    }
+
+    /// Adjust the ref and name tags of all roundabout segments
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if *self == Self::default() {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            let roundabout = segment.tags.iter()
+                .any(|(k, v)| (k == "junction" || k.starts_with("junction:")) && v == "roundabout");
+            if !roundabout {
+                continue;
+            }
+
+            match self.refs {
+                RoundaboutRef::Keep => {}
+                RoundaboutRef::Drop => {
+                    segment.tags.remove("ref");
+                }
+                RoundaboutRef::JunctionRef => {
+                    if let Some(road_ref) = segment.tags.remove("ref") {
+                        segment.tags.insert("junction:ref".to_string(), road_ref);
+                    }
+                }
+            }
+
+            // Same scope as map_name(): motor roads only, ferries excluded
+            let net_type = segment.prop(Field::NetworkType).and_then(|v| v.as_i64()).unwrap_or(1);
+            if self.names && net_type != 2 && net_type != 4 && !segment.tags.contains_key("route")
+                && !segment.tags.contains_key("name")
+            {
+                if let Some(name) = road_name(segment) {
+                    segment.tags.insert("name".to_string(), name);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RoundaboutRef, RoundaboutTags};
+
+    #[test]
+    fn roundabout_tags_builder() {
+        let tags = RoundaboutTags::default().refs(RoundaboutRef::JunctionRef).names(true);
+        assert_eq!(tags.ref_mode(), RoundaboutRef::JunctionRef);
+    }
+}
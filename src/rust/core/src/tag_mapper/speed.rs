@@ -0,0 +1,93 @@
+//! Implicit speed limits
+//!
+//! NVDB only records signed speed limits. Where a road has none, the general
+//! limits of Trafikförordningen (3 kap. 17 §) apply: 50 km/h inside built-up
+//! areas (tättbebyggt område), 70 km/h outside them and 110 km/h on motorways
+//! and motor roads. This optional post-tagging pass writes them out with a
+//! `maxspeed:type` such as `SE:urban`, for consumers that do not know the
+//! Swedish defaults.
+
+use crate::models::Segment;
+use crate::schema::Field;
+
+/// Highway classes open to motor traffic at general speeds; tracks, living
+/// streets (walking pace) and the cycle and foot network are left out
+const MOTOR_HIGHWAYS: &[&str] = &[
+    "motorway", "motorway_link", "trunk", "trunk_link", "primary", "primary_link",
+    "secondary", "secondary_link", "tertiary", "tertiary_link", "unclassified",
+    "residential", "service",
+];
+
+/// Keys of a signed limit for all traffic; vehicle-specific and conditional
+/// limits (`maxspeed:hgv`, `maxspeed:conditional`) leave the general limit
+/// in force
+const SIGNED_KEYS: &[&str] = &["maxspeed", "maxspeed:forward", "maxspeed:backward"];
+
+/// Swedish general speed limit and its `maxspeed:type` for a segment
+fn implicit_maxspeed(segment: &Segment) -> Option<(&'static str, &'static str)> {
+    let highway = segment.tags.get("highway")?;
+    if !MOTOR_HIGHWAYS.contains(&highway.as_str()) {
+        return None;
+    }
+    if highway.starts_with("motorway") {
+        return Some(("110", "SE:motorway"));
+    }
+    if segment.tags.get("motorroad").map(|s| s.as_str()) == Some("yes") {
+        return Some(("110", "SE:motorroad"));
+    }
+    let urban = segment.prop(Field::UrbanArea).map(|v| v.as_bool()).unwrap_or(false);
+    Some(if urban { ("50", "SE:urban") } else { ("70", "SE:rural") })
+}
+
+/// Tag `maxspeed` and `maxspeed:type` on segments without a signed limit
+///
+/// Segments with `maxspeed`, `maxspeed:forward` or `maxspeed:backward` keep
+/// their mapped values. Runs after the planned roads are retagged, so that
+/// `highway=construction` and `highway=proposed` get no limit.
+pub fn tag_implicit_maxspeed(segments: &mut [Segment]) {
+    for segment in segments.iter_mut() {
+        let signed = SIGNED_KEYS.iter().any(|k| segment.tags.contains_key(*k));
+        if signed {
+            continue;
+        }
+        if let Some((speed, speed_type)) = implicit_maxspeed(segment) {
+            segment.tags.insert("maxspeed".to_string(), speed.to_string());
+            segment.tags.insert("maxspeed:type".to_string(), speed_type.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tag_implicit_maxspeed;
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn implicit_maxspeed_by_area_type() {
+        let road = |properties: &[(&str, PropertyValue)]| {
+            let mut segment = segment(vec![(18.0, 59.0), (18.001, 59.0)]);
+            for (key, value) in properties {
+                segment.properties.insert(key.to_string(), value.clone());
+            }
+            segment
+        };
+        let mut segments = vec![
+            road(&[("Kateg_380", PropertyValue::Integer(3))]),
+            road(&[("Kateg_380", PropertyValue::Integer(3)), ("TattbebyggtOmrade", PropertyValue::Boolean(true))]),
+            road(&[("Motorvag", PropertyValue::Boolean(true))]),
+            road(&[("Kateg_380", PropertyValue::Integer(3)), ("F_Hogst_225", PropertyValue::Integer(90))]),
+            road(&[("Kateg_380", PropertyValue::Integer(3))]),
+        ];
+        tag_network(&mut segments);
+        segments[4].tags.insert("maxspeed:hgv".to_string(), "60".to_string());
+        tag_implicit_maxspeed(&mut segments);
+
+        assert_eq!((&*segments[0].tags["maxspeed"], &*segments[0].tags["maxspeed:type"]), ("70", "SE:rural"));
+        assert_eq!((&*segments[1].tags["maxspeed"], &*segments[1].tags["maxspeed:type"]), ("50", "SE:urban"));
+        assert_eq!((&*segments[2].tags["maxspeed"], &*segments[2].tags["maxspeed:type"]), ("110", "SE:motorway"));
+        assert!(!segments[3].tags.contains_key("maxspeed:type"));
+        // A vehicle-specific limit leaves the general limit in force
+        assert_eq!(segments[4].tags["maxspeed"], "70");
+    }
+}
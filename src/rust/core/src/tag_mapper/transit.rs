@@ -0,0 +1,80 @@
+//! Public transport platforms
+//!
+//! GCM type 16 (plattform) is mapped to `highway=platform`. This pass adds
+//! the public transport schema: every platform gets
+//! `public_transport=platform` and its number as `ref` where the delivery
+//! has one. Platforms touching rail (a railway-tagged way or a road segment
+//! with a level crossing) are train platforms: they are tagged
+//! `railway=platform` and kept routable for pedestrians as
+//! `highway=footway`, since `highway=platform` is reserved for bus stops.
+
+use rustc_hash::FxHashSet;
+
+use crate::models::{CoordHash, Segment};
+use crate::schema::Field;
+
+/// Add public transport tags to the platform segments
+///
+/// Runs at the end of `tag_network()`, after `non_road::NonRoadTags`, which
+/// tags the funiculars.
+pub fn tag_platforms(segments: &mut [Segment]) {
+    if !segments.iter().any(is_platform) {
+        return;
+    }
+
+    // Endpoints of rail: funiculars and other railway-tagged ways, and road
+    // segments with a railway crossing
+    let rail_nodes: FxHashSet<CoordHash> = segments.iter()
+        .filter(|s| s.tags.contains_key("railway") || s.prop(Field::RailwayCrossing).is_some())
+        .flat_map(|s| [s.start_node, s.end_node])
+        .collect();
+
+    for segment in segments.iter_mut().filter(|s| is_platform(s)) {
+        segment.tags.insert("public_transport".to_string(), "platform".to_string());
+        if let Some(platform_ref) = segment.prop(Field::PlatformRef) {
+            let platform_ref = platform_ref.as_string().trim().to_string();
+            if !platform_ref.is_empty() {
+                segment.tags.insert("ref".to_string(), platform_ref);
+            }
+        }
+        if rail_nodes.contains(&segment.start_node) || rail_nodes.contains(&segment.end_node) {
+            segment.tags.insert("railway".to_string(), "platform".to_string());
+            segment.tags.insert("highway".to_string(), "footway".to_string());
+        }
+    }
+}
+
+fn is_platform(segment: &Segment) -> bool {
+    segment.tags.get("highway").map(String::as_str) == Some("platform")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PropertyValue, tag_network};
+    use crate::test_support::segment;
+
+    #[test]
+    fn platforms_for_buses_and_trains() {
+        let gcm = |gcm_type: i64, coords: Vec<(f64, f64)>| {
+            let mut segment = segment(coords);
+            segment.properties.insert("Vagtr_474".to_string(), PropertyValue::Integer(2));
+            segment.properties.insert("GCM_t_502".to_string(), PropertyValue::Integer(gcm_type));
+            segment
+        };
+        let mut bus_stop = gcm(16, vec![(18.0, 59.0), (18.0002, 59.0)]);
+        bus_stop.properties.insert("Plattformsnummer".to_string(), PropertyValue::String(" B ".to_string()));
+        let mut segments = vec![
+            bus_stop,
+            // Platform at the lower station of a funicular
+            gcm(16, vec![(18.01, 59.0), (18.0102, 59.0)]),
+            gcm(23, vec![(18.0102, 59.0), (18.0112, 59.001)]),
+        ];
+        tag_network(&mut segments);
+
+        assert_eq!(segments[0].tags["highway"], "platform");
+        assert_eq!(segments[0].tags["public_transport"], "platform");
+        assert_eq!(segments[0].tags["ref"], "B");
+        assert_eq!(segments[1].tags["railway"], "platform");
+        assert_eq!(segments[1].tags["highway"], "footway");
+    }
+}
@@ -0,0 +1,166 @@
+//! Tunnel names and lengths
+//!
+//! NVDB only names a tunnel through free text on some of its segments
+//! (`Namn_132` containing "tunneln"), so the `tunnel:name` of a tunnel split
+//! into several ways used to depend on which way got such a segment. After
+//! merging, the ways of one tunnel are grouped (by NVDB structure identity,
+//! or else by being connected) and all of them get the same `tunnel:name`
+//! and the `length` of the whole tunnel in metres.
+
+use std::collections::BTreeMap;
+
+use geo::HaversineLength;
+use rustc_hash::FxHashMap;
+
+use crate::models::{CoordHash, Segment, Way};
+use crate::schema::Field;
+
+/// Tag every tunnel way with the name and total length of its tunnel
+pub fn tag_tunnels(ways: &mut [Way], segments: &[Segment]) {
+    let tunnel_ways: Vec<usize> = (0..ways.len())
+        .filter(|&w| ways[w].tags.contains_key("tunnel") && !ways[w].segment_indices.is_empty())
+        .collect();
+    if tunnel_ways.is_empty() {
+        return;
+    }
+
+    let ident = |w: usize| {
+        ways[w].segment_indices.iter()
+            .find_map(|&i| segments[i].prop(Field::StructureId))
+            .map(|v| v.as_string().trim().to_string())
+            .filter(|id| !id.is_empty())
+    };
+    let idents: Vec<Option<String>> = tunnel_ways.iter().map(|&w| ident(w)).collect();
+
+    // Union-find over the tunnel ways: same identity, or connected without
+    // conflicting identities
+    let mut parent: Vec<usize> = (0..tunnel_ways.len()).collect();
+    fn root(parent: &mut [usize], mut k: usize) -> usize {
+        while parent[k] != k {
+            parent[k] = parent[parent[k]];
+            k = parent[k];
+        }
+        k
+    }
+    let mut by_ident: FxHashMap<&str, usize> = FxHashMap::default();
+    let mut by_node: FxHashMap<CoordHash, Vec<usize>> = FxHashMap::default();
+    for (k, &w) in tunnel_ways.iter().enumerate() {
+        if let Some(id) = &idents[k] {
+            let first = *by_ident.entry(id.as_str()).or_insert(k);
+            let (a, b) = (root(&mut parent, first), root(&mut parent, k));
+            parent[b] = a;
+        }
+        for end in [ways[w].first_node(segments), ways[w].last_node(segments)].into_iter().flatten() {
+            by_node.entry(end).or_default().push(k);
+        }
+    }
+    for members in by_node.values() {
+        for pair in members.windows(2) {
+            let compatible = match (&idents[pair[0]], &idents[pair[1]]) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            };
+            if compatible {
+                let (a, b) = (root(&mut parent, pair[0]), root(&mut parent, pair[1]));
+                parent[b] = a;
+            }
+        }
+    }
+
+    let mut tunnels: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (k, &w) in tunnel_ways.iter().enumerate() {
+        let r = root(&mut parent, k);
+        tunnels.entry(r).or_default().push(w);
+    }
+
+    for members in tunnels.values() {
+        let name = members.iter()
+            .find_map(|&w| ways[w].tags.get("tunnel:name").cloned())
+            .or_else(|| {
+                members.iter()
+                    .flat_map(|&w| ways[w].segment_indices.iter())
+                    .find_map(|&i| segments[i].prop(Field::StructureName))
+                    .map(|v| v.as_string().trim().to_string())
+                    .filter(|n| !n.is_empty())
+            });
+        let length: f64 = members.iter()
+            .flat_map(|&w| ways[w].segment_indices.iter())
+            .map(|&i| segments[i].geometry.haversine_length())
+            .sum();
+
+        for &w in members {
+            if let Some(name) = &name {
+                ways[w].tags.insert("tunnel:name".to_string(), name.clone());
+            }
+            ways[w].tags.insert("length".to_string(), format!("{:.0}", length));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tag_tunnels;
+    use crate::{PropertyValue, Way};
+    use crate::schema::Field;
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn connected_tunnel_ways_share_name_and_length() {
+        // One tunnel split into two ways, named on its second segment only,
+        // a separate unnamed tunnel and a road leading into the first
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.002, 59.0)]),
+            segment(vec![(18.1, 59.0), (18.101, 59.0)]),
+            segment(vec![(17.999, 59.0), (18.0, 59.0)]),
+        ];
+        segments[1].properties.insert(Field::StructureName.column().to_string(), PropertyValue::String("Norra länken ".to_string()));
+        let mut ways = vec![
+            Way::new(vec![0], tags(&[("highway", "primary"), ("tunnel", "yes")])),
+            Way::new(vec![1], tags(&[("highway", "primary"), ("tunnel", "yes"), ("maxspeed", "70")])),
+            Way::new(vec![2], tags(&[("highway", "primary"), ("tunnel", "yes")])),
+            Way::new(vec![3], tags(&[("highway", "primary")])),
+        ];
+
+        tag_tunnels(&mut ways, &segments);
+        assert_eq!(ways[0].tags["tunnel:name"], "Norra länken");
+        assert_eq!(ways[1].tags["tunnel:name"], "Norra länken");
+        let length: f64 = ways[0].tags["length"].parse().unwrap();
+        assert!(length > 110.0 && length < 120.0);
+        assert_eq!(ways[1].tags["length"], ways[0].tags["length"]);
+
+        assert!(!ways[2].tags.contains_key("tunnel:name"));
+        assert_eq!(ways[2].tags["length"], "57");
+        assert!(!ways[3].tags.contains_key("length"));
+    }
+
+    #[test]
+    fn structure_identity_groups_tunnel_ways() {
+        // Two tubes of one tunnel that do not touch, and a tunnel of another
+        // structure joining the first tube
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.0, 59.001), (18.001, 59.001)]),
+            segment(vec![(18.001, 59.0), (18.002, 59.0)]),
+        ];
+        for (i, id) in [(0, "T1"), (1, "T1 "), (2, "T2")] {
+            segments[i].properties.insert(Field::StructureId.column().to_string(), PropertyValue::String(id.to_string()));
+        }
+        segments[1].properties.insert(Field::StructureName.column().to_string(), PropertyValue::String("Söderledstunneln".to_string()));
+        let mut ways = vec![
+            Way::new(vec![0], tags(&[("tunnel", "yes"), ("tunnel:name", "Söderledstunnel")])),
+            Way::new(vec![1], tags(&[("tunnel", "yes")])),
+            Way::new(vec![2], tags(&[("tunnel", "yes")])),
+        ];
+
+        tag_tunnels(&mut ways, &segments);
+        // An existing tunnel:name wins over the NVDB structure name
+        assert_eq!(ways[0].tags["tunnel:name"], "Söderledstunnel");
+        assert_eq!(ways[1].tags["tunnel:name"], "Söderledstunnel");
+        assert_eq!(ways[0].tags["length"], "115");
+        assert_eq!(ways[1].tags["length"], "115");
+
+        assert!(!ways[2].tags.contains_key("tunnel:name"));
+        assert_eq!(ways[2].tags["length"], "57");
+    }
+}
@@ -0,0 +1,98 @@
+//! Formatting of restriction values
+//!
+//! OSM assumes tonnes for `maxweight`/`maxaxleload` and metres for
+//! `maxheight`/`maxlength`/`maxwidth` when no unit is given. The mappers
+//! write plain numbers through `number()`; `UnitFormat` optionally appends the
+//! unit afterwards, for data consumers that expect it spelled out.
+
+use rustc_hash::FxHashMap;
+
+use crate::models::Segment;
+
+/// Tag keys holding weights in tonnes
+const WEIGHT_KEYS: &[&str] = &["maxweight", "maxaxleload"];
+/// Tag keys holding dimensions in metres
+const LENGTH_KEYS: &[&str] = &["maxheight", "maxlength", "maxwidth"];
+
+/// Restriction value with at most one decimal, without a spurious ".0"
+pub fn number(value: f64) -> String {
+    let text = format!("{:.1}", value);
+    match text.strip_suffix(".0") {
+        Some(integer) => integer.to_string(),
+        None => text,
+    }
+}
+
+/// Unit suffix of a restriction tag key, including its directional and
+/// physical variants (`maxweight:forward`, `maxwidth:physical`)
+fn unit_of(key: &str) -> Option<&'static str> {
+    let base = key.split(':').next().unwrap_or(key);
+    if WEIGHT_KEYS.contains(&base) {
+        Some("t")
+    } else if LENGTH_KEYS.contains(&base) {
+        Some("m")
+    } else {
+        None
+    }
+}
+
+/// How restriction values are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UnitFormat {
+    units: bool,
+}
+
+impl UnitFormat {
+    /// Plain numbers in the OSM default units (the default)
+    pub fn plain() -> Self {
+        Self { units: false }
+    }
+
+    /// Numbers followed by their unit, e.g. `maxweight=3.5 t`, `maxheight=4.2 m`
+    pub fn with_units() -> Self {
+        Self { units: true }
+    }
+
+    pub fn units(&self) -> bool {
+        self.units
+    }
+
+    /// Append the units to the restriction values in `tags`
+    ///
+    /// Conditional values (`no @ (weight>3.5)`) and values that are not a
+    /// plain number are left unchanged.
+    pub fn apply_tags(&self, tags: &mut FxHashMap<String, String>) {
+        if !self.units {
+            return;
+        }
+        for (key, value) in tags.iter_mut() {
+            if let Some(unit) = unit_of(key) {
+                if value.parse::<f64>().is_ok() {
+                    *value = format!("{} {}", value, unit);
+                }
+            }
+        }
+    }
+
+    /// Append the units to the restriction values of all segments
+    pub fn apply(&self, segments: &mut [Segment]) {
+        if !self.units {
+            return;
+        }
+        for segment in segments.iter_mut() {
+            self.apply_tags(&mut segment.tags);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::number;
+
+    #[test]
+    fn number_drops_trailing_zero() {
+        assert_eq!(number(64.0), "64");
+        assert_eq!(number(3.5), "3.5");
+        assert_eq!(number(4.449), "4.4");
+    }
+}
@@ -0,0 +1,91 @@
+//! Tag statistics of an output
+//!
+//! Counts the tags of a written file per element type, key and value, like
+//! `osmium tagstats`, so a conversion can be sanity-checked ("why are there
+//! no maxspeed tags?") without other tools.
+
+use pbf_craft::models::{Element, Tag};
+use pbf_craft::readers::IterableReader;
+use rustc_hash::FxHashMap;
+
+/// Number of elements of one type carrying a key, or a key and value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    /// "node", "way" or "relation"
+    pub element: &'static str,
+    pub key: String,
+    /// `None` when counted by key only
+    pub value: Option<String>,
+    pub count: usize,
+}
+
+/// Element type order (nodes, ways, relations), key and value, if counted
+type CountKey = (usize, String, Option<String>);
+
+/// Count the tags of the file at `path` by key and value, or by key only
+/// when `by_value` is false
+///
+/// Sorted by element type (nodes, ways, relations), key and descending
+/// count.
+pub fn tag_stats(path: &str, by_value: bool) -> Result<Vec<TagCount>, String> {
+    let reader = IterableReader::from_path(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut counts: FxHashMap<CountKey, usize> = FxHashMap::default();
+    let mut count = |order: usize, tags: Vec<Tag>| {
+        for tag in tags {
+            let value = by_value.then_some(tag.value);
+            *counts.entry((order, tag.key, value)).or_insert(0) += 1;
+        }
+    };
+    for element in reader {
+        match element {
+            Element::Node(node) => count(0, node.tags),
+            Element::Way(way) => count(1, way.tags),
+            Element::Relation(relation) => count(2, relation.tags),
+        }
+    }
+
+    let mut rows: Vec<(CountKey, usize)> = counts.into_iter().collect();
+    rows.sort_by(|((oa, ka, va), ca), ((ob, kb, vb), cb)| (oa, ka, cb, va).cmp(&(ob, kb, ca, vb)));
+    Ok(rows
+        .into_iter()
+        .map(|((order, key, value), count)| TagCount {
+            element: ["node", "way", "relation"][order],
+            key,
+            value,
+            count,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tag_stats;
+    use crate::{PbfEmitter, Way};
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn tag_stats_count_keys_and_values() {
+        let path = std::env::temp_dir().join("tag_stats.osm.pbf").to_string_lossy().into_owned();
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.01, 59.0)]),
+            segment(vec![(18.01, 59.0), (18.02, 59.0)]),
+        ];
+        let ways = vec![
+            Way::new(vec![0], tags(&[("highway", "residential")])),
+            Way::new(vec![1], tags(&[("highway", "service")])),
+        ];
+        let mut emitter = PbfEmitter::create(&path, 1, 1).unwrap();
+        emitter.write_network_nodes(&ways, &mut segments);
+        for way in &ways {
+            let pbf_way = emitter.build_way(way, &segments);
+            emitter.write_way(pbf_way);
+        }
+        emitter.finish().unwrap();
+
+        let by_value = tag_stats(&path, true).unwrap();
+        assert_eq!(by_value.len(), 2);
+        assert_eq!(by_value[0].value.as_deref(), Some("residential"));
+        let by_key = tag_stats(&path, false).unwrap();
+        assert_eq!((by_key[0].element, by_key[0].key.as_str(), by_key[0].count), ("way", "highway", 2));
+    }
+}
@@ -0,0 +1,16 @@
+//! Fixtures shared by the unit tests
+
+use geo_types::LineString;
+use rustc_hash::FxHashMap;
+
+use crate::models::Segment;
+
+/// Segment along `coords` (longitude, latitude), without properties
+pub(crate) fn segment(coords: Vec<(f64, f64)>) -> Segment {
+    Segment::new(String::new(), LineString::from(coords))
+}
+
+/// Tag map of `pairs`
+pub(crate) fn tags(pairs: &[(&str, &str)]) -> FxHashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
@@ -0,0 +1,124 @@
+//! Ways with the same nodes
+//!
+//! A segment delivered twice (overlapping extracts, a link in two
+//! municipalities' deliveries) becomes two ways over the same nodes, the
+//! "doubled road" that validators and import reviewers reject. This final
+//! pass finds ways whose node sequences are identical, in either direction,
+//! and reports or merges them.
+
+use rustc_hash::FxHashMap;
+
+use crate::models::{hash_coord, CoordHash, Segment, Way};
+use crate::stats::{AnnotationRecord, ConversionStats};
+use crate::tag_mapper::reverse::reverse_tags;
+
+/// `AnnotationRecord::kind` of the reported and dropped duplicates
+pub(crate) const DUPLICATE_WAY: &str = "duplicate_way";
+
+/// Handling of ways with identical node sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateWays {
+    /// Write them unchecked (the default, as in the Python script)
+    #[default]
+    Keep,
+    /// Write them, with a "duplicate_way" annotation for each duplicate
+    Report,
+    /// Write only the first of them, with the tags the others add to it
+    /// (turned around for those running the other way), and annotate the
+    /// dropped ones
+    Merge,
+}
+
+impl From<&str> for DuplicateWays {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "report" => DuplicateWays::Report,
+            "merge" => DuplicateWays::Merge,
+            _ => DuplicateWays::Keep, // Default
+        }
+    }
+}
+
+impl DuplicateWays {
+    /// Find the duplicated ways, annotate them and merge them if chosen
+    pub fn apply(&self, ways: &mut Vec<Way>, segments: &[Segment], stats: &mut ConversionStats) {
+        if *self == DuplicateWays::Keep {
+            return;
+        }
+        // First way with each node sequence, by sequence in its canonical
+        // direction, and whether the way runs against that direction
+        let mut first: FxHashMap<Vec<CoordHash>, (usize, bool)> = FxHashMap::default();
+        let mut duplicate_of: Vec<Option<(usize, bool)>> = vec![None; ways.len()];
+        for (w, way) in ways.iter().enumerate() {
+            if way.segment_indices.is_empty() {
+                continue;
+            }
+            let mut nodes: Vec<CoordHash> = way.line(segments).0.iter().map(hash_coord).collect();
+            let mut reversed = nodes.clone();
+            reversed.reverse();
+            let against = reversed < nodes;
+            if against {
+                nodes = reversed;
+            }
+            match first.get(&nodes) {
+                Some(&(kept, kept_against)) => duplicate_of[w] = Some((kept, against != kept_against)),
+                None => { first.insert(nodes, (w, against)); }
+            }
+        }
+
+        for (w, kept) in duplicate_of.iter().enumerate() {
+            let Some((kept, opposite)) = *kept else { continue };
+            stats.annotations.push(AnnotationRecord {
+                source_row: segments[ways[w].segment_indices[0]].source_row,
+                kind: DUPLICATE_WAY,
+                message: format!("same nodes as the way of row {}", segments[ways[kept].segment_indices[0]].source_row + 1),
+            });
+            if *self == DuplicateWays::Merge {
+                let tags = match opposite {
+                    true => reverse_tags(&ways[w].tags).unwrap_or_else(|| ways[w].tags.clone()),
+                    false => ways[w].tags.clone(),
+                };
+                for (key, value) in tags {
+                    ways[kept].tags.entry(key).or_insert(value);
+                }
+            }
+        }
+        if *self == DuplicateWays::Merge {
+            let mut w = 0;
+            ways.retain(|_| {
+                w += 1;
+                duplicate_of[w - 1].is_none()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DuplicateWays;
+    use crate::{ConversionStats, Way};
+    use crate::test_support::{segment, tags};
+
+    #[test]
+    fn duplicate_ways_merge_tags() {
+        // The same road delivered twice, digitised in opposite directions
+        let segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.0, 59.0)]),
+        ];
+        let way = |i: usize, key: &str| Way::new(vec![i], tags(&[(key, "yes")]));
+        let mut ways = vec![way(0, "highway"), way(1, "lit")];
+        let mut stats = ConversionStats::default();
+
+        DuplicateWays::Merge.apply(&mut ways, &segments, &mut stats);
+        assert_eq!(ways.len(), 1);
+        assert_eq!(ways[0].tags["lit"], "yes");
+        assert_eq!(stats.annotations[0].kind, "duplicate_way");
+    }
+
+    #[test]
+    fn duplicate_ways_from_name() {
+        assert_eq!(DuplicateWays::from("merge"), DuplicateWays::Merge);
+        assert_eq!(DuplicateWays::from("anything else"), DuplicateWays::Keep);
+    }
+}
@@ -0,0 +1,201 @@
+//! Edge list of the simplified network
+//!
+//! Routing and network research tools (igraph, dodgr) take a graph as a
+//! node table plus an edge list. The graph nodes are the junctions of the
+//! network (see `junctions`) and the ends of every way; each way is split
+//! into one edge per stretch between two graph nodes, in its digitised
+//! direction.
+
+use geo::HaversineLength;
+use rustc_hash::FxHashMap;
+
+use crate::models::{CoordHash, Segment, Way};
+
+/// Node of the routing graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    pub lon: f64,
+    pub lat: f64,
+    /// Segment ends meeting at the node
+    pub degree: usize,
+}
+
+/// Stretch of a way between two graph nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    /// Index into `EdgeList::nodes`
+    pub from: usize,
+    pub to: usize,
+    /// Index of the way the edge belongs to
+    pub way: usize,
+    /// Length in metres
+    pub length: f64,
+}
+
+/// Node table and edge list of the network
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EdgeList {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Graph of `ways`, nodes numbered in order of first use
+pub fn edge_list(segments: &[Segment], ways: &[Way]) -> EdgeList {
+    // Degree and the ways meeting at every segment end
+    let mut ends: FxHashMap<CoordHash, (usize, Option<usize>, bool)> = FxHashMap::default();
+    for (w, way) in ways.iter().enumerate() {
+        for &s in &way.segment_indices {
+            for hash in [segments[s].start_node, segments[s].end_node] {
+                let entry = ends.entry(hash).or_insert((0, Some(w), false));
+                entry.0 += 1;
+                entry.2 |= entry.1 != Some(w);
+            }
+        }
+    }
+    let is_junction = |hash: &CoordHash| ends.get(hash).is_some_and(|&(degree, _, shared)| degree != 2 || shared);
+
+    let mut graph = EdgeList::default();
+    let mut node_index: FxHashMap<CoordHash, usize> = FxHashMap::default();
+    let mut node = |hash: CoordHash, lon: f64, lat: f64, nodes: &mut Vec<GraphNode>| -> usize {
+        *node_index.entry(hash).or_insert_with(|| {
+            nodes.push(GraphNode { lon, lat, degree: ends.get(&hash).map_or(0, |e| e.0) });
+            nodes.len() - 1
+        })
+    };
+
+    for (w, way) in ways.iter().enumerate() {
+        let Some(&first) = way.segment_indices.first() else { continue };
+        let start = if way.is_reversed(0) { segments[first].end_coord() } else { segments[first].start_coord() };
+        let mut from = node(way.segment_ends(0, segments).0, start.x, start.y, &mut graph.nodes);
+        let mut length = 0.0;
+        for (k, (s, reversed)) in way.oriented().enumerate() {
+            let segment = &segments[s];
+            length += segment.geometry.haversine_length();
+            let last = k + 1 == way.segment_indices.len();
+            let exit = way.segment_ends(k, segments).1;
+            if last || is_junction(&exit) {
+                let end = if reversed { segment.start_coord() } else { segment.end_coord() };
+                let to = node(exit, end.x, end.y, &mut graph.nodes);
+                graph.edges.push(GraphEdge { from, to, way: w, length });
+                from = to;
+                length = 0.0;
+            }
+        }
+    }
+    graph
+}
+
+/// One random query of `path_check()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathQuery {
+    /// Indices into `EdgeList::nodes`
+    pub from: usize,
+    pub to: usize,
+    /// Shortest path length in metres, `None` if `to` is unreachable
+    pub distance: Option<f64>,
+}
+
+/// Shortest-path smoke test: Dijkstra queries between `queries` random node
+/// pairs (reproducible for a `seed`), ignoring oneway restrictions
+///
+/// On a connected network every pair is reachable; unreachable pairs point
+/// at broken junctions or over-aggressive clipping.
+pub fn path_check(graph: &EdgeList, queries: usize, seed: u64) -> Vec<PathQuery> {
+    let n = graph.nodes.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut adjacency: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    for edge in &graph.edges {
+        adjacency[edge.from].push((edge.to, edge.length));
+        adjacency[edge.to].push((edge.from, edge.length));
+    }
+
+    // xorshift64*, so that results do not depend on a random number crate
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    let mut random_node = || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        (state.wrapping_mul(0x2545_F491_4F6C_DD1D) % n as u64) as usize
+    };
+
+    (0..queries)
+        .map(|_| {
+            let (from, to) = (random_node(), random_node());
+            PathQuery { from, to, distance: dijkstra(&adjacency, from, to) }
+        })
+        .collect()
+}
+
+/// Length of the shortest path from `from` to `to`
+fn dijkstra(adjacency: &[Vec<(usize, f64)>], from: usize, to: usize) -> Option<f64> {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    // Non-negative f64 distances order like their bit patterns
+    let mut best = vec![f64::INFINITY; adjacency.len()];
+    let mut heap = BinaryHeap::new();
+    best[from] = 0.0;
+    heap.push(Reverse((0f64.to_bits(), from)));
+    while let Some(Reverse((bits, node))) = heap.pop() {
+        let distance = f64::from_bits(bits);
+        if node == to {
+            return Some(distance);
+        }
+        if distance > best[node] {
+            continue;
+        }
+        for &(next, length) in &adjacency[node] {
+            let candidate = distance + length;
+            if candidate < best[next] {
+                best[next] = candidate;
+                heap.push(Reverse((candidate.to_bits(), next)));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::{edge_list, path_check};
+    use crate::Way;
+    use crate::test_support::segment;
+
+    #[test]
+    fn edge_list_counts_junction_degree() {
+        // One way of two segments, with a side road joining in the middle
+        let segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.002, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.001, 59.001)]),
+        ];
+        let ways = vec![
+            Way::new(vec![0, 1], FxHashMap::default()),
+            Way::new(vec![2], FxHashMap::default()),
+        ];
+        let graph = edge_list(&segments, &ways);
+
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.nodes[graph.edges[0].to].degree, 3);
+    }
+
+    #[test]
+    fn path_check_finds_unreachable_islands() {
+        let segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.001, 59.001)]),
+            segment(vec![(19.0, 59.0), (19.001, 59.0)]), // an island
+        ];
+        let ways: Vec<Way> = (0..3).map(|i| Way::new(vec![i], FxHashMap::default())).collect();
+        let queries = path_check(&edge_list(&segments, &ways), 20, 1);
+
+        assert_eq!(queries.len(), 20);
+        assert!(queries.iter().any(|q| q.distance.is_none()));
+        assert!(queries.iter().all(|q| q.distance.is_some() == ((q.from < 3) == (q.to < 3))));
+    }
+}
@@ -0,0 +1,113 @@
+//! Junctions of the simplified network
+//!
+//! Summarizes the junction graph for analysis outside the crate
+//! (intersection density, signalization heuristics): every point where
+//! segments meet, with its degree, the ways through it and the spread of
+//! the directions leaving it.
+
+use rustc_hash::FxHashMap;
+
+use crate::geometry::compute_bearing;
+use crate::models::{CoordHash, Segment, Way};
+
+/// One junction of the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct JunctionSummary {
+    pub lon: f64,
+    pub lat: f64,
+    /// Segment ends meeting here (1 at a dead end, a way passing through
+    /// counts 2)
+    pub degree: usize,
+    /// Indices of the ways touching the junction, ascending
+    pub ways: Vec<usize>,
+    /// Angle in degrees covered by the branches leaving the junction: 360
+    /// minus the largest gap between neighbouring branch bearings, so 0 at a
+    /// dead end, 180 on a straight road and 270 at a right-angled crossing
+    pub angle_spread: f64,
+}
+
+/// Junctions of `ways`: the dead ends and all points where the degree is
+/// not 2 or more than one way meets, ordered by position
+pub fn junction_table(segments: &[Segment], ways: &[Way]) -> Vec<JunctionSummary> {
+    let mut way_of = vec![usize::MAX; segments.len()];
+    for (w, way) in ways.iter().enumerate() {
+        for &s in &way.segment_indices {
+            way_of[s] = w;
+        }
+    }
+
+    // Bearings of the branches leaving each junction, and the ways there
+    let mut branches: FxHashMap<CoordHash, (geo_types::Coord, Vec<f64>, Vec<usize>)> = FxHashMap::default();
+    for (s, segment) in segments.iter().enumerate() {
+        let coords = &segment.geometry.0;
+        if coords.len() < 2 || way_of[s] == usize::MAX {
+            continue;
+        }
+        let n = coords.len();
+        for (hash, at, next) in [
+            (segment.start_node, coords[0], coords[1]),
+            (segment.end_node, coords[n - 1], coords[n - 2]),
+        ] {
+            let entry = branches.entry(hash).or_insert_with(|| (at, Vec::new(), Vec::new()));
+            entry.1.push(compute_bearing(&at, &next));
+            entry.2.push(way_of[s]);
+        }
+    }
+
+    let mut junctions: Vec<JunctionSummary> = branches.into_values()
+        .filter_map(|(at, mut bearings, mut ways)| {
+            ways.sort_unstable();
+            ways.dedup();
+            if bearings.len() == 2 && ways.len() == 1 {
+                return None;
+            }
+            Some(JunctionSummary {
+                lon: at.x,
+                lat: at.y,
+                degree: bearings.len(),
+                ways,
+                angle_spread: angle_spread(&mut bearings),
+            })
+        })
+        .collect();
+    junctions.sort_by(|a, b| a.lon.total_cmp(&b.lon).then(a.lat.total_cmp(&b.lat)));
+    junctions
+}
+
+/// 360 minus the largest gap between sorted bearings
+fn angle_spread(bearings: &mut [f64]) -> f64 {
+    if bearings.len() < 2 {
+        return 0.0;
+    }
+    bearings.sort_by(f64::total_cmp);
+    let wrap = bearings[0] + 360.0 - bearings[bearings.len() - 1];
+    let largest_gap = bearings.windows(2)
+        .map(|w| w[1] - w[0])
+        .fold(wrap, f64::max);
+    360.0 - largest_gap
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::junction_table;
+    use crate::Way;
+    use crate::test_support::segment;
+
+    #[test]
+    fn junction_table_lists_meeting_ways() {
+        let segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.002, 59.0)]),
+            segment(vec![(18.001, 59.0), (18.001, 59.001)]),
+        ];
+        let way = |i: usize| Way::new(vec![i], FxHashMap::default());
+        let junctions = junction_table(&segments, &[way(0), way(1), way(2)]);
+
+        let tee = junctions.iter().find(|j| j.degree == 3).unwrap();
+        assert_eq!(tee.ways, vec![0, 1, 2]);
+        assert!((tee.angle_spread - 180.0).abs() < 0.1);
+        assert_eq!(junctions.iter().filter(|j| j.degree == 1).count(), 3);
+    }
+}
@@ -0,0 +1,102 @@
+//! Closed ways that are not roundabouts
+//!
+//! Merging can close a way on itself: a loop at the end of a cul-de-sac, or
+//! a street running around a block. Some validators reject or misread such
+//! ways, above all when they carry `oneway` tags, which are ambiguous on a
+//! ring without `junction=roundabout`. This pass opens them into two ways
+//! at the vertex farthest from where the ring closes.
+
+use geo::HaversineDistance;
+use geo_types::Point;
+
+use crate::models::{Segment, Way};
+
+/// Split every closed way that is not a roundabout into two ways at the
+/// vertex farthest from its first node, cutting the segment there if the
+/// vertex is inside one
+///
+/// The pieces of cut segments are appended to `segments`.
+pub fn split_loops(ways: &mut Vec<Way>, segments: &mut Vec<Segment>) {
+    let mut out = Vec::with_capacity(ways.len());
+    for way in ways.drain(..) {
+        let closed = way.first_node(segments).is_some_and(|node| Some(node) == way.last_node(segments));
+        if !closed || is_roundabout(&way) {
+            out.push(way);
+            continue;
+        }
+        let Some((k, vertex)) = farthest_vertex(&way, segments) else {
+            out.push(way);
+            continue;
+        };
+
+        let mut oriented: Vec<(usize, bool)> = way.oriented().collect();
+        let (s, reversed) = oriented[k];
+        let n = segments[s].geometry.0.len();
+        // Number of segments before the cut, in way order
+        let cut = if vertex == n - 1 {
+            k + 1
+        } else if reversed {
+            // The head of the cut segment comes last when run through reversed
+            let tail = segments[s].split_at_vertex(n - 1 - vertex);
+            segments.push(tail);
+            oriented.insert(k, (segments.len() - 1, true));
+            k + 1
+        } else {
+            let tail = segments[s].split_at_vertex(vertex);
+            segments.push(tail);
+            oriented.insert(k + 1, (segments.len() - 1, false));
+            k + 1
+        };
+        let second = oriented.split_off(cut);
+        out.push(Way::from_oriented(oriented, way.tags.clone()));
+        out.push(Way::from_oriented(second, way.tags));
+    }
+    *ways = out;
+}
+
+/// `junction=roundabout` or `junction=circular`
+fn is_roundabout(way: &Way) -> bool {
+    matches!(way.tags.get("junction").map(String::as_str), Some("roundabout" | "circular"))
+}
+
+/// Segment (position in the way) and vertex (counted in way order) farthest
+/// from the first node of a way; `None` if no vertex is apart from it
+fn farthest_vertex(way: &Way, segments: &[Segment]) -> Option<(usize, usize)> {
+    let first = &segments[way.segment_indices[0]];
+    let origin = Point::from(if way.is_reversed(0) { *first.end_coord() } else { *first.start_coord() });
+    let mut best: Option<(f64, usize, usize)> = None;
+    for (k, (s, reversed)) in way.oriented().enumerate() {
+        let coords = &segments[s].geometry.0;
+        let n = coords.len();
+        for vertex in 1..n {
+            let coord = if reversed { coords[n - 1 - vertex] } else { coords[vertex] };
+            let distance = origin.haversine_distance(&Point::from(coord));
+            if distance > best.map_or(0.0, |b| b.0) {
+                best = Some((distance, k, vertex));
+            }
+        }
+    }
+    best.map(|(_, k, vertex)| (k, vertex))
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::LineString;
+    use rustc_hash::FxHashMap;
+
+    use super::split_loops;
+    use crate::{Segment, Way};
+
+    #[test]
+    fn split_loops_breaks_closed_ways() {
+        // A cul-de-sac loop digitised as one segment
+        let ring = LineString::from(vec![(18.0, 59.0), (18.001, 59.0), (18.002, 59.001), (18.001, 59.002), (18.0, 59.0)]);
+        let mut segments = vec![Segment::new(String::new(), ring)];
+        let mut ways = vec![Way::new(vec![0], FxHashMap::default())];
+
+        split_loops(&mut ways, &mut segments);
+        assert_eq!(ways.len(), 2);
+        assert_eq!(ways[0].line(&segments), LineString::from(vec![(18.0, 59.0), (18.001, 59.0), (18.002, 59.001), (18.001, 59.002)]));
+        assert_eq!(ways[1].line(&segments), LineString::from(vec![(18.001, 59.002), (18.0, 59.0)]));
+    }
+}
@@ -0,0 +1,424 @@
+pub mod graph;
+pub mod duplicates;
+pub mod junctions;
+pub mod loops;
+pub mod splitting;
+
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use crate::models::{Segment, Way, Junction, SimplifyMethod, UnnamedGroups, CoordHash, OnewayDirection};
+use crate::geometry::{compute_junction_angle, simplify_polygon};
+use crate::grouping::group_segments;
+use crate::tag_mapper::non_road::is_non_road;
+use crate::tag_mapper::reverse::reverse_tags;
+
+/// Global configuration constants - MUST match Python exactly
+pub const ANGLE_MARGIN: f64 = 45.0; // Maximum turn angle for merging (degrees)
+pub const SIMPLIFY_FACTOR: f64 = 0.2; // Douglas-Peucker epsilon in meters
+
+/// Main entry point for network simplification
+/// 
+/// Port of simplify_network() from Python - matches Python behavior exactly,
+/// except that segments that are not oneways are also chained when digitised
+/// against each other; the way records them as reversed (`Way::reversed`)
+/// and takes their direction-dependent tags turned around
+/// (`tag_mapper::reverse`); and that the junction angles are measured on
+/// the geometry before Douglas-Peucker (`Segment::end_neighbours`).
+pub fn simplify_network(
+    segments: &mut [Segment],
+    method: SimplifyMethod,
+    unnamed: UnnamedGroups,
+) -> Vec<Way> {
+    // 1. Simplify segment geometries (Douglas-Peucker) - matches Python line 1726-1730
+    // NOTE: Python does NOT recompute start/end nodes after simplification.
+    // The original start/end nodes (set from pre-simplified coordinates) are preserved
+    // to ensure adjacent segments still share common node hashes.
+    if SIMPLIFY_FACTOR > 0.0 {
+        for segment in segments.iter_mut() {
+            if segment.end_neighbours.is_none() {
+                segment.end_neighbours = Some((*segment.second_coord(), *segment.penultimate_coord()));
+            }
+            let simplified = simplify_polygon(&segment.geometry.0, SIMPLIFY_FACTOR);
+            if simplified.len() >= 2 {
+                segment.geometry = geo_types::LineString::from(simplified);
+                // KEEP original start_node and end_node - don't recompute from simplified geometry!
+                // Recomputing would cause coordinate mismatches with adjacent segments.
+            }
+        }
+    }
+    
+    // 2. Group segments - matches Python line 1769-1793
+    let mut groups = group_segments(segments, method, unnamed);
+
+    // Non-road ways (cable cars, funiculars) are never merged
+    let non_road: Vec<usize> = (0..segments.len()).filter(|&i| is_non_road(&segments[i])).collect();
+    if !non_road.is_empty() && method != SimplifyMethod::Segment {
+        for group in groups.values_mut() {
+            group.retain(|&i| !is_non_road(&segments[i]));
+        }
+    }
+    
+    // 3. Build junction index - matches Python line 1735-1752
+    let junctions = build_junctions(segments);
+    
+    // 4. Merge based on method - matches Python line 1797-1803
+    let mut ways = match method {
+        SimplifyMethod::Recursive => {
+            simplify_recursive(segments, &groups, &junctions)
+        }
+        SimplifyMethod::Route | SimplifyMethod::Refname | SimplifyMethod::Linear
+        | SimplifyMethod::Municipality | SimplifyMethod::Rlid => {
+            // NOTE: Python's linear algorithm (simplify_network_linear) is used for 
+            // both "route" and "refname" methods. It does NOT check oneway or group
+            // compatibility - only angle and tag equality.
+            simplify_linear(segments, &groups)
+        }
+        SimplifyMethod::Segment => return unmerged_ways(segments),
+    };
+    ways.extend(non_road.into_iter().map(|idx| Way::new(vec![idx], segments[idx].tags.clone())));
+    ways
+}
+
+/// One way per segment, with the segment's geometry and tags unchanged
+///
+/// Used for `SimplifyMethod::Segment` (after Douglas-Peucker) and on its
+/// own in fidelity mode.
+pub fn unmerged_ways(segments: &[Segment]) -> Vec<Way> {
+    segments.iter().enumerate()
+        .map(|(idx, seg)| Way::new(vec![idx], seg.tags.clone()))
+        .collect()
+}
+
+/// Number of segment ends at each junction (a self-loop counts twice);
+/// junctions of degree 1 are dead ends
+pub fn junction_degrees(segments: &[Segment]) -> FxHashMap<CoordHash, usize> {
+    let mut degrees: FxHashMap<CoordHash, usize> = FxHashMap::default();
+    for segment in segments {
+        *degrees.entry(segment.start_node).or_default() += 1;
+        *degrees.entry(segment.end_node).or_default() += 1;
+    }
+    degrees
+}
+
+/// Build junction index from segments
+/// Port of Python junction building (lines 1735-1752)
+fn build_junctions(segments: &[Segment]) -> FxHashMap<CoordHash, Junction> {
+    let mut junctions: FxHashMap<CoordHash, Junction> = FxHashMap::default();
+    
+    for (idx, segment) in segments.iter().enumerate() {
+        // Start node
+        junctions.entry(segment.start_node)
+            .or_default()
+            .segment_indices.push(idx);
+        
+        // End node
+        junctions.entry(segment.end_node)
+            .or_default()
+            .segment_indices.push(idx);
+    }
+    
+    junctions
+}
+
+/// Linear simplification algorithm
+/// 
+/// EXACT port of simplify_network_linear() from Python lines 1626-1711
+/// 
+/// Key behaviors:
+/// - NO oneway check (unlike recursive algorithm)
+/// - NO group compatibility check (segments are pre-grouped)
+/// - Only checks angle and tag equality
+fn simplify_linear(
+    segments: &[Segment],
+    groups: &FxHashMap<String, Vec<usize>>,
+) -> Vec<Way> {
+    // Groups are independent, so each one is merged on its own rayon task.
+    // Sorting by group id keeps the concatenated way order deterministic.
+    let mut ordered_groups: Vec<(&String, &Vec<usize>)> = groups.iter().collect();
+    ordered_groups.sort_by(|a, b| a.0.cmp(b.0));
+
+    ordered_groups
+        .par_iter()
+        .map(|(_group_id, segment_indices)| simplify_linear_group(segments, segment_indices))
+        .collect::<Vec<Vec<Way>>>()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Merge the segments of a single group into ways (body of the Python per-group loop)
+fn simplify_linear_group(
+    segments: &[Segment],
+    segment_indices: &[usize],
+) -> Vec<Way> {
+    let mut ways: Vec<Way> = Vec::new();
+
+    if segment_indices.is_empty() {
+        return ways;
+    }
+
+    // Use BTreeSet for deterministic ordering and O(log n) removal
+    // Python dicts preserve insertion order (3.7+), so we maintain original order
+    let mut remaining: std::collections::BTreeSet<usize> = segment_indices.iter().cloned().collect();
+    
+    // Build O(1) lookup dicts for this group - matches Python lines 1638-1643
+    let mut by_start: FxHashMap<CoordHash, Vec<usize>> = FxHashMap::default();
+    let mut by_end: FxHashMap<CoordHash, Vec<usize>> = FxHashMap::default();
+    
+    for &idx in segment_indices {
+        let seg = &segments[idx];
+        by_start.entry(seg.start_node).or_default().push(idx);
+        by_end.entry(seg.end_node).or_default().push(idx);
+    }
+    
+    // Repeat building sequences of longer ways until all segments have been used
+    // Matches Python line 1646
+    while !remaining.is_empty() {
+        // Get first available segment (deterministic) - matches Python line 1648
+        let start_idx = *remaining.iter().next().unwrap();
+        remaining.remove(&start_idx);
+        
+        // Remove from lookup dicts - matches Python lines 1651-1652
+        let seg = &segments[start_idx];
+        remove_from_lookup(&mut by_start, seg.start_node, start_idx);
+        remove_from_lookup(&mut by_end, seg.end_node, start_idx);
+        
+        // (segment, reversed) in way order
+        let mut way = vec![(start_idx, false)];
+        let mut first_node = seg.start_node;
+        let mut last_node = seg.end_node;
+        
+        // Build way forward - O(1) lookup - matches Python lines 1659-1675
+        let mut found = true;
+        while found {
+            found = false;
+            // Get candidates from by_start using last_node, then the
+            // undirected segments digitised the other way from by_end
+            let candidates = oriented_candidates(segments, &by_start, &by_end, last_node);
+
+            for (candidate_idx, reversed) in candidates {
+                if !remaining.contains(&candidate_idx) {
+                    continue;
+                }
+                
+                let candidate = &segments[candidate_idx];
+                
+                // NOTE: Python does NOT check group compatibility or oneway here!
+                // It only checks angle (line 1667-1668)
+                
+                // Check angle - matches Python line 1668
+                let last_seg = &segments[way.last().unwrap().0];
+                let angle = compute_junction_angle(last_seg, candidate);
+                if angle.abs() >= ANGLE_MARGIN {
+                    continue;
+                }
+                
+                // Found valid continuation
+                last_node = if reversed { candidate.start_node } else { candidate.end_node };
+                way.push((candidate_idx, reversed));
+                remaining.remove(&candidate_idx);
+                remove_from_lookup(&mut by_start, candidate.start_node, candidate_idx);
+                remove_from_lookup(&mut by_end, candidate.end_node, candidate_idx);
+                found = true;
+                break;
+            }
+        }
+        
+        // Build way backward - O(1) lookup - matches Python lines 1677-1693
+        let mut found = true;
+        while found {
+            found = false;
+            // Get candidates from by_end using first_node, then the
+            // undirected segments digitised the other way from by_start
+            let candidates = oriented_candidates(segments, &by_end, &by_start, first_node);
+
+            for (candidate_idx, reversed) in candidates {
+                if !remaining.contains(&candidate_idx) {
+                    continue;
+                }
+                
+                let candidate = &segments[candidate_idx];
+                
+                // NOTE: Python does NOT check group compatibility or oneway here!
+                // It only checks angle (line 1685-1686)
+                
+                // Check angle (note: reversed order for backward extension)
+                let first_seg = &segments[way[0].0];
+                let angle = compute_junction_angle(candidate, first_seg);
+                if angle.abs() >= ANGLE_MARGIN {
+                    continue;
+                }
+                
+                // Found valid continuation
+                first_node = if reversed { candidate.end_node } else { candidate.start_node };
+                way.insert(0, (candidate_idx, reversed));
+                remaining.remove(&candidate_idx);
+                remove_from_lookup(&mut by_start, candidate.start_node, candidate_idx);
+                remove_from_lookup(&mut by_end, candidate.end_node, candidate_idx);
+                found = true;
+                break;
+            }
+        }
+        
+        // MATCH PYTHON: Do NOT split at junctions. 
+        // Python does not split ways just because they cross an intersection.
+        // Only split if tags change.
+
+        let mut current_way = vec![way[0]];
+        let mut current_tags = way_tags(&segments[way[0].0], way[0].1);
+
+        // Reversed segments are compared by their tags turned to the way
+        for &(seg_idx, reversed) in &way[1..] {
+            let tags = way_tags(&segments[seg_idx], reversed);
+            if tags == current_tags {
+                current_way.push((seg_idx, reversed));
+            } else {
+                ways.push(Way::from_oriented(current_way, current_tags));
+                current_way = vec![(seg_idx, reversed)];
+                current_tags = tags;
+            }
+        }
+
+        if !current_way.is_empty() {
+            ways.push(Way::from_oriented(current_way, current_tags));
+        }
+    }
+    
+    ways
+}
+
+/// Segments continuing a way at `node`: those of `along` (digitised in the
+/// way's direction) first, then the reversible ones of `against`, which the
+/// way runs through reversed
+fn oriented_candidates(
+    segments: &[Segment],
+    along: &FxHashMap<CoordHash, Vec<usize>>,
+    against: &FxHashMap<CoordHash, Vec<usize>>,
+    node: CoordHash,
+) -> Vec<(usize, bool)> {
+    let along = along.get(&node).into_iter().flatten().map(|&idx| (idx, false));
+    let against = against.get(&node).into_iter().flatten()
+        .filter(|&&idx| is_reversible(&segments[idx]))
+        .map(|&idx| (idx, true));
+    along.chain(against).collect()
+}
+
+/// Whether a segment can be run through against its digitised direction:
+/// not a oneway, and its tags can be turned around (`reverse_tags()`)
+fn is_reversible(segment: &Segment) -> bool {
+    segment.oneway_direction == OnewayDirection::None && reverse_tags(&segment.tags).is_some()
+}
+
+/// Tags of a segment in the direction the way runs through it
+fn way_tags(segment: &Segment, reversed: bool) -> FxHashMap<String, String> {
+    match reversed {
+        true => reverse_tags(&segment.tags).unwrap_or_else(|| segment.tags.clone()),
+        false => segment.tags.clone(),
+    }
+}
+
+/// Remove a segment index from lookup
+fn remove_from_lookup(
+    lookup: &mut FxHashMap<CoordHash, Vec<usize>>,
+    node: CoordHash,
+    idx: usize,
+) {
+    if let Some(vec) = lookup.get_mut(&node) {
+        vec.retain(|&x| x != idx);
+        if vec.is_empty() {
+            lookup.remove(&node);
+        }
+    }
+}
+
+/// Recursive simplification algorithm
+/// 
+/// Port of simplify_network_recursive() from Python
+/// 
+/// NOTE: Python's recursive algorithm checks oneway in connected_way()
+/// but we use the linear algorithm for "refname" which is the default.
+fn simplify_recursive(
+    segments: &[Segment],
+    groups: &FxHashMap<String, Vec<usize>>,
+    _junctions: &FxHashMap<CoordHash, Junction>,
+) -> Vec<Way> {
+    let mut ways: Vec<Way> = Vec::new();
+    
+    for (_group_id, segment_indices) in groups.iter() {
+        let mut remaining: Vec<usize> = segment_indices.clone();
+        
+        while !remaining.is_empty() {
+            let start_idx = remaining[0];
+            
+            // Build sequence forward using recursive search
+            // For now, use linear approach (can be enhanced with true recursive search)
+            // TODO: Implement true recursive search matching Python's connected_way()
+            let sequence = vec![start_idx];
+            
+            // Remove used segments
+            for idx in &sequence {
+                if let Some(pos) = remaining.iter().position(|&x| x == *idx) {
+                    remaining.remove(pos);
+                }
+            }
+            
+            if !sequence.is_empty() {
+                let first_seg = &segments[sequence[0]];
+                ways.push(Way::new(sequence, first_seg.tags.clone()));
+            }
+        }
+    }
+    
+    ways
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::LineString;
+
+    use crate::{simplify_network, SimplifyMethod, UnnamedGroups};
+    use crate::test_support::segment;
+
+    #[test]
+    fn reversed_segments_merge_with_their_sides_turned() {
+        let segment = |coords: Vec<(f64, f64)>, noise_barrier: &str| {
+            let mut segment = segment(coords);
+            segment.tags.insert("highway".to_string(), "residential".to_string());
+            segment.tags.insert("noise_barrier".to_string(), noise_barrier.to_string());
+            segment
+        };
+        // Digitised head to head, with the barrier on the same side of the road
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)], "left"),
+            segment(vec![(18.002, 59.0), (18.001, 59.0)], "right"),
+        ];
+        let ways = simplify_network(&mut segments, SimplifyMethod::Refname, UnnamedGroups::Class);
+        assert_eq!(ways.len(), 1);
+        assert_eq!(ways[0].tags["noise_barrier"], "left");
+
+        // Barriers on opposite sides of the road are not merged
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)], "left"),
+            segment(vec![(18.002, 59.0), (18.001, 59.0)], "left"),
+        ];
+        let ways = simplify_network(&mut segments, SimplifyMethod::Refname, UnnamedGroups::Class);
+        assert_eq!(ways.len(), 2);
+    }
+
+    #[test]
+    fn simplify_network_merges_head_to_head_segments() {
+        let segment = |coords: Vec<(f64, f64)>| {
+            let mut segment = segment(coords);
+            segment.tags.insert("highway".to_string(), "residential".to_string());
+            segment
+        };
+        // Digitised head to head
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)]),
+            segment(vec![(18.002, 59.0), (18.001, 59.0)]),
+        ];
+        let ways = simplify_network(&mut segments, SimplifyMethod::Refname, UnnamedGroups::Class);
+        assert_eq!(ways.len(), 1);
+        assert_eq!(ways[0].line(&segments), LineString::from(vec![(18.0, 59.0), (18.001, 59.0), (18.002, 59.0)]));
+    }
+}
@@ -0,0 +1,126 @@
+//! Where merged ways end
+//!
+//! The merging ends a way only where its tags change, so a way always ends
+//! where the highway class changes or a bridge or tunnel begins, and runs
+//! on through junctions and across municipal borders, as in the Python
+//! script. Consumers that want one way per street block (QA, conflation,
+//! some routing imports) or per municipality split the merged ways further
+//! with this pass.
+
+use rustc_hash::FxHashMap;
+
+use crate::models::{CoordHash, Segment, Way};
+use crate::schema::Field;
+
+/// Extra points where merged ways are split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaySplitting {
+    junctions: bool,
+    municipalities: bool,
+    driveways: bool,
+}
+
+impl Default for WaySplitting {
+    fn default() -> Self {
+        WaySplitting { junctions: false, municipalities: false, driveways: true }
+    }
+}
+
+impl WaySplitting {
+    /// Split wherever three or more segment ends meet (default: false)
+    pub fn junctions(mut self, enabled: bool) -> Self {
+        self.junctions = enabled;
+        self
+    }
+
+    /// Split where the municipality (Kommunnr) changes (default: false)
+    pub fn municipalities(mut self, enabled: bool) -> Self {
+        self.municipalities = enabled;
+        self
+    }
+
+    /// Count private driveways (`highway=service`, väghållare 3) as
+    /// branches of a junction (default: true); without them, a street is
+    /// not split at every property entrance
+    pub fn driveways(mut self, enabled: bool) -> Self {
+        self.driveways = enabled;
+        self
+    }
+
+    /// Split the ways into runs of whole segments at the chosen points
+    pub fn apply(&self, ways: &mut Vec<Way>, segments: &[Segment]) {
+        if !self.junctions && !self.municipalities {
+            return;
+        }
+        let mut degrees: FxHashMap<CoordHash, usize> = FxHashMap::default();
+        if self.junctions {
+            for segment in segments.iter().filter(|s| self.driveways || !is_driveway(s)) {
+                *degrees.entry(segment.start_node).or_default() += 1;
+                *degrees.entry(segment.end_node).or_default() += 1;
+            }
+        }
+
+        let mut out = Vec::with_capacity(ways.len());
+        for way in ways.drain(..) {
+            let mut current: Vec<(usize, bool)> = Vec::new();
+            for (k, (s, reversed)) in way.oriented().enumerate() {
+                if k > 0 {
+                    let node = way.segment_ends(k, segments).0;
+                    let junction = self.junctions && degrees.get(&node).copied().unwrap_or(0) >= 3;
+                    let border = self.municipalities
+                        && municipality(&segments[way.segment_indices[k - 1]]) != municipality(&segments[s]);
+                    if junction || border {
+                        out.push(Way::from_oriented(std::mem::take(&mut current), way.tags.clone()));
+                    }
+                }
+                current.push((s, reversed));
+            }
+            out.push(Way::from_oriented(current, way.tags));
+        }
+        *ways = out;
+    }
+}
+
+/// Municipality code of a segment, if it has one
+fn municipality(segment: &Segment) -> Option<i64> {
+    segment.prop(Field::Municipality).and_then(|v| v.as_i64())
+}
+
+/// `highway=service` of a private road owner (väghållare 3): a driveway
+/// or the access road of a property
+fn is_driveway(segment: &Segment) -> bool {
+    segment.tags.get("highway").map(String::as_str) == Some("service")
+        && segment.prop(Field::RoadOwner).and_then(|v| v.as_i64()) == Some(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WaySplitting;
+    use crate::{PropertyValue, Way};
+    use crate::test_support::segment;
+
+    #[test]
+    fn ways_split_at_branches() {
+        let segment = |coords: Vec<(f64, f64)>, highway: &str| {
+            let mut segment = segment(coords);
+            segment.tags.insert("highway".to_string(), highway.to_string());
+            segment
+        };
+        // A street with a driveway branching off in the middle
+        let mut segments = vec![
+            segment(vec![(18.0, 59.0), (18.001, 59.0)], "residential"),
+            segment(vec![(18.001, 59.0), (18.002, 59.0)], "residential"),
+            segment(vec![(18.001, 59.0), (18.001, 59.001)], "service"),
+        ];
+        segments[2].properties.insert("Vagha_6".to_string(), PropertyValue::Integer(3));
+        let street = Way::new(vec![0, 1], segments[0].tags.clone());
+
+        let mut ways = vec![street.clone()];
+        WaySplitting::default().junctions(true).apply(&mut ways, &segments);
+        assert_eq!(ways.len(), 2);
+
+        let mut ways = vec![street];
+        WaySplitting::default().junctions(true).driveways(false).apply(&mut ways, &segments);
+        assert_eq!(ways.len(), 1);
+    }
+}
@@ -0,0 +1,547 @@
+//! WKB input
+//!
+//! Minimal WKB reader for the LineString/MultiLineString geometries found in
+//! NVDB extracts, and construction of `Segment`s from WKB plus properties.
+//! Polygons are read too, for clipping boundaries (`parse_polygon_wkb()`).
+//! Curves (CircularString, CompoundCurve, MultiCurve and CurvePolygon, found
+//! in some GDB exports) are densified to line segments.
+//! Unreadable geometries fail with a `WkbError` naming the problem, which
+//! the bindings report per input row.
+
+use geo_types::{Coord, LineString, MultiPolygon, Polygon};
+use rustc_hash::FxHashMap;
+
+use crate::models::{PropertyValue, Segment};
+
+/// Default largest distance between an arc and its densified line, in metres
+pub const DEFAULT_ARC_TOLERANCE_M: f64 = 0.1;
+
+/// Metres per degree, to express the arc tolerance in WGS84 coordinates
+const METRES_PER_DEGREE: f64 = 111_320.0;
+
+/// Most line segments an arc is densified to, whatever the tolerance
+const MAX_ARC_SEGMENTS: usize = 1024;
+
+/// How WKB geometries are turned into segment geometries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WkbOptions {
+    /// Round coordinates to 7 decimal places (off in fidelity mode)
+    pub round_coords: bool,
+    /// Largest distance between a curve and the line segments replacing it,
+    /// in metres
+    pub arc_tolerance_m: f64,
+    /// Keep the Z values of the segment ends as `Segment::elevations` (for
+    /// `Pipeline::incline()`)
+    pub keep_elevation: bool,
+}
+
+impl Default for WkbOptions {
+    fn default() -> Self {
+        Self { round_coords: true, arc_tolerance_m: DEFAULT_ARC_TOLERANCE_M, keep_elevation: false }
+    }
+}
+
+/// Build a segment from a WKB geometry and its NVDB properties
+///
+/// Coordinates are rounded to 7 decimal places using Banker's Rounding, and
+/// the optional global node columns (`global_start_node_id`, ...) are picked
+/// up from the properties. Fails with the reason if the WKB cannot be parsed.
+pub fn segment_from_wkb(wkb: &[u8], properties: FxHashMap<String, PropertyValue>) -> Result<Segment, WkbError> {
+    segment_from_wkb_with(wkb, properties, &WkbOptions::default())
+}
+
+/// Like `segment_from_wkb()`, keeping the coordinates exactly as stored
+/// (fidelity mode, see `Pipeline::fidelity()`)
+pub fn segment_from_wkb_exact(wkb: &[u8], properties: FxHashMap<String, PropertyValue>) -> Result<Segment, WkbError> {
+    segment_from_wkb_with(wkb, properties, &WkbOptions { round_coords: false, ..WkbOptions::default() })
+}
+
+/// Build a segment with explicit rounding and curve densification settings
+pub fn segment_from_wkb_with(
+    wkb: &[u8],
+    properties: FxHashMap<String, PropertyValue>,
+    options: &WkbOptions,
+) -> Result<Segment, WkbError> {
+    let mut segment = segment_from_line(parse_wkb_densified(wkb, options.arc_tolerance_m)?, properties, options);
+    if options.keep_elevation {
+        if let Some((start, end)) = end_elevations(wkb) {
+            segment.elevations = vec![(segment.start_node, start), (segment.end_node, end)];
+        }
+    }
+    Ok(segment)
+}
+
+/// Z values of the first and last point of a LineString Z (or of the first
+/// non-empty part of a MultiLineString Z); `None` for 2D and curved
+/// geometries
+pub fn end_elevations(wkb: &[u8]) -> Option<(f64, f64)> {
+    let mut offset = 0;
+    let (base_type, little_endian, coord_size) = read_geometry_header(wkb, &mut offset).ok()?;
+    let (base_type, little_endian, coord_size) = match base_type {
+        5 => {
+            let num_parts = read_u32(wkb, &mut offset, little_endian).ok()?;
+            let mut part = None;
+            for _ in 0..num_parts {
+                let header = read_geometry_header(wkb, &mut offset).ok()?;
+                let num_points = read_u32(wkb, &mut offset.clone(), header.1).ok()?;
+                if num_points > 0 {
+                    part = Some(header);
+                    break;
+                }
+                offset += 4;
+            }
+            part?
+        }
+        _ => (base_type, little_endian, coord_size),
+    };
+    if base_type != 2 || !has_z(wkb) {
+        return None;
+    }
+    let num_points = read_u32(wkb, &mut offset, little_endian).ok()? as usize;
+    if num_points == 0 || wkb.len() < offset.checked_add(num_points.checked_mul(coord_size)?)? {
+        return None;
+    }
+    let z_at = |i: usize| read_f64(&wkb[offset + i * coord_size + 16..offset + i * coord_size + 24], little_endian);
+    let (start, end) = (z_at(0), z_at(num_points - 1));
+    (start.is_finite() && end.is_finite()).then_some((start, end))
+}
+
+/// Whether the outer geometry of a WKB has Z values (ISO or EWKB flag)
+fn has_z(wkb: &[u8]) -> bool {
+    let mut offset = 1;
+    match (wkb.first(), read_u32(wkb, &mut offset, wkb.first() == Some(&1))) {
+        (Some(_), Ok(geom_type)) => (geom_type & 0x80000000) != 0 || matches!((geom_type & 0x1FFFFFFF) / 1000, 1 | 3),
+        _ => false,
+    }
+}
+
+/// Build a segment from a parsed geometry (from WKB, `wkt` or coordinates
+/// handed over directly), rounding its coordinates if `options` ask for it
+pub fn segment_from_line(
+    mut geometry: LineString<f64>,
+    properties: FxHashMap<String, PropertyValue>,
+    options: &WkbOptions,
+) -> Segment {
+    if options.round_coords {
+        for coord in geometry.0.iter_mut() {
+            coord.x = round_ties_even(coord.x * 10_000_000.0) / 10_000_000.0;
+            coord.y = round_ties_even(coord.y * 10_000_000.0) / 10_000_000.0;
+        }
+    }
+    segment_with_properties(geometry, properties)
+}
+
+fn segment_with_properties(geometry: LineString<f64>, properties: FxHashMap<String, PropertyValue>) -> Segment {
+    let mut seg = Segment::new(String::new(), geometry);
+    seg.global_start_node_id = get_i64_property(&properties, "global_start_node_id");
+    seg.global_end_node_id = get_i64_property(&properties, "global_end_node_id");
+    seg.global_start_owned = get_bool_property(&properties, "global_start_owned").unwrap_or(false);
+    seg.global_end_owned = get_bool_property(&properties, "global_end_owned").unwrap_or(false);
+    seg.properties = properties;
+    seg
+}
+
+/// Why a WKB geometry could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WkbError {
+    /// The buffer ends before the `needed` bytes (of `len`)
+    Truncated { needed: usize, len: usize },
+    /// Byte order flag other than 0 (big endian) or 1 (little endian)
+    BadByteOrder(u8),
+    /// Geometry type other than a (multi) line or curve
+    UnsupportedType(u32),
+    /// No coordinates (empty LineString, or MultiLineString of empty parts)
+    Empty,
+    /// NaN or infinite coordinate
+    NonFinite,
+}
+
+impl std::fmt::Display for WkbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WkbError::Truncated { needed, len } => write!(f, "truncated WKB: {} bytes needed, {} available", needed, len),
+            WkbError::BadByteOrder(flag) => write!(f, "bad WKB byte order flag {}", flag),
+            WkbError::UnsupportedType(geom_type) => write!(f, "unsupported WKB geometry type {}", geom_type),
+            WkbError::Empty => write!(f, "empty geometry"),
+            WkbError::NonFinite => write!(f, "non-finite coordinate"),
+        }
+    }
+}
+
+impl std::error::Error for WkbError {}
+
+/// Parse WKB (Well-Known Binary) geometry
+/// Handles 2D, 3D (Z), and 4D (ZM) coordinate types, ISO and EWKB type codes
+///
+/// A MultiLineString yields its first non-empty part; each part may have its
+/// own byte order. Point counts are checked against the buffer length before
+/// anything is allocated, so corrupt input fails with an error instead of
+/// panicking or exhausting memory. Curves are densified with the default
+/// tolerance (see `parse_wkb_densified()`).
+pub fn parse_wkb(wkb: &[u8]) -> Result<LineString<f64>, WkbError> {
+    parse_wkb_densified(wkb, DEFAULT_ARC_TOLERANCE_M)
+}
+
+/// Parse WKB geometry, replacing arcs by line segments that stay within
+/// `arc_tolerance_m` metres of the arc
+///
+/// CircularStrings, CompoundCurves (of LineStrings and CircularStrings) and
+/// MultiCurves are read like LineStrings and MultiLineStrings.
+pub fn parse_wkb_densified(wkb: &[u8], arc_tolerance_m: f64) -> Result<LineString<f64>, WkbError> {
+    let tolerance = arc_tolerance_m.max(1e-6) / METRES_PER_DEGREE;
+    let mut offset = 0;
+    let (base_type, little_endian, coord_size) = read_geometry_header(wkb, &mut offset)?;
+    let coords = match base_type {
+        2 | 8 | 9 => read_curve(wkb, &mut offset, base_type, little_endian, coord_size, tolerance)?,
+        5 | 11 => {
+            let num_parts = read_u32(wkb, &mut offset, little_endian)?;
+            // Every part header takes bytes, so a bogus count runs into the
+            // end of the buffer
+            let mut coords = Vec::new();
+            for _ in 0..num_parts {
+                let (part_type, part_le, part_size) = read_geometry_header(wkb, &mut offset)?;
+                if !matches!(part_type, 2 | 8 | 9) {
+                    return Err(WkbError::UnsupportedType(part_type));
+                }
+                coords = read_curve(wkb, &mut offset, part_type, part_le, part_size, tolerance)?;
+                if !coords.is_empty() {
+                    break;
+                }
+            }
+            coords
+        }
+        _ => return Err(WkbError::UnsupportedType(base_type)),
+    };
+    if coords.is_empty() {
+        return Err(WkbError::Empty);
+    }
+    Ok(LineString::from(coords))
+}
+
+/// Read the body of a LineString, CircularString or CompoundCurve whose
+/// header has been read, densifying arcs to `tolerance` (in degrees)
+fn read_curve(
+    wkb: &[u8],
+    offset: &mut usize,
+    base_type: u32,
+    little_endian: bool,
+    coord_size: usize,
+    tolerance: f64,
+) -> Result<Vec<Coord>, WkbError> {
+    match base_type {
+        2 => Ok(read_points(wkb, offset, little_endian, coord_size)?.0),
+        8 => Ok(densify_circular_string(&read_points(wkb, offset, little_endian, coord_size)?.0, tolerance)),
+        9 => {
+            let num_parts = read_u32(wkb, offset, little_endian)?;
+            let mut coords: Vec<Coord> = Vec::new();
+            for _ in 0..num_parts {
+                let (part_type, part_le, part_size) = read_geometry_header(wkb, offset)?;
+                // Compound curves hold plain curves only, so this does not nest
+                if !matches!(part_type, 2 | 8) {
+                    return Err(WkbError::UnsupportedType(part_type));
+                }
+                let part = read_curve(wkb, offset, part_type, part_le, part_size, tolerance)?;
+                // Consecutive parts share their end and start point
+                let skip = usize::from(coords.last().is_some() && coords.last() == part.first());
+                coords.extend(part.into_iter().skip(skip));
+            }
+            Ok(coords)
+        }
+        _ => Err(WkbError::UnsupportedType(base_type)),
+    }
+}
+
+/// Line through the arcs of a CircularString (each arc runs through three
+/// points, consecutive arcs share an end point)
+fn densify_circular_string(points: &[Coord], tolerance: f64) -> Vec<Coord> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut coords = vec![points[0]];
+    let mut i = 0;
+    while i + 2 < points.len() {
+        densify_arc(points[i], points[i + 1], points[i + 2], tolerance, &mut coords);
+        i += 2;
+    }
+    // A trailing point that completes no arc is kept as a straight line
+    coords.extend_from_slice(&points[i + 1..]);
+    coords
+}
+
+/// Append the points after `a` of the arc from `a` through `b` to `c`
+fn densify_arc(a: Coord, b: Coord, c: Coord, tolerance: f64, coords: &mut Vec<Coord>) {
+    // Circle centre from the perpendicular bisectors
+    let d = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+    let scale = (b.x - a.x).abs().max((b.y - a.y).abs()).max((c.x - a.x).abs()).max((c.y - a.y).abs());
+    if d.abs() <= 1e-12 * scale * scale || !d.is_finite() {
+        // Collinear points (or a degenerate arc) are a straight line
+        coords.extend([b, c]);
+        return;
+    }
+    let sq = |p: Coord| p.x * p.x + p.y * p.y;
+    let centre = Coord {
+        x: (sq(a) * (b.y - c.y) + sq(b) * (c.y - a.y) + sq(c) * (a.y - b.y)) / d,
+        y: (sq(a) * (c.x - b.x) + sq(b) * (a.x - c.x) + sq(c) * (b.x - a.x)) / d,
+    };
+    let radius = ((a.x - centre.x).powi(2) + (a.y - centre.y).powi(2)).sqrt();
+    let angle = |p: Coord| (p.y - centre.y).atan2(p.x - centre.x);
+    let (start, through, end) = (angle(a), angle(b), angle(c));
+
+    // Sweep from a to c in the direction that passes b
+    let ccw = |from: f64, to: f64| (to - from).rem_euclid(2.0 * std::f64::consts::PI);
+    let sweep = if ccw(start, through) <= ccw(start, end) {
+        ccw(start, end)
+    } else {
+        ccw(start, end) - 2.0 * std::f64::consts::PI
+    };
+
+    // Largest step whose chord stays within the tolerance of the arc
+    let max_step = if tolerance < radius { 2.0 * (1.0 - tolerance / radius).acos() } else { std::f64::consts::PI };
+    let steps = ((sweep.abs() / max_step).ceil() as usize).clamp(1, MAX_ARC_SEGMENTS);
+    for k in 1..steps {
+        let t = start + sweep * k as f64 / steps as f64;
+        coords.push(Coord { x: centre.x + radius * t.cos(), y: centre.y + radius * t.sin() });
+    }
+    coords.push(c);
+}
+
+/// Round float to nearest integer, rounding half to even ("Banker's Rounding")
+/// Matches Python 3's round() function behavior
+fn round_ties_even(x: f64) -> f64 {
+    let fract = x.fract().abs();
+    if (fract - 0.5).abs() < f64::EPSILON {
+        // Exact half - round to even
+        let floor = x.floor();
+        if floor as i64 % 2 == 0 {
+            floor
+        } else {
+            x.ceil()
+        }
+    } else {
+        x.round()
+    }
+}
+
+/// Read a point count and the points that follow, dropping Z and M
+fn read_points(wkb: &[u8], offset: &mut usize, little_endian: bool, coord_size: usize) -> Result<LineString<f64>, WkbError> {
+    let num_points = read_u32(wkb, offset, little_endian)? as usize;
+    let needed = offset.saturating_add(num_points.saturating_mul(coord_size));
+    if wkb.len() < needed {
+        return Err(WkbError::Truncated { needed, len: wkb.len() });
+    }
+
+    let mut coords = Vec::with_capacity(num_points);
+    for i in 0..num_points {
+        let base = *offset + i * coord_size;
+        let x = read_f64(&wkb[base..base + 8], little_endian);
+        let y = read_f64(&wkb[base + 8..base + 16], little_endian);
+        if !x.is_finite() || !y.is_finite() {
+            return Err(WkbError::NonFinite);
+        }
+        coords.push(Coord { x, y });
+    }
+    *offset = needed;
+    Ok(LineString::from(coords))
+}
+
+/// Parse a Polygon or MultiPolygon WKB geometry (e.g. a clipping boundary)
+///
+/// CurvePolygons and MultiSurfaces are read too, their curved rings densified
+/// with the default tolerance. Z and M values are dropped; returns `None` for
+/// other geometry types or truncated input.
+pub fn parse_polygon_wkb(wkb: &[u8]) -> Option<MultiPolygon<f64>> {
+    let mut offset = 0;
+    let (base_type, little_endian, coord_size) = read_geometry_header(wkb, &mut offset).ok()?;
+    match base_type {
+        3 => Some(MultiPolygon::new(vec![read_polygon(wkb, &mut offset, little_endian, coord_size)?])),
+        10 => Some(MultiPolygon::new(vec![read_curve_polygon(wkb, &mut offset, little_endian)?])),
+        6 | 12 => {
+            let count = read_u32(wkb, &mut offset, little_endian).ok()?;
+            let mut polygons = Vec::new();
+            for _ in 0..count {
+                let (inner_type, inner_le, inner_size) = read_geometry_header(wkb, &mut offset).ok()?;
+                polygons.push(match inner_type {
+                    3 => read_polygon(wkb, &mut offset, inner_le, inner_size)?,
+                    10 => read_curve_polygon(wkb, &mut offset, inner_le)?,
+                    _ => return None,
+                });
+            }
+            Some(MultiPolygon::new(polygons))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a LineString or MultiLineString WKB geometry into all its lines
+/// (e.g. railways); returns `None` for other geometry types or truncated input
+pub fn parse_lines_wkb(wkb: &[u8]) -> Option<Vec<LineString<f64>>> {
+    let mut offset = 0;
+    let (base_type, little_endian, coord_size) = read_geometry_header(wkb, &mut offset).ok()?;
+    match base_type {
+        2 => Some(vec![read_points(wkb, &mut offset, little_endian, coord_size).ok()?]),
+        5 => {
+            let count = read_u32(wkb, &mut offset, little_endian).ok()?;
+            let mut lines = Vec::new();
+            for _ in 0..count {
+                let (inner_type, inner_le, inner_size) = read_geometry_header(wkb, &mut offset).ok()?;
+                if inner_type != 2 {
+                    return None;
+                }
+                lines.push(read_points(wkb, &mut offset, inner_le, inner_size).ok()?);
+            }
+            Some(lines)
+        }
+        _ => None,
+    }
+}
+
+/// Read the rings of a CurvePolygon, each a full curve geometry
+fn read_curve_polygon(wkb: &[u8], offset: &mut usize, little_endian: bool) -> Option<Polygon<f64>> {
+    let tolerance = DEFAULT_ARC_TOLERANCE_M / METRES_PER_DEGREE;
+    let num_rings = read_u32(wkb, offset, little_endian).ok()?;
+    let mut rings = Vec::new();
+    for _ in 0..num_rings {
+        let (ring_type, ring_le, ring_size) = read_geometry_header(wkb, offset).ok()?;
+        rings.push(LineString::from(read_curve(wkb, offset, ring_type, ring_le, ring_size, tolerance).ok()?));
+    }
+    let mut rings = rings.into_iter();
+    let exterior = rings.next()?;
+    Some(Polygon::new(exterior, rings.collect()))
+}
+
+/// Byte order, type and coordinate size of the geometry at `offset`,
+/// advancing past the header (and SRID, for EWKB)
+fn read_geometry_header(wkb: &[u8], offset: &mut usize) -> Result<(u32, bool, usize), WkbError> {
+    let byte_order = *wkb.get(*offset).ok_or(WkbError::Truncated { needed: *offset + 1, len: wkb.len() })?;
+    if byte_order > 1 {
+        return Err(WkbError::BadByteOrder(byte_order));
+    }
+    let little_endian = byte_order == 1;
+    *offset += 1;
+    let geom_type = read_u32(wkb, offset, little_endian)?;
+    let clean_geom_type = geom_type & 0x1FFFFFFF;
+    let has_z = (geom_type & 0x80000000) != 0 || matches!(clean_geom_type / 1000, 1 | 3);
+    let has_m = (geom_type & 0x40000000) != 0 || matches!(clean_geom_type / 1000, 2 | 3);
+    if (geom_type & 0x20000000) != 0 {
+        *offset += 4;
+    }
+    let coord_size = 16 + if has_z { 8 } else { 0 } + if has_m { 8 } else { 0 };
+    Ok((clean_geom_type % 1000, little_endian, coord_size))
+}
+
+fn read_polygon(wkb: &[u8], offset: &mut usize, little_endian: bool, coord_size: usize) -> Option<Polygon<f64>> {
+    let num_rings = read_u32(wkb, offset, little_endian).ok()?;
+    let mut rings = Vec::new();
+    for _ in 0..num_rings {
+        let num_points = read_u32(wkb, offset, little_endian).ok()? as usize;
+        let end = offset.checked_add(num_points.checked_mul(coord_size)?)?;
+        if wkb.len() < end {
+            return None;
+        }
+        let coords: Vec<Coord> = (0..num_points)
+            .map(|i| {
+                let base = *offset + i * coord_size;
+                Coord {
+                    x: read_f64(&wkb[base..base + 8], little_endian),
+                    y: read_f64(&wkb[base + 8..base + 16], little_endian),
+                }
+            })
+            .collect();
+        *offset = end;
+        rings.push(LineString::from(coords));
+    }
+    let mut rings = rings.into_iter();
+    let exterior = rings.next()?;
+    Some(Polygon::new(exterior, rings.collect()))
+}
+
+fn read_u32(wkb: &[u8], offset: &mut usize, little_endian: bool) -> Result<u32, WkbError> {
+    let bytes: [u8; 4] = wkb
+        .get(*offset..*offset + 4)
+        .and_then(|b| b.try_into().ok())
+        .ok_or(WkbError::Truncated { needed: *offset + 4, len: wkb.len() })?;
+    *offset += 4;
+    Ok(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+}
+
+fn read_f64(bytes: &[u8], little_endian: bool) -> f64 {
+    let arr: [u8; 8] = [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]];
+    if little_endian {
+        f64::from_le_bytes(arr)
+    } else {
+        f64::from_be_bytes(arr)
+    }
+}
+
+/// Read an integer-like property (global node IDs are stored as doubles in R)
+pub fn get_i64_property(props: &FxHashMap<String, PropertyValue>, key: &str) -> Option<i64> {
+    props.get(key).and_then(|value| match value {
+        PropertyValue::Integer(i) => Some(*i),
+        PropertyValue::Float(f) => Some(*f as i64),
+        PropertyValue::String(s) => s.parse::<i64>().ok(),
+        PropertyValue::Boolean(b) => Some(if *b { 1 } else { 0 }),
+        PropertyValue::Array(_) | PropertyValue::Null => None,
+    })
+}
+
+/// Read a boolean-like property (logical, 0/1 or "true"/"false" strings)
+pub fn get_bool_property(props: &FxHashMap<String, PropertyValue>, key: &str) -> Option<bool> {
+    props.get(key).and_then(|value| match value {
+        PropertyValue::Boolean(b) => Some(*b),
+        PropertyValue::Integer(i) => Some(*i != 0),
+        PropertyValue::Float(f) => Some(*f != 0.0),
+        PropertyValue::String(s) => match s.to_lowercase().as_str() {
+            "1" | "true" | "t" | "yes" => Some(true),
+            "0" | "false" | "f" | "no" => Some(false),
+            _ => None,
+        },
+        PropertyValue::Array(_) | PropertyValue::Null => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WkbError, end_elevations, parse_wkb, parse_wkb_densified};
+
+    #[test]
+    fn parse_wkb_reports_malformed_input() {
+        // Big-endian LineString of two points
+        let mut wkb = vec![0, 0, 0, 0, 2, 0, 0, 0, 2];
+        for v in [18.0f64, 59.0, 18.1, 59.1] {
+            wkb.extend_from_slice(&v.to_be_bytes());
+        }
+        assert_eq!(parse_wkb(&wkb).unwrap().0.len(), 2);
+
+        assert_eq!(parse_wkb(&wkb[..20]), Err(WkbError::Truncated { needed: 41, len: 20 }));
+        assert_eq!(parse_wkb(&[7, 2, 0, 0, 0]), Err(WkbError::BadByteOrder(7)));
+        assert_eq!(parse_wkb(&[1, 1, 0, 0, 0]), Err(WkbError::UnsupportedType(1)));
+        assert_eq!(parse_wkb(&[1, 2, 0, 0, 0, 0, 0, 0, 0]), Err(WkbError::Empty));
+        // Four billion points claimed in a 9-byte buffer
+        assert!(matches!(parse_wkb(&[1, 2, 0, 0, 0, 255, 255, 255, 255]), Err(WkbError::Truncated { .. })));
+    }
+
+    #[test]
+    fn circular_strings_are_densified() {
+        // CircularString: a half circle of radius 0.001° through (0, 0.001)
+        let mut wkb = vec![1, 8, 0, 0, 0, 3, 0, 0, 0];
+        for v in [-0.001f64, 0.0, 0.0, 0.001, 0.001, 0.0] {
+            wkb.extend_from_slice(&v.to_le_bytes());
+        }
+        let coarse = parse_wkb_densified(&wkb, 1.0).unwrap();
+        let fine = parse_wkb_densified(&wkb, 0.01).unwrap();
+        assert!(coarse.0.len() > 3 && fine.0.len() > coarse.0.len());
+        assert_eq!((coarse.0[0].x, coarse.0.last().unwrap().x), (-0.001, 0.001));
+        // Every vertex lies on the circle
+        assert!(fine.0.iter().all(|c| ((c.x * c.x + c.y * c.y).sqrt() - 0.001).abs() < 1e-12));
+    }
+
+    #[test]
+    fn end_elevations_of_linestring_z() {
+        // Little-endian ISO LineString Z (type 1002) of two points
+        let mut wkb = vec![1, 0xEA, 3, 0, 0, 2, 0, 0, 0];
+        for v in [18.0f64, 59.0, 12.5, 18.1, 59.1, 20.0] {
+            wkb.extend_from_slice(&v.to_le_bytes());
+        }
+        assert_eq!(end_elevations(&wkb), Some((12.5, 20.0)));
+    }
+}
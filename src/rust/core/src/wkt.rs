@@ -0,0 +1,220 @@
+//! WKT input
+//!
+//! Small reader for LineString and MultiLineString WKT (and PostGIS EWKT with
+//! an `SRID=...;` prefix), for R workflows that produce text geometries more
+//! readily than WKB. Geometries are read like their WKB counterparts (see
+//! `wkb`): Z and M values are dropped and a MultiLineString yields its first
+//! non-empty part.
+
+use geo_types::{Coord, LineString};
+use rustc_hash::FxHashMap;
+
+use crate::models::{PropertyValue, Segment};
+use crate::wkb::{segment_from_line, WkbOptions};
+
+/// Why a WKT geometry could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WktError {
+    /// Unexpected text at byte `position`, where `expected` should be
+    Syntax { position: usize, expected: &'static str },
+    /// Geometry type other than LINESTRING or MULTILINESTRING
+    UnsupportedType(String),
+    /// No coordinates (`EMPTY`, or a MultiLineString of empty parts)
+    Empty,
+    /// NaN or infinite coordinate
+    NonFinite,
+}
+
+impl std::fmt::Display for WktError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WktError::Syntax { position, expected } => write!(f, "invalid WKT at character {}: expected {}", position + 1, expected),
+            WktError::UnsupportedType(name) => write!(f, "unsupported WKT geometry type {}", name),
+            WktError::Empty => write!(f, "empty geometry"),
+            WktError::NonFinite => write!(f, "non-finite coordinate"),
+        }
+    }
+}
+
+impl std::error::Error for WktError {}
+
+/// Build a segment from a WKT geometry and its NVDB properties, like
+/// `wkb::segment_from_wkb_with()`
+pub fn segment_from_wkt(
+    wkt: &str,
+    properties: FxHashMap<String, PropertyValue>,
+    options: &WkbOptions,
+) -> Result<Segment, WktError> {
+    Ok(segment_from_line(parse_wkt(wkt)?, properties, options))
+}
+
+/// Parse a LINESTRING or MULTILINESTRING, with optional Z, M or ZM
+pub fn parse_wkt(wkt: &str) -> Result<LineString<f64>, WktError> {
+    let mut parser = Parser { text: wkt.as_bytes(), pos: 0 };
+
+    // EWKT prefix: SRID=3006;
+    if parser.keyword().eq_ignore_ascii_case("SRID") {
+        parser.expect(b'=', "'='")?;
+        parser.number()?;
+        parser.expect(b';', "';'")?;
+    } else {
+        parser.pos = 0;
+    }
+
+    let geometry_type = parser.keyword().to_ascii_uppercase();
+    let multi = match geometry_type.as_str() {
+        "LINESTRING" => false,
+        "MULTILINESTRING" => true,
+        "" => return Err(parser.error("a geometry type")),
+        _ => return Err(WktError::UnsupportedType(geometry_type)),
+    };
+
+    let mut word_start = parser.pos;
+    let mut word = parser.keyword().to_ascii_uppercase();
+    if matches!(word.as_str(), "Z" | "M" | "ZM") {
+        word_start = parser.pos;
+        word = parser.keyword().to_ascii_uppercase();
+    }
+    let coords = match word.as_str() {
+        "EMPTY" => Vec::new(),
+        "" if multi => {
+            parser.expect(b'(', "'('")?;
+            let mut coords = Vec::new();
+            loop {
+                let part = parser.points()?;
+                if coords.is_empty() {
+                    coords = part;
+                }
+                if !parser.next_if(b',') {
+                    break;
+                }
+            }
+            parser.expect(b')', "')'")?;
+            coords
+        }
+        "" => parser.points()?,
+        _ => {
+            parser.pos = word_start;
+            return Err(parser.error("'(' or EMPTY"));
+        }
+    };
+    parser.skip_whitespace();
+    if parser.pos < parser.text.len() {
+        return Err(parser.error("end of geometry"));
+    }
+    if coords.is_empty() {
+        return Err(WktError::Empty);
+    }
+    Ok(LineString::from(coords))
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> WktError {
+        WktError::Syntax { position: self.pos, expected }
+    }
+
+    /// Consume `c` if it is the next character
+    fn next_if(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.text.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8, expected: &'static str) -> Result<(), WktError> {
+        if self.next_if(c) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    /// Next run of letters (empty if none)
+    fn keyword(&mut self) -> String {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.text.get(self.pos).is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.text[start..self.pos]).into_owned()
+    }
+
+    fn number(&mut self) -> Result<f64, WktError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.text.get(self.pos).is_some_and(|c| c.is_ascii_digit() || matches!(c, b'+' | b'-' | b'.' | b'e' | b'E')) {
+            self.pos += 1;
+        }
+        let value: f64 = std::str::from_utf8(&self.text[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(WktError::Syntax { position: start, expected: "a number" })?;
+        if !value.is_finite() {
+            return Err(WktError::NonFinite);
+        }
+        Ok(value)
+    }
+
+    /// `EMPTY` or a parenthesised list of points of 2 to 4 numbers, keeping
+    /// the first two
+    fn points(&mut self) -> Result<Vec<Coord>, WktError> {
+        let start = self.pos;
+        if self.keyword().eq_ignore_ascii_case("EMPTY") {
+            return Ok(Vec::new());
+        }
+        self.pos = start;
+        self.expect(b'(', "'('")?;
+        let mut coords = Vec::new();
+        loop {
+            let x = self.number()?;
+            let y = self.number()?;
+            for _ in 0..2 {
+                self.skip_whitespace();
+                if !self.text.get(self.pos).is_some_and(|c| c.is_ascii_digit() || matches!(c, b'+' | b'-' | b'.')) {
+                    break;
+                }
+                self.number()?;
+            }
+            coords.push(Coord { x, y });
+            if !self.next_if(b',') {
+                break;
+            }
+        }
+        self.expect(b')', "')'")?;
+        Ok(coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_wkt, WktError};
+
+    #[test]
+    fn parse_wkt_reports_syntax_errors() {
+        let line = parse_wkt("LINESTRING (18.0 59.0, 18.1 59.1)").unwrap();
+        assert_eq!(line.0[1].x, 18.1);
+        let line = parse_wkt("SRID=4326;MultiLineString Z ((18 59 12.5, 18.1 59.1 13))").unwrap();
+        assert_eq!(line.0.len(), 2);
+
+        assert_eq!(parse_wkt("LINESTRING EMPTY"), Err(WktError::Empty));
+        assert_eq!(parse_wkt("POINT (18 59)"), Err(WktError::UnsupportedType("POINT".to_string())));
+        assert_eq!(
+            parse_wkt("LINESTRING (18 59, 18.1)"),
+            Err(WktError::Syntax { position: 23, expected: "a number" })
+        );
+    }
+}
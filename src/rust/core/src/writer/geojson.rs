@@ -0,0 +1,67 @@
+//! GeoJSON debug output
+//!
+//! Writes the simplified ways with their OSM tags as a GeoJSON FeatureCollection
+//! so the tagging can be inspected in QGIS or geojson.io without an OSM toolchain.
+//! Way IDs match the ones written to the PBF.
+
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use super::PendingFile;
+use crate::models::{Segment, Way};
+
+/// Write ways as GeoJSON LineString features; returns the number of features written
+pub fn write_ways_geojson(
+    ways: &[Way],
+    segments: &[Segment],
+    output_path: &str,
+    way_id_start: i64,
+) -> Result<usize, String> {
+    let pending = PendingFile::new(output_path);
+    let file = File::create(pending.temp_path())
+        .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+    let mut out = BufWriter::new(file);
+    let io_err = |e: std::io::Error| format!("Failed to write GeoJSON: {}", e);
+
+    out.write_all(b"{\"type\":\"FeatureCollection\",\"features\":[\n").map_err(io_err)?;
+
+    let mut written = 0;
+    for (i, way) in ways.iter().enumerate() {
+        let coords: Vec<[f64; 2]> = way.line(segments).0.iter().map(|c| [c.x, c.y]).collect();
+        if coords.len() < 2 {
+            continue;
+        }
+
+        // Sorted keys keep the output stable between runs
+        let mut tags: Vec<(&String, &String)> = way.tags.iter().collect();
+        tags.sort();
+        let properties: Map<String, Value> = tags
+            .into_iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+
+        let feature = json!({
+            "type": "Feature",
+            "id": way_id_start + i as i64,
+            "geometry": {
+                "type": "LineString",
+                "coordinates": coords,
+            },
+            "properties": properties,
+        });
+
+        if written > 0 {
+            out.write_all(b",\n").map_err(io_err)?;
+        }
+        serde_json::to_writer(&mut out, &feature)
+            .map_err(|e| format!("Failed to write GeoJSON: {}", e))?;
+        written += 1;
+    }
+
+    out.write_all(b"\n]}\n").map_err(io_err)?;
+    out.flush().map_err(io_err)?;
+    drop(out);
+    pending.commit()?;
+    Ok(written)
+}
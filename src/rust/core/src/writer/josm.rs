@@ -0,0 +1,158 @@
+//! JOSM session output
+//!
+//! Import reviewers open the converted data in JOSM together with the places
+//! the tag mapper flagged. `write_josm_session()` writes a `.jos` session
+//! that opens both as separate layers: the `.osm` output, and a review layer
+//! with one node per annotation (`kind`, `row` and `message` tags) at the
+//! start of its segment. JOSM loads session data layers as OSM XML only, so
+//! the review layer is written as `.osm` too rather than GeoJSON, marked
+//! `upload="never"` so the markers cannot be uploaded by mistake.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use pbf_craft::models::{Element, Node, Tag};
+use rustc_hash::FxHashMap;
+
+use super::xml::{escape, XmlWriter};
+use super::PendingFile;
+use crate::models::Segment;
+use crate::stats::AnnotationRecord;
+
+/// Path of the review layer of the session at `session_path`: its name
+/// with `.review.osm` in place of `.jos`
+pub fn review_layer_path(session_path: &str) -> String {
+    let path = Path::new(session_path);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.review.osm", stem)).to_string_lossy().into_owned()
+}
+
+/// Write a JOSM session opening the OSM XML file `osm_path` and a review
+/// layer of the annotations (see `review_layer_path()`); returns the number
+/// of review nodes
+///
+/// Annotations of rows without a segment (unreadable geometries) are left
+/// out of the review layer.
+pub fn write_josm_session(
+    session_path: &str,
+    osm_path: &str,
+    annotations: &[AnnotationRecord],
+    segments: &[Segment],
+) -> Result<usize, String> {
+    let review_path = review_layer_path(session_path);
+    let written = write_review_layer(&review_path, annotations, segments)?;
+
+    let pending = PendingFile::new(session_path);
+    let file = File::create(pending.temp_path())
+        .map_err(|e| format!("Failed to create {}: {}", session_path, e))?;
+    let mut out = BufWriter::new(file);
+    let io_err = |e: std::io::Error| format!("Failed to write JOSM session: {}", e);
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").map_err(io_err)?;
+    writeln!(out, "<josm-session version=\"0.1\">").map_err(io_err)?;
+    writeln!(out, "  <layers active=\"1\">").map_err(io_err)?;
+    let name = |path: &str| Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    for (index, path) in [osm_path, review_path.as_str()].into_iter().enumerate() {
+        writeln!(
+            out,
+            "    <layer index=\"{}\" name=\"{}\" type=\"osm-data\" version=\"0.1\" visible=\"true\">",
+            index + 1,
+            escape(&name(path))
+        ).map_err(io_err)?;
+        writeln!(out, "      <file>{}</file>", escape(&file_uri(path))).map_err(io_err)?;
+        writeln!(out, "    </layer>").map_err(io_err)?;
+    }
+    writeln!(out, "  </layers>").map_err(io_err)?;
+    writeln!(out, "</josm-session>").map_err(io_err)?;
+    out.flush().map_err(io_err)?;
+    drop(out);
+    pending.commit()?;
+    Ok(written)
+}
+
+/// Write the annotations as OSM XML nodes with negative IDs
+fn write_review_layer(path: &str, annotations: &[AnnotationRecord], segments: &[Segment]) -> Result<usize, String> {
+    // First vertex of the first segment of each input row
+    let mut starts: FxHashMap<usize, (i64, i64)> = FxHashMap::default();
+    for segment in segments {
+        let c = segment.start_coord();
+        starts.entry(segment.source_row).or_insert(((c.y * 1e9).round() as i64, (c.x * 1e9).round() as i64));
+    }
+
+    let pending = PendingFile::new(path);
+    let mut writer = XmlWriter::from_path(&pending.temp_path())?;
+    writer.never_upload();
+    let io_err = |e: std::io::Error| format!("Failed to write review layer: {}", e);
+    let mut written = 0;
+    for annotation in annotations {
+        let Some(&(latitude, longitude)) = starts.get(&annotation.source_row) else { continue };
+        written += 1;
+        let tag = |key: &str, value: String| Tag { key: key.to_string(), value };
+        writer.write(Element::Node(Node {
+            id: -(written as i64),
+            latitude,
+            longitude,
+            tags: vec![
+                tag("kind", annotation.kind.to_string()),
+                tag("row", (annotation.source_row + 1).to_string()),
+                tag("message", annotation.message.clone()),
+            ],
+            ..Default::default()
+        })).map_err(io_err)?;
+    }
+    writer.finish().map_err(io_err)?;
+    drop(writer);
+    pending.commit()?;
+    Ok(written)
+}
+
+/// `file:` URI of `path`, made absolute, as JOSM sessions refer to files
+fn file_uri(path: &str) -> String {
+    let absolute = std::fs::canonicalize(path)
+        .or_else(|_| std::env::current_dir().map(|dir| dir.join(path)))
+        .unwrap_or_else(|_| PathBuf::from(path));
+    let mut text = absolute.to_string_lossy().replace('\\', "/");
+    // Windows: \\?\C:\... from canonicalize, or C:/...
+    if let Some(stripped) = text.strip_prefix("//?/") {
+        text = stripped.to_string();
+    }
+    if !text.starts_with('/') {
+        text.insert(0, '/');
+    }
+    let mut uri = String::from("file:");
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AnnotationRecord;
+    use crate::writer::{review_layer_path, write_josm_session};
+    use crate::test_support::segment;
+
+    #[test]
+    fn josm_session_writes_review_layer() {
+        let dir = std::env::temp_dir();
+        let session = dir.join("josm_test.jos").to_string_lossy().into_owned();
+        let osm = dir.join("josm_test.osm").to_string_lossy().into_owned();
+        let mut segment = segment(vec![(18.0, 59.0), (18.01, 59.0)]);
+        segment.source_row = 4;
+        let annotations = vec![AnnotationRecord { source_row: 4, kind: "out_of_range", message: "maxspeed 250".to_string() }];
+
+        assert_eq!(write_josm_session(&session, &osm, &annotations, &[segment]).unwrap(), 1);
+        let jos = std::fs::read_to_string(&session).unwrap();
+        assert!(jos.contains("josm_test.review.osm</file>"));
+        let review = std::fs::read_to_string(review_layer_path(&session)).unwrap();
+        assert!(review.contains("upload=\"never\"") && review.contains("v=\"maxspeed 250\""));
+    }
+
+    #[test]
+    fn review_layer_next_to_the_session() {
+        assert_eq!(review_layer_path("out/umea.jos"), "out/umea.review.osm");
+    }
+}
@@ -0,0 +1,1237 @@
+//! PBF output
+//!
+//! Writes ways to PBF using the three-pass approach (nodes first, then ways).
+//! This matches Python's behavior and ensures Osmium compatibility.
+//!
+//! Output paths ending in `.opl` are written as osmium's OPL text format instead
+//! (see `opl.rs`), paths ending in `.osm` or `.xml` as OSM XML (see `xml.rs`), and `postgresql://` URLs are loaded into an OSM API database
+//! when built with the `postgis` feature (see `postgis.rs`). The node/way ID
+//! assignment is identical for all targets.
+//!
+//! `write_pbf_tiles()` splits the output into one file per z/x/y tile (see
+//! `tiles.rs`), and `write_josm_session()` opens OSM XML output in JOSM with
+//! a review layer (see `josm.rs`).
+//!
+//! Output can also be appended to an existing extract (`BaseExtract`): its
+//! elements are copied into the output and the converted ones are numbered
+//! after its highest IDs, so no external merge tooling is needed.
+//!
+//! The node ID bookkeeping lives in `PbfEmitter` so it can be kept alive across
+//! several batches of segments (chunked conversion): junction IDs assigned in one
+//! chunk are reused when the same coordinate shows up in a later chunk. A
+//! feature node (crossing, barrier, ...) at a junction coordinate becomes the
+//! junction node itself, so the feature is part of the routable graph.
+//!
+//! Elements are written sorted by type, then ID, and PBF headers declare this
+//! with the `Sort.Type_then_ID` optional feature so that osmium and osmosis
+//! need not re-sort. Junction nodes with global IDs (split conversion) are held
+//! back and written after the locally numbered nodes, before the first way.
+//! Output that turns out unsorted after such a header was written fails in
+//! `PbfEmitter::finish()` rather than carrying a wrong header.
+
+mod geojson;
+mod josm;
+pub mod ogr;
+mod opl;
+mod parts;
+mod renumber;
+mod tiles;
+mod xml;
+#[cfg(feature = "postgis")]
+pub mod postgis;
+
+pub use geojson::write_ways_geojson;
+pub use josm::{review_layer_path, write_josm_session};
+pub use parts::part_path;
+pub use renumber::{renumber, Renumbered};
+pub use tiles::{tile_of, tile_path};
+use opl::OplWriter;
+use xml::XmlWriter;
+#[cfg(feature = "postgis")]
+use postgis::PgWriter;
+
+use rustc_hash::FxHashMap;
+use geo_types::Coord;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use crate::models::{self, Segment, Way, NodeFeature, CoordHash, RelationFeature};
+use pbf_craft::models::{Bound, Element, ElementType, Node, Relation, RelationMember, Tag, WayNode};
+/// Encoded OSM way as produced by `PbfEmitter::build_way()`
+pub use pbf_craft::models::Way as PbfWay;
+use pbf_craft::readers::{IterableReader, PbfReader};
+use pbf_craft::writers::PbfWriter;
+
+/// Whether `path` is written as OSM XML (`.osm` or `.xml`, in any case)
+pub fn is_xml_path(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".osm") || lower.ends_with(".xml")
+}
+
+/// Output format chosen from the output path
+enum ElementSink {
+    Pbf(PbfWriter<BufWriter<File>>),
+    Opl(OplWriter),
+    Xml(XmlWriter),
+    /// Elements kept for `write_pbf_tiles()`
+    Memory(Vec<Element>),
+    #[cfg(feature = "postgis")]
+    Postgres(Box<PgWriter>),
+}
+
+impl ElementSink {
+    /// Sink for the format of `output_path`; files are written under a
+    /// temporary name, to be moved into place with the returned
+    /// `PendingFile` once finished
+    fn from_path(output_path: &str, options: &WriteOptions) -> Result<(Self, Option<PendingFile>), String> {
+        if output_path.starts_with("postgresql://") || output_path.starts_with("postgres://") {
+            #[cfg(feature = "postgis")]
+            return Ok((ElementSink::Postgres(Box::new(PgWriter::connect(output_path, options)?)), None));
+            #[cfg(not(feature = "postgis"))]
+            return Err("PostGIS output requires building with the `postgis` feature".to_string());
+        }
+        let file = PendingFile::new(output_path);
+        let path = file.temp_path();
+        if output_path.to_lowercase().ends_with(".opl") {
+            return Ok((ElementSink::Opl(OplWriter::from_path(&path)?), Some(file)));
+        }
+        if is_xml_path(output_path) {
+            return Ok((ElementSink::Xml(XmlWriter::from_path(&path)?), Some(file)));
+        }
+        let mut writer = PbfWriter::from_path(&path, options.dense_nodes)
+            .map_err(|e| format!("Failed to create writer: {}", e))?;
+        writer.set_block_size(options.block_size);
+        writer.set_compression_level(options.compression_level);
+        if options.parallel_encoding {
+            writer.set_parallel_blocks(rayon::current_num_threads());
+        }
+        Ok((ElementSink::Pbf(writer), Some(file)))
+    }
+
+    fn write(&mut self, element: Element) -> Result<(), String> {
+        match self {
+            ElementSink::Pbf(w) => w.write(element).map_err(|e| format!("Failed to write: {}", e)),
+            ElementSink::Opl(w) => w.write(element).map_err(|e| format!("Failed to write: {}", e)),
+            ElementSink::Xml(w) => w.write(element).map_err(|e| format!("Failed to write: {}", e)),
+            ElementSink::Memory(elements) => {
+                elements.push(element);
+                Ok(())
+            }
+            #[cfg(feature = "postgis")]
+            ElementSink::Postgres(w) => w.write(element),
+        }
+    }
+
+    /// OPL and the database have no header, so the bbox only applies to PBF
+    /// and XML output
+    fn set_bbox(&mut self, bbox: Bound) {
+        match self {
+            ElementSink::Pbf(w) => w.set_bbox(bbox),
+            ElementSink::Xml(w) => w.set_bbox(bbox),
+            _ => {}
+        }
+    }
+
+    /// Declare in the PBF header whether the elements are sorted by type,
+    /// then ID; returns false once the header has been written
+    fn set_sorted(&mut self, sorted: bool) -> bool {
+        match self {
+            ElementSink::Pbf(w) => {
+                let features = if sorted { vec![SORTED_FEATURE.to_string()] } else { Vec::new() };
+                w.set_optional_features(features);
+                !w.has_written_header()
+            }
+            _ => true,
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        match self {
+            ElementSink::Pbf(w) => w.finish().map_err(|e| format!("Failed to finish: {}", e)),
+            ElementSink::Opl(w) => w.finish().map_err(|e| format!("Failed to finish: {}", e)),
+            ElementSink::Xml(w) => w.finish().map_err(|e| format!("Failed to finish: {}", e)),
+            ElementSink::Memory(_) => Ok(()),
+            #[cfg(feature = "postgis")]
+            ElementSink::Postgres(w) => w.finish(),
+        }
+    }
+}
+
+/// Output file written under a temporary name next to its target and
+/// renamed into place by `commit()`, so that the target never holds a
+/// truncated file, even if the process dies while writing; dropped without
+/// `commit()` (on an error), the temporary file is removed
+pub(crate) struct PendingFile {
+    temp: PathBuf,
+    target: PathBuf,
+    committed: bool,
+}
+
+impl PendingFile {
+    pub(crate) fn new(target: &str) -> Self {
+        let target = PathBuf::from(target);
+        let name = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let temp = target.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+        Self { temp, target, committed: false }
+    }
+
+    /// Path to write to
+    pub(crate) fn temp_path(&self) -> String {
+        self.temp.to_string_lossy().into_owned()
+    }
+
+    /// Move the finished file to its target path; its writer must be
+    /// closed first
+    pub(crate) fn commit(mut self) -> Result<(), String> {
+        std::fs::rename(&self.temp, &self.target)
+            .map_err(|e| format!("Failed to move output into place at {}: {}", self.target.display(), e))?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for PendingFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// Header bounding box of PBF and OSM XML output
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HeaderBbox {
+    /// Extent of all converted segments and feature nodes, and of the base
+    /// extract when appending
+    #[default]
+    All,
+    /// Extent of the written elements only: segments left out of every way
+    /// do not count
+    Emitted,
+    /// No bbox in the header
+    Omit,
+    /// Given (west, south, east, north) in degrees
+    Fixed(f64, f64, f64, f64),
+}
+
+impl From<&str> for HeaderBbox {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "emitted" => HeaderBbox::Emitted,
+            "none" | "omit" => HeaderBbox::Omit,
+            _ => HeaderBbox::All,
+        }
+    }
+}
+
+/// Output settings shared by all writers; the block settings only apply to
+/// PBF output
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOptions {
+    pub ids: ElementIds,
+    pub bbox: HeaderBbox,
+    /// zlib level of the PBF blobs, 0 (stored) to 9 (smallest)
+    pub compression_level: u32,
+    /// Maximum number of elements per primitive block
+    pub block_size: usize,
+    /// Write nodes as DenseNodes (the `DenseNodes` required feature) rather
+    /// than one message per node
+    pub dense_nodes: bool,
+    /// Encode and compress one PBF block per rayon thread at a time instead
+    /// of one after the other; the file is the same either way
+    pub parallel_encoding: bool,
+    /// Changeset referenced by every row of PostGIS output
+    pub changeset_id: i64,
+    /// Schema of the `current_*` tables of PostGIS output, `None` for the
+    /// first schema of the connection's `search_path`
+    pub db_schema: Option<String>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            ids: ElementIds::default(),
+            bbox: HeaderBbox::All,
+            compression_level: 6,
+            block_size: 8000,
+            dense_nodes: true,
+            parallel_encoding: true,
+            changeset_id: 1,
+            db_schema: None,
+        }
+    }
+}
+
+/// First IDs of the written nodes, ways and relations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementIds {
+    pub node: i64,
+    pub way: i64,
+    pub relation: i64,
+}
+
+impl Default for ElementIds {
+    fn default() -> Self {
+        Self { node: 1, way: 1, relation: 1 }
+    }
+}
+
+fn feature_tags(node: &NodeFeature) -> Vec<Tag> {
+    node.tags
+        .iter()
+        .map(|(k, v)| Tag {
+            key: k.clone(),
+            value: v.clone(),
+        })
+        .collect()
+}
+
+/// Node, coordinate and global node ID (with its ownership) of the end where
+/// a way enters a segment (`entry`) or leaves it, for the segment's
+/// orientation in the way
+fn way_end(seg: &Segment, reversed: bool, entry: bool) -> (CoordHash, Coord, Option<(i64, bool)>) {
+    if entry != reversed {
+        (seg.start_node, *seg.start_coord(), seg.global_start_node_id.map(|id| (id, seg.global_start_owned)))
+    } else {
+        (seg.end_node, *seg.end_coord(), seg.global_end_node_id.map(|id| (id, seg.global_end_owned)))
+    }
+}
+
+/// PBF header feature of files sorted by element type, then ID
+const SORTED_FEATURE: &str = "Sort.Type_then_ID";
+
+/// Existing PBF extract that the output is appended to
+///
+/// `scan()` reads the ID ranges and extent of the file once; the elements
+/// themselves are copied while writing (`PbfEmitter::copy_base()`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaseExtract {
+    path: String,
+    pub max_node_id: i64,
+    pub max_way_id: i64,
+    pub max_relation_id: i64,
+    /// (min_lon, min_lat, max_lon, max_lat) of the nodes, if there are any
+    pub bounds: Option<(f64, f64, f64, f64)>,
+    /// Whether the elements are sorted by type, then ID
+    pub sorted: bool,
+}
+
+impl BaseExtract {
+    pub fn scan(path: &str) -> Result<Self, String> {
+        let mut reader = PbfReader::from_path(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        let mut base = Self {
+            path: path.to_string(),
+            max_node_id: 0,
+            max_way_id: 0,
+            max_relation_id: 0,
+            bounds: None,
+            sorted: true,
+        };
+        let mut last: Option<(u8, i64)> = None;
+        reader
+            .read(|_, element| {
+                if let Some(key) = element.as_ref().map(sort_key) {
+                    base.sorted &= last.is_none_or(|last| last < key);
+                    last = Some(key);
+                }
+                match element {
+                    Some(Element::Node(node)) => {
+                        base.max_node_id = base.max_node_id.max(node.id);
+                        let (lon, lat) = (node.longitude as f64 / 1e9, node.latitude as f64 / 1e9);
+                        let b = base.bounds.get_or_insert((lon, lat, lon, lat));
+                        *b = (b.0.min(lon), b.1.min(lat), b.2.max(lon), b.3.max(lat));
+                    }
+                    Some(Element::Way(way)) => base.max_way_id = base.max_way_id.max(way.id),
+                    Some(Element::Relation(rel)) => base.max_relation_id = base.max_relation_id.max(rel.id),
+                    None => {}
+                }
+            })
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Ok(base)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// First node ID after the extract's nodes
+    pub fn next_node_id(&self) -> i64 {
+        self.max_node_id + 1
+    }
+
+    /// First way ID after the extract's ways
+    pub fn next_way_id(&self) -> i64 {
+        self.max_way_id + 1
+    }
+
+    /// First relation ID after the extract's relations
+    pub fn next_relation_id(&self) -> i64 {
+        self.max_relation_id + 1
+    }
+}
+
+/// Position of an element in type-then-ID order
+fn sort_key(element: &Element) -> (u8, i64) {
+    match element {
+        Element::Node(node) => (0, node.id),
+        Element::Way(way) => (1, way.id),
+        Element::Relation(relation) => (2, relation.id),
+    }
+}
+
+/// Stateful PBF/OPL writer that owns node/way ID assignment
+pub struct PbfEmitter {
+    writer: ElementSink,
+    junction_ids: FxHashMap<CoordHash, i64>,
+    written_node_ids: HashSet<i64>,
+    node_id: i64,
+    way_id: i64,
+    nodes_written: usize,
+    /// Feature nodes of the current chunk, written by `write_network_nodes()`
+    pending_features: Vec<NodeFeature>,
+    /// First pending feature node at each coordinate
+    feature_ids: FxHashMap<CoordHash, i64>,
+    /// Pending feature nodes whose tags moved onto a global junction node
+    absorbed_features: HashSet<i64>,
+    /// Junction nodes with global IDs, written in ID order before the first way
+    deferred_nodes: Vec<(i64, Coord, Vec<Tag>)>,
+    last_written: Option<(u8, i64)>,
+    sorted: bool,
+    /// First error writing an element, or the output turning unsorted after
+    /// its header declared it sorted; returned by `finish()` instead of
+    /// moving the incomplete file into place
+    write_error: Option<String>,
+    /// Output file to move into place on `finish()`; after `writer`, so
+    /// that the writer is closed before the file is removed on an error
+    file: Option<PendingFile>,
+}
+
+impl PbfEmitter {
+    pub fn create(output_path: &str, node_id_start: i64, way_id_start: i64) -> Result<Self, String> {
+        let ids = ElementIds { node: node_id_start, way: way_id_start, ..ElementIds::default() };
+        Self::create_with(output_path, &WriteOptions { ids, ..WriteOptions::default() })
+    }
+
+    /// Like `create()`, with the first IDs and block settings of `options`
+    /// (the header bbox is set separately, see `set_header_bbox()`)
+    ///
+    /// Files are written under a temporary name in the same directory and
+    /// only appear at `output_path` on a successful `finish()`.
+    pub fn create_with(output_path: &str, options: &WriteOptions) -> Result<Self, String> {
+        let (mut writer, file) = ElementSink::from_path(output_path, options)?;
+        writer.set_sorted(true);
+        let mut emitter = Self::with_sink(writer, options.ids.node, options.ids.way);
+        emitter.file = file;
+        Ok(emitter)
+    }
+
+    /// Emitter that keeps the elements in memory (see `into_elements()`)
+    fn in_memory(node_id_start: i64, way_id_start: i64) -> Self {
+        Self::with_sink(ElementSink::Memory(Vec::new()), node_id_start, way_id_start)
+    }
+
+    fn with_sink(writer: ElementSink, node_id_start: i64, way_id_start: i64) -> Self {
+        Self {
+            writer,
+            junction_ids: FxHashMap::default(),
+            written_node_ids: HashSet::new(),
+            node_id: node_id_start,
+            way_id: way_id_start,
+            nodes_written: 0,
+            pending_features: Vec::new(),
+            feature_ids: FxHashMap::default(),
+            absorbed_features: HashSet::new(),
+            deferred_nodes: Vec::new(),
+            last_written: None,
+            sorted: true,
+            write_error: None,
+            file: None,
+        }
+    }
+
+    fn into_elements(self) -> Vec<Element> {
+        match self.writer {
+            ElementSink::Memory(elements) => elements,
+            _ => Vec::new(),
+        }
+    }
+
+    /// Next node ID that would be assigned
+    pub fn next_node_id(&self) -> i64 {
+        self.node_id
+    }
+
+    /// Whether everything written so far is sorted by type, then ID
+    ///
+    /// While it is, PBF output declares `Sort.Type_then_ID` in its header.
+    /// Junction nodes with global IDs are written after the local ones:
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    fn mark_unsorted(&mut self) {
+        if self.sorted {
+            self.sorted = false;
+            if !self.writer.set_sorted(false) && self.write_error.is_none() {
+                self.write_error = Some(format!(
+                    "Output is not sorted by type and ID, but its header already declares {}",
+                    SORTED_FEATURE
+                ));
+            }
+        }
+    }
+
+    /// Write an element, keeping track of whether the output stays sorted
+    fn emit(&mut self, element: Element) {
+        if self.write_error.is_some() {
+            return;
+        }
+        let key = sort_key(&element);
+        if self.last_written.is_some_and(|last| last >= key) {
+            self.mark_unsorted();
+        }
+        self.last_written = Some(key);
+        if let Err(e) = self.writer.write(element) {
+            self.write_error = Some(e);
+        }
+    }
+
+    /// Write the held-back global junction nodes, in ID order
+    fn write_deferred_nodes(&mut self) {
+        self.write_pending_features();
+        let mut deferred = std::mem::take(&mut self.deferred_nodes);
+        deferred.sort_unstable_by_key(|&(id, _, _)| id);
+        for (id, coord, tags) in deferred {
+            self.write_node(id, coord.y, coord.x, tags);
+        }
+    }
+
+    /// Write the buffered feature nodes, except those merged into a global
+    /// junction node
+    fn write_pending_features(&mut self) {
+        for node in std::mem::take(&mut self.pending_features) {
+            if !self.absorbed_features.remove(&node.id) {
+                self.write_node(node.id, node.lat, node.lon, feature_tags(&node));
+            }
+        }
+        self.feature_ids.clear();
+    }
+
+    /// Copy the elements of one type from the base extract; nodes go before
+    /// the converted nodes, ways and relations after the converted ways
+    pub fn copy_base(&mut self, base: &BaseExtract, element_type: ElementType) -> Result<(), String> {
+        if !base.sorted {
+            self.mark_unsorted();
+        }
+        if element_type != ElementType::Node {
+            self.write_deferred_nodes();
+        }
+        let reader = IterableReader::from_path(base.path())
+            .map_err(|e| format!("Failed to open {}: {}", base.path(), e))?;
+        for element in reader {
+            let matches = match &element {
+                Element::Node(_) => element_type == ElementType::Node,
+                Element::Way(_) => element_type == ElementType::Way,
+                Element::Relation(_) => element_type == ElementType::Relation,
+            };
+            if matches {
+                self.emit(element);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the header bbox from all segment geometries and feature nodes
+    ///
+    /// Must be called before the first block is flushed (i.e. before writing).
+    pub fn set_bbox_from(&mut self, segments: &[Segment], feature_nodes: &[NodeFeature]) {
+        self.set_bbox_including(segments, feature_nodes, None);
+    }
+
+    /// Like `set_bbox_from()`, also covering the nodes of a base extract
+    pub fn set_bbox_including(&mut self, segments: &[Segment], feature_nodes: &[NodeFeature], base: Option<&BaseExtract>) {
+        self.set_bbox_of(segments.iter(), feature_nodes, base);
+    }
+
+    /// Set the header bbox as chosen by `bbox`; `ways` decide which segments
+    /// are written for `HeaderBbox::Emitted`
+    pub fn set_header_bbox(
+        &mut self,
+        bbox: HeaderBbox,
+        ways: &[Way],
+        segments: &[Segment],
+        feature_nodes: &[NodeFeature],
+        base: Option<&BaseExtract>,
+    ) {
+        match bbox {
+            HeaderBbox::All => self.set_bbox_including(segments, feature_nodes, base),
+            HeaderBbox::Emitted => {
+                let written = ways.iter().flat_map(|way| &way.segment_indices).filter_map(|&i| segments.get(i));
+                self.set_bbox_of(written, feature_nodes, base);
+            }
+            HeaderBbox::Omit => {}
+            HeaderBbox::Fixed(west, south, east, north) => self.set_bbox(west, south, east, north),
+        }
+    }
+
+    /// Set a fixed header bbox, in degrees
+    pub fn set_bbox(&mut self, west: f64, south: f64, east: f64, north: f64) {
+        self.writer.set_bbox(Bound {
+            left: deg_to_nanodeg(west),
+            right: deg_to_nanodeg(east),
+            top: deg_to_nanodeg(north),
+            bottom: deg_to_nanodeg(south),
+            origin: "nvdb2osmr".to_string(),
+        });
+    }
+
+    fn set_bbox_of<'a>(&mut self, segments: impl Iterator<Item = &'a Segment>, feature_nodes: &[NodeFeature], base: Option<&BaseExtract>) {
+        let (mut min_lat, mut max_lat) = (f64::MAX, f64::MIN);
+        let (mut min_lon, mut max_lon) = (f64::MAX, f64::MIN);
+        for seg in segments {
+            for coord in &seg.geometry.0 {
+                min_lat = min_lat.min(coord.y);
+                max_lat = max_lat.max(coord.y);
+                min_lon = min_lon.min(coord.x);
+                max_lon = max_lon.max(coord.x);
+            }
+        }
+        // Include feature nodes in bbox calculation
+        for node in feature_nodes {
+            min_lat = min_lat.min(node.lat);
+            max_lat = max_lat.max(node.lat);
+            min_lon = min_lon.min(node.lon);
+            max_lon = max_lon.max(node.lon);
+        }
+        if let Some((left, bottom, right, top)) = base.and_then(|b| b.bounds) {
+            min_lon = min_lon.min(left);
+            min_lat = min_lat.min(bottom);
+            max_lon = max_lon.max(right);
+            max_lat = max_lat.max(top);
+        }
+        self.set_bbox(min_lon, min_lat, max_lon, max_lat);
+    }
+
+    fn write_node(&mut self, id: i64, lat: f64, lon: f64, tags: Vec<Tag>) {
+        let node = Node {
+            id,
+            latitude: deg_to_nanodeg(lat),
+            longitude: deg_to_nanodeg(lon),
+            tags,
+            version: 0,
+            timestamp: None,
+            user: None,
+            changeset_id: 0,
+            visible: true,
+        };
+        self.emit(Element::Node(node));
+        self.nodes_written += 1;
+    }
+
+    /// Pass 0 - Feature nodes (crossings, cameras, barriers, etc.)
+    ///
+    /// They are written by the following `write_network_nodes()`, once it is
+    /// known which of them are at junctions: a junction at the coordinate of a
+    /// feature node takes that node's ID (or, with a global ID, its tags), so
+    /// there is a single node that the ways pass through.
+    pub fn write_feature_nodes(&mut self, feature_nodes: &[NodeFeature]) {
+        self.write_pending_features();
+        for node in feature_nodes {
+            let hash = models::hash_coord(&Coord { x: node.lon, y: node.lat });
+            self.feature_ids.entry(hash).or_insert(node.id);
+
+            // Update node_id to be after all feature nodes
+            if node.id >= self.node_id {
+                self.node_id = node.id + 1;
+            }
+        }
+        self.pending_features.extend(feature_nodes.iter().cloned());
+        self.pending_features.sort_by_key(|node| node.id);
+    }
+
+    /// Assign an ID to a junction coordinate unless it already has one
+    ///
+    /// A feature node at the coordinate becomes the junction node. New local
+    /// junctions are returned for writing after the feature nodes; global IDs
+    /// are held back for `write_deferred_nodes()` when this chunk owns them.
+    fn assign_junction(&mut self, hash: CoordHash, coord: Coord, global: Option<(i64, bool)>) -> Option<(i64, Coord)> {
+        if self.junction_ids.contains_key(&hash) {
+            return None;
+        }
+        let feature_id = self.feature_ids.get(&hash).copied();
+        let Some((global_id, owned)) = global else {
+            if let Some(id) = feature_id {
+                self.junction_ids.insert(hash, id);
+                return None;
+            }
+            let id = self.node_id;
+            self.node_id += 1;
+            self.junction_ids.insert(hash, id);
+            self.written_node_ids.insert(id);
+            return Some((id, coord));
+        };
+        self.junction_ids.insert(hash, global_id);
+
+        if owned && self.written_node_ids.insert(global_id) {
+            let mut tags = Vec::new();
+            if let Some(id) = feature_id {
+                if let Some(node) = self.pending_features.iter().find(|node| node.id == id) {
+                    tags = feature_tags(node);
+                    self.absorbed_features.insert(id);
+                }
+            }
+            self.deferred_nodes.push((global_id, coord, tags));
+        }
+        None
+    }
+
+    /// Pass 1 and 2 - Write junction nodes and internal segment nodes
+    pub fn write_network_nodes(&mut self, ways: &[Way], segments: &mut [Segment]) {
+        // Pass 1: Identify all junction nodes (start/end of segments that are used in ways)
+        // and assign them IDs
+        let mut new_junctions = Vec::new();
+        for way in ways {
+            let oriented: Vec<(usize, bool)> = way.oriented().collect();
+            if let (Some(&(first, first_reversed)), Some(&(last, last_reversed))) = (oriented.first(), oriented.last()) {
+                // Start junction of the way
+                let (node, coord, global) = way_end(&segments[first], first_reversed, true);
+                new_junctions.extend(self.assign_junction(node, coord, global));
+
+                // End junction of the way
+                let (node, coord, global) = way_end(&segments[last], last_reversed, false);
+                new_junctions.extend(self.assign_junction(node, coord, global));
+            }
+
+            // Also need internal junctions (where segments connect within a way)
+            for pair in oriented.windows(2) {
+                let (node, coord, global1) = way_end(&segments[pair[0].0], pair[0].1, false);
+                let (_, _, global2) = way_end(&segments[pair[1].0], pair[1].1, true);
+
+                // The junction between segments
+                let chosen_global = match (global1, global2) {
+                    (Some((id1, owned1)), Some((id2, owned2))) if id1 == id2 => Some((id1, owned1 || owned2)),
+                    (Some(global), _) | (None, Some(global)) => Some(global),
+                    (None, None) => None,
+                };
+                // The exit of the first segment is the entry of the second
+                new_junctions.extend(self.assign_junction(node, coord, chosen_global));
+            }
+        }
+
+        // Feature nodes have the lower IDs, so they go first
+        self.write_pending_features();
+        for (id, coord) in new_junctions {
+            self.write_node(id, coord.y, coord.x, vec![]);
+        }
+
+        // Pass 2: Write internal nodes for each segment
+        // Internal nodes are all coordinates except start and end
+        // If an internal coordinate matches a junction (from Pass 1), reuse its ID
+        // First, collect all (seg_idx, coord, maybe_junction_id) tuples
+        let mut internal_node_data: Vec<(usize, InternalCoords)> = Vec::new();
+        for way in ways {
+            for &seg_idx in &way.segment_indices {
+                let seg = &segments[seg_idx];
+                let coords: InternalCoords = seg.internal_coords().iter().map(|c| {
+                    let h = models::hash_coord(c);
+                    (*c, self.junction_ids.get(&h).copied())
+                }).collect();
+                internal_node_data.push((seg_idx, coords));
+            }
+        }
+
+        // Now process each segment's internal nodes
+        for (seg_idx, coords) in internal_node_data {
+            let mut internal_ids = Vec::with_capacity(coords.len());
+
+            for (coord, maybe_junction_id) in coords {
+                if let Some(junction_id) = maybe_junction_id {
+                    // This internal coordinate is at a junction — reuse the junction node ID
+                    internal_ids.push(junction_id);
+                } else {
+                    let id = self.node_id;
+                    self.node_id += 1;
+                    internal_ids.push(id);
+                    self.write_node(id, coord.y, coord.x, vec![]);
+                }
+            }
+
+            segments[seg_idx].internal_node_ids = internal_ids;
+        }
+    }
+
+    /// Pass 3 - Build the PBF way for a merged way, assigning the next way ID
+    ///
+    /// Requires `write_network_nodes()` to have run for the way's segments.
+    pub fn build_way(&mut self, way: &Way, segments: &[Segment]) -> PbfWay {
+        let mut way_node_ids: Vec<i64> = Vec::new();
+
+        if !way.segment_indices.is_empty() {
+            // Start with first segment's entry junction
+            let (start_node, _, _) = way_end(&segments[way.segment_indices[0]], way.is_reversed(0), true);
+            let start_id = match self.junction_ids.get(&start_node) {
+                Some(&id) => id,
+                None => {
+                    // Fallback: create new node
+                    let id = self.node_id;
+                    self.node_id += 1;
+                    id
+                }
+            };
+            way_node_ids.push(start_id);
+
+            // Add internal nodes and end junctions for each segment
+            for (seg_idx, reversed) in way.oriented() {
+                let seg = &segments[seg_idx];
+
+                // Add internal nodes, stored in digitised order
+                if reversed {
+                    way_node_ids.extend(seg.internal_node_ids.iter().rev());
+                } else {
+                    way_node_ids.extend(&seg.internal_node_ids);
+                }
+
+                // Add exit junction
+                let (exit_node, _, _) = way_end(seg, reversed, false);
+                let end_id = match self.junction_ids.get(&exit_node) {
+                    Some(&id) => id,
+                    None => {
+                        let id = self.node_id;
+                        self.node_id += 1;
+                        id
+                    }
+                };
+                way_node_ids.push(end_id);
+            }
+        }
+
+        // Deduplicate consecutive nodes (in case junctions overlap)
+        way_node_ids.dedup();
+
+        let way_nodes: Vec<WayNode> = way_node_ids
+            .iter()
+            .map(|&id| WayNode::new_without_coords(id))
+            .collect();
+
+        let tags: Vec<Tag> = way.tags
+            .iter()
+            .map(|(k, v)| Tag {
+                key: k.clone(),
+                value: v.clone(),
+            })
+            .collect();
+
+        let pbf_way = PbfWay {
+            id: self.way_id,
+            way_nodes,
+            tags,
+            version: 0,
+            timestamp: None,
+            user: None,
+            changeset_id: 0,
+            visible: true,
+        };
+        self.way_id += 1;
+        pbf_way
+    }
+
+    pub fn write_way(&mut self, pbf_way: PbfWay) {
+        self.write_deferred_nodes();
+        self.emit(Element::Way(pbf_way));
+    }
+
+    /// Write relations after the ways; members refer to ways by their index,
+    /// which `build_way()` numbered from `first_way_id`
+    pub fn write_relations(&mut self, relations: &[RelationFeature], first_way_id: i64, relation_id_start: i64) {
+        self.write_deferred_nodes();
+        for (i, relation) in relations.iter().enumerate() {
+            let members = relation.members
+                .iter()
+                .map(|(way_idx, role)| RelationMember {
+                    member_id: first_way_id + *way_idx as i64,
+                    member_type: ElementType::Way,
+                    role: role.clone(),
+                })
+                .collect();
+            let tags = relation.tags
+                .iter()
+                .map(|(k, v)| Tag { key: k.clone(), value: v.clone() })
+                .collect();
+            self.emit(Element::Relation(Relation {
+                id: relation_id_start + i as i64,
+                version: 0,
+                timestamp: None,
+                user: None,
+                changeset_id: 0,
+                visible: true,
+                tags,
+                members,
+            }));
+        }
+    }
+
+    /// Flush the last block; returns the total number of nodes written
+    ///
+    /// Fails with the first error writing an element, if any, or when the
+    /// output stopped being sorted after a PBF header declaring it sorted was
+    /// written; the output file is then removed instead of moved into place.
+    pub fn finish(mut self) -> Result<usize, String> {
+        self.write_deferred_nodes();
+        if let Some(e) = self.write_error.take() {
+            return Err(e);
+        }
+        self.writer.finish()?;
+        if let Some(file) = self.file.take() {
+            // Close the file before renaming it
+            self.writer = ElementSink::Memory(Vec::new());
+            file.commit()?;
+        }
+        Ok(self.nodes_written)
+    }
+}
+
+/// Ways of a chunked conversion kept on disk until every chunk's nodes are written
+///
+/// PBF requires all nodes before any way, so the ways of each chunk are
+/// spilled to a temporary PBF next to the output instead of being held in
+/// memory, and copied to the output by `write_to()`. The file is removed when
+/// the spill is dropped.
+pub struct WaySpill {
+    path: PathBuf,
+    writer: PbfWriter<BufWriter<File>>,
+    len: usize,
+}
+
+impl WaySpill {
+    pub fn create(output_path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(format!("{}.ways.tmp", output_path));
+        let writer = PbfWriter::from_path(&path, true)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(Self { path, writer, len: 0 })
+    }
+
+    pub fn push(&mut self, pbf_way: PbfWay) -> Result<(), String> {
+        self.writer.write(Element::Way(pbf_way)).map_err(|e| format!("Failed to spill way: {}", e))?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Number of ways pushed so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Write the spilled ways to `emitter` in the order they were pushed
+    pub fn write_to(mut self, emitter: &mut PbfEmitter) -> Result<(), String> {
+        self.writer.finish().map_err(|e| format!("Failed to finish {}: {}", self.path.display(), e))?;
+        let reader = IterableReader::from_path(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        for element in reader {
+            if let Element::Way(pbf_way) = element {
+                emitter.write_way(pbf_way);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WaySpill {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Internal coordinates of a segment, with the ID of the junction at each
+/// (if it is one)
+type InternalCoords = Vec<(Coord, Option<i64>)>;
+
+/// Write ways to PBF file using three-pass approach (nodes first, then ways)
+/// This matches Python's behavior and ensures Osmium compatibility
+///
+/// UPDATED: Now also writes feature nodes (crossings, cameras, barriers, etc.)
+///
+/// Relations (members referring to `ways` by index) are written last. With a
+/// `base` extract, its elements are copied around the converted ones; the
+/// ID starts in `options` must then lie above its IDs. The header bbox is
+/// set as chosen in `options`.
+///
+/// Returns the total number of nodes written (feature, junction and internal),
+/// not counting copied ones.
+pub fn write_pbf_three_pass(
+    ways: &[Way],
+    segments: &mut [Segment],
+    feature_nodes: &[NodeFeature],
+    relations: &[RelationFeature],
+    output_path: &str,
+    base: Option<&BaseExtract>,
+    options: &WriteOptions,
+) -> Result<usize, String> {
+    if let Some(base) = base {
+        let same_file = std::fs::canonicalize(base.path()).ok()
+            .zip(std::fs::canonicalize(output_path).ok())
+            .is_some_and(|(a, b)| a == b);
+        if same_file {
+            return Err(format!("Cannot append to {} in place, write to another file", base.path()));
+        }
+    }
+
+    let ids = options.ids;
+    let mut emitter = PbfEmitter::create_with(output_path, options)?;
+
+    emitter.set_header_bbox(options.bbox, ways, segments, feature_nodes, base);
+
+    if let Some(base) = base {
+        emitter.copy_base(base, ElementType::Node)?;
+    }
+
+    // Feature nodes are written before junction nodes
+    emitter.write_feature_nodes(feature_nodes);
+    emitter.write_network_nodes(ways, segments);
+
+    if let Some(base) = base {
+        emitter.copy_base(base, ElementType::Way)?;
+    }
+
+    // Pass 3: Write all ways
+    for way in ways {
+        let pbf_way = emitter.build_way(way, segments);
+        emitter.write_way(pbf_way);
+    }
+
+    if let Some(base) = base {
+        emitter.copy_base(base, ElementType::Relation)?;
+    }
+    emitter.write_relations(relations, ids.way, ids.relation);
+
+    emitter.finish()
+}
+
+/// Write the network as one file per z/x/y tile at `zoom`
+///
+/// `template` names the tile files (see `tile_path()`); the format follows
+/// its extension as for `write_pbf_three_pass()`. IDs are assigned as for a
+/// single file; relations are not written, as their ways can span tiles.
+/// Each tile's header bbox is the extent of its nodes, unless omitted with
+/// `HeaderBbox::Omit`.
+/// Returns the number of nodes converted and of tiles written.
+pub fn write_pbf_tiles(
+    ways: &[Way],
+    segments: &mut [Segment],
+    feature_nodes: &[NodeFeature],
+    template: &str,
+    zoom: u8,
+    options: &WriteOptions,
+) -> Result<(usize, usize), String> {
+    if zoom > 24 {
+        return Err(format!("Tile zoom {} is out of range (0-24)", zoom));
+    }
+    let mut emitter = PbfEmitter::in_memory(options.ids.node, options.ids.way);
+    emitter.write_feature_nodes(feature_nodes);
+    emitter.write_network_nodes(ways, segments);
+    for way in ways {
+        let pbf_way = emitter.build_way(way, segments);
+        emitter.write_way(pbf_way);
+    }
+    emitter.write_deferred_nodes();
+    let (nodes_written, sorted) = (emitter.nodes_written, emitter.is_sorted());
+    let tiles = tiles::write_tiles(&emitter.into_elements(), template, zoom, options, sorted)?;
+    Ok((nodes_written, tiles))
+}
+
+/// Write the network as parts of at most `max_elements` nodes and ways,
+/// e.g. for uploading in changesets of limited size
+///
+/// `template` names the part files (see `part_path()`); the format follows
+/// its extension as for `write_pbf_three_pass()`. IDs are assigned as for a
+/// single file, and nodes shared between parts keep their ID in each;
+/// relations are not written, as their ways can span parts.
+/// Returns the number of nodes converted and of parts written.
+pub fn write_pbf_parts(
+    ways: &[Way],
+    segments: &mut [Segment],
+    feature_nodes: &[NodeFeature],
+    template: &str,
+    max_elements: usize,
+    options: &WriteOptions,
+) -> Result<(usize, usize), String> {
+    let mut emitter = PbfEmitter::in_memory(options.ids.node, options.ids.way);
+    emitter.write_feature_nodes(feature_nodes);
+    emitter.write_network_nodes(ways, segments);
+    for way in ways {
+        let pbf_way = emitter.build_way(way, segments);
+        emitter.write_way(pbf_way);
+    }
+    emitter.write_deferred_nodes();
+    let (nodes_written, sorted) = (emitter.nodes_written, emitter.is_sorted());
+    let parts = parts::write_parts(&emitter.into_elements(), template, max_elements, options, sorted)?;
+    Ok((nodes_written, parts))
+}
+
+/// Convert degrees to nanodegrees (for PBF format)
+pub fn deg_to_nanodeg(deg: f64) -> i64 {
+    (deg * 1_000_000_000.0) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use pbf_craft::models::{Element, WayNode};
+    use pbf_craft::readers::IterableReader;
+
+    use super::{PbfWay, PendingFile, WaySpill, part_path, write_pbf_parts};
+    use crate::{NodeFeature, PbfEmitter, Segment, Way, WriteOptions};
+    use crate::test_support::{segment, tags};
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn failed_writes_leave_no_output() {
+        let dir = std::env::temp_dir().join("nvdb2osm-full-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("full.opl").to_string_lossy().into_owned();
+        // The temporary file links to /dev/full, where every write fails
+        let temp = PendingFile::new(&path).temp_path();
+        let _ = std::fs::remove_file(&temp);
+        std::os::unix::fs::symlink("/dev/full", &temp).unwrap();
+        let crossings: Vec<NodeFeature> = (1..=1000)
+            .map(|id| {
+                let mut crossing = NodeFeature::new(id, 59.0, 18.0);
+                crossing.tags.insert("highway".to_string(), "crossing".to_string());
+                crossing
+            })
+            .collect();
+
+        let mut emitter = PbfEmitter::create(&path, 1, 1).unwrap();
+        emitter.write_feature_nodes(&crossings);
+        assert!(emitter.finish().unwrap_err().starts_with("Failed to write"));
+        assert!(!std::path::Path::new(&path).exists());
+        assert!(std::fs::symlink_metadata(&temp).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsorted_output_after_a_sorted_header_is_refused() {
+        let (first, second) = (NodeFeature::new(2, 59.0, 18.0), NodeFeature::new(1, 59.0, 18.01));
+        let options = WriteOptions { compression_level: 0, ..WriteOptions::default() };
+
+        // Out of order before any block is flushed: the header is not sorted
+        let path = std::env::temp_dir().join("unsorted.osm.pbf").to_string_lossy().into_owned();
+        let mut emitter = PbfEmitter::create_with(&path, &options).unwrap();
+        emitter.write_feature_nodes(std::slice::from_ref(&first));
+        emitter.write_feature_nodes(std::slice::from_ref(&second));
+        emitter.finish().unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(!bytes.windows(17).any(|w| w == b"Sort.Type_then_ID"));
+
+        // Out of order after the first block wrote a sorted header
+        let path = std::env::temp_dir().join("mislabeled.osm.pbf").to_string_lossy().into_owned();
+        let _ = std::fs::remove_file(&path);
+        let options = WriteOptions { block_size: 1, ..options };
+        let mut emitter = PbfEmitter::create_with(&path, &options).unwrap();
+        emitter.write_feature_nodes(&[first]);
+        emitter.write_feature_nodes(&[second]);
+        assert!(emitter.finish().unwrap_err().contains("not sorted"));
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[test]
+    fn spilled_ways_are_copied_in_order() {
+        let output = std::env::temp_dir().join("nvdb2osm_spill.osm.pbf");
+        let output = output.to_str().unwrap();
+        let mut emitter = PbfEmitter::create(output, 1, 1).unwrap();
+        let mut spill = WaySpill::create(output).unwrap();
+        for id in [7, 8] {
+            let way_nodes = vec![WayNode { id: 1, ..WayNode::default() }, WayNode { id: 2, ..WayNode::default() }];
+            spill.push(PbfWay { id, way_nodes, ..PbfWay::default() }).unwrap();
+        }
+        spill.write_to(&mut emitter).unwrap();
+        emitter.finish().unwrap();
+
+        let ways: Vec<i64> = IterableReader::from_path(output).unwrap()
+            .filter_map(|element| match element {
+                Element::Way(way) => Some(way.id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ways, [7, 8]);
+        assert!(!std::path::Path::new(&format!("{}.ways.tmp", output)).exists());
+    }
+
+    #[test]
+    fn global_junction_nodes_keep_the_output_sorted() {
+        let mut segment = segment(vec![(18.0, 59.0), (18.005, 59.0), (18.01, 59.0)]);
+        segment.global_start_node_id = Some(1_000_000_000_001);
+        segment.global_start_owned = true;
+        let mut segments = vec![segment];
+        let way = Way::new(vec![0], tags(&[("highway", "service")]));
+
+        let path = std::env::temp_dir().join("sorted.osm.pbf").to_string_lossy().into_owned();
+        let options = WriteOptions { compression_level: 0, ..WriteOptions::default() };
+        let mut emitter = PbfEmitter::create_with(&path, &options).unwrap();
+        emitter.write_network_nodes(std::slice::from_ref(&way), &mut segments);
+        let pbf_way = emitter.build_way(&way, &segments);
+        assert_eq!(pbf_way.way_nodes[0].id, 1_000_000_000_001);
+        emitter.write_way(pbf_way);
+        assert!(emitter.is_sorted());
+        emitter.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.windows(17).any(|w| w == b"Sort.Type_then_ID"));
+    }
+
+    #[test]
+    fn feature_nodes_are_written() {
+        let mut segments = vec![segment(vec![(18.0, 59.0), (18.01, 59.0)])];
+        let way = Way::new(vec![0], tags(&[("highway", "footway")]));
+        let mut crossing = NodeFeature::new(1, 59.0, 18.0);
+        crossing.tags.insert("highway".to_string(), "crossing".to_string());
+
+        let path = std::env::temp_dir().join("feature_junction.osm.pbf").to_string_lossy().into_owned();
+        let mut emitter = PbfEmitter::create(&path, 1, 1).unwrap();
+        emitter.write_feature_nodes(&[crossing]);
+        emitter.write_network_nodes(std::slice::from_ref(&way), &mut segments);
+        let pbf_way = emitter.build_way(&way, &segments);
+        assert_eq!(pbf_way.way_nodes[0].id, 1);
+        emitter.write_way(pbf_way);
+        assert_eq!(emitter.finish().unwrap(), 2);
+    }
+
+    #[test]
+    fn output_appears_on_finish() {
+        let path = std::env::temp_dir().join("nvdb2osm_atomic.osm.pbf");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let emitter = PbfEmitter::create_with(path, &WriteOptions::default()).unwrap();
+        assert!(!std::path::Path::new(path).exists());
+        emitter.finish().unwrap();
+        assert!(std::path::Path::new(path).exists());
+    }
+
+    #[test]
+    fn parts_repeat_shared_nodes() {
+        // Four roads in a row, of two nodes each, sharing their end nodes
+        let mut segments: Vec<Segment> = (0..4)
+            .map(|i| segment(vec![(18.0 + 0.01 * i as f64, 59.0), (18.01 + 0.01 * i as f64, 59.0)]))
+            .collect();
+        let ways: Vec<Way> = (0..4)
+            .map(|i| Way::new(vec![i], tags(&[("highway", "primary")])))
+            .collect();
+        let dir = std::env::temp_dir().join("nvdb2osm-parts-test");
+        let template = dir.join("{n}.opl").to_string_lossy().into_owned();
+
+        let (nodes, parts) = write_pbf_parts(&ways, &mut segments, &[], &template, 6, &WriteOptions::default()).unwrap();
+        assert_eq!((nodes, parts), (5, 2));
+        let node_lines = |n| std::fs::read_to_string(part_path(&template, n)).unwrap()
+            .lines().filter(|l| l.starts_with('n')).map(str::to_string).collect::<Vec<_>>();
+        let (first, second) = (node_lines(1), node_lines(2));
+        // The node between the second and third road is in both parts
+        assert_eq!((first.len(), second.len()), (3, 3));
+        assert_eq!(first.iter().filter(|l| second.contains(l)).count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
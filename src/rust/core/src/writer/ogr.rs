@@ -0,0 +1,76 @@
+//! Layout of GDAL's OSM driver
+//!
+//! GIS users read OSM data through ogr2ogr, whose OSM driver turns it into a
+//! `points` layer of the tagged nodes and a `lines` layer of the ways, with a
+//! column for each of a few common keys and all other tags in one
+//! `other_tags` column in hstore syntax (`"key"=>"value",...`). Writing the
+//! conversion in the same layout lets the existing styles and queries for
+//! those layers work on it unchanged.
+
+use rustc_hash::FxHashMap;
+
+/// Tag columns of the `lines` layer, after `osm_id`
+pub const LINE_COLUMNS: [&str; 6] = ["name", "highway", "waterway", "aerialway", "barrier", "man_made"];
+
+/// Tag columns of the `points` layer, after `osm_id`
+pub const POINT_COLUMNS: [&str; 8] = ["name", "barrier", "highway", "ref", "address", "is_in", "place", "man_made"];
+
+/// Tags without a column of their own as an hstore string, sorted by key;
+/// `None` when there are none
+pub fn other_tags(tags: &FxHashMap<String, String>, columns: &[&str]) -> Option<String> {
+    let mut others: Vec<(&String, &String)> = tags.iter().filter(|(k, _)| !columns.contains(&k.as_str())).collect();
+    if others.is_empty() {
+        return None;
+    }
+    others.sort();
+    let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    Some(others.iter().map(|(k, v)| format!("\"{}\"=>\"{}\"", quote(k), quote(v))).collect::<Vec<_>>().join(","))
+}
+
+/// Drawing order of a line as computed by osm2pgsql and GDAL: the road
+/// class rank, 10 per `layer`, +10 on bridges, -10 in tunnels and +5 for
+/// railways
+pub fn z_order(tags: &FxHashMap<String, String>) -> i32 {
+    let tag = |key: &str| tags.get(key).map(String::as_str);
+    let is_yes = |key: &str| matches!(tag(key), Some("yes" | "true" | "1"));
+    let rank = match tag("highway").map(|h| h.trim_end_matches("_link")) {
+        Some("motorway") => 9,
+        Some("trunk") => 8,
+        Some("primary") => 7,
+        Some("secondary") => 6,
+        Some("tertiary") => 4,
+        Some("residential" | "unclassified" | "road" | "minor") => 3,
+        _ => 0,
+    };
+    let layer = tag("layer").and_then(|l| l.trim().parse::<i32>().ok()).unwrap_or(0);
+    let mut z = rank + 10 * layer;
+    if is_yes("bridge") {
+        z += 10;
+    }
+    if is_yes("tunnel") {
+        z -= 10;
+    }
+    if tag("railway").is_some() {
+        z += 5;
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LINE_COLUMNS, other_tags, z_order};
+    use crate::test_support::tags;
+
+    #[test]
+    fn other_tags_quote_hstore_values() {
+        let tags = tags(&[("highway", "primary"), ("ref", "E4"), ("name:sv", "Väg \"4\"")]);
+        assert_eq!(other_tags(&tags, &LINE_COLUMNS).unwrap(), r#""name:sv"=>"Väg \"4\"","ref"=>"E4""#);
+    }
+
+    #[test]
+    fn z_order_of_roads() {
+        assert_eq!(z_order(&tags(&[("highway", "motorway")])), 9);
+        assert_eq!(z_order(&tags(&[("highway", "residential"), ("bridge", "yes"), ("layer", "1")])), 23);
+        assert_eq!(z_order(&tags(&[("highway", "cycleway"), ("tunnel", "yes")])), -10);
+    }
+}
@@ -0,0 +1,94 @@
+//! OPL output
+//!
+//! Writes osmium's line-based OPL text format (one object per line). Unlike PBF
+//! the output is greppable and diff-friendly, which makes it handy for
+//! regression-testing tag mapping changes. Tags are sorted by key so that two
+//! runs over the same input produce identical files.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use pbf_craft::models::{Element, ElementType, Tag};
+
+pub struct OplWriter {
+    out: BufWriter<File>,
+}
+
+impl OplWriter {
+    pub fn from_path(output_path: &str) -> Result<Self, String> {
+        let file = File::create(output_path)
+            .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+        Ok(Self { out: BufWriter::new(file) })
+    }
+
+    /// Write an element as a single OPL line
+    pub fn write(&mut self, element: Element) -> std::io::Result<()> {
+        let line = match element {
+            Element::Node(node) => format!(
+                "n{} v0 dV c0 t i0 u T{} x{:.7} y{:.7}",
+                node.id,
+                encode_tags(&node.tags),
+                node.longitude as f64 / 1e9,
+                node.latitude as f64 / 1e9,
+            ),
+            Element::Way(way) => {
+                let refs: Vec<String> = way.way_nodes.iter().map(|wn| format!("n{}", wn.id)).collect();
+                format!(
+                    "w{} v0 dV c0 t i0 u T{} N{}",
+                    way.id,
+                    encode_tags(&way.tags),
+                    refs.join(","),
+                )
+            }
+            Element::Relation(relation) => {
+                let members: Vec<String> = relation.members.iter().map(|m| {
+                    let kind = match m.member_type {
+                        ElementType::Node => 'n',
+                        ElementType::Way => 'w',
+                        ElementType::Relation => 'r',
+                    };
+                    format!("{}{}@{}", kind, m.member_id, encode_string(&m.role))
+                }).collect();
+                format!(
+                    "r{} v0 dV c0 t i0 u T{} M{}",
+                    relation.id,
+                    encode_tags(&relation.tags),
+                    members.join(","),
+                )
+            }
+        };
+        writeln!(self.out, "{}", line)
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn encode_tags(tags: &[Tag]) -> String {
+    let mut sorted: Vec<&Tag> = tags.iter().collect();
+    sorted.sort_by(|a, b| a.key.cmp(&b.key));
+    sorted
+        .iter()
+        .map(|t| format!("{}={}", encode_string(&t.key), encode_string(&t.value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Escape a string the way osmium does: characters outside its plain ranges
+/// (including space, `%`, `,`, `=` and `@`) become `%<hex codepoint>%`
+fn encode_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        let cp = c as u32;
+        let plain = matches!(cp,
+            0x21..=0x24 | 0x26..=0x2b | 0x2d..=0x3c | 0x3e..=0x3f | 0x41..=0x7e
+            | 0xa1..=0xac | 0xae..=0x05ff);
+        if plain {
+            out.push(c);
+        } else {
+            out.push_str(&format!("%{:x}%", cp));
+        }
+    }
+    out
+}
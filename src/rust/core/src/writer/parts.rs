@@ -0,0 +1,136 @@
+//! Output in upload-sized parts
+//!
+//! Staged imports upload a conversion in changesets of at most a few thousand
+//! elements. `write_pbf_parts()` splits the network into files below a
+//! given number of nodes and ways: the area is halved along its longer side,
+//! at the median of the element positions, until each part is small enough,
+//! so every part covers a compact area. A way is placed by the centre of its
+//! extent and a feature node by its position.
+//!
+//! As with tiles, elements are numbered once for the whole network, and the
+//! nodes a way references are written to its part; a node shared by ways in
+//! two parts appears in both with the same ID. Parts are numbered from 1 in
+//! the order they are split off, so neighbouring numbers are neighbouring
+//! areas.
+
+use pbf_craft::models::Element;
+use rustc_hash::FxHashSet;
+
+use super::tiles::{node_index, write_group};
+use super::WriteOptions;
+
+/// Output path of part `n`: `{n}` in `template` is replaced, a template
+/// without it is treated as a directory (`<dir>/{n}.osm.pbf`)
+pub fn part_path(template: &str, n: usize) -> String {
+    let template = if template.contains("{n}") {
+        template.to_string()
+    } else {
+        format!("{}/{{n}}.osm.pbf", template.trim_end_matches('/'))
+    };
+    template.replace("{n}", &n.to_string())
+}
+
+/// A way or feature node to place in a part
+struct Item {
+    /// Index in the elements
+    index: usize,
+    /// Position in nanodegrees (longitude, latitude)
+    position: (i64, i64),
+    is_way: bool,
+    /// IDs of the nodes written with it, the item itself for a node
+    nodes: Vec<i64>,
+}
+
+/// Write buffered elements (nodes before ways, as produced by `PbfEmitter`)
+/// as parts of at most `max_elements` nodes and ways, with the extent of
+/// its nodes as header bbox unless omitted in `options`, declared sorted if
+/// `sorted`; returns the number of parts written
+///
+/// A single way with more nodes than `max_elements` is written to a part of
+/// its own, above the limit.
+pub(super) fn write_parts(elements: &[Element], template: &str, max_elements: usize, options: &WriteOptions, sorted: bool) -> Result<usize, String> {
+    let node_index = node_index(elements);
+    let coord = |id: i64| match node_index.get(&id).map(|&i| &elements[i]) {
+        Some(Element::Node(n)) => Some((n.longitude, n.latitude)),
+        _ => None,
+    };
+
+    let mut items = Vec::new();
+    for (index, element) in elements.iter().enumerate() {
+        match element {
+            Element::Way(way) => {
+                let mut extent: Option<(i64, i64, i64, i64)> = None;
+                for wn in &way.way_nodes {
+                    if let Some((lon, lat)) = coord(wn.id) {
+                        let e = extent.get_or_insert((lon, lat, lon, lat));
+                        *e = (e.0.min(lon), e.1.min(lat), e.2.max(lon), e.3.max(lat));
+                    }
+                }
+                let Some((left, bottom, right, top)) = extent else { continue };
+                let nodes = way.way_nodes.iter().map(|wn| wn.id).collect();
+                items.push(Item { index, position: ((left + right) / 2, (bottom + top) / 2), is_way: true, nodes });
+            }
+            Element::Node(node) if !node.tags.is_empty() => {
+                items.push(Item { index, position: (node.longitude, node.latitude), is_way: false, nodes: vec![node.id] });
+            }
+            _ => {}
+        }
+    }
+
+    let mut parts = Vec::new();
+    split(&mut items, max_elements.max(1), &mut parts);
+    for (n, part) in parts.iter().enumerate() {
+        let mut members: Vec<usize> = part.iter().map(|item| item.index).collect();
+        members.sort_unstable();
+        let referenced: FxHashSet<i64> = part.iter().flat_map(|item| item.nodes.iter().copied()).collect();
+        write_group(elements, &node_index, &part_path(template, n + 1), &members, &referenced, options, sorted)?;
+    }
+    Ok(parts.len())
+}
+
+/// Split `items` at the median of their positions along the longer side of
+/// their extent until each part has at most `max_elements` nodes and ways
+fn split<'a>(items: &'a mut [Item], max_elements: usize, parts: &mut Vec<&'a [Item]>) {
+    if items.is_empty() {
+        return;
+    }
+    if items.len() == 1 || element_count(items) <= max_elements {
+        parts.push(items);
+        return;
+    }
+    let (mut min, mut max) = (items[0].position, items[0].position);
+    for item in items.iter() {
+        min = (min.0.min(item.position.0), min.1.min(item.position.1));
+        max = (max.0.max(item.position.0), max.1.max(item.position.1));
+    }
+    // Compare the sides in metres: a degree of longitude shrinks with latitude
+    let lat = ((min.1 + max.1) as f64 / 2e9).to_radians().cos();
+    let by_longitude = (max.0 - min.0) as f64 * lat >= (max.1 - min.1) as f64;
+    let middle = items.len() / 2;
+    if by_longitude {
+        items.select_nth_unstable_by_key(middle, |item| (item.position.0, item.index));
+    } else {
+        items.select_nth_unstable_by_key(middle, |item| (item.position.1, item.index));
+    }
+    let (first, second) = items.split_at_mut(middle);
+    split(first, max_elements, parts);
+    split(second, max_elements, parts);
+}
+
+/// Number of nodes and ways written for `items`
+fn element_count(items: &[Item]) -> usize {
+    let ways = items.iter().filter(|item| item.is_way).count();
+    let nodes: FxHashSet<i64> = items.iter().flat_map(|item| item.nodes.iter().copied()).collect();
+    ways + nodes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::part_path;
+
+    #[test]
+    fn part_path_fills_the_template() {
+        assert_eq!(part_path("parts", 3), "parts/3.osm.pbf");
+        assert_eq!(part_path("upload_{n}.osm", 3), "upload_3.osm");
+    }
+}
@@ -0,0 +1,253 @@
+//! PostGIS output (feature `postgis`)
+//!
+//! Inverse of the osm-schema reader in the vendored pbf-craft CLI
+//! (`pbf-craft-cli/src/db/db_reader.rs`): inserts nodes, ways and relations into
+//! the `current_*` tables of an OSM API database so osm2pgsql-style stacks can
+//! skip the intermediate PBF. Rows are buffered per table and loaded with
+//! `COPY ... FROM STDIN` every `FLUSH_ROWS` elements, all in one transaction.
+//!
+//! The target is a connection URL, e.g. `postgresql://user:pw@host/osm`. The
+//! tables are those of `WriteOptions::db_schema`, or else of the connection's
+//! `search_path`. Every element references `WriteOptions::changeset_id`,
+//! which must exist when the schema has the standard foreign keys.
+
+use std::io::Write;
+
+use pbf_craft::models::{Element, ElementType, Tag};
+use postgres::{Client, NoTls};
+
+use super::WriteOptions;
+
+const FLUSH_ROWS: usize = 8000;
+
+const TABLES: [(&str, &str); 8] = [
+    ("current_nodes", "id, latitude, longitude, changeset_id, visible, \"timestamp\", tile, version"),
+    ("current_node_tags", "node_id, k, v"),
+    ("current_ways", "id, changeset_id, \"timestamp\", visible, version"),
+    ("current_way_tags", "way_id, k, v"),
+    ("current_way_nodes", "way_id, node_id, sequence_id"),
+    ("current_relations", "id, changeset_id, \"timestamp\", visible, version"),
+    ("current_relation_tags", "relation_id, k, v"),
+    ("current_relation_members", "relation_id, member_type, member_id, member_role, sequence_id"),
+];
+
+/// COPY text rows of the elements written since the last flush, per table
+///
+/// Coordinates are rounded to the API database's 1e-7 degree integers.
+#[derive(Debug, Default)]
+pub struct CopyRows {
+    buffers: [String; TABLES.len()],
+    elements: usize,
+}
+
+impl CopyRows {
+    pub fn push(&mut self, element: Element, changeset_id: i64, timestamp: &str) {
+        let (cs, ts) = (changeset_id, timestamp);
+        match element {
+            Element::Node(node) => {
+                let lat = nanodeg_to_api(node.latitude);
+                let lon = nanodeg_to_api(node.longitude);
+                self.row(0, format!("{}\t{}\t{}\t{}\tt\t{}\t{}\t1", node.id, lat, lon, cs, ts, quad_tile(lat, lon)));
+                self.tags(1, node.id, &node.tags);
+            }
+            Element::Way(way) => {
+                self.row(2, format!("{}\t{}\t{}\tt\t1", way.id, cs, ts));
+                self.tags(3, way.id, &way.tags);
+                for (seq, wn) in way.way_nodes.iter().enumerate() {
+                    self.row(4, format!("{}\t{}\t{}", way.id, wn.id, seq + 1));
+                }
+            }
+            Element::Relation(relation) => {
+                self.row(5, format!("{}\t{}\t{}\tt\t1", relation.id, cs, ts));
+                self.tags(6, relation.id, &relation.tags);
+                for (seq, member) in relation.members.iter().enumerate() {
+                    let member_type = match member.member_type {
+                        ElementType::Node => "Node",
+                        ElementType::Way => "Way",
+                        ElementType::Relation => "Relation",
+                    };
+                    self.row(7, format!(
+                        "{}\t{}\t{}\t{}\t{}",
+                        relation.id, member_type, member.member_id, escape_copy(&member.role), seq + 1
+                    ));
+                }
+            }
+        }
+        self.elements += 1;
+    }
+
+    /// Rows buffered for `table`, `None` for a table not in the schema
+    pub fn table(&self, table: &str) -> Option<&str> {
+        TABLES.iter().position(|(name, _)| *name == table).map(|i| self.buffers[i].as_str())
+    }
+
+    /// Number of elements buffered
+    pub fn len(&self) -> usize {
+        self.elements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements == 0
+    }
+
+    fn row(&mut self, table: usize, row: String) {
+        let buffer = &mut self.buffers[table];
+        buffer.push_str(&row);
+        buffer.push('\n');
+    }
+
+    fn tags(&mut self, table: usize, id: i64, tags: &[Tag]) {
+        for tag in tags {
+            self.row(table, format!("{}\t{}\t{}", id, escape_copy(&tag.key), escape_copy(&tag.value)));
+        }
+    }
+}
+
+/// Loads elements into the database in a single transaction
+///
+/// Nothing is visible to other sessions until `finish()` commits; a failed
+/// COPY (e.g. a foreign key violation) rolls the whole load back, so the
+/// schema never holds nodes without their ways. The first error is returned
+/// by every later call, `finish()` included.
+pub struct PgWriter {
+    client: Client,
+    changeset_id: i64,
+    /// Table names as written in the COPY statements, in `TABLES` order
+    tables: Vec<String>,
+    timestamp: String,
+    rows: CopyRows,
+    error: Option<String>,
+}
+
+impl PgWriter {
+    /// Connect to `url` and start the transaction, with the changeset and
+    /// schema of `options`
+    pub fn connect(url: &str, options: &WriteOptions) -> Result<Self, String> {
+        let mut client = Client::connect(url, NoTls)
+            .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+        let row = client
+            .query_one("SELECT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')", &[])
+            .map_err(|e| format!("Failed to query server time: {}", e))?;
+        client.batch_execute("BEGIN").map_err(|e| format!("Failed to start a transaction: {}", e))?;
+
+        Ok(Self {
+            client,
+            changeset_id: options.changeset_id,
+            tables: TABLES.iter().map(|(table, _)| qualified_table(options.db_schema.as_deref(), table)).collect(),
+            timestamp: row.get(0),
+            rows: CopyRows::default(),
+            error: None,
+        })
+    }
+
+    pub fn write(&mut self, element: Element) -> Result<(), String> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.rows.push(element, self.changeset_id, &self.timestamp);
+        if self.rows.len() >= FLUSH_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// COPY all buffered rows, parents before children (nodes, ways, relations);
+    /// rolls the transaction back on failure
+    fn flush(&mut self) -> Result<(), String> {
+        let rows = std::mem::take(&mut self.rows);
+        let columns = TABLES.iter().map(|(_, columns)| columns);
+        let result = self.tables.iter().zip(columns).zip(&rows.buffers).filter(|(_, buffer)| !buffer.is_empty()).try_for_each(
+            |((table, columns), buffer)| {
+                let sql = format!("COPY {} ({}) FROM STDIN", table, columns);
+                let mut copy = self.client.copy_in(sql.as_str())
+                    .map_err(|e| format!("Failed to start COPY into {}: {}", table, e))?;
+                copy.write_all(buffer.as_bytes())
+                    .map_err(|e| format!("Failed to COPY into {}: {}", table, e))?;
+                copy.finish().map(|_| ()).map_err(|e| format!("Failed to COPY into {}: {}", table, e))
+            },
+        );
+        if let Err(error) = result {
+            let _ = self.client.batch_execute("ROLLBACK");
+            let error = format!("PostGIS load rolled back: {}", error);
+            self.error = Some(error.clone());
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Load the remaining rows and commit; fails with the first error of the
+    /// load if there was one
+    pub fn finish(&mut self) -> Result<(), String> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.flush()?;
+        self.client.batch_execute("COMMIT").map_err(|e| format!("Failed to commit: {}", e))
+    }
+}
+
+/// `table` in `schema` (a quoted identifier), or unqualified without one
+pub fn qualified_table(schema: Option<&str>, table: &str) -> String {
+    match schema {
+        Some(schema) => format!("\"{}\".{}", schema.replace('"', "\"\""), table),
+        None => table.to_string(),
+    }
+}
+
+/// Nanodegrees to the API database's 1e-7 degree integers, rounded to nearest
+fn nanodeg_to_api(nanodeg: i64) -> i64 {
+    (nanodeg as f64 / 100.0).round() as i64
+}
+
+/// Escape a value for COPY's text format
+fn escape_copy(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// OSM API quadtile index: 16-bit x/y tile numbers with interleaved bits
+fn quad_tile(lat: i64, lon: i64) -> i64 {
+    let x = (((lon as f64 / 1e7 + 180.0) * 65535.0 / 360.0).round() as u64).min(65535);
+    let y = (((lat as f64 / 1e7 + 90.0) * 65535.0 / 180.0).round() as u64).min(65535);
+    let mut tile = 0u64;
+    for i in (0..16).rev() {
+        tile = (tile << 1) | ((x >> i) & 1);
+        tile = (tile << 1) | ((y >> i) & 1);
+    }
+    tile as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use pbf_craft::models::{Element, Node, Tag};
+
+    use super::{CopyRows, qualified_table};
+
+    #[test]
+    fn copy_rows_escape_text() {
+        let mut rows = CopyRows::default();
+        let tags = vec![Tag { key: "name".to_string(), value: "Storgatan\t1".to_string() }];
+        let node = Node { id: 5, latitude: 63_825_847_450, longitude: -20_263_035_050, tags, ..Node::default() };
+        rows.push(Element::Node(node), 1, "2024-05-01 12:00:00");
+        assert_eq!(rows.len(), 1);
+        let node_row = rows.table("current_nodes").unwrap();
+        assert!(node_row.starts_with("5\t638258475\t-202630351\t1\tt\t2024-05-01 12:00:00\t"));
+        assert_eq!(rows.table("current_node_tags"), Some("5\tname\tStorgatan\\t1\n"));
+    }
+
+    #[test]
+    fn qualified_table_quotes_the_schema() {
+        assert_eq!(qualified_table(None, "current_nodes"), "current_nodes");
+        assert_eq!(qualified_table(Some("nvdb"), "current_nodes"), "\"nvdb\".current_nodes");
+        assert_eq!(qualified_table(Some("a\"b"), "current_ways"), "\"a\"\"b\".current_ways");
+    }
+}
@@ -0,0 +1,196 @@
+//! Renumbering of written files
+//!
+//! Outputs numbered from 1 collide with other datasets when merged.
+//! `renumber()` gives the nodes, ways and relations of a file new IDs from
+//! given first IDs, in file order, and rewrites way nodes and relation
+//! members to match. A negative first ID counts down (-1, -2, ...), as
+//! editors number new objects.
+//!
+//! The file is read twice: the first pass assigns the IDs, so relation
+//! members can refer to relations further on. Way nodes and relation members
+//! referring to elements not in the file (as in clipped extracts) are
+//! dropped, as their old IDs could collide with the new ones.
+
+use pbf_craft::models::{Bound, Element, ElementType};
+use pbf_craft::readers::IterableReader;
+use rustc_hash::FxHashMap;
+
+use super::{deg_to_nanodeg, sort_key, ElementSink, HeaderBbox, WriteOptions};
+
+/// Counts of a `renumber()` run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Renumbered {
+    pub nodes: usize,
+    pub ways: usize,
+    pub relations: usize,
+    /// Way nodes and relation members referring to elements not in the
+    /// file, which were dropped
+    pub dangling: usize,
+}
+
+/// New IDs of one element type
+struct IdMap {
+    ids: FxHashMap<i64, i64>,
+    next: i64,
+}
+
+impl IdMap {
+    fn new(first: i64) -> Self {
+        Self { ids: FxHashMap::default(), next: first }
+    }
+
+    fn assign(&mut self, id: i64) {
+        let next = self.next;
+        if self.ids.insert(id, next).is_none() {
+            self.next += next.signum();
+        }
+    }
+}
+
+/// Write the file at `input` to `output` with IDs from `options.ids`, keeping
+/// way nodes and relation members pointing at the same elements
+///
+/// The format of `output` follows its extension as for
+/// `write_pbf_three_pass()`; its header bbox is the extent of the nodes,
+/// unless given or omitted in `options`. Fails if a first ID is 0.
+pub fn renumber(input: &str, output: &str, options: &WriteOptions) -> Result<Renumbered, String> {
+    let first = options.ids;
+    if first.node == 0 || first.way == 0 || first.relation == 0 {
+        return Err("First IDs must not be 0".to_string());
+    }
+    let open = || IterableReader::from_path(input).map_err(|e| format!("Failed to open {}: {}", input, e));
+
+    // Pass 1: new IDs in file order, extent of the nodes, input order
+    let (mut nodes, mut ways, mut relations) = (IdMap::new(first.node), IdMap::new(first.way), IdMap::new(first.relation));
+    let mut extent: Option<(i64, i64, i64, i64)> = None;
+    let mut by_type = true;
+    let mut last_type = 0;
+    for element in open()? {
+        let element_type = sort_key(&element).0;
+        by_type &= element_type >= last_type;
+        last_type = element_type;
+        match element {
+            Element::Node(node) => {
+                nodes.assign(node.id);
+                let (lon, lat) = (node.longitude, node.latitude);
+                let e = extent.get_or_insert((lon, lat, lon, lat));
+                *e = (e.0.min(lon), e.1.min(lat), e.2.max(lon), e.3.max(lat));
+            }
+            Element::Way(way) => ways.assign(way.id),
+            Element::Relation(relation) => relations.assign(relation.id),
+        }
+    }
+
+    let (mut sink, file) = ElementSink::from_path(output, options)?;
+    // IDs follow file order, so they ascend within each type when counting up
+    sink.set_sorted(by_type && first.node > 0 && first.way > 0 && first.relation > 0);
+    let bbox = match options.bbox {
+        HeaderBbox::Omit => None,
+        HeaderBbox::Fixed(west, south, east, north) => {
+            Some((deg_to_nanodeg(west), deg_to_nanodeg(south), deg_to_nanodeg(east), deg_to_nanodeg(north)))
+        }
+        HeaderBbox::All | HeaderBbox::Emitted => extent,
+    };
+    if let Some((left, bottom, right, top)) = bbox {
+        sink.set_bbox(Bound { left, right, top, bottom, origin: "nvdb2osmr".to_string() });
+    }
+
+    // Pass 2: rewrite, dropping the references that cannot be remapped
+    let mut counts = Renumbered::default();
+    let mut remap = |map: &IdMap, id: &mut i64| match map.ids.get(id) {
+        Some(&new) => {
+            *id = new;
+            true
+        }
+        None => {
+            counts.dangling += 1;
+            false
+        }
+    };
+    for mut element in open()? {
+        match &mut element {
+            Element::Node(node) => {
+                remap(&nodes, &mut node.id);
+            }
+            Element::Way(way) => {
+                remap(&ways, &mut way.id);
+                way.way_nodes.retain_mut(|way_node| remap(&nodes, &mut way_node.id));
+            }
+            Element::Relation(relation) => {
+                remap(&relations, &mut relation.id);
+                relation.members.retain_mut(|member| {
+                    let map = match member.member_type {
+                        ElementType::Node => &nodes,
+                        ElementType::Way => &ways,
+                        ElementType::Relation => &relations,
+                    };
+                    remap(map, &mut member.member_id)
+                });
+            }
+        }
+        sink.write(element)?;
+    }
+    sink.finish()?;
+    drop(sink);
+    if let Some(file) = file {
+        file.commit()?;
+    }
+    counts.nodes = nodes.ids.len();
+    counts.ways = ways.ids.len();
+    counts.relations = relations.ids.len();
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use pbf_craft::models::WayNode;
+
+    use crate::{ElementIds, PbfEmitter, RelationFeature, Way, WriteOptions};
+    use crate::test_support::{segment, tags};
+    use crate::writer::renumber;
+
+    #[test]
+    fn renumber_drops_references_outside_the_file() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("renumber_dangling_in.osm.pbf").to_string_lossy().into_owned();
+        let output = dir.join("renumber_dangling_out.opl").to_string_lossy().into_owned();
+        let mut segments = vec![segment(vec![(18.0, 59.0), (18.01, 59.0)])];
+        let ways = vec![Way::new(vec![0], tags(&[("highway", "primary")]))];
+        let mut emitter = PbfEmitter::create(&input, 1, 1).unwrap();
+        emitter.write_network_nodes(&ways, &mut segments);
+        // A way node and a relation member clipped away
+        let mut pbf_way = emitter.build_way(&ways[0], &segments);
+        pbf_way.way_nodes.push(WayNode::new_without_coords(3));
+        emitter.write_way(pbf_way);
+        let relation = RelationFeature { tags: tags(&[("type", "bridge")]), members: vec![(0, "across".to_string()), (1, "across".to_string())] };
+        emitter.write_relations(&[relation], 1, 1);
+        emitter.finish().unwrap();
+
+        let options = WriteOptions { ids: ElementIds { node: 3, way: 2, relation: 2 }, ..WriteOptions::default() };
+        let counts = renumber(&input, &output, &options).unwrap();
+        assert_eq!((counts.nodes, counts.ways, counts.relations, counts.dangling), (2, 1, 1, 2));
+        let opl = std::fs::read_to_string(&output).unwrap();
+        assert!(opl.lines().any(|l| l.starts_with("w2 ") && l.ends_with("Nn3,n4")), "{}", opl);
+        assert!(opl.lines().any(|l| l.starts_with("r2 ") && l.ends_with("Mw2@across")), "{}", opl);
+    }
+
+    #[test]
+    fn renumber_uses_negative_ids() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("renumber_in.osm.pbf").to_string_lossy().into_owned();
+        let output = dir.join("renumber_out.opl").to_string_lossy().into_owned();
+        let mut segments = vec![segment(vec![(18.0, 59.0), (18.01, 59.0)])];
+        let ways = vec![Way::new(vec![0], tags(&[("highway", "primary")]))];
+        let mut emitter = PbfEmitter::create(&input, 1, 1).unwrap();
+        emitter.write_network_nodes(&ways, &mut segments);
+        let pbf_way = emitter.build_way(&ways[0], &segments);
+        emitter.write_way(pbf_way);
+        emitter.finish().unwrap();
+
+        let options = WriteOptions { ids: ElementIds { node: -1, way: -1, relation: -1 }, ..WriteOptions::default() };
+        let counts = renumber(&input, &output, &options).unwrap();
+        assert_eq!((counts.nodes, counts.ways, counts.dangling), (2, 1, 0));
+        let opl = std::fs::read_to_string(&output).unwrap();
+        assert!(opl.lines().any(|l| l.starts_with("w-1 ") && l.ends_with("Nn-1,n-2")));
+    }
+}
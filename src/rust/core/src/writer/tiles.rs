@@ -0,0 +1,171 @@
+//! Tiled output
+//!
+//! Splits the network over a web mercator z/x/y tile grid and writes one file
+//! per tile, e.g. for serving routing graphs region by region. Each way goes to
+//! the tile containing the centre of its extent; the nodes it references are
+//! written to that tile too, so nodes on tile borders appear in every tile that
+//! uses them, with the same ID. Feature nodes go to the tile they lie in.
+//!
+//! Elements are numbered once for the whole network (in memory), so tiles can
+//! be merged back with `osmium merge` without ID clashes.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use pbf_craft::models::{Bound, Element};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::{ElementSink, HeaderBbox, WriteOptions};
+
+/// Output path of the tile `z/x/y`: `{z}`, `{x}` and `{y}` in `template` are
+/// replaced, a template without `{x}` is treated as a directory
+/// (`<dir>/{z}/{x}/{y}.osm.pbf`)
+pub fn tile_path(template: &str, z: u8, x: u32, y: u32) -> String {
+    let template = if template.contains("{x}") {
+        template.to_string()
+    } else {
+        format!("{}/{{z}}/{{x}}/{{y}}.osm.pbf", template.trim_end_matches('/'))
+    };
+    template
+        .replace("{z}", &z.to_string())
+        .replace("{x}", &x.to_string())
+        .replace("{y}", &y.to_string())
+}
+
+/// Tile `(x, y)` containing a coordinate at zoom `z`
+pub fn tile_of(lon: f64, lat: f64, z: u8) -> (u32, u32) {
+    let n = f64::from(1u32 << z);
+    let lat = lat.clamp(-85.051_128, 85.051_128).to_radians();
+    let x = ((lon + 180.0) / 360.0 * n).floor();
+    let y = ((1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n).floor();
+    (x.clamp(0.0, n - 1.0) as u32, y.clamp(0.0, n - 1.0) as u32)
+}
+
+/// Write buffered elements (nodes before ways, as produced by `PbfEmitter`)
+/// as one file per tile, with the extent of its nodes as header bbox unless
+/// omitted in `options`, declared sorted if `sorted`; returns the number of
+/// tiles written
+pub(super) fn write_tiles(elements: &[Element], template: &str, zoom: u8, options: &WriteOptions, sorted: bool) -> Result<usize, String> {
+    let node_index = node_index(elements);
+    let coord = |id: i64| match node_index.get(&id).map(|&i| &elements[i]) {
+        Some(Element::Node(n)) => Some((n.longitude, n.latitude)),
+        _ => None,
+    };
+
+    // Element indices per tile, ordered for reproducible output
+    let mut tiles: BTreeMap<(u32, u32), Vec<usize>> = BTreeMap::new();
+    let mut referenced: FxHashMap<(u32, u32), FxHashSet<i64>> = FxHashMap::default();
+    for (i, element) in elements.iter().enumerate() {
+        match element {
+            Element::Way(way) => {
+                let mut extent: Option<(i64, i64, i64, i64)> = None;
+                for wn in &way.way_nodes {
+                    if let Some((lon, lat)) = coord(wn.id) {
+                        let e = extent.get_or_insert((lon, lat, lon, lat));
+                        *e = (e.0.min(lon), e.1.min(lat), e.2.max(lon), e.3.max(lat));
+                    }
+                }
+                let Some((left, bottom, right, top)) = extent else { continue };
+                let lon = (left + right) as f64 / 2e9;
+                let lat = (bottom + top) as f64 / 2e9;
+                let tile = tile_of(lon, lat, zoom);
+                tiles.entry(tile).or_default().push(i);
+                referenced.entry(tile).or_default().extend(way.way_nodes.iter().map(|wn| wn.id));
+            }
+            // Feature nodes (the tagged ones) are placed by their own position
+            Element::Node(node) if !node.tags.is_empty() => {
+                let tile = tile_of(node.longitude as f64 / 1e9, node.latitude as f64 / 1e9, zoom);
+                tiles.entry(tile).or_default().push(i);
+            }
+            _ => {}
+        }
+    }
+
+    let empty = FxHashSet::default();
+    for (&(x, y), members) in &tiles {
+        let path = tile_path(template, zoom, x, y);
+        let refs = referenced.get(&(x, y)).unwrap_or(&empty);
+        write_group(elements, &node_index, &path, members, refs, options, sorted)?;
+    }
+    Ok(tiles.len())
+}
+
+/// Index in `elements` of each node ID
+pub(super) fn node_index(elements: &[Element]) -> FxHashMap<i64, usize> {
+    elements
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| match e {
+            Element::Node(n) => Some((n.id, i)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Write the elements `members` of `elements` and the nodes `referenced` by
+/// them to `path`, creating its directory, nodes first; `node_index` maps
+/// node IDs to their index in `elements`
+pub(super) fn write_group(
+    elements: &[Element],
+    node_index: &FxHashMap<i64, usize>,
+    path: &str,
+    members: &[usize],
+    referenced: &FxHashSet<i64>,
+    options: &WriteOptions,
+    sorted: bool,
+) -> Result<(), String> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let (mut sink, file) = ElementSink::from_path(path, options)?;
+    sink.set_sorted(sorted);
+
+    let mut node_indices: Vec<usize> = members
+        .iter()
+        .copied()
+        .filter(|&i| matches!(elements[i], Element::Node(_)))
+        .collect();
+    node_indices.extend(referenced.iter().filter_map(|id| node_index.get(id).copied()));
+    node_indices.sort_unstable();
+    node_indices.dedup();
+
+    // Header bbox of the file's own nodes
+    let mut bbox: Option<(i64, i64, i64, i64)> = None;
+    for &i in &node_indices {
+        if let Element::Node(n) = &elements[i] {
+            let b = bbox.get_or_insert((n.longitude, n.latitude, n.longitude, n.latitude));
+            *b = (b.0.min(n.longitude), b.1.min(n.latitude), b.2.max(n.longitude), b.3.max(n.latitude));
+        }
+    }
+    if let Some((left, bottom, right, top)) = bbox.filter(|_| options.bbox != HeaderBbox::Omit) {
+        sink.set_bbox(Bound { left, right, top, bottom, origin: "nvdb2osmr".to_string() });
+    }
+
+    for &i in &node_indices {
+        sink.write(elements[i].clone())?;
+    }
+    for &i in members {
+        if matches!(elements[i], Element::Way(_)) {
+            sink.write(elements[i].clone())?;
+        }
+    }
+    sink.finish()?;
+    drop(sink);
+    if let Some(file) = file {
+        file.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::{tile_of, tile_path};
+
+    #[test]
+    fn tile_path_fills_the_template() {
+        let (x, y) = tile_of(18.07, 59.33, 10); // Stockholm
+        assert_eq!(tile_path("tiles", 10, x, y), "tiles/10/563/301.osm.pbf");
+        assert_eq!(tile_path("sthlm_{z}_{x}_{y}.opl", 10, x, y), "sthlm_10_563_301.opl");
+    }
+}
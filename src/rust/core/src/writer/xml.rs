@@ -0,0 +1,134 @@
+//! OSM XML output
+//!
+//! Writes the `.osm` XML format read by JOSM, osmium and most other OSM
+//! tools. Like OPL it is meant for inspection and small extracts; PBF stays
+//! the format for whole-country output. Tags are sorted by key so that two
+//! runs over the same input produce identical files.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use pbf_craft::models::{Bound, Element, ElementType, Tag};
+
+pub struct XmlWriter {
+    out: BufWriter<File>,
+    bbox: Option<Bound>,
+    upload: bool,
+    started: bool,
+}
+
+impl XmlWriter {
+    pub fn from_path(output_path: &str) -> Result<Self, String> {
+        let file = File::create(output_path)
+            .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+        Ok(Self { out: BufWriter::new(file), bbox: None, upload: true, started: false })
+    }
+
+    /// Written as `<bounds>` unless elements have been written already
+    pub fn set_bbox(&mut self, bbox: Bound) {
+        if !self.started {
+            self.bbox = Some(bbox);
+        }
+    }
+
+    /// Mark the file as not for upload (`upload="never"`, honoured by JOSM)
+    /// unless elements have been written already
+    pub fn never_upload(&mut self) {
+        self.upload = false;
+    }
+
+    fn start(&mut self) -> std::io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        writeln!(self.out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        let upload = if self.upload { "" } else { " upload=\"never\"" };
+        writeln!(self.out, "<osm version=\"0.6\" generator=\"nvdb2osmr\"{}>", upload)?;
+        if let Some(b) = &self.bbox {
+            writeln!(
+                self.out,
+                "  <bounds minlat=\"{:.7}\" minlon=\"{:.7}\" maxlat=\"{:.7}\" maxlon=\"{:.7}\"/>",
+                b.bottom as f64 / 1e9,
+                b.left as f64 / 1e9,
+                b.top as f64 / 1e9,
+                b.right as f64 / 1e9,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write a node, way or relation element
+    pub fn write(&mut self, element: Element) -> std::io::Result<()> {
+        self.start()?;
+        match element {
+            Element::Node(node) => {
+                let open = format!(
+                    "  <node id=\"{}\" lat=\"{:.7}\" lon=\"{:.7}\"",
+                    node.id,
+                    node.latitude as f64 / 1e9,
+                    node.longitude as f64 / 1e9,
+                );
+                if node.tags.is_empty() {
+                    writeln!(self.out, "{}/>", open)
+                } else {
+                    writeln!(self.out, "{}>", open)?;
+                    self.write_tags(&node.tags)?;
+                    writeln!(self.out, "  </node>")
+                }
+            }
+            Element::Way(way) => {
+                writeln!(self.out, "  <way id=\"{}\">", way.id)?;
+                for wn in &way.way_nodes {
+                    writeln!(self.out, "    <nd ref=\"{}\"/>", wn.id)?;
+                }
+                self.write_tags(&way.tags)?;
+                writeln!(self.out, "  </way>")
+            }
+            Element::Relation(relation) => {
+                writeln!(self.out, "  <relation id=\"{}\">", relation.id)?;
+                for m in &relation.members {
+                    let kind = match m.member_type {
+                        ElementType::Node => "node",
+                        ElementType::Way => "way",
+                        ElementType::Relation => "relation",
+                    };
+                    writeln!(self.out, "    <member type=\"{}\" ref=\"{}\" role=\"{}\"/>", kind, m.member_id, escape(&m.role))?;
+                }
+                self.write_tags(&relation.tags)?;
+                writeln!(self.out, "  </relation>")
+            }
+        }
+    }
+
+    fn write_tags(&mut self, tags: &[Tag]) -> std::io::Result<()> {
+        let mut sorted: Vec<&Tag> = tags.iter().collect();
+        sorted.sort_by(|a, b| a.key.cmp(&b.key));
+        for t in sorted {
+            writeln!(self.out, "    <tag k=\"{}\" v=\"{}\"/>", escape(&t.key), escape(&t.value))?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        self.start()?;
+        writeln!(self.out, "</osm>")?;
+        self.out.flush()
+    }
+}
+
+/// Escape a string for use in a double-quoted XML attribute
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
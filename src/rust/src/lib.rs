@@ -1,59 +1,133 @@
-use extendr_api::*;
-use rustc_hash::FxHashMap;
-use geo_types::{Coord, LineString};
-use std::collections::HashSet;
-
-// Module imports
-mod models;
-mod geometry;
-mod grouping;
-mod tag_mapper;
-mod topology;
-
-use models::{Segment, Way, NodeFeature, SimplifyMethod, CoordHash, PropertyValue};
-use pbf_craft::models::{Bound, Element, Node, Way as PbfWay, Tag, WayNode};
-use pbf_craft::writers::PbfWriter;
+//! R bindings for the nvdb2osm-core conversion engine
+//!
+//! Everything here is glue: reading R vectors into `Segment`s and handing
+//! results back to R. The conversion itself lives in the `nvdb2osm-core` crate.
+
+use extendr_api::prelude::*;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHasher};
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use nvdb2osm_core::{arrow_input, geometry, wkb, wkt};
+use nvdb2osm_core::elevation::{ElevationProvider, GeoTiffDem};
+use nvdb2osm_core::compare::{compare_pbf, ElementDiff, MatchBy};
+use nvdb2osm_core::coverage::compare_coverage;
+use nvdb2osm_core::tag_stats::tag_stats;
+use nvdb2osm_core::profile::BoundsError;
+use nvdb2osm_core::schema::is_esri_boolean;
+use nvdb2osm_core::tag_mapper::dates::{date_from_days, datetime_from_seconds};
+use nvdb2osm_core::synthetic::{Layout, SyntheticNetwork};
+use nvdb2osm_core::topology::{graph::{edge_list, path_check}, junctions::junction_table};
+use nvdb2osm_core::{AccessDefaults, AnnotationRecord, Conversion, ConversionStats, DateTag, DuplicateWays, HeaderBbox, LayBys, NodeDirections, NoiseBarriers, NonRoadTags, NullTokens, OnewayGeometry, PlannedRoads, PrivateRoads, PhaseTimer, Pipeline, Profile, RelationFeature, Schema, Segment, SimplifyMethod, PropertyValue, UnnamedGroups, RoundaboutRef, RoutingPreset, RoundaboutTags, UnitFormat, Way, WaySplitting, INTERRUPTED};
+use nvdb2osm_core::writer::{ogr, renumber, BaseExtract, ElementIds, PbfEmitter, WaySpill, WriteOptions};
 
 /// Container for pre-processed column data
-struct PreprocessedColumns {
+///
+/// Columns are borrowed straight from the R vectors instead of being copied:
+/// `col_data` outlives the whole conversion, and plain reads of vector memory
+/// are safe from worker threads as long as no R API is called there.
+struct PreprocessedColumns<'a> {
     names: Vec<String>,
-    // Store data as owned vectors to avoid lifetime issues
-    string_cols: Vec<(usize, Vec<String>)>,
-    int_cols: Vec<(usize, Vec<i32>)>,
-    real_cols: Vec<(usize, Vec<f64>)>,
-    logical_cols: Vec<(usize, Vec<i32>)>,
+    string_cols: Vec<(usize, Vec<&'a str>)>,
+    int_cols: Vec<(usize, &'a [i32])>,
+    real_cols: Vec<(usize, &'a [f64])>,
+    logical_cols: Vec<(usize, &'a [i32])>,
+    date_cols: Vec<(usize, DateValues<'a>, DateClass)>,
+    factor_cols: Vec<(usize, &'a [i32], Vec<String>)>,
+    list_cols: Vec<(usize, Vec<Option<PropertyValue>>)>,
+}
+
+/// R temporal class of a numeric column
+#[derive(Clone, Copy)]
+enum DateClass {
+    /// `Date`: days since 1970-01-01
+    Date,
+    /// `POSIXct`: seconds since 1970-01-01 00:00:00 UTC
+    DateTime,
+}
+
+/// Storage of a temporal column: R keeps `Date` and `POSIXct` as doubles,
+/// but integer vectors with the class are valid too (e.g. from `structure()`
+/// or some readers)
+enum DateValues<'a> {
+    Int(&'a [i32]),
+    Real(&'a [f64]),
+}
+
+impl DateValues<'_> {
+    /// Whole days or seconds at `row`, `None` when out of range or missing
+    fn get(&self, row: usize) -> Option<i64> {
+        match self {
+            DateValues::Int(values) => values.get(row).filter(|&&v| v != i32::MIN).map(|&v| v as i64),
+            DateValues::Real(values) => values.get(row).filter(|v| v.is_finite()).map(|v| v.floor() as i64),
+        }
+    }
 }
 
-impl PreprocessedColumns {
-    fn new(col_names: Vec<String>, col_data: &[Robj]) -> Self {
+impl<'a> PreprocessedColumns<'a> {
+    /// Columns of `col_data`, leaving out those `schema` never reads (all
+    /// are kept without a schema, as profiles other than Sweden translate
+    /// their own columns)
+    fn new(col_names: Vec<String>, col_data: &'a [Robj], schema: Option<&Schema>) -> Self {
         let mut string_cols = Vec::new();
         let mut int_cols = Vec::new();
         let mut real_cols = Vec::new();
         let mut logical_cols = Vec::new();
+        let mut date_cols = Vec::new();
+        let mut factor_cols = Vec::new();
+        let mut list_cols = Vec::new();
         
         for (i, col) in col_data.iter().enumerate() {
             if i >= col_names.len() {
                 break;
             }
+            if schema.is_some_and(|schema| !schema.uses_column(&col_names[i])) {
+                continue;
+            }
             
             // Try to extract data based on type
-            if let Some(chars) = col.as_str_vector() {
-                // Convert to owned Strings
-                let strings: Vec<String> = chars.iter().map(|s| s.to_string()).collect();
-                string_cols.push((i, strings));
+            let date_class = if col.inherits("Date") {
+                Some(DateClass::Date)
+            } else if col.inherits("POSIXct") {
+                Some(DateClass::DateTime)
+            } else {
+                None
+            };
+            if let Some(class) = date_class {
+                // Checked before the storage type, so an integer Date is not
+                // taken for a plain day count
+                if let Some(reals) = col.as_real_slice() {
+                    date_cols.push((i, DateValues::Real(reals), class));
+                } else if let Some(ints) = col.as_integer_slice() {
+                    date_cols.push((i, DateValues::Int(ints), class));
+                }
+            } else if let Some(list) = col.as_list() {
+                // List elements are separate R objects, so they are converted
+                // here on the main thread rather than read from worker threads
+                let values = list.values().map(|element| list_element_value(&element)).collect();
+                list_cols.push((i, values));
+            } else if col.inherits("factor") {
+                // Factor codes index into the levels attribute, 1-based;
+                // the levels are few, so they are copied
+                let levels = col.get_attrib("levels")
+                    .and_then(|l| l.as_str_vector().map(|v| v.into_iter().map(str::to_string).collect::<Vec<_>>()));
+                if let (Some(codes), Some(levels)) = (col.as_integer_slice(), levels) {
+                    factor_cols.push((i, codes, levels));
+                }
+            } else if let Some(chars) = col.as_str_vector() {
+                // Only the pointers are collected; the strings stay in R's CHARSXP cache
+                string_cols.push((i, chars));
             } else if let Some(ints) = col.as_integer_slice() {
                 // Check if it's actually a logical vector
                 // R logical values: 0=FALSE, 1=TRUE, NA=INT_MIN
                 if col.is_logical() {
-                    let logicals: Vec<i32> = ints.to_vec();
-                    logical_cols.push((i, logicals));
+                    logical_cols.push((i, ints));
                 } else {
-                    let ints_vec: Vec<i32> = ints.to_vec();
-                    int_cols.push((i, ints_vec));
+                    int_cols.push((i, ints));
                 }
             } else if let Some(reals) = col.as_real_slice() {
-                let reals_vec: Vec<f64> = reals.to_vec();
-                real_cols.push((i, reals_vec));
+                real_cols.push((i, reals));
             }
             // Unknown types are skipped
         }
@@ -64,6 +138,9 @@ impl PreprocessedColumns {
             int_cols,
             real_cols,
             logical_cols,
+            date_cols,
+            factor_cols,
+            list_cols,
         }
     }
     
@@ -73,9 +150,9 @@ impl PreprocessedColumns {
         // Process string columns
         for (col_idx, values) in &self.string_cols {
             if row_idx < values.len() {
-                let s = &values[row_idx];
+                let s = values[row_idx];
                 if !s.is_empty() {
-                    props.insert(self.names[*col_idx].clone(), PropertyValue::String(s.clone()));
+                    props.insert(self.names[*col_idx].clone(), PropertyValue::String(s.to_string()));
                 }
             }
         }
@@ -88,7 +165,7 @@ impl PreprocessedColumns {
                 if val != i32::MIN {
                     // NVDB GDB boolean normalization: -1 means true, convert to 1
                     // (matches Python load_file() lines 2237-2277)
-                    let normalized = if val == -1 && is_boolean_field(&self.names[*col_idx]) {
+                    let normalized = if val == -1 && is_esri_boolean(&self.names[*col_idx]) {
                         1i64
                     } else {
                         val as i64
@@ -107,7 +184,7 @@ impl PreprocessedColumns {
                     let pv = if val == val.floor() {
                         let int_val = val as i64;
                         // NVDB GDB boolean normalization for real columns too
-                        let normalized = if int_val == -1 && is_boolean_field(&self.names[*col_idx]) {
+                        let normalized = if int_val == -1 && is_esri_boolean(&self.names[*col_idx]) {
                             1i64
                         } else {
                             int_val
@@ -132,654 +209,1500 @@ impl PreprocessedColumns {
             }
         }
 
+        // Process factor columns as their level strings
+        for (col_idx, codes, levels) in &self.factor_cols {
+            if row_idx < codes.len() {
+                let code = codes[row_idx];
+                // NA_INTEGER is negative, so it falls outside the levels
+                let level = usize::try_from(code).ok()
+                    .and_then(|k| k.checked_sub(1))
+                    .and_then(|k| levels.get(k));
+                if let Some(level) = level {
+                    if !level.is_empty() {
+                        props.insert(self.names[*col_idx].clone(), PropertyValue::String(level.clone()));
+                    }
+                }
+            }
+        }
+
+        // Process list columns (multi-valued attributes)
+        for (col_idx, values) in &self.list_cols {
+            if let Some(Some(value)) = values.get(row_idx) {
+                props.insert(self.names[*col_idx].clone(), value.clone());
+            }
+        }
+
+        // Process date columns as ISO 8601 strings
+        for (col_idx, values, class) in &self.date_cols {
+            if let Some(val) = values.get(row_idx) {
+                let iso = match class {
+                    DateClass::Date => date_from_days(val),
+                    DateClass::DateTime => datetime_from_seconds(val),
+                };
+                props.insert(self.names[*col_idx].clone(), PropertyValue::String(iso));
+            }
+        }
+
         props
     }
 }
 
-/// NVDB GDB boolean fields that use -1 for true (ESRI convention)
-/// Matches Python load_file() boolean_fields list (lines 2237-2277)
-fn is_boolean_field(name: &str) -> bool {
-    matches!(name,
-        "F_ForbudTrafik" | "B_ForbudTrafik" |
-        "F_ForbjudenFardriktning" | "B_ForbjudenFardriktning" |
-        "F_Cirkulationsplats" | "B_Cirkulationsplats" |
-        "TattbebyggtOmrade" |
-        "Farjeled" |
-        "Motorvag" | "Motortrafikled" |
-        "GCM_belyst" | "GCM_passage" |
-        "F_Omkorningsforbud" | "B_Omkorningsforbud" |
-        "L_Gagata" | "R_Gagata" |
-        "L_Gangfartsomrade" | "R_Gangfartsomrade" |
-        "Miljozon" |
-        "C_Rekbilvagcykeltrafik" |
-        "Rastplats" |
-        "L_Rastficka_2" | "R_Rastficka_2" |
-        "F_ATK_Matplats" | "B_ATK_Matplats" |
-        "Provisorisk_vag" | "F_Stigningsfalt" | "B_Stigningsfalt" |
-        "Katastrofoverfart" | "Viltpassage_i_plan" |
-        "L_Viltuthopp" | "R_Viltuthopp" |
-        "L_P_ficka" | "R_P_ficka" | "M_P_ficka" |
-        "Driftvandplats_2" | "Brunn___Slamsugning" | "Hallplats"
-    )
+/// Value of one list-column element: a scalar for a single value, an array
+/// for several, nothing when empty or all NA
+fn list_element_value(element: &Robj) -> Option<PropertyValue> {
+    let mut values: Vec<PropertyValue> = if let Some(chars) = element.as_str_vector() {
+        chars.into_iter()
+            .filter(|s| !s.is_empty() && *s != "NA")
+            .map(|s| PropertyValue::String(s.to_string()))
+            .collect()
+    } else if let Some(ints) = element.as_integer_slice() {
+        let logical = element.is_logical();
+        ints.iter()
+            .filter(|&&v| v != i32::MIN)
+            .map(|&v| if logical { PropertyValue::Boolean(v != 0) } else { PropertyValue::Integer(v as i64) })
+            .collect()
+    } else if let Some(reals) = element.as_real_slice() {
+        reals.iter()
+            .filter(|v| !v.is_nan())
+            .map(|&v| if v == v.floor() { PropertyValue::Integer(v as i64) } else { PropertyValue::Float(v) })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    match values.len() {
+        0 => None,
+        1 => values.pop(),
+        _ => Some(PropertyValue::Array(values)),
+    }
 }
 
-/// Parse WKB (Well-Known Binary) geometry
-/// Handles 2D, 3D (Z), and 4D (ZM) coordinate types
-fn parse_wkb(wkb: &[u8]) -> Option<LineString<f64>> {
-    if wkb.len() < 9 {
-        return None;
-    }
-    
-    let byte_order = wkb[0];
-    if byte_order > 1 {
-        return None;
-    }
-    let little_endian = byte_order == 1;
-    
-    let geom_type = if little_endian {
-        u32::from_le_bytes([wkb[1], wkb[2], wkb[3], wkb[4]])
-    } else {
-        u32::from_be_bytes([wkb[1], wkb[2], wkb[3], wkb[4]])
+/// Process NVDB data with WKB geometries and direct R property columns
+/// 
+/// # Arguments
+/// * `wkb_geoms` - List of raw WKB bytes (one per geometry), or a character
+///   vector of WKT (`process_nvdb_wkt()` in R)
+/// * `col_names` - Vector of column names for properties
+/// * `col_data` - List of vectors (one per column), each vector has same length as wkb_geoms
+/// * `output_path` - Path to write the PBF file
+/// * `debug_geojson` - Optional path for a GeoJSON dump of the simplified ways
+/// * `config` - Conversion options from `nvdb_config()` (see `pipeline_from_config()`)
+///
+/// Returns a named list with `success`, per-phase timings and element counts
+/// (see `stats_to_list()`).
+#[extendr]
+fn process_nvdb_wkb(
+    wkb_geoms: Robj,
+    col_names: Vec<String>,
+    col_data: List,
+    output_path: String,
+    debug_geojson: Nullable<String>,
+    config: List,
+) -> List {
+    let mut stats = ConversionStats::default();
+    let mut pipeline = match pipeline_from_config(&config) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            eprintln!("{}", e);
+            return stats_to_list(&stats, false);
+        }
     };
-    
-    // Handle EWKB flags (PostGIS style)
-    let has_srid = (geom_type & 0x20000000) != 0;
-    let ewkb_z = (geom_type & 0x80000000) != 0;
-    let ewkb_m = (geom_type & 0x40000000) != 0;
-    
-    // Mask out EWKB flags for base type and ISO-style Z/M
-    let clean_geom_type = geom_type & 0x1FFFFFFF;
-    
-    let base_type = clean_geom_type % 1000;
-    let iso_z = (clean_geom_type / 1000) == 1 || (clean_geom_type / 1000) == 3;
-    let iso_m = (clean_geom_type / 1000) == 2 || (clean_geom_type / 1000) == 3;
-    
-    let has_z = ewkb_z || iso_z;
-    let has_m = ewkb_m || iso_m;
-    let coord_size = 16 + if has_z { 8 } else { 0 } + if has_m { 8 } else { 0 };
-    
-    let mut offset = 5;
-    if has_srid {
-        offset += 4;
+
+    if let NotNull(geojson_path) = debug_geojson {
+        pipeline = pipeline.debug_geojson(geojson_path);
     }
-    
-    match base_type {
-        2 => parse_linestring_wkb(wkb, offset, little_endian, coord_size),
-        5 => parse_multilinestring_wkb(wkb, little_endian, coord_size),
-        _ => None,
-    }
-}
-
-/// Round float to nearest integer, rounding half to even ("Banker's Rounding")
-/// Matches Python 3's round() function behavior
-fn round_ties_even(x: f64) -> f64 {
-    let fract = x.fract().abs();
-    if (fract - 0.5).abs() < f64::EPSILON {
-        // Exact half - round to even
-        let floor = x.floor();
-        if floor as i64 % 2 == 0 {
-            floor
-        } else {
-            x.ceil()
+    if config_value(&config, "checkpoint").is_some() {
+        let mut hasher = FxHasher::default();
+        for robj in [&wkb_geoms, &Robj::from(&col_names), &Robj::from(col_data.clone())] {
+            hash_robj(&mut hasher, robj);
         }
-    } else {
-        x.round()
+        pipeline = pipeline.input_fingerprint(hasher.finish());
+    }
+    if let Some(result) = resume_checkpoint(&pipeline, &output_path, &config, &mut stats) {
+        return result;
     }
+
+    let segments = IngestOptions::from_config(&config)
+        .and_then(|options| ingest_segments(&wkb_geoms, col_names, col_data, &options, &mut stats));
+    let segments = match segments {
+        Ok(segments) => segments,
+        Err(e) => {
+            eprintln!("{}", e);
+            return stats_to_list(&stats, false);
+        }
+    };
+
+    let result = pipeline.run(segments, &output_path, &mut stats);
+    run_summary(result, &pipeline, &config, &stats)
 }
 
-fn parse_linestring_wkb(wkb: &[u8], offset: usize, little_endian: bool, coord_size: usize) -> Option<LineString<f64>> {
-    if wkb.len() < offset + 4 {
-        return None;
+/// Process an Arrow IPC (Feather v2) or GeoParquet file of NVDB road links
+///
+/// The file is read by `arrow_input::read_arrow_segments()` without any
+/// columns passing through R. Arguments and return value as for
+/// `process_nvdb_wkb()`, with `path` in place of the geometries and columns.
+#[extendr]
+fn process_nvdb_arrow(path: String, output_path: String, debug_geojson: Nullable<String>, config: List) -> List {
+    let mut stats = ConversionStats::default();
+    let (mut pipeline, options) = match pipeline_from_config(&config)
+        .and_then(|pipeline| Ok((pipeline, IngestOptions::from_config(&config)?)))
+    {
+        Ok(configured) => configured,
+        Err(e) => {
+            eprintln!("{}", e);
+            return stats_to_list(&stats, false);
+        }
+    };
+
+    if let NotNull(geojson_path) = debug_geojson {
+        pipeline = pipeline.debug_geojson(geojson_path);
     }
-    
-    let num_points = if little_endian {
-        u32::from_le_bytes([wkb[offset], wkb[offset+1], wkb[offset+2], wkb[offset+3]]) as usize
-    } else {
-        u32::from_be_bytes([wkb[offset], wkb[offset+1], wkb[offset+2], wkb[offset+3]]) as usize
+    if config_value(&config, "checkpoint").is_some() {
+        // The file is not read twice for this: its path, size and
+        // modification time stand for its contents
+        let mut hasher = FxHasher::default();
+        hasher.write(path.as_bytes());
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            hasher.write_u64(metadata.len());
+            let modified = metadata.modified().ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok());
+            hasher.write_u128(modified.map(|d| d.as_nanos()).unwrap_or(0));
+        }
+        pipeline = pipeline.input_fingerprint(hasher.finish());
+    }
+    if let Some(result) = resume_checkpoint(&pipeline, &output_path, &config, &mut stats) {
+        return result;
+    }
+
+    let timer = PhaseTimer::start();
+    let schema = (options.profile == Profile::Sweden).then(Schema::nvdb);
+    let input = match arrow_input::read_arrow_segments(&path, schema.as_ref(), &options.wkb) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            return stats_to_list(&stats, false);
+        }
     };
-    
-    let point_offset = offset + 4;
-    let expected_len = point_offset + num_points * coord_size;
-    
-    if wkb.len() < expected_len {
-        return None;
+    stats.parse_secs += timer.secs();
+    stats.segments_in += input.rows;
+    stats.parse_failures += input.failures.len();
+    for (source_row, message) in input.failures {
+        stats.annotations.push(AnnotationRecord { source_row, kind: "invalid_geometry", message });
     }
-    
-    let mut coords = Vec::with_capacity(num_points);
-    
-    for i in 0..num_points {
-        let base = point_offset + i * coord_size;
-        let x = read_f64(&wkb[base..base+8], little_endian);
-        let y = read_f64(&wkb[base+8..base+16], little_endian);
-        // Skip Z and M coordinates if present (we only need X,Y for OSM)
-        coords.push(Coord { x, y });
+    if !input.unresolved_fields.is_empty() {
+        eprintln!(
+            "Warning: {} NVDB fields not found in the input columns: {}",
+            input.unresolved_fields.len(),
+            input.unresolved_fields.join(", ")
+        );
     }
-    
-    Some(LineString::from(coords))
+    stats.unresolved_fields = input.unresolved_fields;
+    if input.segments.is_empty() {
+        eprintln!("No valid geometries parsed");
+        return stats_to_list(&stats, false);
+    }
+    if let Err(e) = check_bounds(&input.segments, &options) {
+        eprintln!("{}", e);
+        return stats_to_list(&stats, false);
+    }
+
+    let result = pipeline.run(input.segments, &output_path, &mut stats);
+    run_summary(result, &pipeline, &config, &stats)
 }
 
-fn parse_multilinestring_wkb(wkb: &[u8], little_endian: bool, _coord_size: usize) -> Option<LineString<f64>> {
-    if wkb.len() < 9 {
-        return None;
+/// Feed the values of an R vector, or of the vectors in a list, to `hasher`,
+/// for the input fingerprint of checkpoints
+fn hash_robj(hasher: &mut FxHasher, robj: &Robj) {
+    if let Some(bytes) = robj.as_raw_slice() {
+        hasher.write(bytes);
+    } else if let Some(reals) = robj.as_real_slice() {
+        reals.iter().for_each(|v| hasher.write_u64(v.to_bits()));
+    } else if let Some(ints) = robj.as_integer_slice() {
+        ints.iter().for_each(|&v| hasher.write_i32(v));
+    } else if let Some(strings) = robj.as_str_vector() {
+        for s in strings {
+            hasher.write(s.as_bytes());
+            hasher.write_u8(0xff);
+        }
+    } else if let Some(list) = robj.as_list() {
+        list.values().for_each(|element| hash_robj(hasher, &element));
     }
-    
-    // MultiLineString has a num_geoms field at offset 5, then each geometry
-    let num_geoms = if little_endian {
-        u32::from_le_bytes([wkb[5], wkb[6], wkb[7], wkb[8]]) as usize
-    } else {
-        u32::from_be_bytes([wkb[5], wkb[6], wkb[7], wkb[8]]) as usize
+    hasher.write_usize(robj.len());
+}
+
+/// Finish the run saved in the pipeline's `checkpoint` file, if there is
+/// one, without parsing the input; `None` when the input is to be converted
+fn resume_checkpoint(pipeline: &Pipeline, output_path: &str, config: &List, stats: &mut ConversionStats) -> Option<List> {
+    let checkpoint = match pipeline.load_checkpoint(stats) {
+        Ok(checkpoint) => checkpoint?,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Some(stats_to_list(stats, false));
+        }
     };
-    
-    if num_geoms == 0 {
-        return None;
+    eprintln!("Resuming from the checkpoint after the {} phase", checkpoint.phase());
+    let result = pipeline.resume(checkpoint, output_path, stats);
+    Some(run_summary(result, pipeline, config, stats))
+}
+
+/// Summary list of a `run()` or `resume()`; when the config sets a
+/// `parquet_dir`, the GeoParquet tables of the written conversion are
+/// attached as attribute `parquet_tables` for the R wrapper to write
+fn run_summary(result: Result<Conversion, String>, pipeline: &Pipeline, config: &List, stats: &ConversionStats) -> List {
+    match result {
+        Ok(conversion) => {
+            let mut summary = stats_to_list(stats, true);
+            if config_value(config, "parquet_dir").is_some() {
+                let _ = summary.set_attrib("parquet_tables", parquet_tables(&conversion, pipeline.first_way_id()));
+            }
+            summary
+        }
+        Err(e) => {
+            eprintln!("Failed to write PBF: {}", e);
+            stats_to_list(stats, false)
+        }
     }
-    
-    // For simplicity, parse just the first LineString
-    // Each geometry in MultiLineString is: byte_order (1) + type (4) + num_points (4) + points
-    // Skip to first geometry: offset 9 (after num_geoms)
-    let geom_start = 9;
-    if wkb.len() < geom_start + 5 {
-        return None;
+}
+
+/// Tag NVDB segments without simplifying or writing any output (dry run)
+///
+/// Runs parsing and the full tag mapper, so mapping rules can be checked
+/// quickly. Returns a long data frame with one row per tag: `segment` (1-based
+/// input row), `key` and `value`. Segments that fail to parse are left out.
+#[extendr]
+fn tag_nvdb_wkb(wkb_geoms: List, col_names: Vec<String>, col_data: List, config: List) -> Robj {
+    let mut stats = ConversionStats::default();
+    let mut rows: Vec<i32> = Vec::new();
+    let mut keys: Vec<String> = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+
+    let segments = pipeline_from_config(&config)
+        .and_then(|pipeline| Ok((pipeline, ingest_segments(&wkb_geoms.into(), col_names, col_data, &IngestOptions::from_config(&config)?, &mut stats)?)));
+    match segments {
+        Ok((pipeline, mut segments)) => {
+            pipeline.retain_valid(&mut segments);
+            pipeline.tag(&mut segments, &mut stats);
+
+            for seg in &segments {
+                // Sorted keys keep the output stable between runs
+                let mut tags: Vec<(&String, &String)> = seg.tags.iter().collect();
+                tags.sort();
+                for (k, v) in tags {
+                    rows.push(seg.source_row as i32 + 1);
+                    keys.push(k.clone());
+                    values.push(v.clone());
+                }
+            }
+        }
+        Err(e) => eprintln!("{}", e),
     }
-    
-    // Verify it's a LineString
-    let geom_byte_order = wkb[geom_start];
-    if geom_byte_order > 1 {
-        return None;
-    }
-    let geom_little_endian = geom_byte_order == 1;
-    let geom_type = if geom_little_endian {
-        u32::from_le_bytes([wkb[geom_start+1], wkb[geom_start+2], wkb[geom_start+3], wkb[geom_start+4]])
-    } else {
-        u32::from_be_bytes([wkb[geom_start+1], wkb[geom_start+2], wkb[geom_start+3], wkb[geom_start+4]])
+
+    data_frame!(segment = rows, key = keys, value = values)
+}
+
+/// Canonical NVDB name of each column (NA for columns that are no NVDB field)
+///
+/// Resolves the full attribute names of GeoPackage deliveries and other
+/// aliases, so R can select the needed columns whatever the delivery format.
+#[extendr]
+fn resolve_nvdb_columns(col_names: Vec<String>) -> Robj {
+    let schema = Schema::nvdb();
+    let canonical: Vec<Option<&str>> = col_names
+        .iter()
+        .map(|name| schema.field(name).map(|field| field.column()))
+        .collect();
+    Robj::from(canonical)
+}
+
+/// Compare the ways and tagged nodes of two OSM files
+///
+/// Returns `list(summary, tag_changes, added, removed, geometry)`: counts per
+/// element type (`element` "way" or "node"), tag changes per key, the IDs of
+/// added (only in `b`) and removed (only in `a`) elements, and the geometry
+/// changes of elements matched by ID.
+#[extendr]
+fn compare_pbf_files(a: String, b: String, match_by: String) -> extendr_api::Result<List> {
+    let diff = compare_pbf(&a, &b, MatchBy::from(match_by.as_str())).map_err(Error::Other)?;
+    let types = [("way", &diff.ways), ("node", &diff.nodes)];
+
+    let count = |f: fn(&ElementDiff) -> usize| -> Vec<i32> { types.iter().map(|(_, d)| f(d) as i32).collect() };
+    let summary = data_frame!(
+        element = types.iter().map(|(t, _)| *t).collect::<Vec<&str>>(),
+        in_a = count(|d| d.count_a),
+        in_b = count(|d| d.count_b),
+        matched = count(|d| d.matched.len()),
+        added = count(|d| d.added.len()),
+        removed = count(|d| d.removed.len()),
+        retagged = count(|d| d.retagged.len()),
+        geometry_changed = count(|d| d.geometry_changed.len())
+    );
+    let tag_changes = data_frame!(
+        key = diff.tag_changes.iter().map(|c| c.key.as_str()).collect::<Vec<&str>>(),
+        added = diff.tag_changes.iter().map(|c| c.added as i32).collect::<Vec<i32>>(),
+        removed = diff.tag_changes.iter().map(|c| c.removed as i32).collect::<Vec<i32>>(),
+        changed = diff.tag_changes.iter().map(|c| c.changed as i32).collect::<Vec<i32>>()
+    );
+    let id_table = |ids: fn(&ElementDiff) -> &Vec<i64>| {
+        let rows: Vec<(&str, i64)> = types.iter().flat_map(|(t, d)| ids(d).iter().map(move |&id| (*t, id))).collect();
+        data_frame!(
+            element = rows.iter().map(|r| r.0).collect::<Vec<&str>>(),
+            id = rows.iter().map(|r| r.1 as f64).collect::<Vec<f64>>()
+        )
     };
-    
-    // Handle EWKB flags for inner geom
-    let inner_has_srid = (geom_type & 0x20000000) != 0;
-    let inner_ewkb_z = (geom_type & 0x80000000) != 0;
-    let inner_ewkb_m = (geom_type & 0x40000000) != 0;
-    
-    let clean_geom_type = geom_type & 0x1FFFFFFF;
-    let base_geom_type = clean_geom_type % 1000;
-    
-    if base_geom_type != 2 {
-        return None;
+    let changes: Vec<(&str, &nvdb2osm_core::compare::GeometryChange)> = types.iter()
+        .flat_map(|(t, d)| d.geometry_changed.iter().map(move |g| (*t, g)))
+        .collect();
+    let geometry = data_frame!(
+        element = changes.iter().map(|c| c.0).collect::<Vec<&str>>(),
+        id = changes.iter().map(|c| c.1.id as f64).collect::<Vec<f64>>(),
+        max_offset_m = changes.iter().map(|c| c.1.max_offset_m).collect::<Vec<f64>>(),
+        length_delta_m = changes.iter().map(|c| c.1.length_delta_m).collect::<Vec<f64>>()
+    );
+
+    Ok(list!(
+        summary = summary,
+        tag_changes = tag_changes,
+        added = id_table(|d| &d.added),
+        removed = id_table(|d| &d.removed),
+        geometry = geometry
+    ))
+}
+
+/// Coverage of a conversion output against an OSM extract
+///
+/// Returns `list(coverage, tags, unmatched)`: per `source` ("nvdb", "osm")
+/// the number of highway `ways`, their `length_km` and `unmatched_km`; per
+/// compared `key` the matched length where the values agree, disagree or
+/// only one side is tagged; and the ways with unmatched length.
+#[extendr]
+fn compare_osm_coverage(nvdb: String, osm: String, tolerance: f64, keys: Vec<String>) -> extendr_api::Result<List> {
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let report = compare_coverage(&nvdb, &osm, tolerance, &keys).map_err(Error::Other)?;
+    let sources = [("nvdb", &report.nvdb), ("osm", &report.osm)];
+    let coverage = data_frame!(
+        source = sources.iter().map(|(s, _)| *s).collect::<Vec<&str>>(),
+        ways = sources.iter().map(|(_, c)| c.ways as f64).collect::<Vec<f64>>(),
+        length_km = sources.iter().map(|(_, c)| c.length_m / 1000.0).collect::<Vec<f64>>(),
+        unmatched_km = sources.iter().map(|(_, c)| c.unmatched_m / 1000.0).collect::<Vec<f64>>()
+    );
+    let km = |f: fn(&nvdb2osm_core::coverage::TagAgreement) -> f64| -> Vec<f64> { report.tags.iter().map(|t| f(t) / 1000.0).collect() };
+    let tags = data_frame!(
+        key = report.tags.iter().map(|t| t.key.as_str()).collect::<Vec<&str>>(),
+        agree_km = km(|t| t.agree_m),
+        disagree_km = km(|t| t.disagree_m),
+        nvdb_only_km = km(|t| t.nvdb_only_m),
+        osm_only_km = km(|t| t.osm_only_m)
+    );
+    let unmatched = data_frame!(
+        source = report.unmatched.iter().map(|u| u.source).collect::<Vec<&str>>(),
+        id = report.unmatched.iter().map(|u| u.id as f64).collect::<Vec<f64>>(),
+        length_km = report.unmatched.iter().map(|u| u.length_m / 1000.0).collect::<Vec<f64>>(),
+        unmatched_km = report.unmatched.iter().map(|u| u.unmatched_m / 1000.0).collect::<Vec<f64>>()
+    );
+    Ok(list!(coverage = coverage, tags = tags, unmatched = unmatched))
+}
+
+/// Tag counts of an output file
+///
+/// Returns a data frame with one row per element type (`element` "node",
+/// "way" or "relation") and `key`, and also `value` when `by_value`, with the
+/// number of elements carrying it in `count`.
+#[extendr]
+fn pbf_tag_stats(path: String, by_value: bool) -> extendr_api::Result<Robj> {
+    let counts = tag_stats(&path, by_value).map_err(Error::Other)?;
+    let element: Vec<&str> = counts.iter().map(|c| c.element).collect();
+    let key: Vec<&str> = counts.iter().map(|c| c.key.as_str()).collect();
+    let count: Vec<f64> = counts.iter().map(|c| c.count as f64).collect();
+    if !by_value {
+        return Ok(data_frame!(element = element, key = key, count = count));
     }
-    
-    let iso_z = (clean_geom_type / 1000) == 1 || (clean_geom_type / 1000) == 3;
-    let iso_m = (clean_geom_type / 1000) == 2 || (clean_geom_type / 1000) == 3;
-    let has_z = inner_ewkb_z || iso_z;
-    let has_m = inner_ewkb_m || iso_m;
-    
-    let inner_coord_size = 16 + if has_z { 8 } else { 0 } + if has_m { 8 } else { 0 };
-    let mut inner_offset = geom_start + 5;
-    if inner_has_srid {
-        inner_offset += 4;
+    let value: Vec<&str> = counts.iter().map(|c| c.value.as_deref().unwrap_or_default()).collect();
+    Ok(data_frame!(element = element, key = key, value = value, count = count))
+}
+
+/// Renumber the elements of a file
+///
+/// Writes `input` to `output` with IDs from the given first IDs (counting
+/// down when negative) and returns `list(nodes, ways, relations, dangling)`,
+/// the numbers of renumbered elements and of dropped references to elements
+/// not in the file.
+#[extendr]
+fn renumber_pbf_ids(input: String, output: String, node: f64, way: f64, relation: f64) -> extendr_api::Result<List> {
+    let ids = ElementIds { node: node as i64, way: way as i64, relation: relation as i64 };
+    let counts = renumber(&input, &output, &WriteOptions { ids, ..WriteOptions::default() }).map_err(Error::Other)?;
+    Ok(list!(
+        nodes = counts.nodes as f64,
+        ways = counts.ways as f64,
+        relations = counts.relations as f64,
+        dangling = counts.dangling as f64
+    ))
+}
+
+/// Generate synthetic NVDB links
+///
+/// Returns `list(wkb_geoms, col_names, col_data)`, the arguments the WKB
+/// entry points take: a list of raw WKB vectors, the canonical NVDB column
+/// names and one vector per column (integer, double or character, NA where
+/// the generator left the attribute out).
+#[extendr]
+fn synthetic_nvdb_wkb(layout: String, size: i32, spacing: f64, coverage: f64, seed: f64) -> List {
+    let input = SyntheticNetwork {
+        layout: Layout::from(layout.as_str()),
+        size: size.max(0) as usize,
+        spacing_m: spacing,
+        coverage,
+        seed: seed as u64,
+        ..SyntheticNetwork::default()
     }
-    
-    parse_linestring_wkb(wkb, inner_offset, geom_little_endian, inner_coord_size)
+    .generate();
+
+    let wkb_geoms = List::from_values(input.wkb.iter().map(|wkb| Raw::from_bytes(wkb)));
+    let col_names: Vec<&str> = input.columns.iter().map(|(field, _)| field.column()).collect();
+    let col_data = List::from_values(input.columns.iter().map(|(_, values)| -> Robj {
+        let present = || values.iter().filter(|v| !matches!(v, PropertyValue::Null));
+        if present().any(|v| matches!(v, PropertyValue::String(_))) {
+            values
+                .iter()
+                .map(|v| match v {
+                    PropertyValue::Null => None,
+                    v => Some(v.as_string()),
+                })
+                .collect::<Vec<Option<String>>>()
+                .into()
+        } else if present().any(|v| matches!(v, PropertyValue::Float(_))) {
+            values.iter().map(|v| v.as_f64()).collect::<Vec<Option<f64>>>().into()
+        } else {
+            values.iter().map(|v| v.as_i64().map(|i| i as i32)).collect::<Vec<Option<i32>>>().into()
+        }
+    }));
+
+    list!(wkb_geoms = wkb_geoms, col_names = col_names, col_data = col_data)
 }
 
-fn read_f64(bytes: &[u8], little_endian: bool) -> f64 {
-    let arr: [u8; 8] = [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]];
-    if little_endian {
-        f64::from_le_bytes(arr)
-    } else {
-        f64::from_be_bytes(arr)
+/// Convert NVDB segments and return the result to R instead of writing a file
+///
+/// Runs the same pipeline as `process_nvdb_wkb()` up to simplification and
+/// returns `list(ways, nodes, stats)`. `ways` and `nodes` are data frames with
+/// an `id` column, a `geometry` list column of little-endian WKB (class `WKB`,
+/// as understood by `sf::st_as_sfc()`) and one character column per OSM key.
+#[extendr]
+fn simplify_nvdb_wkb(wkb_geoms: List, col_names: Vec<String>, col_data: List, config: List) -> List {
+    let mut stats = ConversionStats::default();
+    let pipeline = match pipeline_from_config(&config) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            eprintln!("{}", e);
+            return list!(ways = NULL, nodes = NULL, stats = stats_to_list(&stats, false));
+        }
+    };
+
+    let segments = IngestOptions::from_config(&config)
+        .and_then(|options| ingest_segments(&wkb_geoms.into(), col_names, col_data, &options, &mut stats));
+    let segments = match segments {
+        Ok(segments) => segments,
+        Err(e) => {
+            eprintln!("{}", e);
+            return list!(ways = NULL, nodes = NULL, stats = stats_to_list(&stats, false));
+        }
+    };
+
+    let conversion = match pipeline.convert(segments, &mut stats) {
+        Ok(conversion) => conversion,
+        Err(e) => {
+            eprintln!("{}", e);
+            return list!(ways = NULL, nodes = NULL, stats = stats_to_list(&stats, false));
+        }
+    };
+
+    // Building the R objects counts as the write phase
+    let timer = PhaseTimer::start();
+    let (ways_df, nodes_df) = conversion_data_frames(&conversion, pipeline.first_way_id());
+    stats.write_secs = timer.secs();
+    stats.record_peak_memory(&conversion.segments);
+
+    list!(ways = ways_df, nodes = nodes_df, stats = stats_to_list(&stats, true))
+}
+
+/// Ways and feature nodes of a conversion as data frames (see
+/// `tagged_data_frame()`), numbering the ways from `way_id_start`
+fn conversion_data_frames(conversion: &Conversion, way_id_start: i64) -> (Robj, Robj) {
+    let (segments, ways, nodes) = (&conversion.segments, &conversion.ways, &conversion.feature_nodes);
+
+    let (way_ids, way_geoms) = way_geometries(ways, segments, way_id_start);
+    let way_tags: Vec<&FxHashMap<String, String>> = ways.iter().map(|way| &way.tags).collect();
+    let ways_df = tagged_data_frame(way_ids, way_geoms, &way_tags);
+
+    let node_ids: Vec<f64> = nodes.iter().map(|node| node.id as f64).collect();
+    let node_geoms: Vec<Vec<u8>> = nodes.iter().map(|node| geometry::point_to_wkb(node.lon, node.lat)).collect();
+    let node_tags: Vec<&FxHashMap<String, String>> = nodes.iter().map(|node| &node.tags).collect();
+    let nodes_df = tagged_data_frame(node_ids, node_geoms, &node_tags);
+
+    (ways_df, nodes_df)
+}
+
+/// IDs and WKB geometries of the ways, numbered from `way_id_start`
+fn way_geometries(ways: &[Way], segments: &[Segment], way_id_start: i64) -> (Vec<f64>, Vec<Vec<u8>>) {
+    let ids = (0..ways.len()).map(|i| (way_id_start + i as i64) as f64).collect();
+    let geoms = ways.par_iter().map(|way| geometry::linestring_to_wkb(&way.line(segments))).collect();
+    (ids, geoms)
+}
+
+/// Data frame of one layer of GDAL's OSM driver layout (see
+/// `NetworkHandle::gpkg_layers()`)
+fn ogr_layer(ids: &[f64], geoms: Vec<Vec<u8>>, tags: &[&FxHashMap<String, String>], columns: &[&str], z_order: Option<Vec<i32>>) -> Robj {
+    let mut names: Vec<String> = vec!["osm_id".to_string()];
+    let osm_id: Vec<String> = ids.iter().map(|&id| (id as i64).to_string()).collect();
+    let mut values: Vec<Robj> = vec![Robj::from(osm_id)];
+    for &key in columns {
+        names.push(key.to_string());
+        values.push(Robj::from(tags.iter().map(|t| t.get(key).map(String::as_str)).collect::<Vec<Option<&str>>>()));
     }
+    if let Some(z_order) = z_order {
+        names.push("z_order".to_string());
+        values.push(Robj::from(z_order));
+    }
+    names.push("other_tags".to_string());
+    values.push(Robj::from(tags.iter().map(|t| ogr::other_tags(t, columns)).collect::<Vec<Option<String>>>()));
+
+    let mut geometry = List::from_values(geoms.iter().map(|wkb| Raw::from_bytes(wkb)));
+    let _ = geometry.set_class(&["WKB"]);
+    names.push("geometry".to_string());
+    values.push(geometry.into());
+
+    let mut df: Robj = List::from_names_and_values(names, values)
+        .expect("names and columns have equal length")
+        .into();
+    let _ = df.set_attrib(row_names_symbol(), [i32::MIN, -(ids.len() as i32)]);
+    let _ = df.set_class(&["data.frame"]);
+    df
 }
 
-fn get_i64_property(props: &FxHashMap<String, PropertyValue>, key: &str) -> Option<i64> {
-    props.get(key).and_then(|value| match value {
-        PropertyValue::Integer(i) => Some(*i),
-        PropertyValue::Float(f) => Some(*f as i64),
-        PropertyValue::String(s) => s.parse::<i64>().ok(),
-        PropertyValue::Boolean(b) => Some(if *b { 1 } else { 0 }),
-        PropertyValue::Null => None,
-    })
+/// Tags of the elements `ids` as a data frame of `id`, `key` and `value`
+fn long_tags(ids: &[f64], tags: &[&FxHashMap<String, String>]) -> Robj {
+    let rows: Vec<(f64, &str, &str)> = ids
+        .iter()
+        .zip(tags)
+        .flat_map(|(&id, tags)| tags.iter().map(move |(k, v)| (id, k.as_str(), v.as_str())))
+        .collect();
+    data_frame!(
+        id = rows.iter().map(|r| r.0).collect::<Vec<f64>>(),
+        key = rows.iter().map(|r| r.1).collect::<Vec<&str>>(),
+        value = rows.iter().map(|r| r.2).collect::<Vec<&str>>()
+    )
 }
 
-fn get_bool_property(props: &FxHashMap<String, PropertyValue>, key: &str) -> Option<bool> {
-    props.get(key).and_then(|value| match value {
-        PropertyValue::Boolean(b) => Some(*b),
-        PropertyValue::Integer(i) => Some(*i != 0),
-        PropertyValue::Float(f) => Some(*f != 0.0),
-        PropertyValue::String(s) => match s.to_lowercase().as_str() {
-            "1" | "true" | "t" | "yes" => Some(true),
-            "0" | "false" | "f" | "no" => Some(false),
-            _ => None,
-        },
-        PropertyValue::Null => None,
-    })
+/// Ways and feature nodes of `conversion` as plain vectors for writing
+/// GeoParquet: `ways` and `nodes` with `id` and WKB `geometry` (as from
+/// `tagged_data_frame()`), and their `way_tags` and `node_tags` in long form
+/// with `id`, `key` and `value`
+fn parquet_tables(conversion: &Conversion, way_id_start: i64) -> List {
+    let (way_ids, way_geoms) = way_geometries(&conversion.ways, &conversion.segments, way_id_start);
+    let way_tags: Vec<&FxHashMap<String, String>> = conversion.ways.iter().map(|way| &way.tags).collect();
+    let nodes = &conversion.feature_nodes;
+    let node_ids: Vec<f64> = nodes.iter().map(|node| node.id as f64).collect();
+    let node_geoms: Vec<Vec<u8>> = nodes.iter().map(|node| geometry::point_to_wkb(node.lon, node.lat)).collect();
+    let node_tags: Vec<&FxHashMap<String, String>> = nodes.iter().map(|node| &node.tags).collect();
+    list!(
+        way_tags = long_tags(&way_ids, &way_tags),
+        ways = tagged_data_frame(way_ids, way_geoms, &[]),
+        node_tags = long_tags(&node_ids, &node_tags),
+        nodes = tagged_data_frame(node_ids, node_geoms, &[])
+    )
 }
 
-/// Process NVDB data with WKB geometries and direct R property columns
-/// 
-/// # Arguments
-/// * `wkb_geoms` - List of raw WKB bytes (one per geometry)
-/// * `col_names` - Vector of column names for properties
-/// * `col_data` - List of vectors (one per column), each vector has same length as wkb_geoms
-/// * `output_path` - Path to write the PBF file
-/// * `simplify_method` - Simplification method name
-/// * `node_id_start` - Starting ID for nodes
-/// * `way_id_start` - Starting ID for ways
-#[extendr]
-fn process_nvdb_wkb(
-    wkb_geoms: List,
-    col_names: Vec<String>,
+/// Build an R data frame with `id`, a WKB `geometry` list column and one
+/// character column per tag key (NA where an element lacks the key)
+///
+/// Tag keys that would clash with the `id` and `geometry` columns (or with
+/// another column renamed that way) get a `tag:` prefix.
+fn tagged_data_frame(ids: Vec<f64>, geoms: Vec<Vec<u8>>, tags: &[&FxHashMap<String, String>]) -> Robj {
+    let n_rows = ids.len();
+
+    let mut keys: Vec<&String> = tags.iter().flat_map(|t| t.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut geometry = List::from_values(geoms.iter().map(|wkb| Raw::from_bytes(wkb)));
+    let _ = geometry.set_class(&["WKB"]);
+
+    let mut names: Vec<String> = vec!["id".to_string(), "geometry".to_string()];
+    let mut columns: Vec<Robj> = vec![Robj::from(ids), geometry.into()];
+    for &key in &keys {
+        let values: Vec<Option<&str>> = tags.iter().map(|t| t.get(key).map(|v| v.as_str())).collect();
+        let mut name = key.clone();
+        while names.contains(&name) || (name != *key && keys.contains(&&name)) {
+            name = format!("tag:{}", name);
+        }
+        names.push(name);
+        columns.push(Robj::from(values));
+    }
+
+    let mut df: Robj = List::from_names_and_values(names, columns)
+        .expect("names and columns have equal length")
+        .into();
+    let _ = df.set_attrib(row_names_symbol(), [i32::MIN, -(n_rows as i32)]);
+    let _ = df.set_class(&["data.frame"]);
+    df
+}
+
+/// Parse WKB geometries and R property columns into segments
+///
+/// Records the parse phase in `stats`. Errors are human-readable messages for
+/// the cases where nothing can be converted.
+///
+/// Swedish input columns are first renamed to their canonical NVDB names (so
+/// GeoPackage deliveries with full attribute names work too); fields that no
+/// column resolves to are reported once and kept in `stats.unresolved_fields`.
+fn ingest_segments(
+    wkb_geoms: &Robj,
+    mut col_names: Vec<String>,
     col_data: List,
-    output_path: String,
-    simplify_method: String,
-    node_id_start: i64,
-    way_id_start: i64,
-) -> bool {
-    let n = wkb_geoms.len();
+    options: &IngestOptions,
+    stats: &mut ConversionStats,
+) -> std::result::Result<Vec<Segment>, String> {
+    remember_r_thread();
+    // Borrow the raw WKB bytes (or WKT strings, or coordinates) from R
+    // instead of copying them. Only plain slices cross into the parallel
+    // phase; no R API is called from workers.
+    let elements: Vec<Robj> = wkb_geoms.as_list().map(|list| list.values().collect()).unwrap_or_default();
+    let geometries = input_geometries(wkb_geoms, &elements)?;
+    let n = geometries.len();
+    stats.segments_in += n;
+
+    let schema = (options.profile == Profile::Sweden).then(Schema::nvdb);
+    if let Some(schema) = &schema {
+        let unresolved: Vec<&'static str> = schema
+            .resolve_columns(&mut col_names)
+            .into_iter()
+            .map(|field| field.column())
+            .collect();
+        // Chunked sessions only repeat the warning when the columns change
+        if !unresolved.is_empty() && unresolved != stats.unresolved_fields {
+            eprintln!(
+                "Warning: {} NVDB fields not found in the input columns: {}",
+                unresolved.len(),
+                unresolved.join(", ")
+            );
+        }
+        stats.unresolved_fields = unresolved;
+    }
     
     if n == 0 {
-        eprintln!("No geometries provided");
-        return false;
+        return Err("No geometries provided".to_string());
     }
     
     if col_data.len() != col_names.len() {
-        eprintln!("Column names and data length mismatch: {} vs {}", col_data.len(), col_names.len());
-        return false;
+        return Err(format!("Column names and data length mismatch: {} vs {}", col_data.len(), col_names.len()));
     }
     
+    let timer = PhaseTimer::start();
+
     // Convert List to Vec<Robj> for easier access
     let col_data_vec: Vec<Robj> = col_data.into_iter().map(|(_, v)| v).collect();
     
     // Pre-process columns for efficient access
-    let preprocessed = PreprocessedColumns::new(col_names, &col_data_vec);
-    
-    // Parse geometries and build segments
-    let mut segments: Vec<Segment> = Vec::with_capacity(n);
+    let preprocessed = PreprocessedColumns::new(col_names, &col_data_vec, schema.as_ref());
     
-    // Iterate over the wkb_geoms list
-    for (i, (_, wkb_robj)) in wkb_geoms.into_iter().enumerate() {
-        // Extract raw bytes from Robj
-        let wkb_bytes: Vec<u8> = if let Some(raw_slice) = wkb_robj.as_raw_slice() {
-            raw_slice.to_vec()
-        } else {
-            eprintln!("Geometry {} is not raw bytes", i);
-            continue;
-        };
-        
-        // Parse WKB and round coordinates to 7 decimal places using Banker's Rounding
-        let geometry = match parse_wkb(&wkb_bytes) {
-            Some(mut geom) => {
-                for coord in geom.0.iter_mut() {
-                    coord.x = round_ties_even(coord.x * 10_000_000.0) / 10_000_000.0;
-                    coord.y = round_ties_even(coord.y * 10_000_000.0) / 10_000_000.0;
-                }
-                geom
-            }
-            None => {
-                if i < 5 || i % 1000 == 0 {
-                    let first_bytes: Vec<String> = wkb_bytes.iter().take(16).map(|b| format!("{:02X}", b)).collect();
-                    eprintln!("Failed to parse WKB for geometry {}. First 16 bytes: {}", i, first_bytes.join(" "));
-                }
-                continue;
-            }
-        };
-
-        // Build segment
-        let mut seg = Segment::new(format!("seg_{}", i), geometry);
-        seg.properties = preprocessed.build_properties(i);
-        seg.global_start_node_id = get_i64_property(&seg.properties, "global_start_node_id");
-        seg.global_end_node_id = get_i64_property(&seg.properties, "global_end_node_id");
-        seg.global_start_owned = get_bool_property(&seg.properties, "global_start_owned").unwrap_or(false);
-        seg.global_end_owned = get_bool_property(&seg.properties, "global_end_owned").unwrap_or(false);
-        
-        segments.push(seg);
+    // Parse geometries and build segments in parallel (input order is preserved)
+    let parse = || -> Vec<std::result::Result<Segment, String>> {
+        geometries
+            .par_iter()
+            .enumerate()
+            .map(|(i, geometry)| build_segment(i, *geometry, &preprocessed, options))
+            .collect()
+    };
+    let parsed = match options.threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| format!("Failed to start {} parse threads: {}", threads, e))?
+            .install(parse),
+        None => parse(),
+    };
+    // Unreadable geometries are reported with the tag mapper's warnings
+    let mut segments = Vec::with_capacity(n);
+    for (i, result) in parsed.into_iter().enumerate() {
+        match result {
+            Ok(seg) => segments.push(seg),
+            Err(message) => stats.annotations.push(AnnotationRecord { source_row: i, kind: "invalid_geometry", message }),
+        }
     }
-    
-    if segments.is_empty() {
-        eprintln!("No valid geometries parsed");
-        return false;
+    stats.parse_failures += n - segments.len();
+    stats.parse_secs += timer.secs();
+    if r_interrupted() {
+        return Err(INTERRUPTED.to_string());
     }
     
-    // Apply tags
-    tag_mapper::tag_network(&mut segments);
-    
-    // Generate nodes from segment properties (POIs like crossings, cameras, etc.)
-    let mut nodes: Vec<NodeFeature> = Vec::new();
-    let mut next_node_id = node_id_start;
-    
-    for segment in &segments {
-        let (segment_nodes, new_id) = tag_mapper::nodes::generate_nodes_for_segment(segment, next_node_id);
-        nodes.extend(segment_nodes);
-        next_node_id = new_id;
+    if segments.is_empty() {
+        return Err("No valid geometries parsed".to_string());
     }
-    
-    // Simplify network
-    let method = SimplifyMethod::from(simplify_method.as_str());
-    let ways = topology::simplify_network(&mut segments, method);
-    
-    // Write PBF using three-pass approach (nodes first, then ways)
-    // Feature nodes are written before junction nodes
-    match write_pbf_three_pass(&ways, &mut segments, &nodes, &output_path, node_id_start, way_id_start) {
-        Ok(_) => true,
-        Err(e) => {
-            eprintln!("Failed to write PBF: {}", e);
-            false
+    check_bounds(&segments, options)?;
+
+    Ok(segments)
+}
+
+/// Refuse projected or misplaced input (see `Profile::check_bounds()`);
+/// input outside the country passes when `check_bounds` is off
+fn check_bounds(segments: &[Segment], options: &IngestOptions) -> std::result::Result<(), String> {
+    match options.profile.check_bounds(segments) {
+        Ok(()) => Ok(()),
+        Err(BoundsError::OutsideCountry { .. }) if !options.check_bounds => Ok(()),
+        Err(error @ BoundsError::OutsideCountry { .. }) => {
+            Err(format!("Invalid input coordinates: {}, or set check_bounds = FALSE to convert it anyway", error))
         }
+        Err(error) => Err(format!("Invalid input coordinates: {}", error)),
     }
 }
 
-/// Write ways to PBF file using three-pass approach (nodes first, then ways)
-/// This matches Python's behavior and ensures Osmium compatibility
-/// 
-/// UPDATED: Now also writes feature nodes (crossings, cameras, barriers, etc.)
-fn write_pbf_three_pass(
-    ways: &[Way],
-    segments: &mut [Segment],
-    feature_nodes: &[NodeFeature],
-    output_path: &str,
-    node_id_start: i64,
-    way_id_start: i64,
-) -> std::result::Result<(), String> {
-    let mut writer = PbfWriter::from_path(output_path, true)
-        .map_err(|e| format!("Failed to create writer: {}", e))?;
-
-    // Compute bounding box from all segment geometries and feature nodes
-    let (mut min_lat, mut max_lat) = (f64::MAX, f64::MIN);
-    let (mut min_lon, mut max_lon) = (f64::MAX, f64::MIN);
-    for seg in segments.iter() {
-        for coord in &seg.geometry.0 {
-            min_lat = min_lat.min(coord.y);
-            max_lat = max_lat.max(coord.y);
-            min_lon = min_lon.min(coord.x);
-            max_lon = max_lon.max(coord.x);
-        }
-    }
-    // Include feature nodes in bbox calculation
-    for node in feature_nodes {
-        min_lat = min_lat.min(node.lat);
-        max_lat = max_lat.max(node.lat);
-        min_lon = min_lon.min(node.lon);
-        max_lon = max_lon.max(node.lon);
-    }
-    writer.set_bbox(Bound {
-        left: deg_to_nanodeg(min_lon),
-        right: deg_to_nanodeg(max_lon),
-        top: deg_to_nanodeg(max_lat),
-        bottom: deg_to_nanodeg(min_lat),
-        origin: "nvdb2osmr".to_string(),
-    });
-
-    let mut node_id = node_id_start;
-    let mut way_id = way_id_start;
-    
-    // NEW: Pass 0 - Write feature nodes (crossings, cameras, barriers, etc.)
-    for node in feature_nodes {
-        let tags: Vec<Tag> = node.tags
-            .iter()
-            .map(|(k, v)| Tag {
-                key: k.clone(),
-                value: v.clone(),
-            })
-            .collect();
-        
-        let pbf_node = Node {
-            id: node.id,
-            latitude: deg_to_nanodeg(node.lat),
-            longitude: deg_to_nanodeg(node.lon),
-            tags,
-            version: 0,
-            timestamp: None,
-            user: None,
-            changeset_id: 0,
-            visible: true,
+/// Input geometry of one row, borrowed from R
+#[derive(Clone, Copy)]
+enum Geometry<'a> {
+    Wkb(&'a [u8]),
+    Wkt(&'a str),
+    /// Vertex x and y coordinates
+    Coords(&'a [f64], &'a [f64]),
+}
+
+/// Borrow the geometry of each row: `geoms` is a list of raw WKB vectors, a
+/// character vector of WKT, or a list of class `nvdb_coords` holding a
+/// numeric coordinate matrix (x and y in the first two columns, vertices of
+/// all features one after another) and the number of vertices per feature
+/// (`process_nvdb_coords()` in R). `elements` are the list elements of `geoms`.
+fn input_geometries<'a>(geoms: &'a Robj, elements: &'a [Robj]) -> std::result::Result<Vec<Option<Geometry<'a>>>, String> {
+    if let Some(texts) = geoms.as_str_vector() {
+        return Ok(texts.into_iter().map(|text| Some(Geometry::Wkt(text))).collect());
+    }
+    if !geoms.inherits("nvdb_coords") {
+        return Ok(elements.iter().map(|element| element.as_raw_slice().map(Geometry::Wkb)).collect());
+    }
+
+    let (coords, lengths, n_rows) = match elements {
+        [coords, lengths] => (
+            coords.as_real_slice(),
+            lengths.as_integer_slice(),
+            coords.get_attrib("dim").and_then(|dim| dim.as_integer_slice().and_then(|d| d.first().copied())),
+        ),
+        _ => (None, None, None),
+    };
+    let (Some(coords), Some(lengths), Some(n_rows)) = (coords, lengths, n_rows) else {
+        return Err("Coordinates must be a double matrix and lengths an integer vector".to_string());
+    };
+    let n_rows = n_rows.max(0) as usize;
+    let total: usize = lengths.iter().map(|&l| l.max(0) as usize).sum();
+    if total != n_rows || coords.len() < 2 * n_rows {
+        return Err(format!("Feature lengths add up to {} vertices, the coordinate matrix has {} rows", total, n_rows));
+    }
+
+    let (xs, ys) = (&coords[..n_rows], &coords[n_rows..2 * n_rows]);
+    let mut start = 0;
+    Ok(lengths
+        .iter()
+        .map(|&length| {
+            let end = start + length.max(0) as usize;
+            let geometry = Geometry::Coords(&xs[start..end], &ys[start..end]);
+            start = end;
+            Some(geometry)
+        })
+        .collect())
+}
+
+/// How the input rows are turned into segments
+struct IngestOptions {
+    profile: Profile,
+    /// Coordinate rounding (off in fidelity mode) and curve densification
+    wkb: wkb::WkbOptions,
+    /// Parser threads, `None` for one per core
+    threads: Option<usize>,
+    /// Refuse coordinates outside the profile's country (projected
+    /// coordinates are always refused)
+    check_bounds: bool,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self { profile: Profile::default(), wkb: wkb::WkbOptions::default(), threads: None, check_bounds: true }
+    }
+}
+
+impl IngestOptions {
+    fn from_config(config: &List) -> std::result::Result<Self, String> {
+        Ok(Self {
+            profile: config_profile(config)?,
+            wkb: wkb::WkbOptions {
+                round_coords: !config_bool(config, "fidelity"),
+                arc_tolerance_m: config_f64(config, "arc_tolerance", wkb::DEFAULT_ARC_TOLERANCE_M),
+                keep_elevation: config_bool(config, "incline"),
+            },
+            threads: config_threads(config),
+            check_bounds: config_value(config, "check_bounds").and_then(|v| v.as_bool()).unwrap_or(true),
+        })
+    }
+}
+
+/// Chunked conversion session
+///
+/// Lets R feed the input in chunks (`begin_conversion()`, `add_chunk()`,
+/// `finish_conversion()`) instead of one giant list of WKB blobs. Each chunk is
+/// tagged and simplified on its own and its nodes are written straight away;
+/// its ways are spilled to a temporary file until `finish()`, because PBF
+/// requires all nodes before any way. The junction map inside the emitter persists
+/// across chunks, so endpoints shared between chunks become a single node.
+///
+/// Chunks should be spatially coherent (e.g. one municipality each): bridge
+/// detection, street-name lookups and way merging only see one chunk at a time.
+/// The header has no bbox because the extent is unknown when the first block
+/// is flushed, unless a fixed one is given (`header_bbox`).
+#[extendr]
+struct ConversionSession {
+    emitter: Option<PbfEmitter>,
+    pipeline: Pipeline,
+    ingest: IngestOptions,
+    spill: Option<WaySpill>,
+    /// Relations with members indexing the spilled ways
+    pending_relations: Vec<RelationFeature>,
+    stats: ConversionStats,
+    failed: bool,
+}
+
+#[extendr]
+impl ConversionSession {
+    fn new(output_path: String, config: List) -> Self {
+        let opened = pipeline_from_config(&config).and_then(|pipeline| {
+            if config_value(&config, "append_to").is_some() {
+                return Err("append_to is not supported when converting in chunks".to_string());
+            }
+            if config_value(&config, "tile_zoom").is_some() {
+                return Err("tile_zoom is not supported when converting in chunks".to_string());
+            }
+            if config_value(&config, "max_file_elements").is_some() {
+                return Err("max_file_elements is not supported when converting in chunks".to_string());
+            }
+            if config_value(&config, "parquet_dir").is_some() {
+                return Err("parquet_dir is not supported when converting in chunks".to_string());
+            }
+            let ingest = IngestOptions::from_config(&config)?;
+            let options = pipeline.write_options();
+            let mut emitter = PbfEmitter::create_with(&output_path, &options)?;
+            // Only a fixed bbox is known before the first chunk is written
+            if let HeaderBbox::Fixed(west, south, east, north) = options.bbox {
+                emitter.set_bbox(west, south, east, north);
+            }
+            Ok((pipeline, ingest, Some(emitter), Some(WaySpill::create(&output_path)?)))
+        });
+        let (pipeline, ingest, emitter, spill) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                eprintln!("{}", e);
+                (Pipeline::default(), IngestOptions::default(), None, None)
+            }
         };
-        let _ = writer.write(Element::Node(pbf_node));
-        
-        // Update node_id to be after all feature nodes
-        if node.id >= node_id {
-            node_id = node.id + 1;
+
+        Self {
+            failed: emitter.is_none(),
+            emitter,
+            pipeline,
+            ingest,
+            spill,
+            pending_relations: Vec::new(),
+            stats: ConversionStats::default(),
         }
     }
-    
-    // Build junction index and assign junction node IDs
-    let mut junction_ids: FxHashMap<CoordHash, i64> = FxHashMap::default();
-    let mut written_node_ids: HashSet<i64> = HashSet::new();
-
-    // Pass 1: Identify all junction nodes (start/end of segments that are used in ways)
-    // and assign them IDs
-    for way in ways {
-        if !way.segment_indices.is_empty() {
-            let first_seg = &segments[way.segment_indices[0]];
-            let last_seg = &segments[way.segment_indices[way.segment_indices.len() - 1]];
-
-            // Start junction of the way
-            let start_hash = first_seg.start_node;
-            if !junction_ids.contains_key(&start_hash) {
-                let coord = first_seg.start_coord();
-                let (id, should_write) = if let Some(global_id) = first_seg.global_start_node_id {
-                    (global_id, first_seg.global_start_owned)
-                } else {
-                    let local_id = node_id;
-                    node_id += 1;
-                    (local_id, true)
-                };
-                junction_ids.insert(start_hash, id);
-
-                if should_write && written_node_ids.insert(id) {
-                    let node = Node {
-                        id,
-                        latitude: deg_to_nanodeg(coord.y),
-                        longitude: deg_to_nanodeg(coord.x),
-                        tags: vec![],
-                        version: 0,
-                        timestamp: None,
-                        user: None,
-                        changeset_id: 0,
-                        visible: true,
-                    };
-                    let _ = writer.write(Element::Node(node));
-                }
+
+    /// Convert one chunk; returns FALSE if it could not be added
+    fn add_chunk(&mut self, wkb_geoms: List, col_names: Vec<String>, col_data: List) -> bool {
+        let (emitter, spill) = match (self.emitter.as_mut(), self.spill.as_mut()) {
+            (Some(emitter), Some(spill)) => (emitter, spill),
+            _ => {
+                eprintln!("Conversion session is not open");
+                return false;
             }
+        };
 
-            // End junction of the way
-            let end_hash = last_seg.end_node;
-            if !junction_ids.contains_key(&end_hash) {
-                let coord = last_seg.end_coord();
-                let (id, should_write) = if let Some(global_id) = last_seg.global_end_node_id {
-                    (global_id, last_seg.global_end_owned)
-                } else {
-                    let local_id = node_id;
-                    node_id += 1;
-                    (local_id, true)
-                };
-                junction_ids.insert(end_hash, id);
-
-                if should_write && written_node_ids.insert(id) {
-                    let node = Node {
-                        id,
-                        latitude: deg_to_nanodeg(coord.y),
-                        longitude: deg_to_nanodeg(coord.x),
-                        tags: vec![],
-                        version: 0,
-                        timestamp: None,
-                        user: None,
-                        changeset_id: 0,
-                        visible: true,
-                    };
-                    let _ = writer.write(Element::Node(node));
-                }
+        let segments = match ingest_segments(&wkb_geoms.into(), col_names, col_data, &self.ingest, &mut self.stats) {
+            Ok(segments) => segments,
+            Err(e) => {
+                eprintln!("{}", e);
+                return false;
             }
-        }
+        };
 
-        // Also need internal junctions (where segments connect within a way)
-        for seg_indices in way.segment_indices.windows(2) {
-            let seg1 = &segments[seg_indices[0]];
-            let seg2 = &segments[seg_indices[1]];
+        let conversion = match self.pipeline.clone().node_id_start(emitter.next_node_id()).convert(segments, &mut self.stats) {
+            Ok(conversion) => conversion,
+            Err(e) => {
+                eprintln!("{}", e);
+                return false;
+            }
+        };
+        let (mut segments, ways, nodes) = (conversion.segments, conversion.ways, conversion.feature_nodes);
 
-            // The junction between segments
-            let junction_hash = seg1.end_node; // should match seg2.start_node
-            if !junction_ids.contains_key(&junction_hash) {
-                let coord = seg1.end_coord();
-                let chosen_global = match (seg1.global_end_node_id, seg2.global_start_node_id) {
-                    (Some(id1), Some(id2)) if id1 == id2 => {
-                        Some((id1, seg1.global_end_owned || seg2.global_start_owned))
-                    }
-                    (Some(id1), Some(_)) => Some((id1, seg1.global_end_owned)),
-                    (Some(id1), None) => Some((id1, seg1.global_end_owned)),
-                    (None, Some(id2)) => Some((id2, seg2.global_start_owned)),
-                    (None, None) => None,
-                };
+        let offset = spill.len();
+        self.pending_relations.extend(conversion.relations.into_iter().map(|mut relation| {
+            for (way_idx, _) in &mut relation.members {
+                *way_idx += offset;
+            }
+            relation
+        }));
 
-                let (id, should_write) = if let Some((global_id, owned)) = chosen_global {
-                    (global_id, owned)
-                } else {
-                    let local_id = node_id;
-                    node_id += 1;
-                    (local_id, true)
-                };
-                junction_ids.insert(junction_hash, id);
-
-                if should_write && written_node_ids.insert(id) {
-                    let node = Node {
-                        id,
-                        latitude: deg_to_nanodeg(coord.y),
-                        longitude: deg_to_nanodeg(coord.x),
-                        tags: vec![],
-                        version: 0,
-                        timestamp: None,
-                        user: None,
-                        changeset_id: 0,
-                        visible: true,
-                    };
-                    let _ = writer.write(Element::Node(node));
-                }
+        let timer = PhaseTimer::start();
+        emitter.write_feature_nodes(&nodes);
+        emitter.write_network_nodes(&ways, &mut segments);
+        for way in &ways {
+            if let Err(e) = spill.push(emitter.build_way(way, &segments)) {
+                eprintln!("{}", e);
+                self.failed = true;
+                return false;
             }
         }
+        self.stats.write_secs += timer.secs();
+        self.stats.record_peak_memory(&segments);
+
+        true
     }
-    
-    // Pass 2: Write internal nodes for each segment
-    // Internal nodes are all coordinates except start and end
-    // If an internal coordinate matches a junction (from Pass 1), reuse its ID
-    // First, collect all (seg_idx, coord, maybe_junction_id) tuples
-    let mut internal_node_data: Vec<(usize, Vec<(Coord, Option<i64>)>)> = Vec::new();
-    for way in ways {
-        for &seg_idx in &way.segment_indices {
-            let seg = &segments[seg_idx];
-            let coords: Vec<(Coord, Option<i64>)> = seg.internal_coords().iter().map(|c| {
-                let h = models::hash_coord(c);
-                (*c, junction_ids.get(&h).copied())
-            }).collect();
-            internal_node_data.push((seg_idx, coords));
-        }
-    }
-
-    // Now process each segment's internal nodes
-    for (seg_idx, coords) in internal_node_data {
-        let seg = &mut segments[seg_idx];
-        seg.internal_node_ids.clear();
-
-        for (coord, maybe_junction_id) in coords {
-            if let Some(junction_id) = maybe_junction_id {
-                // This internal coordinate is at a junction — reuse the junction node ID
-                seg.internal_node_ids.push(junction_id);
-            } else {
-                let id = node_id;
-                node_id += 1;
-                seg.internal_node_ids.push(id);
-
-                let node = Node {
-                    id,
-                    latitude: deg_to_nanodeg(coord.y),
-                    longitude: deg_to_nanodeg(coord.x),
-                    tags: vec![],
-                    version: 0,
-                    timestamp: None,
-                    user: None,
-                    changeset_id: 0,
-                    visible: true,
-                };
-                let _ = writer.write(Element::Node(node));
+
+    /// Write the spilled ways and close the file; returns the conversion summary
+    fn finish(&mut self) -> List {
+        let (mut emitter, spill) = match (self.emitter.take(), self.spill.take()) {
+            (Some(emitter), Some(spill)) => (emitter, spill),
+            _ => return stats_to_list(&self.stats, false),
+        };
+
+        let timer = PhaseTimer::start();
+        let result = spill.write_to(&mut emitter).and_then(|_| {
+            let first_ids = self.pipeline.first_ids();
+            emitter.write_relations(&self.pending_relations, first_ids.way, first_ids.relation);
+            emitter.finish()
+        });
+        self.stats.write_secs += timer.secs();
+
+        match result {
+            Ok(nodes_written) => {
+                self.stats.nodes_out = nodes_written;
+                stats_to_list(&self.stats, !self.failed)
+            }
+            Err(e) => {
+                eprintln!("Failed to write PBF: {}", e);
+                stats_to_list(&self.stats, false)
             }
         }
     }
-    
-    // Pass 3: Write all ways
-    for way in ways {
-        let mut way_node_ids: Vec<i64> = Vec::new();
-        
-        if !way.segment_indices.is_empty() {
-            // Start with first segment's start junction
-            let first_seg = &segments[way.segment_indices[0]];
-            let start_id = junction_ids.get(&first_seg.start_node)
-                .copied()
-                .unwrap_or_else(|| {
-                    // Fallback: create new node
-                    let id = node_id;
-                    node_id += 1;
-                    id
-                });
-            way_node_ids.push(start_id);
-            
-            // Add internal nodes and end junctions for each segment
-            for &seg_idx in &way.segment_indices {
-                let seg = &segments[seg_idx];
-                
-                // Add internal nodes
-                for &internal_id in &seg.internal_node_ids {
-                    way_node_ids.push(internal_id);
-                }
-                
-                // Add end junction
-                let end_id = junction_ids.get(&seg.end_node)
-                    .copied()
-                    .unwrap_or_else(|| {
-                        let id = node_id;
-                        node_id += 1;
-                        id
-                    });
-                way_node_ids.push(end_id);
+}
+
+/// Tagged and simplified network kept in memory
+///
+/// `build_network()` runs parsing, tagging and simplification once; the
+/// result can then be written several times (`write_pbf()`, `write_xml()`)
+/// or returned as data frames (`as_dataframe()`) without converting again.
+/// Every output numbers nodes and ways the same way.
+#[extendr]
+struct NetworkHandle {
+    pipeline: Pipeline,
+    conversion: Conversion,
+    stats: ConversionStats,
+    way_id_start: i64,
+}
+
+#[extendr]
+impl NetworkHandle {
+    fn build(wkb_geoms: List, col_names: Vec<String>, col_data: List, config: List) -> extendr_api::Result<Self> {
+        let mut stats = ConversionStats::default();
+        let pipeline = pipeline_from_config(&config).map_err(Error::Other)?;
+        let segments = IngestOptions::from_config(&config)
+            .and_then(|options| ingest_segments(&wkb_geoms.into(), col_names, col_data, &options, &mut stats))
+            .map_err(Error::Other)?;
+        let conversion = pipeline.convert(segments, &mut stats).map_err(Error::Other)?;
+        stats.record_peak_memory(&conversion.segments);
+
+        Ok(Self {
+            way_id_start: pipeline.first_way_id(),
+            pipeline,
+            conversion,
+            stats,
+        })
+    }
+
+    /// Write the network to `output_path` (format from the extension);
+    /// returns the conversion summary with this write's timing and node count
+    fn write(&mut self, output_path: String) -> List {
+        let mut stats = self.stats.clone();
+        match self.pipeline.write(&mut self.conversion, &output_path, &mut stats) {
+            Ok(()) => stats_to_list(&stats, true),
+            Err(e) => {
+                eprintln!("Failed to write {}: {}", output_path, e);
+                stats_to_list(&stats, false)
             }
         }
-        
-        // Deduplicate consecutive nodes (in case junctions overlap)
-        way_node_ids.dedup();
-        
-        let way_nodes: Vec<WayNode> = way_node_ids
-            .iter()
-            .map(|&id| WayNode::new_without_coords(id))
-            .collect();
-        
-        let tags: Vec<Tag> = way.tags
-            .iter()
-            .map(|(k, v)| Tag {
-                key: k.clone(),
-                value: v.clone(),
-            })
-            .collect();
-        
-        let pbf_way = PbfWay {
-            id: way_id,
-            way_nodes,
-            tags,
-            version: 0,
-            timestamp: None,
-            user: None,
-            changeset_id: 0,
-            visible: true,
+    }
+
+    /// `list(ways, nodes)` data frames as returned by `simplify_nvdb_wkb()`
+    fn as_dataframe(&self) -> List {
+        let (ways_df, nodes_df) = conversion_data_frames(&self.conversion, self.way_id_start);
+        list!(ways = ways_df, nodes = nodes_df)
+    }
+
+    /// Merged way geometries as a data frame of `id` and a WKB `geometry`
+    /// column, without the tags
+    fn way_geometries(&self) -> Robj {
+        let (ids, geoms) = way_geometries(&self.conversion.ways, &self.conversion.segments, self.way_id_start);
+        tagged_data_frame(ids, geoms, &[])
+    }
+
+    /// Ways and feature nodes as plain vectors for writing GeoParquet, see
+    /// `parquet_tables()`
+    fn parquet_tables(&self) -> List {
+        parquet_tables(&self.conversion, self.way_id_start)
+    }
+
+    /// Junctions of the network as a data frame: `lon`, `lat`, `degree`,
+    /// `way_ids` (a list column of the IDs of the ways meeting there) and
+    /// `angle_spread` in degrees
+    fn junctions(&self) -> Robj {
+        let junctions = junction_table(&self.conversion.segments, &self.conversion.ways);
+        let lon: Vec<f64> = junctions.iter().map(|j| j.lon).collect();
+        let lat: Vec<f64> = junctions.iter().map(|j| j.lat).collect();
+        let degree: Vec<i32> = junctions.iter().map(|j| j.degree as i32).collect();
+        let way_ids = List::from_values(junctions.iter().map(|j| {
+            j.ways.iter().map(|&w| (self.way_id_start + w as i64) as f64).collect::<Vec<f64>>()
+        }));
+        let angle_spread: Vec<f64> = junctions.iter().map(|j| j.angle_spread).collect();
+
+        // Built by hand: data.frame() would spread the list column
+        let mut df: Robj = List::from_names_and_values(
+            ["lon", "lat", "degree", "way_ids", "angle_spread"],
+            [Robj::from(lon), Robj::from(lat), Robj::from(degree), way_ids.into(), Robj::from(angle_spread)],
+        )
+        .expect("names and columns have equal length")
+        .into();
+        let _ = df.set_attrib(row_names_symbol(), [i32::MIN, -(junctions.len() as i32)]);
+        let _ = df.set_class(&["data.frame"]);
+        df
+    }
+
+    /// Routing graph of the network: `list(nodes, edges)` data frames, nodes
+    /// with `node_id`, `lon`, `lat` and `degree`, edges with `from_node`,
+    /// `to_node`, `way_id`, `length` (metres), `highway`, `maxspeed` and
+    /// `oneway`
+    fn edge_list(&self) -> List {
+        let graph = edge_list(&self.conversion.segments, &self.conversion.ways);
+        let node_id: Vec<f64> = (1..=graph.nodes.len()).map(|i| i as f64).collect();
+        let lon: Vec<f64> = graph.nodes.iter().map(|n| n.lon).collect();
+        let lat: Vec<f64> = graph.nodes.iter().map(|n| n.lat).collect();
+        let degree: Vec<i32> = graph.nodes.iter().map(|n| n.degree as i32).collect();
+
+        let ways = &self.conversion.ways;
+        let tag = |key: &str| -> Vec<Option<String>> {
+            graph.edges.iter().map(|e| ways[e.way].tags.get(key).cloned()).collect()
         };
-        
-        let _ = writer.write(Element::Way(pbf_way));
-        way_id += 1;
+        let from_node: Vec<f64> = graph.edges.iter().map(|e| (e.from + 1) as f64).collect();
+        let to_node: Vec<f64> = graph.edges.iter().map(|e| (e.to + 1) as f64).collect();
+        let way_id: Vec<f64> = graph.edges.iter().map(|e| (self.way_id_start + e.way as i64) as f64).collect();
+        let length: Vec<f64> = graph.edges.iter().map(|e| e.length).collect();
+
+        list!(
+            nodes = data_frame!(node_id = node_id, lon = lon, lat = lat, degree = degree),
+            edges = data_frame!(
+                from_node = from_node,
+                to_node = to_node,
+                way_id = way_id,
+                length = length,
+                highway = tag("highway"),
+                maxspeed = tag("maxspeed"),
+                oneway = tag("oneway")
+            )
+        )
     }
-    
-    writer.finish().map_err(|e| format!("Failed to finish: {}", e))?;
-    Ok(())
+
+    /// Shortest paths between `queries` random node pairs of `edge_list()`:
+    /// `from_node`, `to_node`, their coordinates and `distance` (metres, NA
+    /// if unreachable)
+    fn path_check(&self, queries: i32, seed: f64) -> Robj {
+        let graph = edge_list(&self.conversion.segments, &self.conversion.ways);
+        let results = path_check(&graph, queries.max(0) as usize, seed as u64);
+        let node = |i: usize| &graph.nodes[i];
+        data_frame!(
+            from_node = results.iter().map(|q| (q.from + 1) as f64).collect::<Vec<f64>>(),
+            to_node = results.iter().map(|q| (q.to + 1) as f64).collect::<Vec<f64>>(),
+            from_lon = results.iter().map(|q| node(q.from).lon).collect::<Vec<f64>>(),
+            from_lat = results.iter().map(|q| node(q.from).lat).collect::<Vec<f64>>(),
+            to_lon = results.iter().map(|q| node(q.to).lon).collect::<Vec<f64>>(),
+            to_lat = results.iter().map(|q| node(q.to).lat).collect::<Vec<f64>>(),
+            distance = results.iter().map(|q| q.distance).collect::<Vec<Option<f64>>>()
+        )
+    }
+
+    /// Conversion summary of the build (parse, tag and simplify phases)
+    fn stats(&self) -> List {
+        stats_to_list(&self.stats, true)
+    }
+}
+
+extern "C" {
+    fn R_CheckUserInterrupt();
+    fn R_ToplevelExec(fun: extern "C" fn(*mut std::ffi::c_void), data: *mut std::ffi::c_void) -> i32;
+}
+
+/// Thread R runs on, recorded by the entry points that read R objects
+static R_THREAD: std::sync::OnceLock<std::thread::ThreadId> = std::sync::OnceLock::new();
+
+/// Record the current thread as R's; called where R objects are read
+fn remember_r_thread() {
+    R_THREAD.get_or_init(|| std::thread::current().id());
+}
+
+/// Whether the user has interrupted R (Ctrl-C, Esc in the GUI) since the
+/// last check; always `false` off the R main thread
+///
+/// `R_CheckUserInterrupt()` answers an interrupt with a long jump out of
+/// the caller; `R_ToplevelExec()` stops the jump at its own frame and
+/// reports it, so the conversion stops with its own error instead of
+/// unwinding through Rust frames.
+fn r_interrupted() -> bool {
+    extern "C" fn check(_: *mut std::ffi::c_void) {
+        unsafe { R_CheckUserInterrupt() }
+    }
+    if R_THREAD.get() != Some(&std::thread::current().id()) {
+        return false;
+    }
+    unsafe { R_ToplevelExec(check, std::ptr::null_mut()) == 0 }
+}
+
+/// Named element of an `nvdb_config()` list, `None` if absent or NULL
+fn config_value(config: &List, name: &str) -> Option<Robj> {
+    config.iter().find(|(n, _)| *n == name).map(|(_, v)| v).filter(|v| !v.is_null())
+}
+
+fn config_str(config: &List, name: &str, default: &str) -> String {
+    config_value(config, name)
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn config_bool(config: &List, name: &str) -> bool {
+    config_value(config, name).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Numeric option
+fn config_f64(config: &List, name: &str, default: f64) -> f64 {
+    config_value(config, name)
+        .and_then(|v| v.as_real().or_else(|| v.as_integer().map(f64::from)))
+        .unwrap_or(default)
+}
+
+/// Integer option; R passes large IDs as doubles
+fn config_i64(config: &List, name: &str, default: i64) -> i64 {
+    config_value(config, name)
+        .and_then(|v| v.as_real().or_else(|| v.as_integer().map(f64::from)))
+        .map(|v| v as i64)
+        .unwrap_or(default)
+}
+
+fn config_profile(config: &List) -> std::result::Result<Profile, String> {
+    config_str(config, "country", "se").parse()
+}
+
+/// Parser threads; `None` (one per core) if the option is NULL
+fn config_threads(config: &List) -> Option<usize> {
+    config_value(config, "threads").map(|_| config_i64(config, "threads", 1).max(1) as usize)
+}
+
+/// Pipeline from the conversion options of `nvdb_config()`; options missing
+/// from the list keep their defaults, and options that `Pipeline::validate()`
+/// refuses fail here
+fn pipeline_from_config(config: &List) -> std::result::Result<Pipeline, String> {
+    remember_r_thread();
+    let access_defaults = config_value(config, "access_defaults").unwrap_or_else(|| Robj::from(()));
+    let null_tokens = match config_value(config, "null_tokens") {
+        Some(tokens) => NullTokens::new(tokens.as_str_vector().unwrap_or_default()),
+        None => NullTokens::default(),
+    };
+    let pipeline = Pipeline::new()
+        .interrupt_check(r_interrupted)
+        .profile(config_profile(config)?)
+        .access_defaults(access_defaults_from_r(&access_defaults)?)
+        .non_road_tags(match config_value(config, "non_road_tags") {
+            Some(table) => non_road_tags_from_r(&table)?,
+            None => NonRoadTags::sweden(),
+        })
+        .unit_format(unit_format(config_bool(config, "restriction_units")))
+        .implicit_maxspeed(config_bool(config, "implicit_maxspeed"))
+        .roundabout_tags(roundabout_tags(
+            &config_str(config, "roundabout_ref", "keep"),
+            config_bool(config, "roundabout_names"),
+        ))
+        .review_mode(config_bool(config, "review_mode"))
+        .date_tag(DateTag::from(config_str(config, "date_tag", "none").as_str()))
+        .private_roads(PrivateRoads::from(config_str(config, "private_roads", "permissive").as_str()))
+        .lay_bys(LayBys::from(config_str(config, "lay_bys", "node").as_str()))
+        .noise_barriers(NoiseBarriers::from(config_str(config, "noise_barriers", "tags").as_str()))
+        .planned_roads(PlannedRoads::from(config_str(config, "planned_roads", "tag").as_str()))
+        .oneway_geometry(OnewayGeometry::from(config_str(config, "oneway_geometry", "reverse").as_str()))
+        .node_directions(NodeDirections::from(config_str(config, "node_directions", "none").as_str()))
+        .null_tokens(null_tokens)
+        .simplify_method(SimplifyMethod::from(config_str(config, "simplify_method", "refname").as_str()))
+        .unnamed_groups(UnnamedGroups::from(config_str(config, "unnamed_groups", "class").as_str()))
+        .way_splitting(way_splitting(config))
+        .split_loops(config_bool(config, "split_loops"))
+        .duplicate_ways(DuplicateWays::from(config_str(config, "duplicate_ways", "keep").as_str()))
+        .node_id_start(config_i64(config, "node_id_start", 1))
+        .way_id_start(config_i64(config, "way_id_start", 1))
+        .relation_id_start(config_i64(config, "relation_id_start", 1))
+        .fidelity(config_bool(config, "fidelity"))
+        .routing_preset(RoutingPreset::from(config_str(config, "profile", "none").as_str()))
+        .bridge_relations(config_bool(config, "bridge_relations"))
+        .length_tags(config_bool(config, "length_tags"))
+        .incline(config_bool(config, "incline"))
+        .tile_zoom(config_value(config, "tile_zoom").map(|_| config_i64(config, "tile_zoom", 0).clamp(0, 24) as u8))
+        .max_file_elements(config_value(config, "max_file_elements").map(|_| config_i64(config, "max_file_elements", 1).max(1) as usize))
+        .header_bbox(header_bbox(config))
+        .compression_level(config_i64(config, "compression_level", 6).clamp(0, 9) as u32)
+        .block_size(config_i64(config, "block_size", 8000).max(1) as usize)
+        .dense_nodes(config_value(config, "dense_nodes").and_then(|v| v.as_bool()).unwrap_or(true))
+        .parallel_encoding(config_value(config, "parallel_encoding").and_then(|v| v.as_bool()).unwrap_or(true))
+        .changeset_id(config_i64(config, "changeset_id", 1))
+        .db_schema(config_value(config, "db_schema").and_then(|v| v.as_str().map(str::to_string)));
+    let pipeline = pipeline
+        .clip_boundary(config_value(config, "boundary").map(|b| boundary_from_r(&b)).transpose()?)
+        .railways(config_value(config, "railways").map(|r| railways_from_r(&r)).transpose()?)
+        .as_of(config_value(config, "as_of").and_then(|v| v.as_str().map(str::to_string)))
+        .checkpoint(config_value(config, "checkpoint").and_then(|v| v.as_str().map(str::to_string)))
+        .josm_session(config_value(config, "josm_session").and_then(|v| v.as_str().map(str::to_string)))
+        .elevation(config_value(config, "dem").map(|d| dem_from_r(&d)).transpose()?);
+    let pipeline = match config_value(config, "append_to").and_then(|v| v.as_str().map(str::to_string)) {
+        Some(path) => pipeline.append_to(BaseExtract::scan(&path)?),
+        None => pipeline,
+    };
+    pipeline.validate()?;
+    Ok(pipeline)
 }
 
-/// Convert degrees to nanodegrees (for PBF format)
-fn deg_to_nanodeg(deg: f64) -> i64 {
-    (deg * 1_000_000_000.0) as i64
+/// `dem` option: path of a GeoTIFF elevation model
+fn dem_from_r(value: &Robj) -> std::result::Result<Arc<dyn ElevationProvider>, String> {
+    let path = value.as_str().ok_or("dem must be a file path")?;
+    Ok(Arc::new(GeoTiffDem::open(path)?))
+}
+
+/// `header_bbox` option: "all", "emitted", "none" or c(west, south, east, north)
+fn header_bbox(config: &List) -> HeaderBbox {
+    match config_value(config, "header_bbox") {
+        Some(value) => match value.as_real_slice() {
+            Some(&[west, south, east, north]) => HeaderBbox::Fixed(west, south, east, north),
+            _ => HeaderBbox::from(value.as_str().unwrap_or("all")),
+        },
+        None => HeaderBbox::All,
+    }
+}
+
+/// Clipping boundary from R: Polygon/MultiPolygon WKB as a raw vector, or
+/// GeoJSON text
+fn boundary_from_r(robj: &Robj) -> std::result::Result<geo_types::MultiPolygon<f64>, String> {
+    if let Some(bytes) = robj.as_raw_slice() {
+        return wkb::parse_polygon_wkb(bytes).ok_or_else(|| "boundary WKB is not a valid (multi)polygon".to_string());
+    }
+    match robj.as_str() {
+        Some(text) => geometry::boundary_from_geojson(text),
+        None => Err("boundary must be WKB (raw vector) or GeoJSON text".to_string()),
+    }
+}
+
+/// Railway lines from R: a list of LineString/MultiLineString WKB raw
+/// vectors, or GeoJSON text
+fn railways_from_r(robj: &Robj) -> std::result::Result<Vec<geo_types::LineString<f64>>, String> {
+    if let Some(text) = robj.as_str() {
+        return geometry::lines_from_geojson(text);
+    }
+    let list = robj.as_list().ok_or("railways must be a list of WKB (raw vectors) or GeoJSON text")?;
+    let mut lines = Vec::new();
+    for (_, item) in list.iter() {
+        let bytes = item.as_raw_slice().ok_or("railways must be a list of WKB (raw vectors) or GeoJSON text")?;
+        lines.extend(wkb::parse_lines_wkb(bytes).ok_or("railways WKB is not a valid (multi)linestring")?);
+    }
+    Ok(lines)
+}
+
+/// Restriction value format from R: TRUE appends the units (" t", " m")
+fn unit_format(restriction_units: bool) -> UnitFormat {
+    if restriction_units { UnitFormat::with_units() } else { UnitFormat::plain() }
+}
+
+/// Roundabout ref and name handling from R
+fn roundabout_tags(roundabout_ref: &str, roundabout_names: bool) -> RoundaboutTags {
+    RoundaboutTags::default()
+        .refs(RoundaboutRef::from(roundabout_ref))
+        .names(roundabout_names)
+}
+
+/// Way splitting from R: `split_at`, a character vector of "junctions" and
+/// "municipalities", and `split_driveways`
+fn way_splitting(config: &List) -> WaySplitting {
+    let split_at = config_value(config, "split_at")
+        .and_then(|v| v.as_str_vector().map(|v| v.into_iter().map(str::to_string).collect::<Vec<_>>()))
+        .unwrap_or_default();
+    WaySplitting::default()
+        .junctions(split_at.iter().any(|s| s == "junctions"))
+        .municipalities(split_at.iter().any(|s| s == "municipalities"))
+        .driveways(config_value(config, "split_driveways").and_then(|v| v.as_bool()).unwrap_or(true))
+}
+
+/// Access defaults from R: `NULL` for the Swedish table, `FALSE` for none, or
+/// a data frame with character columns `when` ("highway=motorway"), `key` and
+/// `value`
+fn access_defaults_from_r(robj: &Robj) -> std::result::Result<AccessDefaults, String> {
+    if robj.is_null() {
+        return Ok(AccessDefaults::sweden());
+    }
+    if robj.as_bool() == Some(false) {
+        return Ok(AccessDefaults::none());
+    }
+    let table = robj.as_list().ok_or("access_defaults must be NULL, FALSE or a data frame")?;
+    let column = |name: &str| -> std::result::Result<Vec<String>, String> {
+        table
+            .iter()
+            .find(|(n, _)| *n == name)
+            .and_then(|(_, col)| col.as_str_vector().map(|v| v.iter().map(|s| s.to_string()).collect()))
+            .ok_or_else(|| format!("access_defaults needs a character column `{}`", name))
+    };
+    let (when, keys, values) = (column("when")?, column("key")?, column("value")?);
+
+    let mut defaults = AccessDefaults::none();
+    for ((when, key), value) in when.iter().zip(&keys).zip(&values) {
+        let (when_key, when_value) = when
+            .split_once('=')
+            .ok_or_else(|| format!("access_defaults: `when` must look like \"highway=motorway\", got \"{}\"", when))?;
+        defaults = defaults.rule(when_key, when_value, key, value);
+    }
+    Ok(defaults)
+}
+
+/// Non-road GCM type tags from R: a data frame with columns `gcm_type`,
+/// `key` and `value`; its rows replace the built-in tags of their GCM type
+fn non_road_tags_from_r(robj: &Robj) -> std::result::Result<NonRoadTags, String> {
+    let table = robj.as_list().ok_or("non_road_tags must be a data frame")?;
+    let column = |name: &str| table.iter().find(|(n, _)| *n == name).map(|(_, col)| col);
+    let types: Vec<i64> = column("gcm_type")
+        .and_then(|col| col.as_real_vector().or_else(|| col.as_integer_vector().map(|v| v.into_iter().map(f64::from).collect())))
+        .ok_or("non_road_tags needs a numeric column `gcm_type`")?
+        .into_iter()
+        .map(|t| t as i64)
+        .collect();
+    let text = |name: &str| -> std::result::Result<Vec<String>, String> {
+        column(name)
+            .and_then(|col| col.as_str_vector().map(|v| v.iter().map(|s| s.to_string()).collect()))
+            .ok_or_else(|| format!("non_road_tags needs a character column `{}`", name))
+    };
+    let (keys, values) = (text("key")?, text("value")?);
+
+    let mut tags = NonRoadTags::sweden();
+    for &gcm_type in &types {
+        tags = tags.without(gcm_type);
+    }
+    for ((gcm_type, key), value) in types.iter().zip(&keys).zip(&values) {
+        tags = tags.rule(*gcm_type, key, value);
+    }
+    Ok(tags)
+}
+
+/// The built-in Swedish access defaults as a data frame (`when`, `key`, `value`)
+#[extendr]
+fn swedish_access_defaults() -> Robj {
+    let defaults = AccessDefaults::sweden();
+    let rules = defaults.rules();
+    let when: Vec<String> = rules.iter().map(|r| format!("{}={}", r.when.0, r.when.1)).collect();
+    let keys: Vec<String> = rules.iter().map(|r| r.key.clone()).collect();
+    let values: Vec<String> = rules.iter().map(|r| r.value.clone()).collect();
+    data_frame!(when = when, key = keys, value = values)
+}
+
+/// Convert conversion statistics to a named R list
+fn stats_to_list(stats: &ConversionStats, success: bool) -> List {
+    list!(
+        success = success,
+        parse_secs = stats.parse_secs,
+        clip_secs = stats.clip_secs,
+        tag_secs = stats.tag_secs,
+        nodes_secs = stats.nodes_secs,
+        simplify_secs = stats.simplify_secs,
+        write_secs = stats.write_secs,
+        total_secs = stats.total_secs(),
+        segments_in = stats.segments_in as f64,
+        parse_failures = stats.parse_failures as f64,
+        ways_out = stats.ways_out as f64,
+        nodes_out = stats.nodes_out as f64,
+        feature_nodes_out = stats.feature_nodes_out as f64,
+        relations_out = stats.relations_out as f64,
+        peak_memory_mb = stats.peak_memory_mb,
+        segment_memory_mb = stats.segment_memory_mb,
+        unresolved_fields = stats.unresolved_fields.clone(),
+        annotations = annotations_to_df(stats),
+        lengths = lengths_to_df(stats)
+    )
+}
+
+/// Way counts and lengths per highway class, surface and maxspeed bucket as a
+/// data frame: `group`, `value`, `ways` and `length_km`
+fn lengths_to_df(stats: &ConversionStats) -> Robj {
+    let rows = stats.lengths.rows();
+    data_frame!(
+        group = rows.iter().map(|r| r.group).collect::<Vec<&str>>(),
+        value = rows.iter().map(|r| r.value.as_str()).collect::<Vec<&str>>(),
+        ways = rows.iter().map(|r| r.ways as f64).collect::<Vec<f64>>(),
+        length_km = rows.iter().map(|r| r.length_m / 1000.0).collect::<Vec<f64>>()
+    )
+}
+
+/// Tag mapper annotations and unreadable geometries as a data frame: `row`
+/// (1-based input row), `kind` and `message`
+fn annotations_to_df(stats: &ConversionStats) -> Robj {
+    let rows: Vec<i32> = stats.annotations.iter().map(|a| a.source_row as i32 + 1).collect();
+    let kinds: Vec<&str> = stats.annotations.iter().map(|a| a.kind).collect();
+    let messages: Vec<&str> = stats.annotations.iter().map(|a| a.message.as_str()).collect();
+    data_frame!(row = rows, kind = kinds, message = messages)
+}
+
+/// Parse one WKB, WKT or coordinate geometry and build its segment with properties
+///
+/// Runs on rayon worker threads, so it must only read plain slices and never call into R.
+fn build_segment(i: usize, geometry: Option<Geometry>, preprocessed: &PreprocessedColumns, options: &IngestOptions) -> std::result::Result<Segment, String> {
+    let geometry = geometry.ok_or_else(|| "geometry is not a raw vector".to_string())?;
+
+    // Parse the geometry (coordinates rounded to 7 decimal places unless
+    // keeping them exact, curves densified) and attach properties
+    let properties = preprocessed.build_properties(i);
+    let parsed = match geometry {
+        Geometry::Wkb(wkb_bytes) => wkb::segment_from_wkb_with(wkb_bytes, properties, &options.wkb).map_err(|error| {
+            if i < 5 || i % 1000 == 0 {
+                let first_bytes: Vec<String> = wkb_bytes.iter().take(16).map(|b| format!("{:02X}", b)).collect();
+                eprintln!("Failed to parse WKB for geometry {} ({}). First 16 bytes: {}", i, error, first_bytes.join(" "));
+            }
+            error.to_string()
+        }),
+        Geometry::Wkt(text) => wkt::segment_from_wkt(text, properties, &options.wkb).map_err(|error| error.to_string()),
+        Geometry::Coords(xs, ys) => {
+            if xs.is_empty() {
+                Err(wkb::WkbError::Empty.to_string())
+            } else if xs.iter().chain(ys).any(|v| !v.is_finite()) {
+                Err(wkb::WkbError::NonFinite.to_string())
+            } else {
+                let line = geo_types::LineString::from(xs.iter().zip(ys).map(|(&x, &y)| (x, y)).collect::<Vec<_>>());
+                Ok(wkb::segment_from_line(line, properties, &options.wkb))
+            }
+        }
+    };
+    let mut seg = parsed?;
+    seg.source_row = i;
+
+    Ok(seg)
 }
 
 extendr_module! {
     mod nvdb2osmr;
     fn process_nvdb_wkb;
+    fn process_nvdb_arrow;
+    fn tag_nvdb_wkb;
+    fn resolve_nvdb_columns;
+    fn compare_pbf_files;
+    fn compare_osm_coverage;
+    fn pbf_tag_stats;
+    fn renumber_pbf_ids;
+    fn synthetic_nvdb_wkb;
+    fn swedish_access_defaults;
+    fn simplify_nvdb_wkb;
+    impl ConversionSession;
+    impl NetworkHandle;
 }